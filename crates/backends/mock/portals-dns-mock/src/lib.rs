@@ -0,0 +1,520 @@
+//! Mock implementation of portals-dns for testing.
+//!
+//! Provides a resolver that answers from programmed records or zone files,
+//! injects failures and records queries.
+
+mod zone;
+
+pub use zone::{DEFAULT_TTL, ZoneError};
+
+use portals_dns::{Error, Record, RecordData, RecordType, Resolver, reverse_name};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+
+/// Longest CNAME chain followed before giving up.
+const MAX_CNAME_HOPS: usize = 8;
+
+/// A failure to inject into lookups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    NxDomain,
+    ServFail,
+    Timeout,
+}
+
+impl Failure {
+    fn error(self) -> Error {
+        match self {
+            Failure::NxDomain => Error::NxDomain,
+            Failure::ServFail => Error::ServFail,
+            Failure::Timeout => Error::Timeout,
+        }
+    }
+}
+
+/// A query made to the mock resolver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    /// The name as passed by the caller.
+    pub name: String,
+    pub record_type: RecordType,
+}
+
+/// A mock DNS resolver for testing.
+///
+/// Answers from records added directly or loaded from zone files, and
+/// records every query. Names are case-insensitive and the trailing dot is
+/// optional. CNAMEs are followed as a recursive resolver would, and a name
+/// with no records at all is reported as NXDOMAIN.
+#[derive(Debug, Clone, Default)]
+pub struct MockResolver {
+    inner: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    records: HashMap<String, Vec<Record>>,
+    failures: HashMap<String, Failure>,
+    queued: VecDeque<Failure>,
+    queries: Vec<Query>,
+}
+
+/// Lowercase a name and make it fully qualified.
+fn normalize(name: &str) -> String {
+    let name = name.to_ascii_lowercase();
+    if name.ends_with('.') {
+        name
+    } else {
+        name + "."
+    }
+}
+
+impl MockResolver {
+    /// Create a new mock resolver with no records.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a mock resolver from a zone file.
+    pub fn from_zone(zone: &str, origin: &str) -> Result<Self, ZoneError> {
+        let resolver = Self::new();
+        resolver.load_zone(zone, origin)?;
+        Ok(resolver)
+    }
+
+    /// Add a record with the default TTL.
+    pub fn add(&self, name: &str, data: RecordData) {
+        self.add_with_ttl(name, DEFAULT_TTL, data);
+    }
+
+    /// Add a record with a TTL.
+    pub fn add_with_ttl(&self, name: &str, ttl: u32, data: RecordData) {
+        self.add_record(Record {
+            name: normalize(name),
+            ttl,
+            data,
+        });
+    }
+
+    /// Add a record as is.
+    pub fn add_record(&self, record: Record) {
+        let mut state = self.inner.lock().unwrap();
+        state
+            .records
+            .entry(normalize(&record.name))
+            .or_default()
+            .push(record);
+    }
+
+    /// Load records from a BIND-style zone file. Relative names are
+    /// resolved against `origin` until the file sets `$ORIGIN`.
+    ///
+    /// Returns the number of records loaded. Nothing is added if the file
+    /// has errors.
+    pub fn load_zone(&self, zone: &str, origin: &str) -> Result<usize, ZoneError> {
+        let records = zone::parse(zone, Some(origin))?;
+        let count = records.len();
+        for record in records {
+            self.add_record(record);
+        }
+        Ok(count)
+    }
+
+    /// Remove all records for a name.
+    pub fn remove(&self, name: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.records.remove(&normalize(name));
+    }
+
+    /// Make every lookup of a name fail, including lookups that reach it
+    /// through a CNAME.
+    pub fn fail(&self, name: &str, failure: Failure) {
+        let mut state = self.inner.lock().unwrap();
+        state.failures.insert(normalize(name), failure);
+    }
+
+    /// Stop failing lookups of a name.
+    pub fn clear_failure(&self, name: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.failures.remove(&normalize(name));
+    }
+
+    /// Queue a failure for the next query, whatever its name.
+    pub fn queue_failure(&self, failure: Failure) {
+        let mut state = self.inner.lock().unwrap();
+        state.queued.push_back(failure);
+    }
+
+    /// Get all queries that have been made.
+    ///
+    /// [`lookup_ip`](Resolver::lookup_ip) makes an A and an AAAA query;
+    /// [`reverse_lookup`](Resolver::reverse_lookup) makes a PTR query for
+    /// the reverse name.
+    pub fn queries(&self) -> Vec<Query> {
+        let state = self.inner.lock().unwrap();
+        state.queries.clone()
+    }
+
+    /// Get the number of queries made.
+    pub fn query_count(&self) -> usize {
+        let state = self.inner.lock().unwrap();
+        state.queries.len()
+    }
+
+    /// Clear all recorded queries.
+    pub fn clear_queries(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.queries.clear();
+    }
+
+    /// Assert that a name was queried.
+    pub fn assert_queried(&self, name: &str) {
+        let state = self.inner.lock().unwrap();
+        let name = normalize(name);
+        assert!(
+            state.queries.iter().any(|q| normalize(&q.name) == name),
+            "expected a query for {} but none was made",
+            name
+        );
+    }
+
+    /// Assert that a name was queried for a record type.
+    pub fn assert_queried_with(&self, name: &str, record_type: RecordType) {
+        let state = self.inner.lock().unwrap();
+        let name = normalize(name);
+        assert!(
+            state
+                .queries
+                .iter()
+                .any(|q| normalize(&q.name) == name && q.record_type == record_type),
+            "expected a {} query for {} but none was made",
+            record_type,
+            name
+        );
+    }
+
+    /// Assert that a name was never queried.
+    pub fn assert_not_queried(&self, name: &str) {
+        let state = self.inner.lock().unwrap();
+        let name = normalize(name);
+        assert!(
+            !state.queries.iter().any(|q| normalize(&q.name) == name),
+            "expected no query for {} but one was made",
+            name
+        );
+    }
+
+    /// Record a query and answer it.
+    fn answer(&self, name: &str, record_type: RecordType) -> Result<Vec<Record>, Error> {
+        let mut state = self.inner.lock().unwrap();
+        state.queries.push(Query {
+            name: name.to_string(),
+            record_type,
+        });
+        if let Some(failure) = state.queued.pop_front() {
+            return Err(failure.error());
+        }
+
+        let mut current = normalize(name);
+        for _ in 0..=MAX_CNAME_HOPS {
+            if let Some(failure) = state.failures.get(&current) {
+                return Err(failure.error());
+            }
+            let Some(records) = state.records.get(&current).filter(|r| !r.is_empty()) else {
+                // A name with records below it exists, even if it has none.
+                let suffix = format!(".{}", current);
+                return if state.records.keys().any(|key| key.ends_with(&suffix)) {
+                    Err(Error::NoRecords)
+                } else {
+                    Err(Error::NxDomain)
+                };
+            };
+
+            let matching: Vec<Record> = records
+                .iter()
+                .filter(|record| record.record_type() == record_type)
+                .cloned()
+                .collect();
+            if !matching.is_empty() {
+                return Ok(matching);
+            }
+
+            let cname = records.iter().find_map(|record| match &record.data {
+                RecordData::Cname(target) if record_type != RecordType::Cname => {
+                    Some(normalize(target))
+                }
+                _ => None,
+            });
+            match cname {
+                Some(target) => current = target,
+                None => return Err(Error::NoRecords),
+            }
+        }
+        Err(Error::Lookup(format!(
+            "CNAME chain for {} is too long",
+            name
+        )))
+    }
+}
+
+impl Resolver for MockResolver {
+    async fn lookup_ipv4(&self, host: &str) -> Result<Vec<Ipv4Addr>, Error> {
+        Ok(self
+            .answer(host, RecordType::A)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::A(addr) => Some(addr),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn lookup_ipv6(&self, host: &str) -> Result<Vec<Ipv6Addr>, Error> {
+        Ok(self
+            .answer(host, RecordType::Aaaa)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::Aaaa(addr) => Some(addr),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, Error> {
+        let v4 = self.lookup_ipv4(host).await;
+        let v6 = self.lookup_ipv6(host).await;
+        match (v4, v6) {
+            (Err(Error::NoRecords), Err(Error::NoRecords)) => Err(Error::NoRecords),
+            (Err(e), _) | (_, Err(e)) if !matches!(e, Error::NoRecords) => Err(e),
+            (v4, v6) => {
+                let v4 = v4.unwrap_or_default().into_iter().map(IpAddr::V4);
+                let v6 = v6.unwrap_or_default().into_iter().map(IpAddr::V6);
+                Ok(v4.chain(v6).collect())
+            }
+        }
+    }
+
+    async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, Error> {
+        Ok(self
+            .answer(host, RecordType::Txt)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::Txt(text) => Some(text),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn lookup_mx(&self, domain: &str) -> Result<Vec<(u16, String)>, Error> {
+        Ok(self
+            .answer(domain, RecordType::Mx)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::Mx(mx) => Some((mx.preference, mx.exchange)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn reverse_lookup(&self, addr: IpAddr) -> Result<Vec<String>, Error> {
+        Ok(self
+            .answer(&reverse_name(addr), RecordType::Ptr)?
+            .into_iter()
+            .filter_map(|record| match record.data {
+                RecordData::Ptr(name) => Some(name),
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Result<Vec<Record>, Error> {
+        self.answer(name, record_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_dns::Srv;
+
+    const ZONE: &str = "
+$TTL 300
+@       NS    ns1
+        MX    10 mail
+ns1     A     192.0.2.53
+mail    A     192.0.2.25
+www     A     192.0.2.1
+        AAAA  2001:db8::1
+api     CNAME www
+loop1   CNAME loop2
+loop2   CNAME loop1
+_imaps._tcp SRV 0 5 993 mail
+";
+
+    #[tokio::test]
+    async fn answers_static_records() {
+        let resolver = MockResolver::new();
+        resolver.add("Example.com", RecordData::A(Ipv4Addr::new(192, 0, 2, 1)));
+        resolver.add_with_ttl("example.com.", 60, RecordData::Txt("hello".to_string()));
+
+        assert_eq!(
+            resolver.lookup_ipv4("example.com").await.unwrap(),
+            vec![Ipv4Addr::new(192, 0, 2, 1)]
+        );
+        let records = resolver
+            .lookup("EXAMPLE.COM.", RecordType::Txt)
+            .await
+            .unwrap();
+        assert_eq!(records[0].ttl, 60);
+        assert_eq!(records[0].name, "example.com.");
+    }
+
+    #[tokio::test]
+    async fn serves_zone_files() {
+        let resolver = MockResolver::from_zone(ZONE, "example.com").unwrap();
+
+        assert_eq!(
+            resolver.lookup_mx("example.com").await.unwrap(),
+            vec![(10, "mail.example.com.".to_string())]
+        );
+        assert_eq!(
+            resolver
+                .lookup_srv("_imaps._tcp.example.com")
+                .await
+                .unwrap(),
+            vec![Srv {
+                priority: 0,
+                weight: 5,
+                port: 993,
+                target: "mail.example.com.".to_string(),
+            }]
+        );
+        assert_eq!(
+            resolver.lookup_ip("www.example.com").await.unwrap(),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6("2001:db8::1".parse().unwrap()),
+            ]
+        );
+        assert_eq!(
+            resolver
+                .lookup("ns1.example.com", RecordType::A)
+                .await
+                .unwrap()[0]
+                .ttl,
+            300
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_cnames() {
+        let resolver = MockResolver::from_zone(ZONE, "example.com").unwrap();
+
+        let records = resolver
+            .lookup("api.example.com", RecordType::A)
+            .await
+            .unwrap();
+        assert_eq!(records[0].name, "www.example.com.");
+        assert_eq!(
+            resolver.lookup_cname("api.example.com").await.unwrap(),
+            vec!["www.example.com.".to_string()]
+        );
+        assert!(matches!(
+            resolver.lookup_ipv4("loop1.example.com").await,
+            Err(Error::Lookup(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn distinguishes_nxdomain_from_no_records() {
+        let resolver = MockResolver::from_zone(ZONE, "example.com").unwrap();
+
+        assert!(matches!(
+            resolver.lookup_ipv4("missing.example.com").await,
+            Err(Error::NxDomain)
+        ));
+        assert!(matches!(
+            resolver.lookup_txt("www.example.com").await,
+            Err(Error::NoRecords)
+        ));
+        // Only a record below it: an empty non-terminal.
+        assert!(matches!(
+            resolver.lookup_ipv4("_tcp.example.com").await,
+            Err(Error::NoRecords)
+        ));
+    }
+
+    #[tokio::test]
+    async fn injects_failures() {
+        let resolver = MockResolver::from_zone(ZONE, "example.com").unwrap();
+
+        resolver.fail("www.example.com", Failure::ServFail);
+        assert!(matches!(
+            resolver.lookup_ip("www.example.com").await,
+            Err(Error::ServFail)
+        ));
+        // Reached through a CNAME.
+        assert!(matches!(
+            resolver.lookup_ipv4("api.example.com").await,
+            Err(Error::ServFail)
+        ));
+        resolver.clear_failure("www.example.com");
+        assert!(resolver.lookup_ipv4("www.example.com").await.is_ok());
+
+        resolver.queue_failure(Failure::Timeout);
+        resolver.queue_failure(Failure::NxDomain);
+        assert!(matches!(
+            resolver.lookup_ipv4("www.example.com").await,
+            Err(Error::Timeout)
+        ));
+        assert!(matches!(
+            resolver.lookup_ipv4("www.example.com").await,
+            Err(Error::NxDomain)
+        ));
+        assert!(resolver.lookup_ipv4("www.example.com").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn records_queries() {
+        let resolver = MockResolver::from_zone(ZONE, "example.com").unwrap();
+        resolver.add(
+            "1.2.0.192.in-addr.arpa",
+            RecordData::Ptr("www.example.com.".to_string()),
+        );
+
+        resolver.lookup_ip("WWW.example.com").await.unwrap();
+        let names = resolver
+            .reverse_lookup(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["www.example.com.".to_string()]);
+
+        assert_eq!(resolver.query_count(), 3);
+        resolver.assert_queried("www.example.com.");
+        resolver.assert_queried_with("www.example.com", RecordType::Aaaa);
+        resolver.assert_queried_with("1.2.0.192.in-addr.arpa", RecordType::Ptr);
+        resolver.assert_not_queried("api.example.com");
+        assert_eq!(
+            resolver.queries()[0],
+            Query {
+                name: "WWW.example.com".to_string(),
+                record_type: RecordType::A,
+            }
+        );
+
+        resolver.clear_queries();
+        assert_eq!(resolver.query_count(), 0);
+    }
+
+    #[test]
+    fn zone_errors_add_nothing() {
+        let resolver = MockResolver::new();
+        let err = resolver
+            .load_zone("www A 192.0.2.1\nbad A nope\n", "example.com")
+            .unwrap_err();
+        assert_eq!(err.line, 2);
+        resolver.remove("nothing.example.com");
+        assert!(resolver.inner.lock().unwrap().records.is_empty());
+    }
+}
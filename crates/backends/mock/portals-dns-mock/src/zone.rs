@@ -0,0 +1,656 @@
+//! A loader for BIND-style zone files (RFC 1035 section 5).
+//!
+//! Supports `$ORIGIN` and `$TTL`, `@`, relative names, omitted owners,
+//! TTLs and classes in either order, parentheses, comments and quoted
+//! strings, for every record type in [`RecordData`].
+
+use portals_dns::{Caa, Mx, Record, RecordData, Soa, Srv, SvcParam, Svcb, Tlsa};
+use portals_encoding::{Base64, Hex};
+use portals_encoding_portable::{StdBase64, StdHex};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// TTL for records when the zone has no `$TTL` and the record gives none.
+pub const DEFAULT_TTL: u32 = 3600;
+
+/// An error in a zone file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneError {
+    /// The line the offending entry starts on, from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "zone file line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ZoneError {}
+
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+/// One logical entry: a line, or several joined by parentheses.
+struct Entry {
+    line: usize,
+    /// Whether the entry starts with whitespace, i.e. omits its owner.
+    indented: bool,
+    tokens: Vec<Token>,
+}
+
+fn entries(text: &str) -> Result<Vec<Entry>, ZoneError> {
+    let mut entries = Vec::new();
+    let mut line = 1;
+    let mut entry = Entry {
+        line,
+        indented: false,
+        tokens: Vec::new(),
+    };
+    let mut at_line_start = true;
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let line_start = std::mem::replace(&mut at_line_start, false);
+        match c {
+            '\n' => {
+                line += 1;
+                at_line_start = true;
+                if depth == 0 {
+                    let next = Entry {
+                        line,
+                        indented: false,
+                        tokens: Vec::new(),
+                    };
+                    let done = std::mem::replace(&mut entry, next);
+                    if !done.tokens.is_empty() {
+                        entries.push(done);
+                    }
+                }
+            }
+            ' ' | '\t' | '\r' => {
+                if line_start && depth == 0 {
+                    entry.indented = true;
+                }
+            }
+            ';' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '(' => depth += 1,
+            ')' => {
+                if depth == 0 {
+                    return Err(error(line, "unbalanced ')'"));
+                }
+                depth -= 1;
+            }
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.push(escape(&mut chars, line)?),
+                        Some('\n') | None => return Err(error(line, "unterminated string")),
+                        Some(c) => text.push(c),
+                    }
+                }
+                entry.tokens.push(Token { text, quoted: true });
+            }
+            _ => {
+                let mut text = String::new();
+                let mut c = c;
+                loop {
+                    match c {
+                        '\\' => text.push(escape(&mut chars, line)?),
+                        // A quoted part inside a token, as in `alpn="h2,h3"`.
+                        '"' => loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some('\\') => text.push(escape(&mut chars, line)?),
+                                Some('\n') | None => {
+                                    return Err(error(line, "unterminated string"));
+                                }
+                                Some(c) => text.push(c),
+                            }
+                        },
+                        c => text.push(c),
+                    }
+                    match chars
+                        .next_if(|&c| !matches!(c, ' ' | '\t' | '\r' | '\n' | ';' | '(' | ')'))
+                    {
+                        Some(next) => c = next,
+                        None => break,
+                    }
+                }
+                entry.tokens.push(Token {
+                    text,
+                    quoted: false,
+                });
+            }
+        }
+    }
+
+    if depth != 0 {
+        return Err(error(entry.line, "unbalanced '('"));
+    }
+    if !entry.tokens.is_empty() {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Read the rest of a `\X` or `\DDD` escape.
+fn escape(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    line: usize,
+) -> Result<char, ZoneError> {
+    match chars.next() {
+        Some(d) if d.is_ascii_digit() => {
+            let digits: String = [Some(d), chars.next(), chars.next()]
+                .into_iter()
+                .flatten()
+                .collect();
+            digits
+                .parse::<u8>()
+                .ok()
+                .filter(|_| digits.len() == 3)
+                .map(char::from)
+                .ok_or_else(|| error(line, "invalid \\DDD escape"))
+        }
+        Some(c) => Ok(c),
+        None => Err(error(line, "escape at end of input")),
+    }
+}
+
+fn error(line: usize, message: impl Into<String>) -> ZoneError {
+    ZoneError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Parse a TTL in seconds or with BIND units, e.g. `1h30m`.
+fn parse_ttl(text: &str) -> Option<u32> {
+    if let Ok(seconds) = text.parse() {
+        return Some(seconds);
+    }
+    let mut total: u32 = 0;
+    let mut number: Option<u32> = None;
+    for c in text.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            number = Some(number.unwrap_or(0).checked_mul(10)?.checked_add(digit)?);
+        } else {
+            let unit = match c.to_ascii_lowercase() {
+                's' => 1,
+                'm' => 60,
+                'h' => 3600,
+                'd' => 86400,
+                'w' => 604800,
+                _ => return None,
+            };
+            total = total.checked_add(number.take()?.checked_mul(unit)?)?;
+        }
+    }
+    if number.is_some() {
+        return None;
+    }
+    Some(total)
+}
+
+fn is_class(text: &str) -> bool {
+    matches!(
+        text.to_ascii_uppercase().as_str(),
+        "IN" | "CH" | "HS" | "CS"
+    )
+}
+
+/// Parse a zone file into records.
+///
+/// `origin` is the initial `$ORIGIN`, used until the file sets one;
+/// relative names without any origin are an error.
+pub fn parse(text: &str, origin: Option<&str>) -> Result<Vec<Record>, ZoneError> {
+    let mut origin = origin.map(|origin| match origin.ends_with('.') {
+        true => origin.to_string(),
+        false => format!("{}.", origin),
+    });
+    let mut default_ttl = None;
+    let mut last_ttl = None;
+    let mut last_owner: Option<String> = None;
+    let mut records = Vec::new();
+
+    for entry in entries(text)? {
+        let line = entry.line;
+        let fail = |message: String| error(line, message);
+        let mut tokens = entry.tokens.iter();
+
+        let first = &entry.tokens[0];
+        if !entry.indented && !first.quoted && first.text.starts_with('$') {
+            let argument = entry.tokens.get(1).map(|token| token.text.as_str());
+            match (first.text.to_ascii_uppercase().as_str(), argument) {
+                ("$ORIGIN", Some(name)) => {
+                    origin = Some(absolute(name, origin.as_deref()).map_err(fail)?)
+                }
+                ("$TTL", Some(ttl)) => {
+                    default_ttl =
+                        Some(parse_ttl(ttl).ok_or_else(|| fail(format!("invalid TTL {:?}", ttl)))?)
+                }
+                (directive, _) => return Err(fail(format!("unsupported directive {}", directive))),
+            }
+            continue;
+        }
+
+        let owner = if entry.indented {
+            last_owner
+                .clone()
+                .ok_or_else(|| fail("no previous owner name".to_string()))?
+        } else {
+            let token = tokens.next().expect("entries are non-empty");
+            name(&token.text, origin.as_deref()).map_err(fail)?
+        };
+        last_owner = Some(owner.clone());
+
+        let mut ttl = None;
+        let mut record_type = None;
+        for token in tokens.by_ref() {
+            if let Some(value) = parse_ttl(&token.text).filter(|_| ttl.is_none()) {
+                ttl = Some(value);
+            } else if is_class(&token.text) {
+                if !token.text.eq_ignore_ascii_case("IN") {
+                    return Err(fail(format!("unsupported class {}", token.text)));
+                }
+            } else {
+                record_type = Some(token.text.to_ascii_uppercase());
+                break;
+            }
+        }
+        let record_type = record_type.ok_or_else(|| fail("missing record type".to_string()))?;
+        if ttl.is_some() {
+            last_ttl = ttl;
+        }
+        let ttl = ttl.or(default_ttl).or(last_ttl).unwrap_or(DEFAULT_TTL);
+
+        let rdata: Vec<&Token> = tokens.collect();
+        let data = record_data(&record_type, &rdata, origin.as_deref()).map_err(fail)?;
+        records.push(Record {
+            name: owner,
+            ttl,
+            data,
+        });
+    }
+
+    Ok(records)
+}
+
+/// Resolve `@` and relative names against the origin.
+fn name(text: &str, origin: Option<&str>) -> Result<String, String> {
+    if text == "@" {
+        return origin
+            .map(str::to_string)
+            .ok_or_else(|| "'@' used without an origin".to_string());
+    }
+    absolute(text, origin)
+}
+
+fn absolute(text: &str, origin: Option<&str>) -> Result<String, String> {
+    if text.ends_with('.') {
+        Ok(text.to_string())
+    } else {
+        match origin {
+            Some(".") => Ok(format!("{}.", text)),
+            Some(origin) => Ok(format!("{}.{}", text, origin)),
+            None => Err(format!("relative name {:?} without an origin", text)),
+        }
+    }
+}
+
+fn record_data(
+    record_type: &str,
+    rdata: &[&Token],
+    origin: Option<&str>,
+) -> Result<RecordData, String> {
+    let field = |i: usize| -> Result<&str, String> {
+        rdata
+            .get(i)
+            .map(|token| token.text.as_str())
+            .ok_or_else(|| format!("{} record is missing fields", record_type))
+    };
+    let number = |i: usize| -> Result<u64, String> {
+        let text = field(i)?;
+        text.parse()
+            .map_err(|_| format!("invalid number {:?}", text))
+    };
+    let small = |i: usize, max: u64| -> Result<u64, String> {
+        let value = number(i)?;
+        if value > max {
+            return Err(format!("{} is out of range", value));
+        }
+        Ok(value)
+    };
+    let ttl = |i: usize| -> Result<u32, String> {
+        let text = field(i)?;
+        parse_ttl(text).ok_or_else(|| format!("invalid time {:?}", text))
+    };
+    let target = |i: usize| -> Result<String, String> { name(field(i)?, origin) };
+    let expect_fields = |count: usize| -> Result<(), String> {
+        if rdata.len() > count {
+            return Err(format!("{} record has too many fields", record_type));
+        }
+        Ok(())
+    };
+
+    let data = match record_type {
+        "A" => {
+            expect_fields(1)?;
+            let text = field(0)?;
+            RecordData::A(
+                text.parse::<Ipv4Addr>()
+                    .map_err(|_| format!("invalid IPv4 address {:?}", text))?,
+            )
+        }
+        "AAAA" => {
+            expect_fields(1)?;
+            let text = field(0)?;
+            RecordData::Aaaa(
+                text.parse::<Ipv6Addr>()
+                    .map_err(|_| format!("invalid IPv6 address {:?}", text))?,
+            )
+        }
+        "CNAME" => {
+            expect_fields(1)?;
+            RecordData::Cname(target(0)?)
+        }
+        "NS" => {
+            expect_fields(1)?;
+            RecordData::Ns(target(0)?)
+        }
+        "PTR" => {
+            expect_fields(1)?;
+            RecordData::Ptr(target(0)?)
+        }
+        "MX" => {
+            expect_fields(2)?;
+            RecordData::Mx(Mx {
+                preference: small(0, u16::MAX as u64)? as u16,
+                exchange: target(1)?,
+            })
+        }
+        "SOA" => {
+            expect_fields(7)?;
+            RecordData::Soa(Soa {
+                mname: target(0)?,
+                rname: target(1)?,
+                serial: small(2, u32::MAX as u64)? as u32,
+                refresh: ttl(3)?,
+                retry: ttl(4)?,
+                expire: ttl(5)?,
+                minimum: ttl(6)?,
+            })
+        }
+        "SRV" => {
+            expect_fields(4)?;
+            RecordData::Srv(Srv {
+                priority: small(0, u16::MAX as u64)? as u16,
+                weight: small(1, u16::MAX as u64)? as u16,
+                port: small(2, u16::MAX as u64)? as u16,
+                target: target(3)?,
+            })
+        }
+        "TXT" => {
+            if rdata.is_empty() {
+                return Err("TXT record is missing fields".to_string());
+            }
+            RecordData::Txt(rdata.iter().map(|token| token.text.as_str()).collect())
+        }
+        "CAA" => {
+            expect_fields(3)?;
+            RecordData::Caa(Caa {
+                critical: small(0, u8::MAX as u64)? & 0x80 != 0,
+                tag: field(1)?.to_string(),
+                value: field(2)?.to_string(),
+            })
+        }
+        "TLSA" => {
+            field(3)?;
+            let hex: String = rdata[3..].iter().map(|token| token.text.as_str()).collect();
+            RecordData::Tlsa(Tlsa {
+                cert_usage: small(0, u8::MAX as u64)? as u8,
+                selector: small(1, u8::MAX as u64)? as u8,
+                matching_type: small(2, u8::MAX as u64)? as u8,
+                data: StdHex::decode(&hex).map_err(|e| format!("invalid TLSA data: {}", e))?,
+            })
+        }
+        "SVCB" | "HTTPS" => {
+            let svcb = Svcb {
+                priority: small(0, u16::MAX as u64)? as u16,
+                target: target(1)?,
+                params: rdata[2..]
+                    .iter()
+                    .map(|token| svc_param(&token.text))
+                    .collect::<Result<_, _>>()?,
+            };
+            if record_type == "SVCB" {
+                RecordData::Svcb(svcb)
+            } else {
+                RecordData::Https(svcb)
+            }
+        }
+        other => return Err(format!("unsupported record type {}", other)),
+    };
+    Ok(data)
+}
+
+fn svc_key(name: &str) -> Result<u16, String> {
+    Ok(match name {
+        "mandatory" => 0,
+        "alpn" => 1,
+        "no-default-alpn" => 2,
+        "port" => 3,
+        "ipv4hint" => 4,
+        "ech" => 5,
+        "ipv6hint" => 6,
+        _ => name
+            .strip_prefix("key")
+            .and_then(|number| number.parse().ok())
+            .ok_or_else(|| format!("unknown SvcParamKey {:?}", name))?,
+    })
+}
+
+/// Parse a `key=value` service parameter (RFC 9460 section 2.1).
+fn svc_param(text: &str) -> Result<SvcParam, String> {
+    let (key, value) = match text.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (text, None),
+    };
+    let list = || -> Result<Vec<&str>, String> {
+        value
+            .filter(|value| !value.is_empty())
+            .map(|value| value.split(',').collect())
+            .ok_or_else(|| format!("{} needs a value", key))
+    };
+    let addresses = |kind: &str| format!("invalid {} address in {}", kind, key);
+
+    let key_number = svc_key(key)?;
+    Ok(match key_number {
+        0 => SvcParam::Mandatory(list()?.into_iter().map(svc_key).collect::<Result<_, _>>()?),
+        1 => SvcParam::Alpn(list()?.into_iter().map(str::to_string).collect()),
+        2 if value.is_none() => SvcParam::NoDefaultAlpn,
+        2 => return Err("no-default-alpn takes no value".to_string()),
+        3 => SvcParam::Port(
+            value
+                .and_then(|port| port.parse().ok())
+                .ok_or_else(|| "invalid port".to_string())?,
+        ),
+        4 => SvcParam::Ipv4Hint(
+            list()?
+                .into_iter()
+                .map(|a| a.parse().map_err(|_| addresses("IPv4")))
+                .collect::<Result<_, _>>()?,
+        ),
+        5 => SvcParam::Ech(
+            StdBase64::decode(value.ok_or_else(|| "ech needs a value".to_string())?)
+                .map_err(|e| format!("invalid ech: {}", e))?,
+        ),
+        6 => SvcParam::Ipv6Hint(
+            list()?
+                .into_iter()
+                .map(|a| a.parse().map_err(|_| addresses("IPv6")))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => SvcParam::Unknown(key_number, value.unwrap_or("").as_bytes().to_vec()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZONE: &str = r#"
+$ORIGIN example.com.
+$TTL 1h
+@   IN  SOA ns1 hostmaster (
+            2024010101 ; serial
+            2h         ; refresh
+            1h         ; retry
+            2w         ; expire
+            300 )      ; negative TTL
+    IN  NS  ns1
+    IN  NS  ns2.example.net.
+    IN  MX  10 mail
+    IN  TXT "v=spf1 " "mx -all"
+    IN  CAA 128 issue "letsencrypt.org"
+ns1 300 IN A 192.0.2.53
+www IN 60 A 192.0.2.1
+        AAAA 2001:db8::1
+api     CNAME www
+_imaps._tcp SRV 0 5 993 mail
+_443._tcp.www TLSA 3 1 1 ( abcdef
+                           0123 )
+@   HTTPS 1 . alpn=h2,h3 port=8443 ipv4hint=192.0.2.1 key667="hi"
+"#;
+
+    fn find<'a>(records: &'a [Record], name: &str, index: usize) -> &'a Record {
+        records
+            .iter()
+            .filter(|record| record.name == name)
+            .nth(index)
+            .unwrap_or_else(|| panic!("no record {} for {}", index, name))
+    }
+
+    #[test]
+    fn parses_a_zone() {
+        let records = parse(ZONE, None).unwrap();
+        assert_eq!(records.len(), 13);
+
+        let soa = find(&records, "example.com.", 0);
+        assert_eq!(soa.ttl, 3600);
+        assert_eq!(
+            soa.data,
+            RecordData::Soa(Soa {
+                mname: "ns1.example.com.".to_string(),
+                rname: "hostmaster.example.com.".to_string(),
+                serial: 2024010101,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1209600,
+                minimum: 300,
+            })
+        );
+        assert_eq!(
+            find(&records, "example.com.", 2).data,
+            RecordData::Ns("ns2.example.net.".to_string())
+        );
+        assert_eq!(
+            find(&records, "example.com.", 4).data,
+            RecordData::Txt("v=spf1 mx -all".to_string())
+        );
+        assert_eq!(
+            find(&records, "example.com.", 5).data,
+            RecordData::Caa(Caa {
+                critical: true,
+                tag: "issue".to_string(),
+                value: "letsencrypt.org".to_string(),
+            })
+        );
+
+        assert_eq!(find(&records, "ns1.example.com.", 0).ttl, 300);
+        let www = find(&records, "www.example.com.", 1);
+        // $TTL applies once set; the previous explicit TTL does not carry over.
+        assert_eq!(www.ttl, 3600);
+        assert_eq!(www.data, RecordData::Aaaa("2001:db8::1".parse().unwrap()));
+        assert_eq!(
+            find(&records, "api.example.com.", 0).data,
+            RecordData::Cname("www.example.com.".to_string())
+        );
+        assert_eq!(
+            find(&records, "_imaps._tcp.example.com.", 0).data,
+            RecordData::Srv(Srv {
+                priority: 0,
+                weight: 5,
+                port: 993,
+                target: "mail.example.com.".to_string(),
+            })
+        );
+        assert_eq!(
+            find(&records, "_443._tcp.www.example.com.", 0).data,
+            RecordData::Tlsa(Tlsa {
+                cert_usage: 3,
+                selector: 1,
+                matching_type: 1,
+                data: vec![0xab, 0xcd, 0xef, 0x01, 0x23],
+            })
+        );
+        assert_eq!(
+            find(&records, "example.com.", 6).data,
+            RecordData::Https(Svcb {
+                priority: 1,
+                target: ".".to_string(),
+                params: vec![
+                    SvcParam::Alpn(vec!["h2".to_string(), "h3".to_string()]),
+                    SvcParam::Port(8443),
+                    SvcParam::Ipv4Hint(vec![Ipv4Addr::new(192, 0, 2, 1)]),
+                    SvcParam::Unknown(667, b"hi".to_vec()),
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn uses_the_initial_origin() {
+        let records = parse("www A 192.0.2.1\n", Some("example.org")).unwrap();
+        assert_eq!(records[0].name, "www.example.org.");
+        assert_eq!(records[0].ttl, DEFAULT_TTL);
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        let err = parse("$ORIGIN example.com.\n\nwww A 192.0.2.300\n", None).unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("IPv4"), "{}", err);
+
+        assert_eq!(parse("www A 192.0.2.1\n", None).unwrap_err().line, 1);
+        assert_eq!(
+            parse("@ SOA a b (1 2 3 4 5\n", Some("x."))
+                .unwrap_err()
+                .message,
+            "unbalanced '('"
+        );
+        assert!(
+            parse("a. 60 IN WKS 1\n", None)
+                .unwrap_err()
+                .message
+                .contains("WKS")
+        );
+        assert!(parse("$INCLUDE other.zone\n", None).is_err());
+    }
+
+    #[test]
+    fn parses_ttl_units() {
+        assert_eq!(parse_ttl("300"), Some(300));
+        assert_eq!(parse_ttl("1h30m"), Some(5400));
+        assert_eq!(parse_ttl("1W"), Some(604800));
+        assert_eq!(parse_ttl("h"), None);
+        assert_eq!(parse_ttl("5x"), None);
+        assert_eq!(parse_ttl("10m5"), None);
+    }
+}
@@ -0,0 +1,8 @@
+fn main() {
+    if std::env::var_os("CARGO_FEATURE_RSA_SIGNING").is_some() {
+        println!(
+            "cargo:warning=the `rsa-signing` feature is enabled; RSA \
+             private-key operations are not constant time (RUSTSEC-2023-0071, the Marvin attack)"
+        );
+    }
+}
@@ -1,6 +1,17 @@
 //! Native implementation of portals-crypto using RustCrypto.
 
-use portals_crypto::{Cipher, CryptoError, Hash, Hmac, Kdf, Signature};
+mod keys;
+mod otp;
+mod password;
+
+pub use keys::{symmetric_key_from_jwk, symmetric_key_to_jwk};
+pub use otp::{Hotp, OtpAlgorithm, Totp};
+pub use password::{Argon2idHasher, BcryptHasher, ScryptHasher};
+
+use portals_crypto::{
+    Cipher, CryptoError, Hash, Hkdf, Hmac, JwsAlgorithm, Kdf, KeyAgreement, KeyEncoding, KeyPair,
+    KeyScheme, PublicKey, SecretKey, Signature, Zeroizing,
+};
 
 // ============================================================================
 // Hashing
@@ -50,10 +61,140 @@ impl Hash for Sha512 {
     }
 }
 
+/// SHA-384 hash.
+pub struct Sha384(sha2::Sha384);
+
+impl Hash for Sha384 {
+    const OUTPUT_SIZE: usize = 48;
+
+    fn new() -> Self {
+        use sha2::Digest;
+        Self(sha2::Sha384::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use sha2::Digest;
+        self.0.finalize().to_vec()
+    }
+}
+
+/// SHA3-256 hash.
+pub struct Sha3_256(sha3::Sha3_256);
+
+impl Hash for Sha3_256 {
+    const OUTPUT_SIZE: usize = 32;
+
+    fn new() -> Self {
+        use sha3::Digest;
+        Self(sha3::Sha3_256::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha3::Digest;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use sha3::Digest;
+        self.0.finalize().to_vec()
+    }
+}
+
+/// SHA3-512 hash.
+pub struct Sha3_512(sha3::Sha3_512);
+
+impl Hash for Sha3_512 {
+    const OUTPUT_SIZE: usize = 64;
+
+    fn new() -> Self {
+        use sha3::Digest;
+        Self(sha3::Sha3_512::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha3::Digest;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use sha3::Digest;
+        self.0.finalize().to_vec()
+    }
+}
+
+/// BLAKE2b hash with a 64-byte output.
+pub struct Blake2b(blake2::Blake2b512);
+
+impl Hash for Blake2b {
+    const OUTPUT_SIZE: usize = 64;
+
+    fn new() -> Self {
+        use blake2::Digest;
+        Self(blake2::Blake2b512::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use blake2::Digest;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use blake2::Digest;
+        self.0.finalize().to_vec()
+    }
+}
+
+/// BLAKE3 hash with a 32-byte output.
+pub struct Blake3(blake3::Hasher);
+
+impl Hash for Blake3 {
+    const OUTPUT_SIZE: usize = 32;
+
+    fn new() -> Self {
+        Self(blake3::Hasher::new())
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.0.finalize().as_bytes().to_vec()
+    }
+}
+
 // ============================================================================
 // HMAC
 // ============================================================================
 
+/// HMAC-SHA1.
+///
+/// SHA-1 is broken for collisions but HMAC-SHA1 is still sound; it is here
+/// for protocols that require it, such as HOTP and TOTP.
+pub struct HmacSha1(hmac::Hmac<sha1::Sha1>);
+
+impl Hmac for HmacSha1 {
+    fn new(key: &[u8]) -> Self {
+        use hmac::Mac;
+        Self(hmac::Hmac::new_from_slice(key).expect("HMAC can take any size key"))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use hmac::Mac;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use hmac::Mac;
+        self.0.finalize().into_bytes().to_vec()
+    }
+}
+
 /// HMAC-SHA256.
 pub struct HmacSha256(hmac::Hmac<sha2::Sha256>);
 
@@ -74,6 +215,26 @@ impl Hmac for HmacSha256 {
     }
 }
 
+/// HMAC-SHA512.
+pub struct HmacSha512(hmac::Hmac<sha2::Sha512>);
+
+impl Hmac for HmacSha512 {
+    fn new(key: &[u8]) -> Self {
+        use hmac::Mac;
+        Self(hmac::Hmac::new_from_slice(key).expect("HMAC can take any size key"))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use hmac::Mac;
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        use hmac::Mac;
+        self.0.finalize().into_bytes().to_vec()
+    }
+}
+
 // ============================================================================
 // Symmetric Encryption
 // ============================================================================
@@ -86,8 +247,16 @@ impl Cipher for Aes256Gcm {
     const NONCE_SIZE: usize = 12;
     const TAG_SIZE: usize = 16;
 
-    fn encrypt(key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        use aes_gcm::{aead::{Aead, Payload}, Aes256Gcm as AesGcm, KeyInit, Nonce};
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use aes_gcm::{
+            Aes256Gcm as AesGcm, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
 
         if key.len() != Self::KEY_SIZE {
             return Err(CryptoError::InvalidKeySize);
@@ -98,15 +267,26 @@ impl Cipher for Aes256Gcm {
 
         let cipher = AesGcm::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
         let nonce = Nonce::from_slice(nonce);
-        let payload = Payload { msg: plaintext, aad };
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
 
         cipher
             .encrypt(nonce, payload)
             .map_err(|_| CryptoError::AuthenticationFailed)
     }
 
-    fn decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        use aes_gcm::{aead::{Aead, Payload}, Aes256Gcm as AesGcm, KeyInit, Nonce};
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use aes_gcm::{
+            Aes256Gcm as AesGcm, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
 
         if key.len() != Self::KEY_SIZE {
             return Err(CryptoError::InvalidKeySize);
@@ -117,7 +297,10 @@ impl Cipher for Aes256Gcm {
 
         let cipher = AesGcm::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
         let nonce = Nonce::from_slice(nonce);
-        let payload = Payload { msg: ciphertext, aad };
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
 
         cipher
             .decrypt(nonce, payload)
@@ -133,8 +316,16 @@ impl Cipher for ChaCha20Poly1305 {
     const NONCE_SIZE: usize = 12;
     const TAG_SIZE: usize = 16;
 
-    fn encrypt(key: &[u8], nonce: &[u8], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        use chacha20poly1305::{aead::{Aead, Payload}, ChaCha20Poly1305 as ChaCha, KeyInit, Nonce};
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use chacha20poly1305::{
+            ChaCha20Poly1305 as ChaCha, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
 
         if key.len() != Self::KEY_SIZE {
             return Err(CryptoError::InvalidKeySize);
@@ -145,15 +336,26 @@ impl Cipher for ChaCha20Poly1305 {
 
         let cipher = ChaCha::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
         let nonce = Nonce::from_slice(nonce);
-        let payload = Payload { msg: plaintext, aad };
+        let payload = Payload {
+            msg: plaintext,
+            aad,
+        };
 
         cipher
             .encrypt(nonce, payload)
             .map_err(|_| CryptoError::AuthenticationFailed)
     }
 
-    fn decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        use chacha20poly1305::{aead::{Aead, Payload}, ChaCha20Poly1305 as ChaCha, KeyInit, Nonce};
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use chacha20poly1305::{
+            ChaCha20Poly1305 as ChaCha, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
 
         if key.len() != Self::KEY_SIZE {
             return Err(CryptoError::InvalidKeySize);
@@ -164,7 +366,10 @@ impl Cipher for ChaCha20Poly1305 {
 
         let cipher = ChaCha::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
         let nonce = Nonce::from_slice(nonce);
-        let payload = Payload { msg: ciphertext, aad };
+        let payload = Payload {
+            msg: ciphertext,
+            aad,
+        };
 
         cipher
             .decrypt(nonce, payload)
@@ -176,24 +381,61 @@ impl Cipher for ChaCha20Poly1305 {
 // Signatures
 // ============================================================================
 
+/// Check that key bytes have the expected length.
+fn check_size(bytes: &[u8], size: usize) -> Result<(), CryptoError> {
+    if bytes.len() == size {
+        Ok(())
+    } else {
+        Err(CryptoError::InvalidKeySize)
+    }
+}
+
 /// Ed25519 signatures.
 pub struct Ed25519;
 
+impl Ed25519 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 32;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+
+    /// The signature size in bytes.
+    pub const SIGNATURE_SIZE: usize = 64;
+}
+
+impl JwsAlgorithm for Ed25519 {
+    const ALG: &'static str = "EdDSA";
+}
+
+impl KeyScheme for Ed25519 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)
+    }
+}
+
 impl Signature for Ed25519 {
-    const PUBLIC_KEY_SIZE: usize = 32;
-    const SECRET_KEY_SIZE: usize = 32;
-    const SIGNATURE_SIZE: usize = 64;
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        Self::check_public_key(public_key)?;
+        Ok(Self::SIGNATURE_SIZE)
+    }
 
-    fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+    fn generate_keypair() -> KeyPair<Self> {
         use ed25519_dalek::SigningKey;
         use rand::rngs::OsRng;
 
         let signing_key = SigningKey::generate(&mut OsRng);
         let verifying_key = signing_key.verifying_key();
 
-        (
-            verifying_key.to_bytes().to_vec(),
-            signing_key.to_bytes().to_vec(),
+        KeyPair::new(
+            PublicKey::from_bytes(verifying_key.as_bytes())
+                .expect("Ed25519 public keys are 32 bytes"),
+            SecretKey::from_bytes(Zeroizing::new(signing_key.to_bytes()).as_slice())
+                .expect("Ed25519 secret keys are 32 bytes"),
         )
     }
 
@@ -227,6 +469,352 @@ impl Signature for Ed25519 {
     }
 }
 
+/// ECDSA over NIST P-256 with SHA-256 (ES256).
+///
+/// Public keys are SEC1 uncompressed points (65 bytes), secret keys are
+/// 32-byte scalars, and signatures are fixed-size `r || s` (64 bytes), as
+/// used by JWS. Signing is deterministic (RFC 6979).
+pub struct EcdsaP256;
+
+impl EcdsaP256 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 65;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+
+    /// The signature size in bytes.
+    pub const SIGNATURE_SIZE: usize = 64;
+}
+
+impl JwsAlgorithm for EcdsaP256 {
+    const ALG: &'static str = "ES256";
+}
+
+impl KeyScheme for EcdsaP256 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)?;
+        p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)?;
+        p256::ecdsa::SigningKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+}
+
+impl Signature for EcdsaP256 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        Self::check_public_key(public_key)?;
+        Ok(Self::SIGNATURE_SIZE)
+    }
+
+    fn generate_keypair() -> KeyPair<Self> {
+        use p256::ecdsa::SigningKey;
+        use rand::rngs::OsRng;
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+
+        KeyPair::new(
+            PublicKey::from_bytes(verifying_key.to_encoded_point(false).as_bytes())
+                .expect("uncompressed P-256 points are 65 bytes"),
+            SecretKey::from_bytes(&signing_key.to_bytes()).expect("P-256 scalars are 32 bytes"),
+        )
+    }
+
+    fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        use p256::ecdsa::{Signature as EcSig, SigningKey, signature::Signer};
+
+        let signing_key =
+            SigningKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        let signature: EcSig = signing_key.sign(message);
+
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        use p256::ecdsa::{Signature as EcSig, VerifyingKey, signature::Verifier};
+
+        let verifying_key =
+            VerifyingKey::from_sec1_bytes(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        let sig = EcSig::from_slice(signature).map_err(|_| CryptoError::InvalidSignature)?;
+
+        Ok(verifying_key.verify(message, &sig).is_ok())
+    }
+}
+
+/// ECDSA over secp256k1 with SHA-256 (ES256K).
+///
+/// Keys and signatures are encoded as for [`EcdsaP256`]. Signing is
+/// deterministic (RFC 6979) and produces low-S signatures; verification
+/// rejects high-S signatures, which are malleable.
+pub struct EcdsaSecp256k1;
+
+impl EcdsaSecp256k1 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 65;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+
+    /// The signature size in bytes.
+    pub const SIGNATURE_SIZE: usize = 64;
+}
+
+impl JwsAlgorithm for EcdsaSecp256k1 {
+    const ALG: &'static str = "ES256K";
+}
+
+impl KeyScheme for EcdsaSecp256k1 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)?;
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)?;
+        k256::ecdsa::SigningKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+}
+
+impl Signature for EcdsaSecp256k1 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        Self::check_public_key(public_key)?;
+        Ok(Self::SIGNATURE_SIZE)
+    }
+
+    fn generate_keypair() -> KeyPair<Self> {
+        use k256::ecdsa::SigningKey;
+        use rand::rngs::OsRng;
+
+        let signing_key = SigningKey::random(&mut OsRng);
+        let verifying_key = signing_key.verifying_key();
+
+        KeyPair::new(
+            PublicKey::from_bytes(verifying_key.to_encoded_point(false).as_bytes())
+                .expect("uncompressed secp256k1 points are 65 bytes"),
+            SecretKey::from_bytes(&signing_key.to_bytes()).expect("secp256k1 scalars are 32 bytes"),
+        )
+    }
+
+    fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        use k256::ecdsa::{Signature as EcSig, SigningKey, signature::Signer};
+
+        let signing_key =
+            SigningKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        let signature: EcSig = signing_key.sign(message);
+
+        Ok(signature.to_bytes().to_vec())
+    }
+
+    fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        use k256::ecdsa::{Signature as EcSig, VerifyingKey, signature::Verifier};
+
+        let verifying_key =
+            VerifyingKey::from_sec1_bytes(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        let sig = EcSig::from_slice(signature).map_err(|_| CryptoError::InvalidSignature)?;
+
+        Ok(verifying_key.verify(message, &sig).is_ok())
+    }
+}
+
+/// Smallest RSA modulus accepted, in bits.
+const RSA_MIN_BITS: usize = 2048;
+
+/// Modulus size of generated RSA keys, in bits.
+#[cfg(feature = "rsa-signing")]
+const RSA_GENERATED_BITS: usize = 2048;
+
+fn rsa_public_key(public_key: &[u8]) -> Result<rsa::RsaPublicKey, CryptoError> {
+    use rsa::pkcs1::DecodeRsaPublicKey;
+    use rsa::traits::PublicKeyParts;
+
+    let key =
+        rsa::RsaPublicKey::from_pkcs1_der(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+    if key.n().bits() < RSA_MIN_BITS {
+        return Err(CryptoError::InvalidKeySize);
+    }
+    Ok(key)
+}
+
+/// Why RSA secret keys are refused without the `rsa-signing` feature.
+#[cfg(not(feature = "rsa-signing"))]
+const RSA_SIGNING_DISABLED: &str = "RSA signing is disabled: the `rsa` crate's private-key \
+     operations leak timing (RUSTSEC-2023-0071); enable the `rsa-signing` feature to accept this";
+
+#[cfg(feature = "rsa-signing")]
+fn rsa_private_key(secret_key: &[u8]) -> Result<rsa::RsaPrivateKey, CryptoError> {
+    use rsa::pkcs1::DecodeRsaPrivateKey;
+    use rsa::traits::PublicKeyParts;
+
+    let key =
+        rsa::RsaPrivateKey::from_pkcs1_der(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+    if key.n().bits() < RSA_MIN_BITS {
+        return Err(CryptoError::InvalidKeySize);
+    }
+    key.validate().map_err(|_| CryptoError::InvalidKeySize)?;
+    Ok(key)
+}
+
+#[cfg(not(feature = "rsa-signing"))]
+fn rsa_private_key(_secret_key: &[u8]) -> Result<rsa::RsaPrivateKey, CryptoError> {
+    Err(CryptoError::Other(RSA_SIGNING_DISABLED.to_string()))
+}
+
+#[cfg(feature = "rsa-signing")]
+fn rsa_generate_keypair<S: Signature>() -> KeyPair<S> {
+    use rand::rngs::OsRng;
+    use rsa::pkcs1::{EncodeRsaPrivateKey, EncodeRsaPublicKey};
+
+    let private =
+        rsa::RsaPrivateKey::new(&mut OsRng, RSA_GENERATED_BITS).expect("RSA key generation failed");
+    let public = private
+        .to_public_key()
+        .to_pkcs1_der()
+        .expect("RSA public keys encode");
+    let secret = private.to_pkcs1_der().expect("RSA private keys encode");
+
+    KeyPair::new(
+        PublicKey::from_bytes(public.as_bytes()).expect("generated RSA public keys are valid"),
+        SecretKey::from_bytes(secret.as_bytes()).expect("generated RSA private keys are valid"),
+    )
+}
+
+#[cfg(not(feature = "rsa-signing"))]
+fn rsa_generate_keypair<S: Signature>() -> KeyPair<S> {
+    panic!("{}", RSA_SIGNING_DISABLED)
+}
+
+fn rsa_signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+    use rsa::traits::PublicKeyParts;
+
+    Ok(rsa_public_key(public_key)?.size())
+}
+
+/// RSASSA-PKCS1-v1_5 with SHA-256 (RS256).
+///
+/// Public keys are PKCS#1 `RSAPublicKey` DER and secret keys are PKCS#1
+/// `RSAPrivateKey` DER, with moduli of at least 2048 bits. Signatures are
+/// as long as the modulus. Generated keys are 2048 bits.
+///
+/// # Signing is off by default
+///
+/// Signing goes through the `rsa` crate, whose private-key operations are
+/// not constant time (RUSTSEC-2023-0071, the Marvin attack): an attacker
+/// who can time many signatures may recover the key. Secret keys are
+/// therefore refused, and [`Signature::generate_keypair`] panics, unless
+/// the `rsa-signing` feature is enabled, which prints a build warning.
+/// Verification only touches the public key and is always available.
+/// Prefer [`Ed25519`] or [`EcdsaP256`] for anything this crate signs.
+pub struct RsaPkcs1v15Sha256;
+
+impl JwsAlgorithm for RsaPkcs1v15Sha256 {
+    const ALG: &'static str = "RS256";
+}
+
+impl KeyScheme for RsaPkcs1v15Sha256 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        rsa_public_key(public_key).map(drop)
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        rsa_private_key(secret_key).map(drop)
+    }
+}
+
+impl Signature for RsaPkcs1v15Sha256 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        rsa_signature_size(public_key)
+    }
+
+    fn generate_keypair() -> KeyPair<Self> {
+        rsa_generate_keypair()
+    }
+
+    fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        use rsa::pkcs1v15::SigningKey;
+        use rsa::signature::{SignatureEncoding, Signer};
+
+        let signing_key = SigningKey::<sha2::Sha256>::new(rsa_private_key(secret_key)?);
+        let signature = signing_key
+            .try_sign(message)
+            .map_err(|e| CryptoError::Other(e.to_string()))?;
+
+        Ok(signature.to_vec())
+    }
+
+    fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        use rsa::pkcs1v15::{Signature as RsaSig, VerifyingKey};
+        use rsa::signature::Verifier;
+
+        let verifying_key = VerifyingKey::<sha2::Sha256>::new(rsa_public_key(public_key)?);
+        let sig = RsaSig::try_from(signature).map_err(|_| CryptoError::InvalidSignature)?;
+
+        Ok(verifying_key.verify(message, &sig).is_ok())
+    }
+}
+
+/// RSASSA-PSS with SHA-256, MGF1-SHA-256 and a 32-byte salt (PS256).
+///
+/// Keys and signatures are encoded as for [`RsaPkcs1v15Sha256`], and
+/// signing is likewise behind the `rsa-signing` feature. Signing is
+/// randomized.
+pub struct RsaPssSha256;
+
+impl JwsAlgorithm for RsaPssSha256 {
+    const ALG: &'static str = "PS256";
+}
+
+impl KeyScheme for RsaPssSha256 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        rsa_public_key(public_key).map(drop)
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        rsa_private_key(secret_key).map(drop)
+    }
+}
+
+impl Signature for RsaPssSha256 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        rsa_signature_size(public_key)
+    }
+
+    fn generate_keypair() -> KeyPair<Self> {
+        rsa_generate_keypair()
+    }
+
+    fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        use rand::rngs::OsRng;
+        use rsa::pss::SigningKey;
+        use rsa::signature::{RandomizedSigner, SignatureEncoding};
+
+        let signing_key = SigningKey::<sha2::Sha256>::new(rsa_private_key(secret_key)?);
+        let signature = signing_key
+            .try_sign_with_rng(&mut OsRng, message)
+            .map_err(|e| CryptoError::Other(e.to_string()))?;
+
+        Ok(signature.to_vec())
+    }
+
+    fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        use rsa::pss::{Signature as RsaSig, VerifyingKey};
+        use rsa::signature::Verifier;
+
+        let verifying_key = VerifyingKey::<sha2::Sha256>::new(rsa_public_key(public_key)?);
+        let sig = RsaSig::try_from(signature).map_err(|_| CryptoError::InvalidSignature)?;
+
+        Ok(verifying_key.verify(message, &sig).is_ok())
+    }
+}
+
 // ============================================================================
 // Key Derivation
 // ============================================================================
@@ -246,9 +834,186 @@ impl Kdf for Argon2id {
     }
 }
 
+/// HKDF with HMAC-SHA256.
+pub struct HkdfSha256;
+
+impl Hkdf for HkdfSha256 {
+    const HASH_SIZE: usize = 32;
+
+    fn extract(salt: &[u8], ikm: &[u8]) -> Zeroizing<Vec<u8>> {
+        let (prk, _) = hkdf::Hkdf::<sha2::Sha256>::extract(Some(salt), ikm);
+        Zeroizing::new(prk.to_vec())
+    }
+
+    fn expand(
+        prk: &[u8],
+        info: &[u8],
+        output_len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let hkdf =
+            hkdf::Hkdf::<sha2::Sha256>::from_prk(prk).map_err(|_| CryptoError::InvalidKeySize)?;
+        let mut okm = Zeroizing::new(vec![0u8; output_len]);
+        hkdf.expand(info, &mut okm)
+            .map_err(|_| CryptoError::Other("HKDF output too long".to_string()))?;
+        Ok(okm)
+    }
+}
+
+/// HKDF with HMAC-SHA512.
+pub struct HkdfSha512;
+
+impl Hkdf for HkdfSha512 {
+    const HASH_SIZE: usize = 64;
+
+    fn extract(salt: &[u8], ikm: &[u8]) -> Zeroizing<Vec<u8>> {
+        let (prk, _) = hkdf::Hkdf::<sha2::Sha512>::extract(Some(salt), ikm);
+        Zeroizing::new(prk.to_vec())
+    }
+
+    fn expand(
+        prk: &[u8],
+        info: &[u8],
+        output_len: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let hkdf =
+            hkdf::Hkdf::<sha2::Sha512>::from_prk(prk).map_err(|_| CryptoError::InvalidKeySize)?;
+        let mut okm = Zeroizing::new(vec![0u8; output_len]);
+        hkdf.expand(info, &mut okm)
+            .map_err(|_| CryptoError::Other("HKDF output too long".to_string()))?;
+        Ok(okm)
+    }
+}
+
+// ============================================================================
+// Key Agreement
+// ============================================================================
+
+/// X25519 key agreement (RFC 7748).
+///
+/// Keys and shared secrets are 32 bytes. Agreement fails for low-order peer
+/// keys, which would make the shared secret all zeros.
+pub struct X25519;
+
+impl X25519 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 32;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+}
+
+impl KeyScheme for X25519 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)
+    }
+}
+
+impl KeyAgreement for X25519 {
+    const SHARED_SECRET_SIZE: usize = 32;
+
+    fn generate_keypair() -> KeyPair<Self> {
+        use rand::rngs::OsRng;
+
+        let secret = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+        let secret = SecretKey::from_bytes(&*Zeroizing::new(secret.to_bytes()))
+            .expect("generated X25519 keys are valid");
+        let public = Self::public_key(&secret).expect("generated X25519 keys are valid");
+        KeyPair::new(public, secret)
+    }
+
+    fn agree(
+        secret: &SecretKey<Self>,
+        peer: &PublicKey<Self>,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let secret = x25519_secret(secret)?;
+        let peer: [u8; 32] = peer
+            .as_bytes()
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        let shared = secret.diffie_hellman(&x25519_dalek::PublicKey::from(peer));
+        if !shared.was_contributory() {
+            return Err(CryptoError::InvalidSharedSecret);
+        }
+        Ok(Zeroizing::new(shared.as_bytes().to_vec()))
+    }
+}
+
+pub(crate) fn x25519_secret(
+    secret: &SecretKey<X25519>,
+) -> Result<x25519_dalek::StaticSecret, CryptoError> {
+    let bytes: Zeroizing<[u8; 32]> = Zeroizing::new(
+        secret
+            .as_bytes()
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeySize)?,
+    );
+    Ok(x25519_dalek::StaticSecret::from(*bytes))
+}
+
+/// ECDH over NIST P-256.
+///
+/// Public keys are SEC1 uncompressed points (65 bytes) and secret keys are
+/// 32-byte scalars, as for [`EcdsaP256`]. The shared secret is the 32-byte
+/// x-coordinate of the shared point.
+pub struct EcdhP256;
+
+impl EcdhP256 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 65;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+}
+
+impl KeyScheme for EcdhP256 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)?;
+        // Decoding checks that the point is on the curve.
+        p256::PublicKey::from_sec1_bytes(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)?;
+        p256::SecretKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+}
+
+impl KeyAgreement for EcdhP256 {
+    const SHARED_SECRET_SIZE: usize = 32;
+
+    fn generate_keypair() -> KeyPair<Self> {
+        use rand::rngs::OsRng;
+
+        let secret = p256::SecretKey::random(&mut OsRng);
+        let secret = SecretKey::from_bytes(&Zeroizing::new(secret.to_bytes().to_vec()))
+            .expect("generated P-256 keys are valid");
+        let public = Self::public_key(&secret).expect("generated P-256 keys are valid");
+        KeyPair::new(public, secret)
+    }
+
+    fn agree(
+        secret: &SecretKey<Self>,
+        peer: &PublicKey<Self>,
+    ) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let secret = p256::SecretKey::from_slice(secret.as_bytes())
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        let peer = p256::PublicKey::from_sec1_bytes(peer.as_bytes())
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        let shared = p256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), peer.as_affine());
+        Ok(Zeroizing::new(shared.raw_secret_bytes().to_vec()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use portals_testing::unhex;
 
     #[test]
     fn sha256_works() {
@@ -264,6 +1029,57 @@ mod tests {
         assert_eq!(result.len(), 32);
     }
 
+    #[test]
+    fn hashes_match_known_answers() {
+        fn hex(bytes: Vec<u8>) -> String {
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
+        }
+
+        assert_eq!(
+            hex(Sha384::hash(b"abc")),
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7"
+        );
+        assert_eq!(
+            hex(Sha3_256::hash(b"abc")),
+            "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+        );
+        assert_eq!(
+            hex(Sha3_512::hash(b"abc")),
+            "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0"
+        );
+        assert_eq!(
+            hex(Blake2b::hash(b"abc")),
+            "ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923"
+        );
+        assert_eq!(
+            hex(Blake3::hash(b"abc")),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+
+        // RFC 4231 test case 2.
+        let mut mac = HmacSha512::new(b"Jefe");
+        mac.update(b"what do ya ");
+        mac.update(b"want for nothing?");
+        assert_eq!(
+            hex(mac.finalize()),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"
+        );
+    }
+
+    #[test]
+    fn digest_hashes_while_copying() {
+        use portals_crypto_portable::Digest;
+        use portals_io::OutputStream;
+        use portals_io_native::WriterStream;
+
+        let mut digest = Digest::<Sha3_256, _>::tee(WriterStream::new(Vec::new()));
+        digest.write(b"a").unwrap();
+        digest.write(b"bc").unwrap();
+        let (hash, copy) = digest.finish().unwrap();
+        assert_eq!(hash, Sha3_256::hash(b"abc"));
+        assert_eq!(copy.into_inner(), b"abc");
+    }
+
     #[test]
     fn aes_gcm_roundtrip() {
         let key = [0u8; 32];
@@ -322,15 +1138,369 @@ mod tests {
 
     #[test]
     fn ed25519_sign_verify() {
-        let (public_key, secret_key) = Ed25519::generate_keypair();
+        let pair = Ed25519::generate_keypair();
         let message = b"hello world";
 
-        let signature = Ed25519::sign(&secret_key, message).unwrap();
-        let valid = Ed25519::verify(&public_key, message, &signature).unwrap();
+        let signature = pair.sign(message).unwrap();
+        let valid = Ed25519::verify(pair.public().as_bytes(), message, &signature).unwrap();
 
         assert!(valid);
     }
 
+    #[test]
+    fn ecdsa_p256_sign_verify() {
+        let pair = EcdsaP256::generate_keypair();
+        let message = b"hello world";
+
+        let signature = EcdsaP256::sign(pair.secret().as_bytes(), message).unwrap();
+        assert_eq!(signature.len(), EcdsaP256::SIGNATURE_SIZE);
+        assert!(pair.verify(message, &signature).unwrap());
+        assert!(!pair.verify(b"tampered", &signature).unwrap());
+    }
+
+    /// Left-pad or strip a big-endian integer to 32 bytes.
+    fn coordinate(hex: &str) -> Vec<u8> {
+        let bytes = unhex(hex);
+        let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+        let mut out = vec![0; 32 - (bytes.len() - start)];
+        out.extend_from_slice(&bytes[start..]);
+        out
+    }
+
+    /// Converts a DER signature to the scheme's form, and says whether the
+    /// scheme accepts that form at all, for policies stricter than
+    /// Wycheproof's.
+    type FromDer = fn(&[u8]) -> Option<(Vec<u8>, bool)>;
+
+    /// Run Wycheproof ECDSA vectors. Signatures are DER there; vectors
+    /// whose DER does not parse to a fixed-size signature must be invalid.
+    fn wycheproof<S: Signature>(data: &str, from_der: FromDer) {
+        let mut count = 0;
+        for line in data.lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let [x, y, message, signature, result] = fields[..] else {
+                panic!("bad vector: {}", line);
+            };
+            let mut public = vec![4];
+            public.extend(coordinate(x));
+            public.extend(coordinate(y));
+            let (accepted, expected) = match from_der(&unhex(signature)) {
+                Some((signature, allowed)) => (
+                    S::verify(&public, &unhex(message), &signature).unwrap(),
+                    result == "valid" && allowed,
+                ),
+                None => (false, result == "valid"),
+            };
+            assert_eq!(accepted, expected, "vector {}", line);
+            count += 1;
+        }
+        assert!(count > 300);
+    }
+
+    #[test]
+    fn ecdsa_p256_wycheproof() {
+        wycheproof::<EcdsaP256>(
+            include_str!("../testdata/wycheproof/ecdsa_secp256r1_sha256.csv"),
+            |der| {
+                p256::ecdsa::Signature::from_der(der)
+                    .ok()
+                    .map(|s| (s.to_vec(), true))
+            },
+        );
+    }
+
+    #[test]
+    fn ecdsa_secp256k1_wycheproof() {
+        wycheproof::<EcdsaSecp256k1>(
+            include_str!("../testdata/wycheproof/ecdsa_secp256k1_sha256.csv"),
+            // High-S signatures are malleable and rejected.
+            |der| {
+                k256::ecdsa::Signature::from_der(der)
+                    .ok()
+                    .map(|s| (s.to_vec(), s.normalize_s().is_none()))
+            },
+        );
+    }
+
+    #[test]
+    fn ecdsa_secp256k1_sign_verify() {
+        let pair = EcdsaSecp256k1::generate_keypair();
+        let signature = pair.sign(b"hello world").unwrap();
+        assert_eq!(signature.len(), EcdsaSecp256k1::SIGNATURE_SIZE);
+        assert_eq!(
+            EcdsaSecp256k1::signature_size(pair.public().as_bytes()).unwrap(),
+            signature.len()
+        );
+        assert!(pair.verify(b"hello world", &signature).unwrap());
+        assert!(!pair.verify(b"tampered", &signature).unwrap());
+
+        // Not a point on the curve.
+        let mut public = pair.public().as_bytes().to_vec();
+        public[64] ^= 1;
+        assert!(PublicKey::<EcdsaSecp256k1>::from_bytes(&public).is_err());
+    }
+
+    // The RFC 7520 section 3.4 RSA key and the section 4.1 RS256 example.
+    const RSA_SECRET: &[u8] = include_bytes!("../testdata/rsa/rfc7520-priv.der");
+    const RSA_PUBLIC: &[u8] = include_bytes!("../testdata/rsa/rfc7520-pub.der");
+    const RS256_INPUT: &[u8] = include_bytes!("../testdata/rsa/rfc7520-4.1-message.txt");
+    const RS256_SIGNATURE: &str = include_str!("../testdata/rsa/rfc7520-4.1-signature.hex");
+
+    #[test]
+    fn rsa_pkcs1v15_rfc7520_verifies() {
+        let signature = unhex(RS256_SIGNATURE);
+        assert_eq!(RsaPkcs1v15Sha256::signature_size(RSA_PUBLIC).unwrap(), 256);
+
+        let public = PublicKey::<RsaPkcs1v15Sha256>::from_bytes(RSA_PUBLIC).unwrap();
+        assert!(public.verify(RS256_INPUT, &signature).unwrap());
+        assert!(!public.verify(&RS256_INPUT[1..], &signature).unwrap());
+        let mut tampered = signature.clone();
+        tampered[100] ^= 1;
+        assert!(!public.verify(RS256_INPUT, &tampered).unwrap());
+        assert!(!public.verify(RS256_INPUT, &signature[1..]).unwrap_or(false));
+
+        // The padding schemes don't verify each other's signatures.
+        assert!(!RsaPssSha256::verify(RSA_PUBLIC, RS256_INPUT, &signature).unwrap());
+    }
+
+    #[cfg(feature = "rsa-signing")]
+    #[test]
+    fn rsa_pkcs1v15_rfc7520_signs() {
+        // PKCS#1 v1.5 signing is deterministic, so it reproduces the RFC.
+        let secret = SecretKey::<RsaPkcs1v15Sha256>::from_bytes(RSA_SECRET).unwrap();
+        assert_eq!(secret.sign(RS256_INPUT).unwrap(), unhex(RS256_SIGNATURE));
+    }
+
+    #[cfg(feature = "rsa-signing")]
+    #[test]
+    fn rsa_pss_sign_verify() {
+        let public = PublicKey::<RsaPssSha256>::from_bytes(RSA_PUBLIC).unwrap();
+        let secret = SecretKey::<RsaPssSha256>::from_bytes(RSA_SECRET).unwrap();
+        let first = secret.sign(b"hello world").unwrap();
+        let second = secret.sign(b"hello world").unwrap();
+        assert_ne!(first, second, "PSS signing is randomized");
+        assert!(public.verify(b"hello world", &first).unwrap());
+        assert!(public.verify(b"hello world", &second).unwrap());
+        assert!(!public.verify(b"hello world!", &first).unwrap());
+        assert!(!RsaPkcs1v15Sha256::verify(RSA_PUBLIC, b"hello world", &first).unwrap());
+    }
+
+    #[cfg(not(feature = "rsa-signing"))]
+    #[test]
+    fn rsa_signing_is_off_by_default() {
+        assert!(matches!(
+            SecretKey::<RsaPkcs1v15Sha256>::from_bytes(RSA_SECRET),
+            Err(CryptoError::Other(msg)) if msg.contains("RUSTSEC-2023-0071")
+        ));
+        assert!(RsaPssSha256::sign(RSA_SECRET, b"hello world").is_err());
+        assert!(std::panic::catch_unwind(RsaPkcs1v15Sha256::generate_keypair).is_err());
+    }
+
+    #[test]
+    fn rsa_rejects_weak_and_malformed_keys() {
+        let weak = include_bytes!("../testdata/rsa/rsa1024-pub.der");
+        assert!(PublicKey::<RsaPkcs1v15Sha256>::from_bytes(weak).is_err());
+        assert!(PublicKey::<RsaPssSha256>::from_bytes(&RSA_PUBLIC[1..]).is_err());
+        assert!(SecretKey::<RsaPssSha256>::from_bytes(RSA_PUBLIC).is_err());
+        assert!(PublicKey::<RsaPssSha256>::from_bytes(RSA_SECRET).is_err());
+    }
+
+    #[test]
+    fn keyring_signs_with_active_key() {
+        use portals_crypto_portable::Keyring;
+
+        let mut ring = Keyring::new("k1", Ed25519::generate_keypair());
+        let (kid, old_sig) = ring.sign(b"message").unwrap();
+        assert_eq!(kid, "k1");
+
+        ring.rotate("k2", Ed25519::generate_keypair()).unwrap();
+        let (kid, new_sig) = ring.sign(b"message").unwrap();
+        assert_eq!(kid, "k2");
+
+        let verifiers = ring.public_keys();
+        assert!(verifiers.verify("k1", b"message", &old_sig).unwrap());
+        assert!(verifiers.verify("k2", b"message", &new_sig).unwrap());
+        assert!(!verifiers.verify("k2", b"message", &old_sig).unwrap());
+        assert!(verifiers.verify("k3", b"message", &new_sig).is_err());
+    }
+
+    #[test]
+    fn seals_with_real_ciphers() {
+        use portals_crypto::SymmetricKey;
+        use portals_crypto_portable::{Keyring, Sealer};
+        use portals_random_native::OsRandom;
+
+        let key = SymmetricKey::<Aes256Gcm>::from_bytes(&[7u8; 32]).unwrap();
+        let sealer = Sealer::new(Keyring::new("primary", key), OsRandom).with_envelope(true);
+        let sealed = sealer.seal(b"secret", b"aad").unwrap();
+        assert_eq!(sealer.open(&sealed, b"aad").unwrap(), b"secret");
+
+        let key = SymmetricKey::<ChaCha20Poly1305>::from_bytes(&[7u8; 32]).unwrap();
+        let sealer = Sealer::new(Keyring::new("primary", key), OsRandom);
+        let sealed = sealer.seal(b"secret", b"").unwrap();
+        assert_eq!(sealer.open(&sealed, b"").unwrap(), b"secret");
+        assert!(sealer.open(&sealed[..sealed.len() - 1], b"").is_err());
+    }
+
+    #[test]
+    fn streams_with_real_ciphers() {
+        use portals_crypto::SymmetricKey;
+        use portals_crypto_portable::{StreamDecryptor, StreamEncryptor};
+        use portals_io::InputStream;
+        use portals_io_native::{ReaderStream, WriterStream};
+        use portals_random_native::OsRandom;
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let key = SymmetricKey::<ChaCha20Poly1305>::from_bytes(&[9u8; 32]).unwrap();
+        let mut enc = StreamEncryptor::new(&key, &OsRandom, WriterStream::new(Vec::new())).unwrap();
+        portals_io::OutputStream::write(&mut enc, &data).unwrap();
+        let ciphertext = enc.finish().unwrap().into_inner();
+
+        let mut dec = StreamDecryptor::new(&key, ReaderStream::new(ciphertext.as_slice()));
+        let mut out = Vec::new();
+        while let Ok(chunk) = dec.blocking_read(8192) {
+            out.extend(chunk);
+        }
+        assert_eq!(out, data);
+
+        let truncated = &ciphertext[..ciphertext.len() - 100];
+        let mut dec = StreamDecryptor::new(&key, ReaderStream::new(truncated));
+        let err = loop {
+            if let Err(e) = dec.blocking_read(8192) {
+                break e;
+            }
+        };
+        assert_ne!(err, portals_io::StreamError::Closed);
+    }
+
+    #[test]
+    fn hkdf_sha256_rfc5869() {
+        // RFC 5869 test case 1.
+        let ikm = [0x0b; 22];
+        let salt = unhex("000102030405060708090a0b0c");
+        let info = unhex("f0f1f2f3f4f5f6f7f8f9");
+        let prk = HkdfSha256::extract(&salt, &ikm);
+        assert_eq!(
+            *prk,
+            unhex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5")
+        );
+        let okm = HkdfSha256::expand(&prk, &info, 42).unwrap();
+        assert_eq!(
+            *okm,
+            unhex(
+                "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+            )
+        );
+        assert_eq!(*HkdfSha256::derive(&salt, &ikm, &info, 42).unwrap(), *okm);
+    }
+
+    #[test]
+    fn hkdf_limits() {
+        let prk = HkdfSha512::extract(b"", b"input");
+        assert_eq!(prk.len(), HkdfSha512::HASH_SIZE);
+        assert_eq!(
+            HkdfSha512::expand(&prk, b"", 255 * 64).unwrap().len(),
+            255 * 64
+        );
+        assert!(HkdfSha512::expand(&prk, b"", 255 * 64 + 1).is_err());
+        assert!(HkdfSha512::expand(&prk[..32], b"", 32).is_err());
+        assert_ne!(
+            *HkdfSha512::expand(&prk, b"a", 32).unwrap(),
+            *HkdfSha512::expand(&prk, b"b", 32).unwrap()
+        );
+    }
+
+    #[test]
+    fn x25519_rfc7748() {
+        let alice = SecretKey::<X25519>::from_bytes(&unhex(
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a",
+        ))
+        .unwrap();
+        let bob = SecretKey::<X25519>::from_bytes(&unhex(
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb",
+        ))
+        .unwrap();
+        let alice_public = X25519::public_key(&alice).unwrap();
+        let bob_public = X25519::public_key(&bob).unwrap();
+        assert_eq!(
+            alice_public.as_bytes(),
+            unhex("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            bob_public.as_bytes(),
+            unhex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f")
+        );
+
+        let shared = alice.agree(&bob_public).unwrap();
+        assert_eq!(
+            *shared,
+            unhex("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+        assert_eq!(*bob.agree(&alice_public).unwrap(), *shared);
+    }
+
+    #[test]
+    fn x25519_rejects_low_order_points() {
+        let pair = X25519::generate_keypair();
+        let zero = PublicKey::<X25519>::from_bytes(&[0; 32]).unwrap();
+        assert!(matches!(
+            pair.agree(&zero),
+            Err(CryptoError::InvalidSharedSecret)
+        ));
+        let mut one = [0; 32];
+        one[0] = 1;
+        let one = PublicKey::<X25519>::from_bytes(&one).unwrap();
+        assert!(matches!(
+            pair.agree(&one),
+            Err(CryptoError::InvalidSharedSecret)
+        ));
+        assert!(PublicKey::<X25519>::from_bytes(&[9; 31]).is_err());
+    }
+
+    #[test]
+    fn ecdh_p256_rfc5903() {
+        // RFC 5903 section 8.1.
+        let i = SecretKey::<EcdhP256>::from_bytes(&unhex(
+            "c88f01f510d9ac3f70a292daa2316de544e9aab8afe84049c62a9c57862d1433",
+        ))
+        .unwrap();
+        let r = SecretKey::<EcdhP256>::from_bytes(&unhex(
+            "c6ef9c5d78ae012a011164acb397ce2088685d8f06bf9be0b283ab46476bee53",
+        ))
+        .unwrap();
+        let gi = EcdhP256::public_key(&i).unwrap();
+        let gr = EcdhP256::public_key(&r).unwrap();
+        assert_eq!(
+            gi.as_bytes(),
+            unhex(
+                "04dad0b65394221cf9b051e1feca5787d098dfe637fc90b9ef945d0c37725811805271a0461cdb8252d61f1c456fa3e59ab1f45b33accf5f58389e0577b8990bb3"
+            )
+        );
+        assert_eq!(
+            gr.as_bytes(),
+            unhex(
+                "04d12dfb5289c8d4f81208b70270398c342296970a0bccb74c736fc7554494bf6356fbf3ca366cc23e8157854c13c58d6aac23f046ada30f8353e74f33039872ab"
+            )
+        );
+        let shared = i.agree(&gr).unwrap();
+        assert_eq!(
+            *shared,
+            unhex("d6840f6b42f6edafd13116e0e12565202fef8e9ece7dce03812464d04b9442de")
+        );
+        assert_eq!(*r.agree(&gi).unwrap(), *shared);
+    }
+
+    #[test]
+    fn ecdh_p256_rejects_invalid_points() {
+        let pair = EcdhP256::generate_keypair();
+        let mut public = pair.public().as_bytes().to_vec();
+        assert_eq!(public.len(), EcdhP256::PUBLIC_KEY_SIZE);
+        public[64] ^= 1;
+        assert!(PublicKey::<EcdhP256>::from_bytes(&public).is_err());
+        assert!(PublicKey::<EcdhP256>::from_bytes(&public[..33]).is_err());
+        assert!(SecretKey::<EcdhP256>::from_bytes(&[0; 32]).is_err());
+    }
+
     #[test]
     fn argon2_derives() {
         let password = b"password";
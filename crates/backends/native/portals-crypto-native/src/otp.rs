@@ -0,0 +1,423 @@
+//! One-time passwords: HOTP (RFC 4226) and TOTP (RFC 6238).
+
+use crate::{HmacSha1, HmacSha256, HmacSha512};
+use portals_clocks::WallClock;
+use portals_crypto::{CryptoError, Hmac, Zeroizing};
+use portals_encoding::{Base32, UrlEncoding};
+use portals_encoding_portable::{StdBase32, StdUrlEncoding};
+use portals_random::SecureRandom;
+
+/// Shortest accepted secret, in bytes (RFC 4226 section 4).
+const MIN_SECRET_SIZE: usize = 16;
+
+/// Largest accepted TOTP skew, in periods. Every step in the window is
+/// another code an attacker may guess.
+const MAX_SKEW: u64 = 10;
+
+/// The HMAC underlying a one-time password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OtpAlgorithm {
+    /// HMAC-SHA1, the RFC 4226 default and the only algorithm some
+    /// authenticator apps support.
+    #[default]
+    Sha1,
+    /// HMAC-SHA256.
+    Sha256,
+    /// HMAC-SHA512.
+    Sha512,
+}
+
+impl OtpAlgorithm {
+    /// The name used in `otpauth://` URIs.
+    pub fn name(self) -> &'static str {
+        match self {
+            OtpAlgorithm::Sha1 => "SHA1",
+            OtpAlgorithm::Sha256 => "SHA256",
+            OtpAlgorithm::Sha512 => "SHA512",
+        }
+    }
+
+    /// The recommended secret size in bytes: the hash output size.
+    pub fn secret_size(self) -> usize {
+        match self {
+            OtpAlgorithm::Sha1 => 20,
+            OtpAlgorithm::Sha256 => 32,
+            OtpAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Generate a random secret of the recommended size.
+    pub fn generate_secret(self, random: &impl SecureRandom) -> Zeroizing<Vec<u8>> {
+        let mut secret = Zeroizing::new(vec![0u8; self.secret_size()]);
+        random.fill(&mut secret);
+        secret
+    }
+
+    fn mac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        fn mac<H: Hmac>(key: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut mac = H::new(key);
+            mac.update(data);
+            mac.finalize()
+        }
+
+        match self {
+            OtpAlgorithm::Sha1 => mac::<HmacSha1>(key, data),
+            OtpAlgorithm::Sha256 => mac::<HmacSha256>(key, data),
+            OtpAlgorithm::Sha512 => mac::<HmacSha512>(key, data),
+        }
+    }
+}
+
+/// Compare codes without leaking where they differ.
+fn codes_equal(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Counter-based one-time passwords (HOTP, RFC 4226).
+///
+/// Defaults to SHA-1 and 6 digits, which every authenticator app accepts.
+#[derive(Clone)]
+pub struct Hotp {
+    secret: Zeroizing<Vec<u8>>,
+    algorithm: OtpAlgorithm,
+    digits: u32,
+}
+
+impl Hotp {
+    /// Fails with [`CryptoError::InvalidKeySize`] for secrets shorter
+    /// than 16 bytes.
+    pub fn new(secret: &[u8]) -> Result<Self, CryptoError> {
+        if secret.len() < MIN_SECRET_SIZE {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        Ok(Self {
+            secret: Zeroizing::new(secret.to_vec()),
+            algorithm: OtpAlgorithm::Sha1,
+            digits: 6,
+        })
+    }
+
+    /// Set the HMAC algorithm.
+    pub fn algorithm(mut self, algorithm: OtpAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the code length.
+    ///
+    /// # Panics
+    ///
+    /// If `digits` is not between 6 and 10.
+    pub fn digits(mut self, digits: u32) -> Self {
+        assert!((6..=10).contains(&digits), "OTP codes have 6 to 10 digits");
+        self.digits = digits;
+        self
+    }
+
+    /// The code for a counter value.
+    pub fn generate(&self, counter: u64) -> String {
+        let mac = self.algorithm.mac(&self.secret, &counter.to_be_bytes());
+        // Dynamic truncation (RFC 4226 section 5.3).
+        let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+        let bits = u32::from_be_bytes(mac[offset..offset + 4].try_into().unwrap()) & 0x7fff_ffff;
+        let code = bits as u64 % 10u64.pow(self.digits);
+        format!("{:0width$}", code, width = self.digits as usize)
+    }
+
+    /// Check a code against `counter` and the `look_ahead` values after
+    /// it, returning the counter value that matched.
+    ///
+    /// Store the match plus one as the next expected counter; codes for
+    /// earlier counters must not be accepted again.
+    pub fn verify(&self, code: &str, counter: u64, look_ahead: u64) -> Option<u64> {
+        (counter..=counter.saturating_add(look_ahead))
+            .find(|&c| codes_equal(&self.generate(c), code))
+    }
+
+    /// An `otpauth://hotp/` provisioning URI, usually shown as a QR code.
+    pub fn provisioning_uri(&self, issuer: &str, account: &str, counter: u64) -> String {
+        format!(
+            "{}&counter={}",
+            provisioning_uri(self, "hotp", issuer, account),
+            counter
+        )
+    }
+}
+
+impl std::fmt::Debug for Hotp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hotp")
+            .field("algorithm", &self.algorithm)
+            .field("digits", &self.digits)
+            .finish_non_exhaustive()
+    }
+}
+
+fn provisioning_uri(hotp: &Hotp, kind: &str, issuer: &str, account: &str) -> String {
+    let issuer = StdUrlEncoding::encode(issuer);
+    format!(
+        "otpauth://{}/{}:{}?secret={}&issuer={}&algorithm={}&digits={}",
+        kind,
+        issuer,
+        StdUrlEncoding::encode(account),
+        StdBase32::encode_unpadded(&hotp.secret),
+        issuer,
+        hotp.algorithm.name(),
+        hotp.digits,
+    )
+}
+
+/// Time-based one-time passwords (TOTP, RFC 6238).
+///
+/// Defaults to SHA-1, 6 digits, 30-second periods and accepting codes one
+/// period either side of the current one, to allow for clock drift.
+#[derive(Debug, Clone)]
+pub struct Totp<C> {
+    hotp: Hotp,
+    clock: C,
+    period: u64,
+    skew: u64,
+}
+
+impl<C: WallClock> Totp<C> {
+    /// Fails with [`CryptoError::InvalidKeySize`] for secrets shorter
+    /// than 16 bytes.
+    pub fn new(secret: &[u8], clock: C) -> Result<Self, CryptoError> {
+        Ok(Self {
+            hotp: Hotp::new(secret)?,
+            clock,
+            period: 30,
+            skew: 1,
+        })
+    }
+
+    /// Set the HMAC algorithm.
+    pub fn algorithm(mut self, algorithm: OtpAlgorithm) -> Self {
+        self.hotp = self.hotp.algorithm(algorithm);
+        self
+    }
+
+    /// Set the code length.
+    ///
+    /// # Panics
+    ///
+    /// If `digits` is not between 6 and 10.
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.hotp = self.hotp.digits(digits);
+        self
+    }
+
+    /// Set the period in seconds.
+    ///
+    /// # Panics
+    ///
+    /// If `seconds` is zero.
+    pub fn period(mut self, seconds: u64) -> Self {
+        assert!(seconds > 0, "TOTP period must be positive");
+        self.period = seconds;
+        self
+    }
+
+    /// Accept codes up to `steps` periods before or after the current one.
+    /// Values above 10 are clamped to 10.
+    pub fn skew(mut self, steps: u64) -> Self {
+        self.skew = steps.min(MAX_SKEW);
+        self
+    }
+
+    /// The time step for a Unix time.
+    pub fn time_step(&self, unix_secs: u64) -> u64 {
+        unix_secs / self.period
+    }
+
+    /// The current code.
+    pub fn generate(&self) -> String {
+        self.generate_at(self.clock.now().0)
+    }
+
+    /// The code at a Unix time.
+    pub fn generate_at(&self, unix_secs: u64) -> String {
+        self.hotp.generate(self.time_step(unix_secs))
+    }
+
+    /// Check a code against the current time, returning the time step that
+    /// matched.
+    ///
+    /// A code stays valid for its whole period and the skew window, so
+    /// record the matched step and reject codes for steps at or before it
+    /// to prevent replay.
+    pub fn verify(&self, code: &str) -> Option<u64> {
+        self.verify_at(code, self.clock.now().0)
+    }
+
+    /// Check a code against a Unix time, returning the time step that
+    /// matched.
+    pub fn verify_at(&self, code: &str, unix_secs: u64) -> Option<u64> {
+        let step = self.time_step(unix_secs);
+        self.hotp.verify(
+            code,
+            step.saturating_sub(self.skew),
+            step.min(self.skew).saturating_add(self.skew),
+        )
+    }
+
+    /// An `otpauth://totp/` provisioning URI, usually shown as a QR code.
+    pub fn provisioning_uri(&self, issuer: &str, account: &str) -> String {
+        format!(
+            "{}&period={}",
+            provisioning_uri(&self.hotp, "totp", issuer, account),
+            self.period
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_clocks_mock::MockWallClock;
+
+    const SHA1_SECRET: &[u8] = b"12345678901234567890";
+    const SHA256_SECRET: &[u8] = b"12345678901234567890123456789012";
+    const SHA512_SECRET: &[u8] =
+        b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    #[test]
+    fn hotp_matches_rfc_4226() {
+        let hotp = Hotp::new(SHA1_SECRET).unwrap();
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp.generate(counter as u64), *code);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238() {
+        let vectors = [
+            (59, ["94287082", "46119246", "90693936"]),
+            (1111111109, ["07081804", "68084774", "25091201"]),
+            (1111111111, ["14050471", "67062674", "99943326"]),
+            (1234567890, ["89005924", "91819424", "93441116"]),
+            (2000000000, ["69279037", "90698825", "38618901"]),
+            (20000000000, ["65353130", "77737706", "47863826"]),
+        ];
+        let algorithms = [
+            (OtpAlgorithm::Sha1, SHA1_SECRET),
+            (OtpAlgorithm::Sha256, SHA256_SECRET),
+            (OtpAlgorithm::Sha512, SHA512_SECRET),
+        ];
+
+        for (time, codes) in vectors {
+            for ((algorithm, secret), code) in algorithms.iter().zip(codes) {
+                let totp = Totp::new(secret, MockWallClock::new(time, 0))
+                    .unwrap()
+                    .algorithm(*algorithm)
+                    .digits(8);
+                assert_eq!(totp.generate(), code, "{:?} at {}", algorithm, time);
+                assert_eq!(totp.verify(code), Some(time / 30));
+            }
+        }
+    }
+
+    #[test]
+    fn hotp_verify_looks_ahead() {
+        let hotp = Hotp::new(SHA1_SECRET).unwrap();
+        assert_eq!(hotp.verify("969429", 1, 5), Some(3));
+        assert_eq!(hotp.verify("969429", 1, 1), None);
+        assert_eq!(hotp.verify("755224", 1, 5), None);
+        assert_eq!(hotp.verify("96942", 3, 0), None);
+    }
+
+    #[test]
+    fn totp_accepts_drift_within_skew() {
+        let clock = MockWallClock::new(1_000_020, 0);
+        let totp = Totp::new(SHA1_SECRET, clock.clone()).unwrap();
+        let step = totp.time_step(1_000_020);
+        let code = totp.generate();
+
+        clock.set(1_000_020 + 30, 0);
+        assert_eq!(totp.verify(&code), Some(step));
+        clock.set(1_000_020 - 30, 0);
+        assert_eq!(totp.verify(&code), Some(step));
+        clock.set(1_000_020 + 60, 0);
+        assert_eq!(totp.verify(&code), None);
+
+        let strict = totp.clone().skew(0);
+        assert_eq!(strict.verify_at(&code, 1_000_020 + 30), None);
+        assert_eq!(strict.verify_at(&code, 1_000_020), Some(step));
+    }
+
+    #[test]
+    fn totp_skew_does_not_underflow_at_epoch() {
+        let totp = Totp::new(SHA1_SECRET, MockWallClock::at_epoch()).unwrap();
+        let code = totp.generate();
+        assert_eq!(totp.verify(&code), Some(0));
+    }
+
+    #[test]
+    fn totp_skew_is_clamped() {
+        let totp = Totp::new(SHA1_SECRET, MockWallClock::at_epoch())
+            .unwrap()
+            .skew(u64::MAX);
+        let code = totp.generate_at(1_000_020);
+        let step = totp.time_step(1_000_020);
+        assert_eq!(totp.verify_at(&code, 1_000_020 + 10 * 30), Some(step));
+        assert_eq!(totp.verify_at(&code, 1_000_020 + 11 * 30), None);
+        assert_eq!(totp.verify_at(&code, 1_000_020 - 11 * 30), None);
+        assert_eq!(
+            totp.verify_at(&totp.generate_at(u64::MAX), u64::MAX),
+            Some(u64::MAX / 30)
+        );
+    }
+
+    #[test]
+    fn short_secrets_are_rejected() {
+        assert!(matches!(
+            Hotp::new(&[0; 15]),
+            Err(CryptoError::InvalidKeySize)
+        ));
+        assert!(matches!(
+            Totp::new(&[0; 15], MockWallClock::at_epoch()),
+            Err(CryptoError::InvalidKeySize)
+        ));
+    }
+
+    #[test]
+    fn provisioning_uris() {
+        let totp = Totp::new(SHA1_SECRET, MockWallClock::at_epoch()).unwrap();
+        assert_eq!(
+            totp.provisioning_uri("Example Co", "alice@example.com"),
+            "otpauth://totp/Example%20Co:alice%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=Example%20Co&algorithm=SHA1&digits=6&period=30"
+        );
+
+        let hotp = Hotp::new(SHA256_SECRET)
+            .unwrap()
+            .algorithm(OtpAlgorithm::Sha256)
+            .digits(8);
+        let uri = hotp.provisioning_uri("Example", "bob", 7);
+        assert!(uri.starts_with("otpauth://hotp/Example:bob?secret="));
+        assert!(uri.ends_with("&issuer=Example&algorithm=SHA256&digits=8&counter=7"));
+    }
+
+    #[test]
+    fn generated_secrets_match_the_hash_size() {
+        use portals_random_native::OsRandom;
+
+        for algorithm in [
+            OtpAlgorithm::Sha1,
+            OtpAlgorithm::Sha256,
+            OtpAlgorithm::Sha512,
+        ] {
+            let secret = algorithm.generate_secret(&OsRandom);
+            assert_eq!(secret.len(), algorithm.secret_size());
+            Hotp::new(&secret).unwrap();
+        }
+    }
+}
@@ -0,0 +1,350 @@
+//! Password hashing with Argon2id, scrypt and bcrypt.
+
+use argon2::password_hash::{
+    self, PasswordHash, PasswordHasher as _, PasswordVerifier as _, SaltString,
+};
+use portals_crypto::{CryptoError, PasswordHasher};
+use portals_random::SecureRandom;
+
+/// Salt length for generated hashes, in bytes.
+const SALT_SIZE: usize = 16;
+
+fn invalid_hash(e: impl std::fmt::Display) -> CryptoError {
+    CryptoError::InvalidPasswordHash(e.to_string())
+}
+
+fn salt(random: &impl SecureRandom) -> Result<SaltString, CryptoError> {
+    let mut bytes = [0u8; SALT_SIZE];
+    random.fill(&mut bytes);
+    SaltString::encode_b64(&bytes).map_err(|e| CryptoError::Other(e.to_string()))
+}
+
+/// Parse a PHC string, checking its algorithm.
+fn parse<'a>(hash: &'a str, algorithm: &str) -> Result<PasswordHash<'a>, CryptoError> {
+    let parsed = PasswordHash::new(hash).map_err(invalid_hash)?;
+    if parsed.algorithm.as_str() != algorithm {
+        return Err(invalid_hash(format!(
+            "expected {}, found {}",
+            algorithm, parsed.algorithm
+        )));
+    }
+    Ok(parsed)
+}
+
+/// Map a verification result, treating a mismatch as `false`.
+fn verified(result: password_hash::Result<()>) -> Result<bool, CryptoError> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(invalid_hash(e)),
+    }
+}
+
+// ============================================================================
+// Argon2id
+// ============================================================================
+
+/// Argon2id password hashing, producing PHC strings.
+///
+/// Defaults follow the OWASP recommendation: 19 MiB of memory, two
+/// iterations and one lane.
+pub struct Argon2idHasher<R> {
+    random: R,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl<R: SecureRandom> Argon2idHasher<R> {
+    /// Create a hasher with the default parameters, drawing salts from
+    /// `random`.
+    pub fn new(random: R) -> Self {
+        Self {
+            random,
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+
+    /// Set the memory cost in KiB.
+    pub fn memory_kib(mut self, memory_kib: u32) -> Self {
+        self.memory_kib = memory_kib;
+        self
+    }
+
+    /// Set the number of iterations (time cost).
+    pub fn iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Set the number of lanes.
+    pub fn parallelism(mut self, parallelism: u32) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    fn params(&self) -> Result<argon2::Params, CryptoError> {
+        argon2::Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| CryptoError::Other(e.to_string()))
+    }
+}
+
+impl<R: SecureRandom> PasswordHasher for Argon2idHasher<R> {
+    fn hash(&self, password: &[u8]) -> Result<String, CryptoError> {
+        let argon2 = argon2::Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            self.params()?,
+        );
+        let salt = salt(&self.random)?;
+        let hash = argon2
+            .hash_password(password, &salt)
+            .map_err(|e| CryptoError::Other(e.to_string()))?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, password: &[u8], hash: &str) -> Result<bool, CryptoError> {
+        let parsed = parse(hash, "argon2id")?;
+        verified(argon2::Argon2::default().verify_password(password, &parsed))
+    }
+
+    fn needs_rehash(&self, hash: &str) -> Result<bool, CryptoError> {
+        let parsed = parse(hash, "argon2id")?;
+        let params = argon2::Params::try_from(&parsed).map_err(invalid_hash)?;
+        let wanted = self.params()?;
+        Ok(parsed.version != Some(argon2::Version::V0x13.into())
+            || params.m_cost() != wanted.m_cost()
+            || params.t_cost() != wanted.t_cost()
+            || params.p_cost() != wanted.p_cost()
+            || params.output_len() != Some(argon2::Params::DEFAULT_OUTPUT_LEN))
+    }
+}
+
+// ============================================================================
+// scrypt
+// ============================================================================
+
+/// scrypt password hashing, producing PHC strings.
+///
+/// Defaults follow the OWASP recommendation: N = 2^17, r = 8, p = 1, which
+/// uses 128 MiB of memory.
+pub struct ScryptHasher<R> {
+    random: R,
+    log_n: u8,
+    block_size: u32,
+    parallelism: u32,
+}
+
+impl<R: SecureRandom> ScryptHasher<R> {
+    /// Create a hasher with the default parameters, drawing salts from
+    /// `random`.
+    pub fn new(random: R) -> Self {
+        Self {
+            random,
+            log_n: 17,
+            block_size: 8,
+            parallelism: 1,
+        }
+    }
+
+    /// Set the CPU and memory cost as a power of two (N = 2^log_n).
+    pub fn log_n(mut self, log_n: u8) -> Self {
+        self.log_n = log_n;
+        self
+    }
+
+    /// Set the block size (r).
+    pub fn block_size(mut self, block_size: u32) -> Self {
+        self.block_size = block_size;
+        self
+    }
+
+    /// Set the parallelism (p).
+    pub fn parallelism(mut self, parallelism: u32) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    fn params(&self) -> Result<scrypt::Params, CryptoError> {
+        scrypt::Params::new(
+            self.log_n,
+            self.block_size,
+            self.parallelism,
+            scrypt::Params::RECOMMENDED_LEN,
+        )
+        .map_err(|e| CryptoError::Other(e.to_string()))
+    }
+}
+
+impl<R: SecureRandom> PasswordHasher for ScryptHasher<R> {
+    fn hash(&self, password: &[u8]) -> Result<String, CryptoError> {
+        let salt = salt(&self.random)?;
+        let hash = scrypt::Scrypt
+            .hash_password_customized(password, None, None, self.params()?, &salt)
+            .map_err(|e| CryptoError::Other(e.to_string()))?;
+        Ok(hash.to_string())
+    }
+
+    fn verify(&self, password: &[u8], hash: &str) -> Result<bool, CryptoError> {
+        let parsed = parse(hash, "scrypt")?;
+        verified(scrypt::Scrypt.verify_password(password, &parsed))
+    }
+
+    fn needs_rehash(&self, hash: &str) -> Result<bool, CryptoError> {
+        let parsed = parse(hash, "scrypt")?;
+        let params = scrypt::Params::try_from(&parsed).map_err(invalid_hash)?;
+        Ok(params.log_n() != self.log_n
+            || params.r() != self.block_size
+            || params.p() != self.parallelism
+            || parsed.hash.map(|h| h.len()) != Some(scrypt::Params::RECOMMENDED_LEN))
+    }
+}
+
+// ============================================================================
+// bcrypt
+// ============================================================================
+
+/// bcrypt password hashing.
+///
+/// bcrypt predates PHC strings, so hashes use its own `$2b$<cost>$...`
+/// format. Passwords over 72 bytes are rejected rather than silently
+/// truncated. The default cost is 12.
+pub struct BcryptHasher<R> {
+    random: R,
+    cost: u32,
+}
+
+impl<R: SecureRandom> BcryptHasher<R> {
+    /// Create a hasher with the default cost, drawing salts from `random`.
+    pub fn new(random: R) -> Self {
+        Self { random, cost: 12 }
+    }
+
+    /// Set the cost, as a power of two (4 to 31).
+    pub fn cost(mut self, cost: u32) -> Self {
+        self.cost = cost;
+        self
+    }
+}
+
+impl<R: SecureRandom> PasswordHasher for BcryptHasher<R> {
+    fn hash(&self, password: &[u8]) -> Result<String, CryptoError> {
+        let mut salt = [0u8; SALT_SIZE];
+        self.random.fill(&mut salt);
+        let parts = bcrypt::non_truncating_hash_with_salt(password, self.cost, salt)
+            .map_err(|e| CryptoError::Other(e.to_string()))?;
+        Ok(parts.format_for_version(bcrypt::Version::TwoB))
+    }
+
+    fn verify(&self, password: &[u8], hash: &str) -> Result<bool, CryptoError> {
+        match bcrypt::non_truncating_verify(password, hash) {
+            Ok(matches) => Ok(matches),
+            // No stored hash can match a password we refuse to hash.
+            Err(bcrypt::BcryptError::Truncation(_)) => Ok(false),
+            Err(e) => Err(invalid_hash(e)),
+        }
+    }
+
+    fn needs_rehash(&self, hash: &str) -> Result<bool, CryptoError> {
+        let parts: bcrypt::HashParts = hash.parse().map_err(invalid_hash)?;
+        Ok(parts.get_cost() != self.cost || !hash.starts_with("$2b$"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_random_mock::MockSecureRandom;
+
+    fn random() -> MockSecureRandom {
+        MockSecureRandom::new(42)
+    }
+
+    #[test]
+    fn argon2id_hashes_and_verifies() {
+        let hasher = Argon2idHasher::new(random()).memory_kib(64).iterations(2);
+        let hash = hasher.hash(b"hunter2").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=64,t=2,p=1$"));
+        assert!(hasher.verify(b"hunter2", &hash).unwrap());
+        assert!(!hasher.verify(b"hunter3", &hash).unwrap());
+        assert_ne!(hasher.hash(b"hunter2").unwrap(), hash, "salts are fresh");
+
+        assert!(!hasher.needs_rehash(&hash).unwrap());
+        let stronger = Argon2idHasher::new(random()).memory_kib(128).iterations(2);
+        assert!(stronger.needs_rehash(&hash).unwrap());
+    }
+
+    #[test]
+    fn argon2id_verifies_reference_hash() {
+        // Generated with Python's cryptography package.
+        let hash = "$argon2id$v=19$m=64,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$Gpj7qOY5RCXJvcMzqcdQqvgR3wcPX7SleI4c9NtXk6E";
+        let hasher = Argon2idHasher::new(random());
+        assert!(hasher.verify(b"password", hash).unwrap());
+        assert!(!hasher.verify(b"Password", hash).unwrap());
+        assert!(hasher.needs_rehash(hash).unwrap());
+    }
+
+    #[test]
+    fn scrypt_hashes_and_verifies() {
+        let hasher = ScryptHasher::new(random()).log_n(4);
+        let hash = hasher.hash(b"hunter2").unwrap();
+        assert!(hash.starts_with("$scrypt$ln=4,r=8,p=1$"));
+        assert!(hasher.verify(b"hunter2", &hash).unwrap());
+        assert!(!hasher.verify(b"hunter3", &hash).unwrap());
+        assert!(!hasher.needs_rehash(&hash).unwrap());
+        assert!(
+            ScryptHasher::new(random())
+                .log_n(5)
+                .needs_rehash(&hash)
+                .unwrap()
+        );
+
+        // Generated with Python's hashlib.scrypt.
+        let reference = "$scrypt$ln=4,r=8,p=1$c29tZXNhbHRzb21lc2FsdA$rjCGpPW8r+9XVz9RqXtAszWzNTGPgzIyDDbKAQjn6LU";
+        assert!(hasher.verify(b"password", reference).unwrap());
+    }
+
+    #[test]
+    fn bcrypt_hashes_and_verifies() {
+        let hasher = BcryptHasher::new(random()).cost(4);
+        let hash = hasher.hash(b"hunter2").unwrap();
+        assert!(hash.starts_with("$2b$04$"));
+        assert!(hasher.verify(b"hunter2", &hash).unwrap());
+        assert!(!hasher.verify(b"hunter3", &hash).unwrap());
+        assert!(!hasher.needs_rehash(&hash).unwrap());
+        assert!(
+            BcryptHasher::new(random())
+                .cost(5)
+                .needs_rehash(&hash)
+                .unwrap()
+        );
+
+        let long = [b'a'; 73];
+        assert!(hasher.hash(&long).is_err());
+        assert!(!hasher.verify(&long, &hash).unwrap());
+    }
+
+    #[test]
+    fn rejects_foreign_and_malformed_hashes() {
+        let argon2 = Argon2idHasher::new(random()).memory_kib(64);
+        let scrypt = ScryptHasher::new(random()).log_n(4);
+        let scrypt_hash = scrypt.hash(b"pw").unwrap();
+        let argon2_hash = argon2.hash(b"pw").unwrap();
+
+        for result in [
+            argon2.verify(b"pw", &scrypt_hash),
+            scrypt.verify(b"pw", &argon2_hash),
+            argon2.verify(b"pw", "not a hash"),
+            BcryptHasher::new(random()).verify(b"pw", &argon2_hash),
+        ] {
+            assert!(matches!(result, Err(CryptoError::InvalidPasswordHash(_))));
+        }
+        assert!(
+            argon2
+                .needs_rehash("$argon2i$v=19$m=64,t=2,p=1$c29tZXNhbHQ$aGFzaA")
+                .is_err()
+        );
+    }
+}
@@ -1,13 +1,162 @@
 //! Native DNS implementation using hickory-resolver.
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
 
 use hickory_resolver::{
-    config::{ResolverConfig, ResolverOpts},
+    Name, ResolveError, Resolver, TokioResolver,
+    config::{NameServerConfig, ResolverConfig, ResolverOpts},
     name_server::TokioConnectionProvider,
-    Resolver, TokioResolver,
+    proto::ProtoErrorKind,
+    proto::op::ResponseCode,
+    proto::rr::{
+        self, RData,
+        rdata::{
+            self,
+            svcb::{SVCB, SvcParamValue},
+        },
+    },
+    proto::xfer::Protocol,
+    system_conf,
 };
-use portals_dns::Error;
+use portals_dns::{
+    Caa, Error, LookupFailure, Mx, Record, RecordData, RecordType, Soa, Srv, SvcParam, Svcb, Tlsa,
+};
+
+fn lookup_error(e: ResolveError) -> Error {
+    if let Some(proto) = e.proto() {
+        match proto.kind() {
+            ProtoErrorKind::NoRecordsFound { response_code, .. } => match *response_code {
+                ResponseCode::NoError => return Error::NoRecords,
+                ResponseCode::NXDomain => return Error::NxDomain,
+                ResponseCode::ServFail => return Error::ServFail,
+                _ => {}
+            },
+            ProtoErrorKind::Timeout => return Error::Timeout,
+            _ => {}
+        }
+    }
+    Error::Lookup(e.to_string())
+}
+
+/// Like [`lookup_error`], keeping the SOA record of a negative answer.
+fn lookup_failure(e: ResolveError) -> LookupFailure {
+    let soa = match e.proto().map(|proto| proto.kind()) {
+        Some(ProtoErrorKind::NoRecordsFound { soa: Some(soa), .. }) => Some(Record {
+            name: soa.name().to_string(),
+            ttl: soa.ttl(),
+            data: RecordData::Soa(soa_data(soa.data())),
+        }),
+        _ => None,
+    };
+    LookupFailure {
+        error: lookup_error(e),
+        soa,
+    }
+}
+
+fn hickory_type(record_type: RecordType) -> rr::RecordType {
+    match record_type {
+        RecordType::A => rr::RecordType::A,
+        RecordType::Aaaa => rr::RecordType::AAAA,
+        RecordType::Cname => rr::RecordType::CNAME,
+        RecordType::Mx => rr::RecordType::MX,
+        RecordType::Ns => rr::RecordType::NS,
+        RecordType::Ptr => rr::RecordType::PTR,
+        RecordType::Soa => rr::RecordType::SOA,
+        RecordType::Srv => rr::RecordType::SRV,
+        RecordType::Txt => rr::RecordType::TXT,
+        RecordType::Caa => rr::RecordType::CAA,
+        RecordType::Tlsa => rr::RecordType::TLSA,
+        RecordType::Svcb => rr::RecordType::SVCB,
+        RecordType::Https => rr::RecordType::HTTPS,
+    }
+}
+
+/// Convert hickory record data, or `None` for types we don't model.
+fn record_data(data: &RData) -> Option<RecordData> {
+    Some(match data {
+        RData::A(a) => RecordData::A(a.0),
+        RData::AAAA(aaaa) => RecordData::Aaaa(aaaa.0),
+        RData::CNAME(cname) => RecordData::Cname(cname.0.to_string()),
+        RData::MX(mx) => RecordData::Mx(Mx {
+            preference: mx.preference(),
+            exchange: mx.exchange().to_string(),
+        }),
+        RData::NS(ns) => RecordData::Ns(ns.0.to_string()),
+        RData::PTR(ptr) => RecordData::Ptr(ptr.0.to_string()),
+        RData::SOA(soa) => RecordData::Soa(soa_data(soa)),
+        RData::SRV(srv) => RecordData::Srv(Srv {
+            priority: srv.priority(),
+            weight: srv.weight(),
+            port: srv.port(),
+            target: srv.target().to_string(),
+        }),
+        RData::TXT(txt) => RecordData::Txt(
+            txt.iter()
+                .map(|data| String::from_utf8_lossy(data).into_owned())
+                .collect(),
+        ),
+        RData::CAA(caa) => RecordData::Caa(Caa {
+            critical: caa.issuer_critical(),
+            tag: caa.tag().as_str().to_string(),
+            value: String::from_utf8_lossy(caa.raw_value()).into_owned(),
+        }),
+        RData::TLSA(tlsa) => RecordData::Tlsa(Tlsa {
+            cert_usage: tlsa.cert_usage().into(),
+            selector: tlsa.selector().into(),
+            matching_type: tlsa.matching().into(),
+            data: tlsa.cert_data().to_vec(),
+        }),
+        RData::SVCB(svcb) => RecordData::Svcb(service_binding(svcb)),
+        RData::HTTPS(https) => RecordData::Https(service_binding(&https.0)),
+        _ => return None,
+    })
+}
+
+fn soa_data(soa: &rdata::SOA) -> Soa {
+    Soa {
+        mname: soa.mname().to_string(),
+        rname: soa.rname().to_string(),
+        serial: soa.serial(),
+        refresh: soa.refresh() as u32,
+        retry: soa.retry() as u32,
+        expire: soa.expire() as u32,
+        minimum: soa.minimum(),
+    }
+}
+
+fn service_binding(svcb: &SVCB) -> Svcb {
+    let params = svcb
+        .svc_params()
+        .iter()
+        .map(|(key, value)| match value {
+            SvcParamValue::Mandatory(keys) => {
+                SvcParam::Mandatory(keys.0.iter().map(|&key| key.into()).collect())
+            }
+            SvcParamValue::Alpn(alpn) => SvcParam::Alpn(alpn.0.clone()),
+            SvcParamValue::NoDefaultAlpn => SvcParam::NoDefaultAlpn,
+            SvcParamValue::Port(port) => SvcParam::Port(*port),
+            SvcParamValue::Ipv4Hint(hint) => {
+                SvcParam::Ipv4Hint(hint.0.iter().map(|a| a.0).collect())
+            }
+            SvcParamValue::EchConfigList(ech) => SvcParam::Ech(ech.0.clone()),
+            SvcParamValue::Ipv6Hint(hint) => {
+                SvcParam::Ipv6Hint(hint.0.iter().map(|a| a.0).collect())
+            }
+            SvcParamValue::Unknown(unknown) => {
+                SvcParam::Unknown(u16::from(*key), unknown.0.clone())
+            }
+        })
+        .collect();
+
+    Svcb {
+        priority: svcb.svc_priority(),
+        target: svcb.target_name().to_string(),
+        params,
+    }
+}
 
 /// Native DNS resolver.
 pub struct NativeResolver {
@@ -15,11 +164,15 @@ pub struct NativeResolver {
 }
 
 impl NativeResolver {
+    /// Start configuring a resolver. Without upstream servers it uses the
+    /// system's, with any other settings applied on top.
+    pub fn builder() -> NativeResolverBuilder {
+        NativeResolverBuilder::default()
+    }
+
     /// Create a new resolver using system configuration.
     pub fn new() -> Result<Self, Error> {
-        let inner = Resolver::builder_tokio()
-            .map_err(|e| Error::Lookup(e.to_string()))?
-            .build();
+        let inner = Resolver::builder_tokio().map_err(lookup_error)?.build();
         Ok(Self { inner })
     }
 
@@ -46,6 +199,170 @@ impl NativeResolver {
     }
 }
 
+/// How to reach upstream nameservers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Transport {
+    /// Plain DNS over UDP, retrying over TCP when a response is truncated.
+    #[default]
+    Udp,
+    /// Plain DNS over TCP.
+    Tcp,
+    /// DNS over TLS (RFC 7858).
+    Tls,
+    /// DNS over HTTPS (RFC 8484).
+    Https,
+}
+
+impl Transport {
+    /// The standard port for this transport.
+    pub fn default_port(self) -> u16 {
+        match self {
+            Transport::Udp | Transport::Tcp => 53,
+            Transport::Tls => 853,
+            Transport::Https => 443,
+        }
+    }
+}
+
+/// Builder for a [`NativeResolver`].
+#[derive(Debug, Clone, Default)]
+pub struct NativeResolverBuilder {
+    servers: Vec<(IpAddr, Option<u16>)>,
+    transport: Transport,
+    tls_name: Option<String>,
+    timeout: Option<Duration>,
+    attempts: Option<usize>,
+    search: Vec<String>,
+    ndots: Option<usize>,
+}
+
+impl NativeResolverBuilder {
+    /// Add an upstream server on the transport's standard port.
+    pub fn nameserver(mut self, ip: IpAddr) -> Self {
+        self.servers.push((ip, None));
+        self
+    }
+
+    /// Add an upstream server on a specific port.
+    pub fn nameserver_addr(mut self, addr: SocketAddr) -> Self {
+        self.servers.push((addr.ip(), Some(addr.port())));
+        self
+    }
+
+    /// Set how upstream servers are reached. Defaults to [`Transport::Udp`].
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Set the name to verify upstream certificates against. Required for
+    /// [`Transport::Tls`] and [`Transport::Https`], e.g. `dns.google`.
+    pub fn tls_name(mut self, name: &str) -> Self {
+        self.tls_name = Some(name.to_string());
+        self
+    }
+
+    /// Set how long to wait for each answer.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set how many times to try each query before giving up.
+    pub fn attempts(mut self, attempts: usize) -> Self {
+        self.attempts = Some(attempts);
+        self
+    }
+
+    /// Add a domain to try relative names in, e.g. `corp.example.com`.
+    pub fn search_domain(mut self, domain: &str) -> Self {
+        self.search.push(domain.to_string());
+        self
+    }
+
+    /// Set how many dots a name needs before it is tried as is, ahead of
+    /// the search domains.
+    pub fn ndots(mut self, ndots: usize) -> Self {
+        self.ndots = Some(ndots);
+        self
+    }
+
+    /// Build the resolver.
+    ///
+    /// Without upstream servers, the system's servers are used: as
+    /// configured for [`Transport::Udp`], and otherwise reached over the
+    /// chosen transport on its standard port.
+    pub fn build(self) -> Result<NativeResolver, Error> {
+        let (system, mut opts) = if self.servers.is_empty() {
+            system_conf::read_system_conf().map_err(lookup_error)?
+        } else {
+            (ResolverConfig::new(), ResolverOpts::default())
+        };
+        let config = self.config(system)?;
+
+        if let Some(timeout) = self.timeout {
+            opts.timeout = timeout;
+        }
+        if let Some(attempts) = self.attempts {
+            opts.attempts = attempts;
+        }
+        if let Some(ndots) = self.ndots {
+            opts.ndots = ndots;
+        }
+
+        let inner = Resolver::builder_with_config(config, TokioConnectionProvider::default())
+            .with_options(opts)
+            .build();
+        Ok(NativeResolver { inner })
+    }
+
+    /// Apply the upstream and search settings on top of `base`, the system
+    /// configuration when no servers were added.
+    fn config(&self, base: ResolverConfig) -> Result<ResolverConfig, Error> {
+        if matches!(self.transport, Transport::Tls | Transport::Https) && self.tls_name.is_none() {
+            return Err(Error::Other(
+                "DNS over TLS or HTTPS needs a server name to verify".to_string(),
+            ));
+        }
+
+        let mut servers = self.servers.clone();
+        let mut config = if servers.is_empty() && self.transport == Transport::Udp {
+            base
+        } else {
+            // The system's servers are listed once per protocol; keep each
+            // address once and reach it over the chosen transport instead.
+            for server in base.name_servers() {
+                let ip = (server.socket_addr.ip(), None);
+                if !servers.contains(&ip) {
+                    servers.push(ip);
+                }
+            }
+            ResolverConfig::from_parts(base.domain().cloned(), base.search().to_vec(), vec![])
+        };
+
+        for (ip, port) in &servers {
+            let addr = SocketAddr::new(*ip, port.unwrap_or(self.transport.default_port()));
+            let protocols: &[Protocol] = match self.transport {
+                Transport::Udp => &[Protocol::Udp, Protocol::Tcp],
+                Transport::Tcp => &[Protocol::Tcp],
+                Transport::Tls => &[Protocol::Tls],
+                Transport::Https => &[Protocol::Https],
+            };
+            for &protocol in protocols {
+                let mut server = NameServerConfig::new(addr, protocol);
+                server.tls_dns_name = self.tls_name.clone();
+                config.add_name_server(server);
+            }
+        }
+        for domain in &self.search {
+            let name = Name::from_str(domain)
+                .map_err(|e| Error::Other(format!("invalid search domain {:?}: {}", domain, e)))?;
+            config.add_search(name);
+        }
+        Ok(config)
+    }
+}
+
 impl Default for NativeResolver {
     fn default() -> Self {
         Self::new().expect("failed to create resolver")
@@ -54,11 +371,7 @@ impl Default for NativeResolver {
 
 impl portals_dns::Resolver for NativeResolver {
     async fn lookup_ipv4(&self, host: &str) -> Result<Vec<Ipv4Addr>, Error> {
-        let response = self
-            .inner
-            .ipv4_lookup(host)
-            .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+        let response = self.inner.ipv4_lookup(host).await.map_err(lookup_error)?;
         let addrs: Vec<Ipv4Addr> = response.iter().map(|a| a.0).collect();
         if addrs.is_empty() {
             return Err(Error::NoRecords);
@@ -67,11 +380,7 @@ impl portals_dns::Resolver for NativeResolver {
     }
 
     async fn lookup_ipv6(&self, host: &str) -> Result<Vec<Ipv6Addr>, Error> {
-        let response = self
-            .inner
-            .ipv6_lookup(host)
-            .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+        let response = self.inner.ipv6_lookup(host).await.map_err(lookup_error)?;
         let addrs: Vec<Ipv6Addr> = response.iter().map(|a| a.0).collect();
         if addrs.is_empty() {
             return Err(Error::NoRecords);
@@ -80,11 +389,7 @@ impl portals_dns::Resolver for NativeResolver {
     }
 
     async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, Error> {
-        let response = self
-            .inner
-            .lookup_ip(host)
-            .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+        let response = self.inner.lookup_ip(host).await.map_err(lookup_error)?;
         let addrs: Vec<IpAddr> = response.iter().collect();
         if addrs.is_empty() {
             return Err(Error::NoRecords);
@@ -93,11 +398,7 @@ impl portals_dns::Resolver for NativeResolver {
     }
 
     async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, Error> {
-        let response = self
-            .inner
-            .txt_lookup(host)
-            .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+        let response = self.inner.txt_lookup(host).await.map_err(lookup_error)?;
         let records: Vec<String> = response
             .iter()
             .map(|txt| {
@@ -114,11 +415,7 @@ impl portals_dns::Resolver for NativeResolver {
     }
 
     async fn lookup_mx(&self, domain: &str) -> Result<Vec<(u16, String)>, Error> {
-        let response = self
-            .inner
-            .mx_lookup(domain)
-            .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+        let response = self.inner.mx_lookup(domain).await.map_err(lookup_error)?;
         let records: Vec<(u16, String)> = response
             .iter()
             .map(|mx| (mx.preference(), mx.exchange().to_string()))
@@ -134,11 +431,271 @@ impl portals_dns::Resolver for NativeResolver {
             .inner
             .reverse_lookup(addr)
             .await
-            .map_err(|e| Error::Lookup(e.to_string()))?;
+            .map_err(lookup_error)?;
         let names: Vec<String> = response.iter().map(|name| name.to_string()).collect();
         if names.is_empty() {
             return Err(Error::NoRecords);
         }
         Ok(names)
     }
+
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Result<Vec<Record>, Error> {
+        self.lookup_with_soa(name, record_type)
+            .await
+            .map_err(|failure| failure.error)
+    }
+
+    async fn lookup_with_soa(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> Result<Vec<Record>, LookupFailure> {
+        let response = self
+            .inner
+            .lookup(name, hickory_type(record_type))
+            .await
+            .map_err(lookup_failure)?;
+        let records: Vec<Record> = response
+            .records()
+            .iter()
+            .filter_map(|record| {
+                let data = record_data(record.data())?;
+                (data.record_type() == record_type).then(|| Record {
+                    name: record.name().to_string(),
+                    ttl: record.ttl(),
+                    data,
+                })
+            })
+            .collect();
+        if records.is_empty() {
+            return Err(Error::NoRecords.into());
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hickory_resolver::config::NameServerConfigGroup;
+    use hickory_resolver::proto::rr::Name;
+    use hickory_resolver::proto::rr::rdata::{
+        self,
+        svcb::{Alpn, IpHint, Mandatory, SvcParamKey, Unknown},
+        tlsa::{CertUsage, Matching, Selector},
+    };
+
+    fn name(name: &str) -> Name {
+        Name::from_ascii(name).unwrap()
+    }
+
+    #[test]
+    fn converts_srv_and_soa() {
+        let srv = RData::SRV(rdata::SRV::new(10, 5, 993, name("imap.example.com.")));
+        assert_eq!(
+            record_data(&srv),
+            Some(RecordData::Srv(Srv {
+                priority: 10,
+                weight: 5,
+                port: 993,
+                target: "imap.example.com.".to_string(),
+            }))
+        );
+
+        let soa = RData::SOA(rdata::SOA::new(
+            name("ns1.example.com."),
+            name("hostmaster.example.com."),
+            2024010101,
+            7200,
+            3600,
+            1209600,
+            300,
+        ));
+        let Some(RecordData::Soa(soa)) = record_data(&soa) else {
+            panic!("expected SOA");
+        };
+        assert_eq!(soa.mname, "ns1.example.com.");
+        assert_eq!(soa.serial, 2024010101);
+        assert_eq!(soa.expire, 1209600);
+        assert_eq!(soa.minimum, 300);
+    }
+
+    #[test]
+    fn converts_caa_and_tlsa() {
+        let caa = RData::CAA(rdata::CAA::new_issue(
+            true,
+            Some(name("letsencrypt.org")),
+            vec![],
+        ));
+        assert_eq!(
+            record_data(&caa),
+            Some(RecordData::Caa(Caa {
+                critical: true,
+                tag: "issue".to_string(),
+                value: "letsencrypt.org".to_string(),
+            }))
+        );
+
+        let tlsa = RData::TLSA(rdata::TLSA::new(
+            CertUsage::DaneEe,
+            Selector::Spki,
+            Matching::Sha256,
+            vec![0xab; 32],
+        ));
+        assert_eq!(
+            record_data(&tlsa),
+            Some(RecordData::Tlsa(Tlsa {
+                cert_usage: 3,
+                selector: 1,
+                matching_type: 1,
+                data: vec![0xab; 32],
+            }))
+        );
+    }
+
+    #[test]
+    fn converts_service_bindings() {
+        let svcb = SVCB::new(
+            1,
+            name("."),
+            vec![
+                (
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Alpn])),
+                ),
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_string(), "h3".to_string()])),
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(8443)),
+                (
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![rdata::A::new(192, 0, 2, 1)])),
+                ),
+                (
+                    SvcParamKey::Key(667),
+                    SvcParamValue::Unknown(Unknown(vec![1, 2])),
+                ),
+            ],
+        );
+        let Some(RecordData::Https(https)) = record_data(&RData::HTTPS(rdata::HTTPS(svcb))) else {
+            panic!("expected HTTPS");
+        };
+        assert_eq!(https.priority, 1);
+        assert_eq!(https.target, ".");
+        assert_eq!(
+            https.params,
+            vec![
+                SvcParam::Mandatory(vec![1]),
+                SvcParam::Alpn(vec!["h2".to_string(), "h3".to_string()]),
+                SvcParam::Port(8443),
+                SvcParam::Ipv4Hint(vec![Ipv4Addr::new(192, 0, 2, 1)]),
+                SvcParam::Unknown(667, vec![1, 2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_custom_upstreams() {
+        let builder = NativeResolver::builder()
+            .nameserver(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)))
+            .nameserver_addr("[2001:db8::53]:5353".parse().unwrap())
+            .timeout(Duration::from_secs(2))
+            .attempts(3)
+            .search_domain("corp.example.com")
+            .ndots(2);
+        assert!(builder.clone().build().is_ok());
+        assert!(builder.clone().search_domain("bad..name").build().is_err());
+
+        let tls = builder.transport(Transport::Tls);
+        assert!(matches!(tls.clone().build(), Err(Error::Other(_))));
+        assert!(tls.tls_name("dns.example.com").build().is_ok());
+    }
+
+    #[test]
+    fn system_servers_take_the_chosen_transport() {
+        let system = ResolverConfig::from_parts(
+            Some(name("example.com.")),
+            vec![name("corp.example.com.")],
+            NameServerConfigGroup::from_ips_clear(
+                &[
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)),
+                    "2001:db8::53".parse().unwrap(),
+                ],
+                53,
+                true,
+            ),
+        );
+        let builder = NativeResolver::builder().tls_name("dns.example.com");
+
+        let plain = builder.config(system.clone()).unwrap();
+        assert_eq!(plain.name_servers().len(), 4);
+
+        for (transport, protocol, port) in [
+            (Transport::Tcp, Protocol::Tcp, 53),
+            (Transport::Tls, Protocol::Tls, 853),
+            (Transport::Https, Protocol::Https, 443),
+        ] {
+            let config = builder
+                .clone()
+                .transport(transport)
+                .config(system.clone())
+                .unwrap();
+            assert_eq!(config.name_servers().len(), 2, "{:?}", transport);
+            for server in config.name_servers() {
+                assert_eq!(server.protocol, protocol);
+                assert_eq!(server.socket_addr.port(), port);
+                assert_eq!(server.tls_dns_name.as_deref(), Some("dns.example.com"));
+            }
+            assert_eq!(config.domain(), system.domain());
+            assert_eq!(config.search(), system.search());
+        }
+    }
+
+    #[test]
+    fn negative_answers_keep_their_soa() {
+        use hickory_resolver::proto::ProtoError;
+        use hickory_resolver::proto::op::Query;
+
+        let soa = rdata::SOA::new(
+            name("ns1.example.com."),
+            name("hostmaster.example.com."),
+            1,
+            7200,
+            3600,
+            1209600,
+            300,
+        );
+        let error = ProtoError::nx_error(
+            Box::new(Query::query(name("gone.example.com."), rr::RecordType::A)),
+            Some(Box::new(rr::Record::from_rdata(
+                name("example.com."),
+                900,
+                soa,
+            ))),
+            None,
+            Some(300),
+            ResponseCode::NXDomain,
+            true,
+            None,
+        );
+        let failure = lookup_failure(error.into());
+        assert!(matches!(failure.error, Error::NxDomain));
+        let soa = failure.soa.unwrap();
+        assert_eq!((soa.name.as_str(), soa.ttl), ("example.com.", 900));
+        assert!(matches!(
+            soa.data,
+            RecordData::Soa(Soa { minimum: 300, .. })
+        ));
+
+        let failure = lookup_failure(ProtoError::from(ProtoErrorKind::Timeout).into());
+        assert!(matches!(failure.error, Error::Timeout));
+        assert!(failure.soa.is_none());
+    }
+
+    #[test]
+    fn record_types_map_to_hickory() {
+        assert_eq!(hickory_type(RecordType::Https), rr::RecordType::HTTPS);
+        assert_eq!(hickory_type(RecordType::Tlsa), rr::RecordType::TLSA);
+    }
 }
@@ -0,0 +1,648 @@
+//! Resolving paths without leaving a directory.
+//!
+//! Paths are relative to a root. Absolute paths, `..` above the root and
+//! symlinks that lead out of it are refused with [`Error::Access`].
+//!
+//! On Linux, each operation opens what it acts on, or the directory
+//! holding it, with `openat2` and `RESOLVE_BENEATH`, so the kernel enforces
+//! this during the lookup itself, and then works relative to that
+//! descriptor. Elsewhere, and where `openat2` fails with `ENOSYS` (kernels
+//! before 5.6, seccomp sandboxes that hide it), a userspace walker follows
+//! each symlink by hand, and checks that each entry left with `..` is a
+//! directory, before the path is used. A process that can swap directories
+//! for symlinks inside the root while it runs may race those checks.
+
+use portals_filesystem::{DirEntry, Error, FileType};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Component, Path, PathBuf};
+
+/// Symlinks to follow before giving up, as Linux's `MAXSYMLINKS`.
+const MAX_SYMLINKS: usize = 40;
+
+/// How a file is opened.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Mode {
+    Read,
+    /// Create or truncate for writing.
+    Write,
+    /// Create or append.
+    Append,
+}
+
+impl Mode {
+    fn options(self) -> OpenOptions {
+        let mut options = OpenOptions::new();
+        match self {
+            Mode::Read => options.read(true),
+            Mode::Write => options.write(true).create(true).truncate(true),
+            Mode::Append => options.create(true).append(true),
+        };
+        options
+    }
+}
+
+enum Part {
+    Parent,
+    Name(OsString),
+}
+
+/// Split a relative path into the parts the walker acts on.
+fn parts(path: &Path) -> Result<Vec<Part>, Error> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => return Err(Error::Access),
+            Component::CurDir => {}
+            Component::ParentDir => parts.push(Part::Parent),
+            Component::Normal(name) => parts.push(Part::Name(name.to_owned())),
+        }
+    }
+    Ok(parts)
+}
+
+/// Split the path of an entry that is operated on itself rather than
+/// followed, as when removing or renaming a symlink, into the directory
+/// holding it and its name. Paths naming the root or ending in `..` are
+/// [`Error::Invalid`].
+fn split_entry(path: &Path) -> Result<(&Path, &OsStr), Error> {
+    if path.has_root() {
+        return Err(Error::Access);
+    }
+    match path.components().next_back() {
+        Some(Component::Normal(name)) => Ok((path.parent().unwrap_or(Path::new("")), name)),
+        _ => Err(Error::Invalid),
+    }
+}
+
+/// Resolve `path` beneath `root`, replacing symlinks with their targets.
+pub(crate) fn walk(root: &Path, path: &Path) -> Result<PathBuf, Error> {
+    // A stack, so a symlink's target can be pushed in front of the rest.
+    let mut pending: Vec<Part> = parts(path)?.into_iter().rev().collect();
+    let mut resolved = PathBuf::new();
+    let mut symlinks = 0;
+
+    while let Some(part) = pending.pop() {
+        let name = match part {
+            Part::Parent => {
+                // `resolved` holds no symlinks by now, so once its last
+                // entry is known to be a directory `..` can be taken
+                // lexically, as the kernel would take it.
+                if resolved.as_os_str().is_empty() {
+                    return Err(Error::Access);
+                }
+                check_dir(&root.join(&resolved))?;
+                resolved.pop();
+                continue;
+            }
+            Part::Name(name) => name,
+        };
+        resolved.push(&name);
+
+        let full = root.join(&resolved);
+        let is_symlink = full
+            .symlink_metadata()
+            .is_ok_and(|meta| meta.file_type().is_symlink());
+        if !is_symlink {
+            // Missing and unreadable entries are left for the operation
+            // itself to report.
+            continue;
+        }
+        symlinks += 1;
+        if symlinks > MAX_SYMLINKS {
+            return Err(Error::Other("too many levels of symbolic links".into()));
+        }
+        let target = full.read_link()?;
+        resolved.pop();
+        pending.extend(parts(&target)?.into_iter().rev());
+    }
+
+    Ok(root.join(resolved))
+}
+
+/// Fail unless `path` is a directory itself rather than a symlink to one,
+/// with the error the kernel gives for `..` after anything else.
+fn check_dir(path: &Path) -> Result<(), Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        #[cfg(target_os = "linux")]
+        let flags = libc::O_PATH | libc::O_DIRECTORY | libc::O_NOFOLLOW;
+        #[cfg(not(target_os = "linux"))]
+        let flags = libc::O_DIRECTORY | libc::O_NOFOLLOW;
+        OpenOptions::new()
+            .read(true)
+            .custom_flags(flags)
+            .open(path)?;
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        if fs::symlink_metadata(path)?.is_dir() {
+            Ok(())
+        } else {
+            Err(std::io::Error::from(std::io::ErrorKind::NotADirectory).into())
+        }
+    }
+}
+
+/// Resolve the directory holding an entry beneath `root`, and join the
+/// entry's name to it unresolved.
+fn walk_entry(root: &Path, path: &Path) -> Result<PathBuf, Error> {
+    let (parent, name) = split_entry(path)?;
+    Ok(walk(root, parent)?.join(name))
+}
+
+/// Open a file beneath `root`.
+pub(crate) fn open(root: &Path, path: &Path, mode: Mode) -> Result<File, Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::open(root, path, mode) {
+        return result;
+    }
+    let full = walk(root, path)?;
+    Ok(mode.options().open(full)?)
+}
+
+/// Metadata of a path beneath `root`, following symlinks.
+pub(crate) fn metadata(root: &Path, path: &Path) -> Result<fs::Metadata, Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::metadata(root, path) {
+        return result;
+    }
+    Ok(fs::metadata(walk(root, path)?)?)
+}
+
+/// List a directory beneath `root`.
+pub(crate) fn read_dir(root: &Path, path: &Path) -> Result<ReadDir, Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::read_dir(root, path) {
+        return result.map(ReadDir::Linux);
+    }
+    Ok(ReadDir::Std(fs::read_dir(walk(root, path)?)?))
+}
+
+/// Create a directory beneath `root`.
+pub(crate) fn create_dir(root: &Path, path: &Path) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::create_dir(root, path) {
+        return result;
+    }
+    Ok(fs::create_dir(walk_entry(root, path)?)?)
+}
+
+/// Remove a file, or a symlink itself, beneath `root`.
+pub(crate) fn remove_file(root: &Path, path: &Path) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::remove(root, path, 0) {
+        return result;
+    }
+    Ok(fs::remove_file(walk_entry(root, path)?)?)
+}
+
+/// Remove an empty directory beneath `root`.
+pub(crate) fn remove_dir(root: &Path, path: &Path) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::remove(root, path, libc::AT_REMOVEDIR) {
+        return result;
+    }
+    Ok(fs::remove_dir(walk_entry(root, path)?)?)
+}
+
+/// Rename an entry beneath `root` to another place beneath it.
+pub(crate) fn rename(root: &Path, from: &Path, to: &Path) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    if let Some(result) = linux::rename(root, from, to) {
+        return result;
+    }
+    Ok(fs::rename(walk_entry(root, from)?, walk_entry(root, to)?)?)
+}
+
+/// The portable type of a file.
+pub(crate) fn file_type(file_type: fs::FileType) -> FileType {
+    if file_type.is_file() {
+        FileType::Regular
+    } else if file_type.is_dir() {
+        FileType::Directory
+    } else if file_type.is_symlink() {
+        FileType::Symlink
+    } else {
+        FileType::Unknown
+    }
+}
+
+/// The entries of a directory listed by [`read_dir`].
+pub(crate) enum ReadDir {
+    #[cfg(target_os = "linux")]
+    Linux(linux::Dir),
+    Std(fs::ReadDir),
+}
+
+impl Iterator for ReadDir {
+    type Item = Result<DirEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            #[cfg(target_os = "linux")]
+            ReadDir::Linux(dir) => dir.next(),
+            ReadDir::Std(entries) => {
+                Some(entries.next()?.map_err(Error::from).map(|entry| DirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    file_type: entry.file_type().map_or(FileType::Unknown, file_type),
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::Mode;
+    use portals_filesystem::{DirEntry, Error, FileType};
+    use std::ffi::{CStr, CString};
+    use std::fs::{self, File};
+    use std::mem::MaybeUninit;
+    use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::ptr::NonNull;
+
+    impl Mode {
+        fn flags(self) -> libc::c_int {
+            match self {
+                Mode::Read => libc::O_RDONLY,
+                Mode::Write => libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+                Mode::Append => libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND,
+            }
+        }
+    }
+
+    /// Open `path` beneath `root` with `openat2`, or `None` if the kernel
+    /// does not offer it. An empty path opens the root itself.
+    fn beneath(root: &Path, path: &Path, flags: libc::c_int) -> Option<Result<OwnedFd, Error>> {
+        if path.has_root() {
+            return Some(Err(Error::Access));
+        }
+        let path = if path.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            path
+        };
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return Some(Err(Error::Invalid));
+        };
+        let dir = match File::open(root) {
+            Ok(dir) => dir,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        let flags = flags | libc::O_CLOEXEC;
+        // SAFETY: `open_how` is plain data, and all zeroes is its default.
+        let mut how: libc::open_how = unsafe { std::mem::zeroed() };
+        how.flags = flags as u64;
+        if flags & libc::O_CREAT != 0 {
+            how.mode = 0o666;
+        }
+        how.resolve = libc::RESOLVE_BENEATH | libc::RESOLVE_NO_MAGICLINKS;
+
+        // SAFETY: the pointers are valid for the call and the size is that
+        // of the struct they point to.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_openat2,
+                dir.as_raw_fd(),
+                path.as_ptr(),
+                &how as *const libc::open_how,
+                std::mem::size_of::<libc::open_how>(),
+            )
+        };
+        if fd >= 0 {
+            // SAFETY: the kernel just handed us this descriptor.
+            return Some(Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) }));
+        }
+
+        let err = std::io::Error::last_os_error();
+        match err.raw_os_error() {
+            // RESOLVE_BENEATH refuses escapes with EXDEV.
+            Some(libc::EXDEV) => Some(Err(Error::Access)),
+            // No openat2, from the kernel or a seccomp filter. Anything
+            // else, EPERM included, is the operation's own answer.
+            Some(libc::ENOSYS) => None,
+            _ => Some(Err(err.into())),
+        }
+    }
+
+    /// Open the directory holding an entry beneath `root`, for the `*at`
+    /// calls to act on the entry's name within it.
+    fn parent(root: &Path, path: &Path) -> Option<Result<(OwnedFd, CString), Error>> {
+        let (parent, name) = match super::split_entry(path) {
+            Ok(split) => split,
+            Err(e) => return Some(Err(e)),
+        };
+        let Ok(name) = CString::new(name.as_bytes()) else {
+            return Some(Err(Error::Invalid));
+        };
+        let dir = beneath(root, parent, libc::O_PATH | libc::O_DIRECTORY)?;
+        Some(dir.map(|dir| (dir, name)))
+    }
+
+    /// Turn a libc return value into a result.
+    fn check(ret: libc::c_int) -> Result<(), Error> {
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error().into())
+        }
+    }
+
+    pub(super) fn open(root: &Path, path: &Path, mode: Mode) -> Option<Result<File, Error>> {
+        Some(beneath(root, path, mode.flags())?.map(File::from))
+    }
+
+    /// The path itself is opened, rather than stat'ed from its parent, so
+    /// that a final symlink is only followed beneath the root too.
+    pub(super) fn metadata(root: &Path, path: &Path) -> Option<Result<fs::Metadata, Error>> {
+        let fd = beneath(root, path, libc::O_PATH)?;
+        Some(fd.and_then(|fd| Ok(File::from(fd).metadata()?)))
+    }
+
+    pub(super) fn read_dir(root: &Path, path: &Path) -> Option<Result<Dir, Error>> {
+        let fd = beneath(root, path, libc::O_RDONLY | libc::O_DIRECTORY)?;
+        Some(fd.and_then(Dir::new))
+    }
+
+    pub(super) fn create_dir(root: &Path, path: &Path) -> Option<Result<(), Error>> {
+        Some(parent(root, path)?.and_then(|(dir, name)| {
+            // SAFETY: `dir` is open and `name` is NUL-terminated.
+            check(unsafe { libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), 0o777) })
+        }))
+    }
+
+    /// Remove an entry, with `AT_REMOVEDIR` for a directory.
+    pub(super) fn remove(
+        root: &Path,
+        path: &Path,
+        flags: libc::c_int,
+    ) -> Option<Result<(), Error>> {
+        Some(parent(root, path)?.and_then(|(dir, name)| {
+            // SAFETY: `dir` is open and `name` is NUL-terminated.
+            check(unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), flags) })
+        }))
+    }
+
+    pub(super) fn rename(root: &Path, from: &Path, to: &Path) -> Option<Result<(), Error>> {
+        let (from_dir, from_name) = match parent(root, from)? {
+            Ok(from) => from,
+            Err(e) => return Some(Err(e)),
+        };
+        let (to_dir, to_name) = match parent(root, to)? {
+            Ok(to) => to,
+            Err(e) => return Some(Err(e)),
+        };
+        // SAFETY: both directories are open and both names NUL-terminated.
+        Some(check(unsafe {
+            libc::renameat(
+                from_dir.as_raw_fd(),
+                from_name.as_ptr(),
+                to_dir.as_raw_fd(),
+                to_name.as_ptr(),
+            )
+        }))
+    }
+
+    /// A directory stream read with `readdir`.
+    pub(crate) struct Dir(NonNull<libc::DIR>);
+
+    // SAFETY: the stream is owned and only used through `&mut self`, so it
+    // is never touched from two threads at once.
+    unsafe impl Send for Dir {}
+
+    impl Dir {
+        fn new(fd: OwnedFd) -> Result<Self, Error> {
+            let fd = fd.into_raw_fd();
+            // SAFETY: `fd` is an open directory; the stream owns it on
+            // success.
+            let dir = unsafe { libc::fdopendir(fd) };
+            match NonNull::new(dir) {
+                Some(dir) => Ok(Dir(dir)),
+                None => {
+                    let err = std::io::Error::last_os_error();
+                    // SAFETY: `fdopendir` failed, so `fd` is still ours.
+                    drop(unsafe { OwnedFd::from_raw_fd(fd) });
+                    Err(err.into())
+                }
+            }
+        }
+
+        /// The type of an entry whose type `readdir` did not report.
+        fn lstat_type(&self, name: &CStr) -> FileType {
+            let mut stat = MaybeUninit::<libc::stat>::uninit();
+            // SAFETY: the stream is open, `name` is NUL-terminated and
+            // `stat` is large enough for the result.
+            let ret = unsafe {
+                libc::fstatat(
+                    libc::dirfd(self.0.as_ptr()),
+                    name.as_ptr(),
+                    stat.as_mut_ptr(),
+                    libc::AT_SYMLINK_NOFOLLOW,
+                )
+            };
+            if ret != 0 {
+                return FileType::Unknown;
+            }
+            // SAFETY: `fstatat` filled it in.
+            match unsafe { stat.assume_init() }.st_mode & libc::S_IFMT {
+                libc::S_IFREG => FileType::Regular,
+                libc::S_IFDIR => FileType::Directory,
+                libc::S_IFLNK => FileType::Symlink,
+                _ => FileType::Unknown,
+            }
+        }
+    }
+
+    impl Iterator for Dir {
+        type Item = Result<DirEntry, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                // `readdir` returns null both at the end and on error, and
+                // only sets errno for the latter.
+                // SAFETY: errno is thread-local.
+                unsafe { *libc::__errno_location() = 0 };
+                // SAFETY: the stream is open.
+                let Some(entry) = NonNull::new(unsafe { libc::readdir(self.0.as_ptr()) }) else {
+                    let err = std::io::Error::last_os_error();
+                    return (err.raw_os_error() != Some(0)).then(|| Err(err.into()));
+                };
+                // SAFETY: the entry stays valid until the next `readdir`.
+                let entry = unsafe { entry.as_ref() };
+                // SAFETY: `d_name` is NUL-terminated.
+                let name = unsafe { CStr::from_ptr(entry.d_name.as_ptr()) };
+                if matches!(name.to_bytes(), b"." | b"..") {
+                    continue;
+                }
+                let file_type = match entry.d_type {
+                    libc::DT_REG => FileType::Regular,
+                    libc::DT_DIR => FileType::Directory,
+                    libc::DT_LNK => FileType::Symlink,
+                    libc::DT_UNKNOWN => self.lstat_type(name),
+                    _ => FileType::Unknown,
+                };
+                return Some(Ok(DirEntry {
+                    name: String::from_utf8_lossy(name.to_bytes()).into_owned(),
+                    file_type,
+                }));
+            }
+        }
+    }
+
+    impl Drop for Dir {
+        fn drop(&mut self) {
+            // SAFETY: the stream is open and not used again.
+            unsafe { libc::closedir(self.0.as_ptr()) };
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A root with `file.txt` and `sub/deep/`, next to an `outside/`
+    /// directory holding `secret.txt` that must stay out of reach.
+    /// Returns the directory holding both, and the root.
+    pub(crate) fn sandbox(name: &str) -> (PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!("portals-fs-test-{}", name));
+        let _ = fs::remove_dir_all(&base);
+        let root = base.join("root");
+        fs::create_dir_all(root.join("sub/deep")).unwrap();
+        fs::write(root.join("file.txt"), b"inside").unwrap();
+        fs::create_dir_all(base.join("outside")).unwrap();
+        fs::write(base.join("outside/secret.txt"), b"secret").unwrap();
+        (base, root)
+    }
+
+    fn is_access(result: Result<PathBuf, Error>) -> bool {
+        matches!(result, Err(Error::Access))
+    }
+
+    #[test]
+    fn walker_refuses_lexical_escapes() {
+        let (base, root) = sandbox("lexical");
+        for path in [
+            "/etc/passwd",
+            "..",
+            "../outside/secret.txt",
+            "sub/../../x",
+            "./sub/deep/../../..",
+        ] {
+            assert!(is_access(walk(&root, Path::new(path))), "{}", path);
+        }
+        assert_eq!(
+            walk(&root, Path::new("sub/deep/../../file.txt")).unwrap(),
+            root.join("file.txt")
+        );
+        assert_eq!(walk(&root, Path::new("")).unwrap(), root);
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walker_follows_symlinks_only_while_they_stay_inside() {
+        use std::os::unix::fs::symlink;
+
+        let (base, root) = sandbox("symlinks");
+        symlink(base.join("outside"), root.join("absolute")).unwrap();
+        symlink("../outside", root.join("relative")).unwrap();
+        symlink("sub/deep/../../../outside/secret.txt", root.join("sneaky")).unwrap();
+        symlink("sub/deep", root.join("deep")).unwrap();
+        symlink("../file.txt", root.join("sub/up")).unwrap();
+
+        for path in [
+            "absolute/secret.txt",
+            "relative/secret.txt",
+            "sneaky",
+            "deep/../../..",
+        ] {
+            assert!(is_access(walk(&root, Path::new(path))), "{}", path);
+        }
+        // `..` after a symlink is taken from where the link leads.
+        assert_eq!(
+            walk(&root, Path::new("deep/../x")).unwrap(),
+            root.join("sub/x")
+        );
+        assert_eq!(
+            walk(&root, Path::new("sub/up")).unwrap(),
+            root.join("file.txt")
+        );
+        // An entry's own name is not followed.
+        assert_eq!(
+            walk_entry(&root, Path::new("sneaky")).unwrap(),
+            root.join("sneaky")
+        );
+        assert_eq!(
+            walk_entry(&root, Path::new("deep/x")).unwrap(),
+            root.join("sub/deep/x")
+        );
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walker_gives_up_on_symlink_loops() {
+        use std::os::unix::fs::symlink;
+
+        let (base, root) = sandbox("loops");
+        symlink("b", root.join("a")).unwrap();
+        symlink("a", root.join("b")).unwrap();
+        assert!(matches!(walk(&root, Path::new("a")), Err(Error::Other(_))));
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    /// The OS error a resolution failed with.
+    #[cfg(unix)]
+    fn errno<T>(result: Result<T, Error>) -> Option<i32> {
+        match result {
+            Err(Error::Io(e)) => e.raw_os_error(),
+            _ => None,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dot_dot_only_leaves_directories() {
+        let (base, root) = sandbox("dotdot");
+        for (path, expected) in [
+            ("missing/../file.txt", libc::ENOENT),
+            ("file.txt/..", libc::ENOTDIR),
+            ("file.txt/../sub", libc::ENOTDIR),
+            ("sub/missing/../../file.txt", libc::ENOENT),
+        ] {
+            let path = Path::new(path);
+            assert_eq!(errno(walk(&root, path)), Some(expected), "{:?}", path);
+            #[cfg(target_os = "linux")]
+            if let Some(result) = linux::metadata(&root, path) {
+                assert_eq!(errno(result), Some(expected), "{:?}", path);
+            }
+        }
+        assert_eq!(
+            walk(&root, Path::new("sub/deep/../../file.txt")).unwrap(),
+            root.join("file.txt")
+        );
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn entries_must_name_something_below_the_root() {
+        for path in ["", ".", "sub/..", "a/b/.."] {
+            assert!(
+                matches!(split_entry(Path::new(path)), Err(Error::Invalid)),
+                "{}",
+                path
+            );
+        }
+        assert!(matches!(split_entry(Path::new("/x")), Err(Error::Access)));
+        let (parent, name) = split_entry(Path::new("sub/./x")).unwrap();
+        assert_eq!((parent, name), (Path::new("sub"), OsStr::new("x")));
+    }
+}
@@ -1,11 +1,17 @@
 //! Native implementation of portals-filesystem.
 
-use portals_filesystem::{DirEntry, Directory, Error, FileType, Metadata};
+mod beneath;
+
+use beneath::Mode;
+use portals_filesystem::{DirEntry, Directory, Error, Metadata};
 use portals_io_native::{ReaderStream, WriterStream};
-use std::fs::{self, File, OpenOptions};
 use std::path::{Path, PathBuf};
 
 /// A capability to access a native directory.
+///
+/// Paths are relative to the root and cannot leave it: absolute paths,
+/// `..` above the root and symlinks pointing out of it all fail with
+/// [`Error::Access`].
 #[derive(Debug, Clone)]
 pub struct NativeDir {
     root: PathBuf,
@@ -21,53 +27,33 @@ impl NativeDir {
     pub fn root(&self) -> &Path {
         &self.root
     }
-
-    /// Resolve a relative path against the root.
-    fn resolve(&self, path: &Path) -> PathBuf {
-        self.root.join(path)
-    }
 }
 
 impl Directory for NativeDir {
-    fn open_read(&self, path: &Path) -> Result<impl portals_filesystem::InputStream + portals_filesystem::Seek, Error> {
-        let full_path = self.resolve(path);
-        let file = File::open(&full_path)?;
+    fn open_read(
+        &self,
+        path: &Path,
+    ) -> Result<impl portals_filesystem::InputStream + portals_filesystem::Seek, Error> {
+        let file = beneath::open(&self.root, path, Mode::Read)?;
         Ok(ReaderStream::new(file))
     }
 
-    fn open_write(&self, path: &Path) -> Result<impl portals_filesystem::OutputStream + portals_filesystem::Seek, Error> {
-        let full_path = self.resolve(path);
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&full_path)?;
+    fn open_write(
+        &self,
+        path: &Path,
+    ) -> Result<impl portals_filesystem::OutputStream + portals_filesystem::Seek, Error> {
+        let file = beneath::open(&self.root, path, Mode::Write)?;
         Ok(WriterStream::new(file))
     }
 
     fn open_append(&self, path: &Path) -> Result<impl portals_filesystem::OutputStream, Error> {
-        let full_path = self.resolve(path);
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .append(true)
-            .open(&full_path)?;
+        let file = beneath::open(&self.root, path, Mode::Append)?;
         Ok(WriterStream::new(file))
     }
 
     fn metadata(&self, path: &Path) -> Result<Metadata, Error> {
-        let full_path = self.resolve(path);
-        let meta = fs::metadata(&full_path)?;
-
-        let file_type = if meta.is_file() {
-            FileType::Regular
-        } else if meta.is_dir() {
-            FileType::Directory
-        } else if meta.is_symlink() {
-            FileType::Symlink
-        } else {
-            FileType::Unknown
-        };
+        let meta = beneath::metadata(&self.root, path)?;
+        let file_type = beneath::file_type(meta.file_type());
 
         Ok(Metadata {
             file_type,
@@ -90,58 +76,36 @@ impl Directory for NativeDir {
         })
     }
 
-    fn read_dir(&self, path: &Path) -> Result<impl Iterator<Item = Result<DirEntry, Error>>, Error> {
-        let full_path = self.resolve(path);
-        let entries = fs::read_dir(&full_path)?;
-        Ok(entries.map(|entry| {
-            let entry = entry?;
-            let file_type = entry.file_type().map_or(FileType::Unknown, |ft| {
-                if ft.is_file() {
-                    FileType::Regular
-                } else if ft.is_dir() {
-                    FileType::Directory
-                } else if ft.is_symlink() {
-                    FileType::Symlink
-                } else {
-                    FileType::Unknown
-                }
-            });
-            Ok(DirEntry {
-                name: entry.file_name().to_string_lossy().into_owned(),
-                file_type,
-            })
-        }))
+    fn read_dir(
+        &self,
+        path: &Path,
+    ) -> Result<impl Iterator<Item = Result<DirEntry, Error>>, Error> {
+        beneath::read_dir(&self.root, path)
     }
 
     fn create_dir(&self, path: &Path) -> Result<(), Error> {
-        let full_path = self.resolve(path);
-        fs::create_dir(&full_path)?;
-        Ok(())
+        beneath::create_dir(&self.root, path)
     }
 
     fn remove_file(&self, path: &Path) -> Result<(), Error> {
-        let full_path = self.resolve(path);
-        fs::remove_file(&full_path)?;
-        Ok(())
+        beneath::remove_file(&self.root, path)
     }
 
     fn remove_dir(&self, path: &Path) -> Result<(), Error> {
-        let full_path = self.resolve(path);
-        fs::remove_dir(&full_path)?;
-        Ok(())
+        beneath::remove_dir(&self.root, path)
     }
 
     fn rename(&self, from: &Path, to: &Path) -> Result<(), Error> {
-        let full_from = self.resolve(from);
-        let full_to = self.resolve(to);
-        fs::rename(&full_from, &full_to)?;
-        Ok(())
+        beneath::rename(&self.root, from, to)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use beneath::tests::sandbox;
+    use portals_filesystem::FileType;
+    use std::fs;
 
     #[test]
     fn create_and_read_file() {
@@ -246,4 +210,138 @@ mod tests {
         // Cleanup
         fs::remove_dir_all(&temp_dir).unwrap();
     }
+
+    fn denied<T>(result: Result<T, Error>) -> bool {
+        matches!(result, Err(Error::Access))
+    }
+
+    #[test]
+    fn refuses_absolute_and_parent_paths() {
+        let (base, root) = sandbox("escape");
+        let dir = NativeDir::new(&root);
+        let secret = base.join("outside/secret.txt");
+        let paths = [
+            secret.as_path(),
+            Path::new("/etc/passwd"),
+            Path::new(".."),
+            Path::new("../outside/secret.txt"),
+            Path::new("sub/../../outside/secret.txt"),
+            Path::new("./sub/./../.."),
+        ];
+        for path in paths {
+            assert!(denied(dir.open_read(path)), "{}", path.display());
+            assert!(denied(dir.metadata(path)), "{}", path.display());
+            assert!(denied(dir.read_dir(path)), "{}", path.display());
+        }
+        assert!(denied(dir.open_write(Path::new("../planted.txt"))));
+        assert!(denied(dir.open_append(Path::new("../planted.txt"))));
+        assert!(denied(dir.create_dir(Path::new("../planted"))));
+        assert!(denied(dir.remove_file(Path::new("../outside/secret.txt"))));
+        assert!(denied(dir.remove_dir(Path::new("../outside"))));
+        assert!(denied(
+            dir.rename(Path::new("file.txt"), Path::new("../file.txt"))
+        ));
+        assert!(denied(dir.rename(
+            Path::new("../outside/secret.txt"),
+            Path::new("stolen.txt")
+        )));
+
+        assert!(!base.join("planted.txt").exists());
+        assert!(!base.join("planted").exists());
+        assert!(base.join("outside/secret.txt").exists());
+        assert!(base.join("root/file.txt").exists());
+
+        // `..` that stays inside is fine.
+        let meta = dir.metadata(Path::new("sub/../file.txt")).unwrap();
+        assert_eq!(meta.size, 6);
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_out_of_the_root() {
+        use portals_filesystem::{InputStream, OutputStream};
+        use std::os::unix::fs::symlink;
+
+        let (base, root) = sandbox("symlink-escape");
+        let dir = NativeDir::new(&root);
+        symlink(base.join("outside"), root.join("absolute")).unwrap();
+        symlink("../outside", root.join("relative")).unwrap();
+        symlink("../../outside/secret.txt", root.join("sub/secret")).unwrap();
+        symlink("../outside/planted.txt", root.join("dangling")).unwrap();
+        symlink("sub", root.join("inner")).unwrap();
+        symlink("../file.txt", root.join("sub/up")).unwrap();
+
+        for path in [
+            "absolute/secret.txt",
+            "relative/secret.txt",
+            "sub/secret",
+            "inner/secret",
+            "inner/../..",
+        ] {
+            let path = Path::new(path);
+            assert!(denied(dir.open_read(path)), "{}", path.display());
+            assert!(denied(dir.open_write(path)), "{}", path.display());
+            assert!(denied(dir.metadata(path)), "{}", path.display());
+        }
+        assert!(denied(dir.read_dir(Path::new("absolute"))));
+        assert!(denied(dir.create_dir(Path::new("relative/planted"))));
+        // Writing through a dangling link must not create its target.
+        assert!(denied(dir.open_write(Path::new("dangling"))));
+        assert!(denied(dir.open_append(Path::new("dangling"))));
+        assert!(!base.join("outside/planted.txt").exists());
+        assert_eq!(
+            fs::read(base.join("outside/secret.txt")).unwrap(),
+            b"secret"
+        );
+
+        // Links that stay inside work.
+        let mut file = dir.open_read(Path::new("inner/up")).unwrap();
+        assert_eq!(file.read(6).unwrap(), b"inside");
+        let mut file = dir.open_write(Path::new("inner/new.txt")).unwrap();
+        file.write(b"new").unwrap();
+        file.flush().unwrap();
+        assert_eq!(fs::read(root.join("sub/new.txt")).unwrap(), b"new");
+
+        // Removing a link removes the link, not what it points to.
+        dir.remove_file(Path::new("sub/secret")).unwrap();
+        assert!(base.join("outside/secret.txt").exists());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_fail() {
+        use std::os::unix::fs::symlink;
+
+        let (base, root) = sandbox("symlink-loop");
+        let dir = NativeDir::new(&root);
+        symlink("b", base.join("root/a")).unwrap();
+        symlink("a", base.join("root/b")).unwrap();
+        assert!(dir.open_read(Path::new("a")).is_err());
+        assert!(dir.metadata(Path::new("a")).is_err());
+        fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn the_root_cannot_be_removed_or_renamed() {
+        let (base, root) = sandbox("root");
+        let dir = NativeDir::new(&root);
+        fs::remove_file(base.join("root/file.txt")).unwrap();
+        fs::remove_dir_all(base.join("root/sub")).unwrap();
+        for path in ["", ".", "sub/.."] {
+            assert!(matches!(
+                dir.remove_dir(Path::new(path)),
+                Err(Error::Invalid)
+            ));
+            assert!(matches!(
+                dir.rename(Path::new(path), Path::new("moved")),
+                Err(Error::Invalid)
+            ));
+        }
+        assert!(base.join("root").exists());
+        fs::remove_dir_all(&base).unwrap();
+    }
 }
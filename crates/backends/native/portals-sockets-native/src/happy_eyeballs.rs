@@ -0,0 +1,393 @@
+//! Connecting to named hosts with Happy Eyeballs (RFC 8305).
+
+use crate::NativeTcpConnect;
+use futures_util::FutureExt;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use portals_sockets::{Error, Resolver, TcpConnect, TcpConnectHost};
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::pin;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Connects to hosts by name, racing their addresses.
+///
+/// AAAA and A lookups run concurrently. Connecting starts as soon as the
+/// AAAA answer arrives, or once the resolution delay has passed after the
+/// A answer. Addresses are tried alternating between families, IPv6 first.
+/// A new attempt starts whenever one fails or the attempt delay passes
+/// without a connection; the first connection wins and the rest are
+/// dropped.
+///
+/// ```ignore
+/// let connector = NativeHostConnect::new(NativeResolver::default());
+/// let stream = connector.connect_host("example.com", 443).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct NativeHostConnect<R, C = NativeTcpConnect> {
+    resolver: R,
+    connector: C,
+    resolution_delay: Duration,
+    attempt_delay: Duration,
+}
+
+impl<R: Resolver> NativeHostConnect<R> {
+    /// Resolve names with a resolver and connect with tokio.
+    pub fn new(resolver: R) -> Self {
+        Self::with_connector(resolver, NativeTcpConnect)
+    }
+}
+
+impl<R: Resolver, C: TcpConnect> NativeHostConnect<R, C> {
+    /// Resolve names with a resolver and connect with a connector.
+    pub fn with_connector(resolver: R, connector: C) -> Self {
+        Self {
+            resolver,
+            connector,
+            resolution_delay: Duration::from_millis(50),
+            attempt_delay: Duration::from_millis(250),
+        }
+    }
+
+    /// Set how long to wait for AAAA records after A records arrive.
+    /// Defaults to 50ms.
+    pub fn resolution_delay(mut self, delay: Duration) -> Self {
+        self.resolution_delay = delay;
+        self
+    }
+
+    /// Set how long to wait for an attempt before starting the next.
+    /// Defaults to 250ms; RFC 8305 recommends no less than 10ms.
+    pub fn attempt_delay(mut self, delay: Duration) -> Self {
+        self.attempt_delay = delay;
+        self
+    }
+
+    /// The resolver used for names.
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+}
+
+/// Addresses waiting to be tried, handed out alternating between families.
+#[derive(Default)]
+struct Candidates {
+    v6: VecDeque<IpAddr>,
+    v4: VecDeque<IpAddr>,
+    prefer_v6: bool,
+}
+
+impl Candidates {
+    fn next(&mut self) -> Option<IpAddr> {
+        let (first, second) = if self.prefer_v6 {
+            (&mut self.v6, &mut self.v4)
+        } else {
+            (&mut self.v4, &mut self.v6)
+        };
+        let addr = first.pop_front().or_else(|| second.pop_front())?;
+        self.prefer_v6 = addr.is_ipv4();
+        Some(addr)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.v6.is_empty() && self.v4.is_empty()
+    }
+}
+
+impl<R: Resolver, C: TcpConnect> TcpConnectHost for NativeHostConnect<R, C> {
+    type Stream = C::Stream;
+
+    async fn connect_host(&self, host: &str, port: u16) -> Result<Self::Stream, Error> {
+        if let Ok(ip) = host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            return self.connector.connect(SocketAddr::new(ip, port)).await;
+        }
+
+        let mut v6_lookup = pin!(self.resolver.lookup_ipv6(host).fuse());
+        let mut v4_lookup = pin!(self.resolver.lookup_ipv4(host).fuse());
+        let (mut v6_done, mut v4_done) = (false, false);
+        let mut resolve_error = None;
+        // Set when A records arrive before AAAA records.
+        let mut resolution_deadline = None;
+
+        let mut candidates = Candidates {
+            prefer_v6: true,
+            ..Candidates::default()
+        };
+        let mut attempts = FuturesUnordered::new();
+        let mut next_attempt = Instant::now();
+        let mut last_error = None;
+
+        loop {
+            let now = Instant::now();
+            let can_connect =
+                v6_done || resolution_deadline.is_some_and(|deadline| now >= deadline);
+            if can_connect
+                && now >= next_attempt
+                && let Some(ip) = candidates.next()
+            {
+                attempts.push(self.connector.connect(SocketAddr::new(ip, port)));
+                next_attempt = now + self.attempt_delay;
+                continue;
+            }
+            if v6_done && v4_done && candidates.is_empty() && attempts.is_empty() {
+                return Err(last_error
+                    .or(resolve_error.map(Error::Resolve))
+                    .unwrap_or(Error::Resolve(portals_dns::Error::NoRecords)));
+            }
+
+            let wake = if !can_connect {
+                resolution_deadline
+            } else if !candidates.is_empty() {
+                Some(next_attempt)
+            } else {
+                None
+            };
+            tokio::select! {
+                result = &mut v6_lookup, if !v6_done => {
+                    v6_done = true;
+                    match result {
+                        Ok(addrs) => candidates.v6.extend(addrs.into_iter().map(IpAddr::V6)),
+                        Err(e) => record_resolve_error(&mut resolve_error, e),
+                    }
+                }
+                result = &mut v4_lookup, if !v4_done => {
+                    v4_done = true;
+                    match result {
+                        Ok(addrs) => candidates.v4.extend(addrs.into_iter().map(IpAddr::V4)),
+                        Err(e) => record_resolve_error(&mut resolve_error, e),
+                    }
+                    if !v6_done {
+                        resolution_deadline = Some(Instant::now() + self.resolution_delay);
+                    }
+                }
+                Some(result) = attempts.next(), if !attempts.is_empty() => match result {
+                    Ok(stream) => return Ok(stream),
+                    Err(e) => {
+                        last_error = Some(e);
+                        next_attempt = Instant::now();
+                    }
+                },
+                _ = time::sleep_until(wake.unwrap_or(now)), if wake.is_some() => {}
+            }
+        }
+    }
+}
+
+/// Keep the most telling resolution error: anything beats `NoRecords`.
+fn record_resolve_error(slot: &mut Option<portals_dns::Error>, e: portals_dns::Error) {
+    if slot.is_none() || matches!(slot, Some(portals_dns::Error::NoRecords)) {
+        *slot = Some(e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NativeTcpListener;
+    use portals_dns::RecordData;
+    use portals_dns_mock::{Failure, MockResolver};
+    use portals_sockets::{TcpListener, TcpStream};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::rc::Rc;
+
+    #[tokio::test]
+    async fn connects_through_any_resolver() {
+        let listener = NativeTcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            stream.write(b"hi").await.unwrap();
+        });
+
+        let resolver = MockResolver::new();
+        // Nothing listens on the IPv6 loopback, so that attempt fails.
+        resolver.add("service.test", RecordData::Aaaa(Ipv6Addr::LOCALHOST));
+        resolver.add("service.test", RecordData::A(Ipv4Addr::LOCALHOST));
+        let connector = NativeHostConnect::new(resolver);
+
+        let mut stream = connector.connect_host("service.test", port).await.unwrap();
+        assert_eq!(
+            stream.peer_addr().unwrap().ip(),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+        let mut buf = [0u8; 2];
+        stream.read(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hi");
+        connector.resolver().assert_queried("service.test");
+
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_resolution_failures() {
+        let resolver = MockResolver::new();
+        resolver.fail("down.test", Failure::ServFail);
+        let connector = NativeHostConnect::new(resolver);
+
+        assert!(matches!(
+            connector.connect_host("missing.test", 80).await,
+            Err(Error::Resolve(portals_dns::Error::NxDomain))
+        ));
+        assert!(matches!(
+            connector.connect_host("down.test", 80).await,
+            Err(Error::Resolve(portals_dns::Error::ServFail))
+        ));
+    }
+
+    /// A stream that only knows its peer.
+    struct FakeStream(SocketAddr);
+
+    impl TcpStream for FakeStream {
+        async fn read(&mut self, _buf: &mut [u8]) -> Result<usize, Error> {
+            Ok(0)
+        }
+
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+            Ok(buf.len())
+        }
+
+        async fn flush(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn shutdown(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn local_addr(&self) -> Result<SocketAddr, Error> {
+            Err(Error::NotConnected)
+        }
+
+        fn peer_addr(&self) -> Result<SocketAddr, Error> {
+            Ok(self.0)
+        }
+    }
+
+    /// Connects after a per-address delay, or fails after it if no outcome
+    /// is set. Records when each attempt starts.
+    #[derive(Clone, Default)]
+    struct FakeConnect {
+        outcomes: HashMap<IpAddr, (u64, bool)>,
+        attempts: Rc<RefCell<Vec<(IpAddr, Duration)>>>,
+        start: Option<Instant>,
+    }
+
+    impl FakeConnect {
+        fn with(mut self, ip: &str, delay_ms: u64, succeeds: bool) -> Self {
+            self.outcomes
+                .insert(ip.parse().unwrap(), (delay_ms, succeeds));
+            self
+        }
+
+        fn attempts(&self) -> Vec<(IpAddr, Duration)> {
+            self.attempts.borrow().clone()
+        }
+    }
+
+    impl TcpConnect for FakeConnect {
+        type Stream = FakeStream;
+
+        async fn connect(&self, addr: SocketAddr) -> Result<FakeStream, Error> {
+            let started = self.start.unwrap().elapsed();
+            self.attempts.borrow_mut().push((addr.ip(), started));
+            let (delay, succeeds) = self.outcomes.get(&addr.ip()).copied().unwrap_or((0, false));
+            time::sleep(Duration::from_millis(delay)).await;
+            if succeeds {
+                Ok(FakeStream(addr))
+            } else {
+                Err(Error::ConnectionRefused)
+            }
+        }
+    }
+
+    fn dual_stack() -> MockResolver {
+        let resolver = MockResolver::new();
+        for ip in ["2001:db8::1", "2001:db8::2"] {
+            resolver.add("dual.test", RecordData::Aaaa(ip.parse().unwrap()));
+        }
+        for ip in ["192.0.2.1", "192.0.2.2"] {
+            resolver.add("dual.test", RecordData::A(ip.parse().unwrap()));
+        }
+        resolver
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn staggers_attempts_across_families() {
+        // The IPv6 address hangs; IPv4 connects after 10ms.
+        let fake = FakeConnect {
+            start: Some(Instant::now()),
+            ..FakeConnect::default()
+        }
+        .with("2001:db8::1", 10_000, false)
+        .with("192.0.2.1", 10, true);
+        let connector = NativeHostConnect::with_connector(dual_stack(), fake.clone());
+
+        let stream = connector.connect_host("dual.test", 443).await.unwrap();
+        assert_eq!(
+            stream.peer_addr().unwrap(),
+            "192.0.2.1:443".parse().unwrap()
+        );
+        assert_eq!(
+            fake.attempts(),
+            vec![
+                ("2001:db8::1".parse().unwrap(), ms(0)),
+                ("192.0.2.1".parse().unwrap(), ms(250)),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn moves_on_when_attempts_fail() {
+        let fake = FakeConnect {
+            start: Some(Instant::now()),
+            ..FakeConnect::default()
+        }
+        .with("2001:db8::1", 5, false)
+        .with("192.0.2.1", 5, false)
+        .with("2001:db8::2", 5, false)
+        .with("192.0.2.2", 5, false);
+        let connector =
+            NativeHostConnect::with_connector(dual_stack(), fake.clone()).attempt_delay(ms(100));
+
+        assert!(matches!(
+            connector.connect_host("dual.test", 443).await,
+            Err(Error::ConnectionRefused)
+        ));
+        assert_eq!(
+            fake.attempts(),
+            vec![
+                ("2001:db8::1".parse().unwrap(), ms(0)),
+                ("192.0.2.1".parse().unwrap(), ms(5)),
+                ("2001:db8::2".parse().unwrap(), ms(10)),
+                ("192.0.2.2".parse().unwrap(), ms(15)),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn skips_resolution_for_literals() {
+        let fake = FakeConnect {
+            start: Some(Instant::now()),
+            ..FakeConnect::default()
+        }
+        .with("2001:db8::9", 0, true);
+        let connector = NativeHostConnect::with_connector(MockResolver::new(), fake);
+
+        let stream = connector.connect_host("[2001:db8::9]", 80).await.unwrap();
+        assert_eq!(
+            stream.peer_addr().unwrap(),
+            "[2001:db8::9]:80".parse().unwrap()
+        );
+        assert_eq!(connector.resolver().query_count(), 0);
+    }
+}
@@ -1,7 +1,12 @@
 //! Native implementation of portals-sockets using tokio.
 
+mod happy_eyeballs;
+
+pub use happy_eyeballs::NativeHostConnect;
+
+use portals_dns::{Record, RecordData, RecordType};
 use portals_sockets::{Error, Resolver, TcpConnect, TcpListener, TcpStream, UdpSocket};
-use std::net::{IpAddr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net;
 
@@ -116,14 +121,113 @@ impl UdpSocket for NativeUdpSocket {
     }
 }
 
-/// Native DNS resolver using tokio.
+/// Address resolution through the operating system (`getaddrinfo`).
+///
+/// Honours the hosts file and system DNS settings but only resolves
+/// addresses: other record types fail with [`portals_dns::Error::Other`].
+/// The system does not report TTLs, so records carry a TTL of zero.
 #[derive(Debug, Default, Clone, Copy)]
-pub struct NativeResolver;
+pub struct SystemResolver;
+
+impl SystemResolver {
+    async fn addresses(&self, host: &str) -> Result<Vec<IpAddr>, portals_dns::Error> {
+        let addrs = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| portals_dns::Error::Lookup(e.to_string()))?;
+        let mut ips: Vec<IpAddr> = Vec::new();
+        for addr in addrs {
+            if !ips.contains(&addr.ip()) {
+                ips.push(addr.ip());
+            }
+        }
+        if ips.is_empty() {
+            return Err(portals_dns::Error::NoRecords);
+        }
+        Ok(ips)
+    }
+}
+
+fn unsupported(what: &str) -> portals_dns::Error {
+    portals_dns::Error::Other(format!("the system resolver does not support {}", what))
+}
+
+impl Resolver for SystemResolver {
+    async fn lookup_ipv4(&self, host: &str) -> Result<Vec<Ipv4Addr>, portals_dns::Error> {
+        let addrs: Vec<Ipv4Addr> = self
+            .addresses(host)
+            .await?
+            .into_iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V4(v4) => Some(v4),
+                IpAddr::V6(_) => None,
+            })
+            .collect();
+        if addrs.is_empty() {
+            return Err(portals_dns::Error::NoRecords);
+        }
+        Ok(addrs)
+    }
+
+    async fn lookup_ipv6(&self, host: &str) -> Result<Vec<Ipv6Addr>, portals_dns::Error> {
+        let addrs: Vec<Ipv6Addr> = self
+            .addresses(host)
+            .await?
+            .into_iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V6(v6) => Some(v6),
+                IpAddr::V4(_) => None,
+            })
+            .collect();
+        if addrs.is_empty() {
+            return Err(portals_dns::Error::NoRecords);
+        }
+        Ok(addrs)
+    }
+
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, portals_dns::Error> {
+        self.addresses(host).await
+    }
+
+    async fn lookup_txt(&self, _host: &str) -> Result<Vec<String>, portals_dns::Error> {
+        Err(unsupported("TXT lookups"))
+    }
+
+    async fn lookup_mx(&self, _domain: &str) -> Result<Vec<(u16, String)>, portals_dns::Error> {
+        Err(unsupported("MX lookups"))
+    }
+
+    async fn reverse_lookup(&self, _addr: IpAddr) -> Result<Vec<String>, portals_dns::Error> {
+        Err(unsupported("reverse lookups"))
+    }
 
-impl Resolver for NativeResolver {
-    async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>, Error> {
-        let addrs = tokio::net::lookup_host(format!("{}:0", host)).await?;
-        Ok(addrs.map(|a| a.ip()).collect())
+    async fn lookup(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> Result<Vec<Record>, portals_dns::Error> {
+        let data: Vec<RecordData> = match record_type {
+            RecordType::A => self
+                .lookup_ipv4(name)
+                .await?
+                .into_iter()
+                .map(RecordData::A)
+                .collect(),
+            RecordType::Aaaa => self
+                .lookup_ipv6(name)
+                .await?
+                .into_iter()
+                .map(RecordData::Aaaa)
+                .collect(),
+            _ => return Err(unsupported(&format!("{} lookups", record_type))),
+        };
+        Ok(data
+            .into_iter()
+            .map(|data| Record {
+                name: name.to_string(),
+                ttl: 0,
+                data,
+            })
+            .collect())
     }
 }
 
@@ -133,9 +237,13 @@ mod tests {
 
     #[tokio::test]
     async fn resolver_works() {
-        let resolver = NativeResolver;
-        let addrs = resolver.resolve("localhost").await.unwrap();
+        let resolver = SystemResolver;
+        let addrs = resolver.lookup_ip("localhost").await.unwrap();
         assert!(!addrs.is_empty());
+        assert!(matches!(
+            resolver.lookup_txt("localhost").await,
+            Err(portals_dns::Error::Other(_))
+        ));
     }
 
     #[tokio::test]
@@ -0,0 +1,67 @@
+//! Benchmarks for cron schedule lookups.
+//!
+//! Run with `cargo bench -p portals-cron-portable`. Uses a plain timing loop
+//! rather than a benchmark framework to keep the crate dependency-free.
+
+use portals_cron::{CronParser, CronSchedule};
+use portals_cron_portable::{CronParserImpl, DayMatching};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 10_000;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // Warm up
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let per_iter = start.elapsed() / ITERATIONS;
+    println!("{:<40} {:>12?}/iter", name, per_iter);
+}
+
+fn main() {
+    let parser = CronParserImpl::new();
+    let strict = CronParserImpl::with_day_matching(DayMatching::StrictAnd);
+
+    let every_minute = parser.parse("* * * * *").unwrap();
+    bench("next_after every minute", || {
+        black_box(every_minute.next_after(black_box(2024), 1, 1, 0, 0, 0));
+    });
+
+    let weekdays = parser.parse("*/15 9-17 * * MON-FRI").unwrap();
+    bench("next_after business hours", || {
+        black_box(weekdays.next_after(black_box(2024), 1, 5, 18, 0, 0));
+    });
+
+    let leap_day = parser.parse("0 0 29 2 *").unwrap();
+    bench("next_after leap day", || {
+        black_box(leap_day.next_after(black_box(2025), 1, 1, 0, 0, 0));
+    });
+    bench("prev_before leap day", || {
+        black_box(leap_day.prev_before(black_box(2027), 1, 1, 0, 0, 0));
+    });
+
+    let leap_monday = strict.parse("0 0 29 2 MON").unwrap();
+    bench("next_after leap Monday (20 years)", || {
+        black_box(leap_monday.next_after(black_box(2024), 3, 1, 0, 0, 0));
+    });
+
+    let never = parser.parse("0 0 30 2 *").unwrap();
+    bench("next_after never matches", || {
+        black_box(never.next_after(black_box(2024), 1, 1, 0, 0, 0));
+    });
+
+    let hourly = parser.parse("0 * * * *").unwrap();
+    bench("occurrences_between one year hourly", || {
+        black_box(
+            hourly
+                .occurrences_between((2024, 1, 1, 0, 0, 0), (2025, 1, 1, 0, 0, 0))
+                .count(),
+        );
+    });
+}
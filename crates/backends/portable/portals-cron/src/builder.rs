@@ -0,0 +1,335 @@
+//! Typed construction of cron expressions.
+//!
+//! ```
+//! use portals_cron_portable::builder::{CronBuilder, Field, Weekday};
+//!
+//! let expr = CronBuilder::new()
+//!     .minute(Field::Every(15))
+//!     .hour(Field::Range(9, 17))
+//!     .weekday_range(Weekday::Monday, Weekday::Friday)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(expr, "*/15 9-17 * * 1-5");
+//! ```
+
+use crate::Cron;
+use portals_cron::{CronError, CronParser};
+
+/// The values one field matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Field {
+    /// Any value (`*`).
+    #[default]
+    Any,
+    /// A single value.
+    At(u8),
+    /// An inclusive range of values (`a-b`).
+    Range(u8, u8),
+    /// Every nth value across the whole field (`*/n`).
+    Every(u8),
+    /// Every nth value within an inclusive range (`a-b/n`).
+    RangeEvery(u8, u8, u8),
+    /// Specific values (`a,b,c`).
+    List(Vec<u8>),
+}
+
+impl Field {
+    fn render(&self, field: &'static str, min: u8, max: u8) -> Result<String, CronError> {
+        let check = |value: u8| {
+            if value < min || value > max {
+                Err(CronError::OutOfRange {
+                    field,
+                    value: value as u32,
+                    min: min as u32,
+                    max: max as u32,
+                })
+            } else {
+                Ok(value)
+            }
+        };
+        let check_step = |step: u8| {
+            if step == 0 {
+                Err(CronError::InvalidStep { field, step: 0 })
+            } else {
+                Ok(step)
+            }
+        };
+        let check_range = |start: u8, end: u8| {
+            check(start)?;
+            check(end)?;
+            if start > end {
+                Err(CronError::InvalidField {
+                    field,
+                    value: format!("{}-{}", start, end),
+                    reason: "range start is after its end".to_string(),
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        Ok(match self {
+            Self::Any => "*".to_string(),
+            Self::At(value) => check(*value)?.to_string(),
+            Self::Range(start, end) => {
+                check_range(*start, *end)?;
+                format!("{}-{}", start, end)
+            }
+            Self::Every(step) => format!("*/{}", check_step(*step)?),
+            Self::RangeEvery(start, end, step) => {
+                check_range(*start, *end)?;
+                format!("{}-{}/{}", start, end, check_step(*step)?)
+            }
+            Self::List(values) => {
+                if values.is_empty() {
+                    return Err(CronError::InvalidField {
+                        field,
+                        value: String::new(),
+                        reason: "empty list".to_string(),
+                    });
+                }
+                values
+                    .iter()
+                    .map(|&v| check(v).map(|v| v.to_string()))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(",")
+            }
+        })
+    }
+}
+
+/// Day of the week, numbered as in cron (Sunday is `0`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Weekday {
+    Sunday = 0,
+    Monday = 1,
+    Tuesday = 2,
+    Wednesday = 3,
+    Thursday = 4,
+    Friday = 5,
+    Saturday = 6,
+}
+
+/// Month of the year, numbered as in cron (January is `1`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Month {
+    January = 1,
+    February = 2,
+    March = 3,
+    April = 4,
+    May = 5,
+    June = 6,
+    July = 7,
+    August = 8,
+    September = 9,
+    October = 10,
+    November = 11,
+    December = 12,
+}
+
+/// Builds cron expressions from typed fields.
+///
+/// Unset fields are `*`, except the minute, which defaults to `0` so that
+/// `CronBuilder::new().hour(Field::At(9))` means "at 09:00" rather than
+/// "every minute of the 9 o'clock hour". A seconds field is only emitted
+/// when [`second`](Self::second) is called.
+#[derive(Debug, Clone)]
+pub struct CronBuilder {
+    second: Option<Field>,
+    minute: Field,
+    hour: Field,
+    day: Field,
+    month: Field,
+    weekday: Field,
+}
+
+impl Default for CronBuilder {
+    fn default() -> Self {
+        Self {
+            second: None,
+            minute: Field::At(0),
+            hour: Field::Any,
+            day: Field::Any,
+            month: Field::Any,
+            weekday: Field::Any,
+        }
+    }
+}
+
+impl CronBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the seconds field, producing a 6-field expression.
+    pub fn second(mut self, field: Field) -> Self {
+        self.second = Some(field);
+        self
+    }
+
+    pub fn minute(mut self, field: Field) -> Self {
+        self.minute = field;
+        self
+    }
+
+    pub fn hour(mut self, field: Field) -> Self {
+        self.hour = field;
+        self
+    }
+
+    /// Set the day-of-month field.
+    pub fn day(mut self, field: Field) -> Self {
+        self.day = field;
+        self
+    }
+
+    pub fn month(mut self, field: Field) -> Self {
+        self.month = field;
+        self
+    }
+
+    /// Set the day-of-week field (0-6, Sunday is 0).
+    pub fn weekday(mut self, field: Field) -> Self {
+        self.weekday = field;
+        self
+    }
+
+    /// Run once a day at the given time.
+    pub fn at(self, hour: u8, minute: u8) -> Self {
+        self.hour(Field::At(hour)).minute(Field::At(minute))
+    }
+
+    /// Run on the given days of the week.
+    pub fn on_weekdays(self, weekdays: &[Weekday]) -> Self {
+        self.weekday(Field::List(weekdays.iter().map(|&d| d as u8).collect()))
+    }
+
+    /// Run on an inclusive range of weekdays.
+    pub fn weekday_range(self, first: Weekday, last: Weekday) -> Self {
+        self.weekday(Field::Range(first as u8, last as u8))
+    }
+
+    /// Run in the given months.
+    pub fn in_months(self, months: &[Month]) -> Self {
+        self.month(Field::List(months.iter().map(|&m| m as u8).collect()))
+    }
+
+    /// Render the expression, validating every field.
+    pub fn build(&self) -> Result<String, CronError> {
+        let mut fields = Vec::with_capacity(6);
+        if let Some(second) = &self.second {
+            fields.push(second.render("second", 0, 59)?);
+        }
+        fields.push(self.minute.render("minute", 0, 59)?);
+        fields.push(self.hour.render("hour", 0, 23)?);
+        fields.push(self.day.render("day", 1, 31)?);
+        fields.push(self.month.render("month", 1, 12)?);
+        fields.push(self.weekday.render("weekday", 0, 6)?);
+        Ok(fields.join(" "))
+    }
+
+    /// Build and parse the expression with the given parser.
+    pub fn build_with<P: CronParser<Expr = Cron>>(&self, parser: &P) -> Result<Cron, CronError> {
+        let expr = self.build()?;
+        if self.second.is_some() {
+            parser.parse_with_seconds(&expr)
+        } else {
+            parser.parse(&expr)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CronParserImpl;
+    use portals_cron::CronExpr;
+
+    #[test]
+    fn build_expressions() {
+        assert_eq!(CronBuilder::new().build().unwrap(), "0 * * * *");
+        assert_eq!(CronBuilder::new().at(8, 30).build().unwrap(), "30 8 * * *");
+        assert_eq!(
+            CronBuilder::new()
+                .minute(Field::Every(15))
+                .hour(Field::Range(9, 17))
+                .weekday_range(Weekday::Monday, Weekday::Friday)
+                .build()
+                .unwrap(),
+            "*/15 9-17 * * 1-5"
+        );
+        assert_eq!(
+            CronBuilder::new()
+                .at(0, 0)
+                .day(Field::At(1))
+                .in_months(&[Month::January, Month::July])
+                .build()
+                .unwrap(),
+            "0 0 1 1,7 *"
+        );
+        assert_eq!(
+            CronBuilder::new()
+                .second(Field::RangeEvery(0, 30, 10))
+                .minute(Field::Any)
+                .build()
+                .unwrap(),
+            "0-30/10 * * * * *"
+        );
+    }
+
+    #[test]
+    fn build_rejects_invalid_fields() {
+        assert!(matches!(
+            CronBuilder::new().at(24, 0).build(),
+            Err(CronError::OutOfRange {
+                field: "hour",
+                value: 24,
+                ..
+            })
+        ));
+        assert!(matches!(
+            CronBuilder::new().day(Field::At(0)).build(),
+            Err(CronError::OutOfRange { field: "day", .. })
+        ));
+        assert!(matches!(
+            CronBuilder::new().minute(Field::Every(0)).build(),
+            Err(CronError::InvalidStep {
+                field: "minute",
+                step: 0
+            })
+        ));
+        assert!(matches!(
+            CronBuilder::new().hour(Field::Range(17, 9)).build(),
+            Err(CronError::InvalidField { field: "hour", .. })
+        ));
+        assert!(matches!(
+            CronBuilder::new().on_weekdays(&[]).build(),
+            Err(CronError::InvalidField {
+                field: "weekday",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn build_with_parser() {
+        let parser = CronParserImpl::new();
+        let cron = CronBuilder::new()
+            .at(9, 0)
+            .on_weekdays(&[Weekday::Monday, Weekday::Wednesday])
+            .build_with(&parser)
+            .unwrap();
+        assert!(cron.matches(0, 0, 9, 3, 6, 1));
+        assert!(!cron.matches(0, 0, 9, 4, 6, 2));
+        assert_eq!(cron.describe(), "at 09:00, on Monday and Wednesday");
+
+        let cron = CronBuilder::new()
+            .second(Field::At(30))
+            .minute(Field::Any)
+            .build_with(&parser)
+            .unwrap();
+        assert_eq!(cron.to_canonical(), "30 * * * * *");
+    }
+}
@@ -0,0 +1,539 @@
+//! English descriptions and canonical forms of parsed expressions.
+
+use crate::{Cron, DayMatching, FieldMatcher};
+
+/// English weekday names, starting at 0 (Sunday).
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// English month names, starting at 1 (January).
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A run of values within a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Segment {
+    Value(u8),
+    Range(u8, u8),
+    /// `start`, `start + step`, ... up to and including `last`.
+    Step {
+        start: u8,
+        last: u8,
+        step: u8,
+    },
+}
+
+/// A field's matching values, compressed for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Shape {
+    /// Every value in the field's range.
+    All,
+    /// Every `step`th value from the field's minimum, like `*/step`.
+    Every(u8),
+    Segments(Vec<Segment>),
+}
+
+impl Shape {
+    fn of(field: &FieldMatcher, min: u8, max: u8) -> Self {
+        let values = match field {
+            FieldMatcher::Any => return Self::All,
+            FieldMatcher::Values(values) => values,
+        };
+
+        if values.len() == (max - min + 1) as usize {
+            return Self::All;
+        }
+
+        if let [first, second, ..] = values[..] {
+            let step = second - first;
+            let progression = step > 1 && values.windows(2).all(|w| w[1] - w[0] == step);
+            let last = values[values.len() - 1];
+            let to_end = last + step > max;
+            if progression && first == min && to_end {
+                return Self::Every(step);
+            }
+            if progression && values.len() > 2 {
+                return Self::Segments(vec![Segment::Step {
+                    start: first,
+                    last,
+                    step,
+                }]);
+            }
+        }
+
+        // Consecutive runs of three or more become ranges.
+        let mut segments = Vec::new();
+        let mut i = 0;
+        while i < values.len() {
+            let mut j = i;
+            while j + 1 < values.len() && values[j + 1] == values[j] + 1 {
+                j += 1;
+            }
+            if j - i >= 2 {
+                segments.push(Segment::Range(values[i], values[j]));
+            } else {
+                segments.extend(values[i..=j].iter().map(|&v| Segment::Value(v)));
+            }
+            i = j + 1;
+        }
+        Self::Segments(segments)
+    }
+
+    /// The single value this field matches, if any.
+    fn single(&self) -> Option<u8> {
+        match self {
+            Self::Segments(segments) => match segments[..] {
+                [Segment::Value(v)] => Some(v),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// All matched values, if the field is a plain list of them.
+    fn values(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::Segments(segments) => segments
+                .iter()
+                .map(|s| match s {
+                    Segment::Value(v) => Some(*v),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    /// Format in cron syntax. `star` allows `*` forms, which for the day
+    /// fields also marks them as unrestricted.
+    fn to_expr(&self, min: u8, max: u8, star: bool) -> String {
+        match self {
+            Self::All if star => "*".to_string(),
+            Self::All => format!("{}-{}", min, max),
+            Self::Every(step) if star => format!("*/{}", step),
+            Self::Every(step) => {
+                let last = min + (max - min) / step * step;
+                format!("{}-{}/{}", min, last, step)
+            }
+            Self::Segments(segments) => segments
+                .iter()
+                .map(|s| match *s {
+                    Segment::Value(v) => v.to_string(),
+                    Segment::Range(a, b) => format!("{}-{}", a, b),
+                    Segment::Step { start, last, step } => {
+                        format!("{}-{}/{}", start, last, step)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Join phrases as English prose: "a", "a and b", "a, b and c".
+fn join(items: &[String], conjunction: &str) -> String {
+    match items {
+        [] => String::new(),
+        [one] => one.clone(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), conjunction, last),
+    }
+}
+
+/// Describe segments of a numeric field, e.g. "1, 5 through 7 and 10".
+fn numbers(segments: &[Segment], fmt: impl Fn(u8) -> String) -> String {
+    let mut parts = Vec::new();
+    for segment in segments {
+        match *segment {
+            Segment::Value(v) => parts.push(fmt(v)),
+            Segment::Range(a, b) => parts.push(format!("{} through {}", fmt(a), fmt(b))),
+            // Short progressions read better as a list.
+            Segment::Step { start, last, step } if (last - start) / step < 4 => {
+                parts.extend((start..=last).step_by(step as usize).map(&fmt))
+            }
+            Segment::Step { start, last, step } => parts.push(format!(
+                "{} through {} in steps of {}",
+                fmt(start),
+                fmt(last),
+                step
+            )),
+        }
+    }
+    join(&parts, "and")
+}
+
+/// Describe a named field (weekdays, months), e.g. "Monday through Friday"
+/// or "on Monday and Friday". Steps are spelled out as individual names.
+fn names(shape: &Shape, min: u8, max: u8, table: &[&str], first: u8, preposition: &str) -> String {
+    let name = |v: u8| table[(v - first) as usize].to_string();
+    let segments = match shape {
+        Shape::Segments(segments) => segments.clone(),
+        Shape::Every(step) => (min..=max)
+            .step_by(*step as usize)
+            .map(Segment::Value)
+            .collect(),
+        Shape::All => vec![Segment::Range(min, max)],
+    };
+
+    if let [Segment::Range(a, b)] = segments[..] {
+        return format!("{} through {}", name(a), name(b));
+    }
+
+    let mut parts = Vec::new();
+    for segment in segments {
+        match segment {
+            Segment::Value(v) => parts.push(name(v)),
+            Segment::Range(a, b) => parts.push(format!("{} through {}", name(a), name(b))),
+            Segment::Step { start, last, step } => {
+                parts.extend((start..=last).step_by(step as usize).map(name))
+            }
+        }
+    }
+    format!("{} {}", preposition, join(&parts, "and"))
+}
+
+fn time(hour: u8, minute: u8, second: Option<u8>) -> String {
+    match second {
+        Some(s) if s != 0 => format!("{:02}:{:02}:{:02}", hour, minute, s),
+        _ => format!("{:02}:{:02}", hour, minute),
+    }
+}
+
+fn plural(n: u8, unit: &str) -> String {
+    if n == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", n, unit)
+    }
+}
+
+impl Cron {
+    /// Describe the schedule in English.
+    ///
+    /// `*/15 9-17 * * 1-5` reads "every 15 minutes, between 09:00 and
+    /// 17:59, Monday through Friday".
+    pub fn describe(&self) -> String {
+        let seconds = Shape::of(&self.seconds, 0, 59);
+        let minutes = Shape::of(&self.minutes, 0, 59);
+        let hours = Shape::of(&self.hours, 0, 23);
+
+        let mut parts = Vec::new();
+        parts.extend(self.describe_time(&seconds, &minutes, &hours));
+        parts.extend(self.describe_days());
+
+        let months = Shape::of(&self.months, 1, 12);
+        if months != Shape::All {
+            parts.push(names(&months, 1, 12, &MONTHS, 1, "in"));
+        }
+
+        parts.join(", ")
+    }
+
+    fn describe_time(&self, seconds: &Shape, minutes: &Shape, hours: &Shape) -> Vec<String> {
+        let mut parts = Vec::new();
+        let second = seconds.single();
+
+        // Specific times of day: "at 09:30 and 17:30"
+        if let (Some(minute), Some(hours), Some(_)) = (minutes.single(), hours.values(), second) {
+            let times: Vec<String> = hours.iter().map(|&h| time(h, minute, second)).collect();
+            return vec![format!("at {}", join(&times, "and"))];
+        }
+
+        match seconds {
+            _ if second == Some(0) => {}
+            Shape::All => parts.push("every second".to_string()),
+            Shape::Every(step) => parts.push(format!("every {}", plural(*step, "second"))),
+            Shape::Segments(_) if second.is_some() => parts.push(format!(
+                "at {} past the minute",
+                plural(second.unwrap_or_default(), "second")
+            )),
+            Shape::Segments(segments) => parts.push(format!(
+                "at seconds {} past the minute",
+                numbers(segments, |v| v.to_string())
+            )),
+        }
+
+        match minutes {
+            Shape::All if parts.is_empty() => parts.push("every minute".to_string()),
+            Shape::All => {}
+            Shape::Every(step) => parts.push(format!("every {}", plural(*step, "minute"))),
+            Shape::Segments(_) if minutes.single().is_some() => parts.push(format!(
+                "at {} past the hour",
+                plural(minutes.single().unwrap_or_default(), "minute")
+            )),
+            Shape::Segments(segments) => parts.push(format!(
+                "at minutes {} past the hour",
+                numbers(segments, |v| v.to_string())
+            )),
+        }
+
+        let hour_start = |h: u8| format!("{:02}:00", h);
+        match hours {
+            Shape::All => {}
+            Shape::Every(step) => parts.push(format!("every {}", plural(*step, "hour"))),
+            Shape::Segments(segments) => match segments[..] {
+                [Segment::Value(h)] => parts.push(format!("between {:02}:00 and {:02}:59", h, h)),
+                [Segment::Range(a, b)] => {
+                    parts.push(format!("between {:02}:00 and {:02}:59", a, b))
+                }
+                [Segment::Step { start, last, step }] => parts.push(format!(
+                    "every {} between {:02}:00 and {:02}:59",
+                    plural(step, "hour"),
+                    start,
+                    last
+                )),
+                _ => parts.push(format!(
+                    "during the {} hours",
+                    numbers(segments, hour_start)
+                )),
+            },
+        }
+
+        parts
+    }
+
+    fn describe_days(&self) -> Option<String> {
+        let days = Shape::of(&self.days, 1, 31);
+        let weekdays = Shape::of(&self.weekdays, 0, 6);
+
+        // Whether a field is restricted comes from the raw expression, as in
+        // matching: `1-31` or `0-6` still switches on OR semantics, and then
+        // the job runs every day.
+        let or = self.combines_days_with_or();
+        if or && (days == Shape::All || weekdays == Shape::All) {
+            return None;
+        }
+
+        let day_phrase = match &days {
+            Shape::All => None,
+            Shape::Every(step) => Some(format!("every {}", plural(*step, "day"))),
+            Shape::Segments(segments) => {
+                let unit = if days.single().is_some() {
+                    "day"
+                } else {
+                    "days"
+                };
+                Some(format!(
+                    "on {} {} of the month",
+                    unit,
+                    numbers(segments, |v| v.to_string())
+                ))
+            }
+        };
+        let weekday_phrase =
+            (weekdays != Shape::All).then(|| names(&weekdays, 0, 6, &WEEKDAYS, 0, "on"));
+
+        match (day_phrase, weekday_phrase) {
+            (Some(d), Some(w)) if or => Some(format!("{} or {}", d, w)),
+            (Some(d), Some(w)) => Some(format!(
+                "{}, only if it is {}",
+                d,
+                w.strip_prefix("on ").unwrap_or(&w)
+            )),
+            (d, w) => d.or(w),
+        }
+    }
+
+    fn combines_days_with_or(&self) -> bool {
+        self.day_matching == DayMatching::Vixie && self.days_restricted && self.weekdays_restricted
+    }
+
+    /// The expression in canonical form.
+    ///
+    /// Names become numbers, Sunday is `0`, lists are sorted and compressed
+    /// into ranges and steps, and a seconds field is only included when it
+    /// is not just `0`. The result parses back to the same schedule, with
+    /// [`CronParser::parse`](portals_cron::CronParser::parse) or
+    /// [`CronParser::parse_with_seconds`](portals_cron::CronParser::parse_with_seconds)
+    /// depending on the field count.
+    pub fn to_canonical(&self) -> String {
+        let mut fields = Vec::with_capacity(6);
+        let seconds = Shape::of(&self.seconds, 0, 59);
+        if seconds.single() != Some(0) {
+            fields.push(seconds.to_expr(0, 59, true));
+        }
+        fields.push(Shape::of(&self.minutes, 0, 59).to_expr(0, 59, true));
+        fields.push(Shape::of(&self.hours, 0, 23).to_expr(0, 23, true));
+        fields.push(Shape::of(&self.days, 1, 31).to_expr(1, 31, !self.days_restricted));
+        fields.push(Shape::of(&self.months, 1, 12).to_expr(1, 12, true));
+        fields.push(Shape::of(&self.weekdays, 0, 6).to_expr(0, 6, !self.weekdays_restricted));
+        fields.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CronParserImpl, DayMatching};
+    use portals_cron::{CronExpr, CronParser};
+
+    fn describe(expr: &str) -> String {
+        CronParserImpl::new().parse(expr).unwrap().describe()
+    }
+
+    #[test]
+    fn describe_common_expressions() {
+        assert_eq!(
+            describe("*/15 9-17 * * 1-5"),
+            "every 15 minutes, between 09:00 and 17:59, Monday through Friday"
+        );
+        assert_eq!(describe("* * * * *"), "every minute");
+        assert_eq!(describe("30 8 * * *"), "at 08:30");
+        assert_eq!(describe("0 9,17 * * *"), "at 09:00 and 17:00");
+        assert_eq!(describe("5 * * * *"), "at 5 minutes past the hour");
+        assert_eq!(
+            describe("30 */2 * * *"),
+            "at 30 minutes past the hour, every 2 hours"
+        );
+        assert_eq!(
+            describe("* 9 * * *"),
+            "every minute, between 09:00 and 09:59"
+        );
+        assert_eq!(
+            describe("0,10,20 * * * *"),
+            "at minutes 0, 10 and 20 past the hour"
+        );
+        assert_eq!(
+            describe("5-55/10 * * * *"),
+            "at minutes 5 through 55 in steps of 10 past the hour"
+        );
+        assert_eq!(
+            describe("0 8,12-14,18 * * *"),
+            "at 0 minutes past the hour, during the 08:00, 12:00 through 14:00 and 18:00 hours"
+        );
+    }
+
+    #[test]
+    fn describe_days_and_months() {
+        assert_eq!(describe("0 0 1 * *"), "at 00:00, on day 1 of the month");
+        assert_eq!(
+            describe("0 0 1,15 * *"),
+            "at 00:00, on days 1 and 15 of the month"
+        );
+        assert_eq!(describe("0 12 * * MON"), "at 12:00, on Monday");
+        assert_eq!(
+            describe("0 12 * * 1,3,5"),
+            "at 12:00, on Monday, Wednesday and Friday"
+        );
+        assert_eq!(describe("0 0 */2 * *"), "at 00:00, every 2 days");
+        assert_eq!(
+            describe("0 0 1 1,7 *"),
+            "at 00:00, on day 1 of the month, in January and July"
+        );
+        assert_eq!(
+            describe("0 0 * JAN-MAR *"),
+            "at 00:00, January through March"
+        );
+        assert_eq!(
+            describe("0 0 1 */3 *"),
+            "at 00:00, on day 1 of the month, in January, April, July and October"
+        );
+    }
+
+    #[test]
+    fn describe_day_matching() {
+        assert_eq!(
+            describe("0 0 1 * MON"),
+            "at 00:00, on day 1 of the month or on Monday"
+        );
+        // A full range is still a restriction, so OR makes these daily.
+        assert_eq!(describe("0 0 1-31 * MON"), "at 00:00");
+        assert_eq!(describe("0 0 1 * 0-6"), "at 00:00");
+
+        let strict = CronParserImpl::with_day_matching(DayMatching::StrictAnd);
+        assert_eq!(
+            strict.parse("0 0 13 * FRI").unwrap().describe(),
+            "at 00:00, on day 13 of the month, only if it is Friday"
+        );
+        assert_eq!(
+            strict.parse("0 0 1-31 * MON").unwrap().describe(),
+            "at 00:00, on Monday"
+        );
+    }
+
+    #[test]
+    fn describe_seconds() {
+        let parser = CronParserImpl::new();
+        let describe = |e| parser.parse_with_seconds(e).unwrap().describe();
+        assert_eq!(describe("* * * * * *"), "every second");
+        assert_eq!(describe("*/10 * * * * *"), "every 10 seconds");
+        assert_eq!(describe("30 * * * * *"), "at 30 seconds past the minute");
+        assert_eq!(describe("15 30 9 * * *"), "at 09:30:15");
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let parser = CronParserImpl::new();
+        let canonical = |e| parser.parse(e).unwrap().to_canonical();
+        assert_eq!(canonical("*/15 9-17 * * MON-FRI"), "*/15 9-17 * * 1-5");
+        assert_eq!(canonical("0,15,30,45 * * * *"), "*/15 * * * *");
+        assert_eq!(canonical("0 0 * * 7"), "0 0 * * 0");
+        assert_eq!(canonical("0 0 * * 5-7"), "0 0 * * 0,5,6");
+        assert_eq!(canonical("0 0 1-31 * *"), "0 0 1-31 * *");
+        assert_eq!(canonical("0 0 1-31/2 * MON"), "0 0 1-31/2 * 1");
+        assert_eq!(canonical("0 0 */2 * MON"), "0 0 */2 * 1");
+        assert_eq!(canonical("0 0 5,10,15,20 * *"), "0 0 5-20/5 * *");
+        assert_eq!(
+            parser
+                .parse_with_seconds("*/5 0 0 * * *")
+                .unwrap()
+                .to_canonical(),
+            "*/5 0 0 * * *"
+        );
+    }
+
+    #[test]
+    fn canonical_round_trips() {
+        let parser = CronParserImpl::new();
+        let exprs = [
+            "*/15 9-17 * * MON-FRI",
+            "0 0 1 * MON",
+            "0 0 */2 * MON",
+            "0 0 1-31/2 * MON",
+            "0 0 1-31 * MON",
+            "0 0 1 * 0-6",
+            "5,6,7,30 */3 1,15 JAN,JUL sun",
+            "0 12 * * 5-7",
+            "0 0 29 2 *",
+        ];
+        for expr in exprs {
+            let cron = parser.parse(expr).unwrap();
+            let canonical = cron.to_canonical();
+            let reparsed = parser.parse(&canonical).unwrap();
+            assert_eq!(reparsed.to_canonical(), canonical, "{}", expr);
+            assert_eq!(reparsed.describe(), cron.describe(), "{}", expr);
+            for (day, weekday) in [(1, 1), (2, 3), (13, 5), (15, 0), (29, 4)] {
+                for month in [1, 2, 7] {
+                    for hour in [0, 3, 9, 12] {
+                        for minute in [0, 5, 15, 30] {
+                            assert_eq!(
+                                cron.matches(0, minute, hour, day, month, weekday),
+                                reparsed.matches(0, minute, hour, day, month, weekday),
+                                "{} vs {}",
+                                expr,
+                                canonical
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
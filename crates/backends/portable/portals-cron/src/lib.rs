@@ -1,10 +1,23 @@
 //! Portable cron expression implementation.
 //!
 //! Works on both native and WASM targets.
+//!
+//! The [`scheduler`] module, behind the default `scheduler` feature, runs
+//! async jobs on cron schedules, and [`builder`] constructs expressions from
+//! typed fields. Parsed expressions can be described in English with
+//! [`Cron::describe`].
+
+pub mod builder;
+mod describe;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
 
-use portals_cron::{CronError, CronExpr, CronParser, CronSchedule};
+use portals_cron::{CronDateTime, CronError, CronExpr, CronParser, CronSchedule};
 use std::fmt;
 
+#[cfg(feature = "timezone")]
+use portals_timezone::{DateTime, TimeZone, Timestamp, Zoned};
+
 /// A parsed cron expression.
 #[derive(Debug, Clone)]
 pub struct Cron {
@@ -15,6 +28,29 @@ pub struct Cron {
     days: FieldMatcher,
     months: FieldMatcher,
     weekdays: FieldMatcher,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+    /// No `*` in the second, minute or hour fields (Vixie's "fixed-time"
+    /// jobs, which get special treatment across DST transitions).
+    #[cfg_attr(not(feature = "timezone"), allow(dead_code))]
+    fixed_time: bool,
+    day_matching: DayMatching,
+}
+
+/// How the day-of-month and day-of-week fields combine.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DayMatching {
+    /// Standard Vixie cron semantics.
+    ///
+    /// When both day fields are restricted, a date matches if *either* field
+    /// matches: `0 0 1 * MON` fires on the 1st and on every Monday. If either
+    /// field starts with `*`, both must match.
+    #[default]
+    Vixie,
+    /// Both day fields must always match.
+    ///
+    /// `0 0 1 * MON` fires only when the 1st falls on a Monday.
+    StrictAnd,
 }
 
 /// Matches values for a cron field.
@@ -34,7 +70,29 @@ impl FieldMatcher {
         }
     }
 
-    fn parse(s: &str, field: &'static str, min: u8, max: u8) -> Result<Self, CronError> {
+    /// Smallest matching value at or above `value`.
+    fn next_from(&self, value: u8) -> Option<u8> {
+        match self {
+            Self::Any => Some(value),
+            Self::Values(values) => values.iter().copied().find(|&v| v >= value),
+        }
+    }
+
+    /// Largest matching value at or below `value`.
+    fn prev_from(&self, value: u8) -> Option<u8> {
+        match self {
+            Self::Any => Some(value),
+            Self::Values(values) => values.iter().rev().copied().find(|&v| v <= value),
+        }
+    }
+
+    fn parse(
+        s: &str,
+        field: &'static str,
+        min: u8,
+        max: u8,
+        names: &[&str],
+    ) -> Result<Self, CronError> {
         let s = s.trim();
 
         if s == "*" {
@@ -64,23 +122,24 @@ impl FieldMatcher {
                 let (start, end) = if range == "*" {
                     (min, max)
                 } else if let Some((a, b)) = range.split_once('-') {
-                    let a: u8 = a.parse().map_err(|_| CronError::InvalidField {
+                    let a = parse_value(a, names, min).ok_or_else(|| CronError::InvalidField {
                         field,
                         value: part.to_string(),
                         reason: "invalid range start".to_string(),
                     })?;
-                    let b: u8 = b.parse().map_err(|_| CronError::InvalidField {
+                    let b = parse_value(b, names, min).ok_or_else(|| CronError::InvalidField {
                         field,
                         value: part.to_string(),
                         reason: "invalid range end".to_string(),
                     })?;
                     (a, b)
                 } else {
-                    let v: u8 = range.parse().map_err(|_| CronError::InvalidField {
-                        field,
-                        value: part.to_string(),
-                        reason: "invalid value".to_string(),
-                    })?;
+                    let v =
+                        parse_value(range, names, min).ok_or_else(|| CronError::InvalidField {
+                            field,
+                            value: part.to_string(),
+                            reason: "invalid value".to_string(),
+                        })?;
                     (v, max)
                 };
 
@@ -91,12 +150,13 @@ impl FieldMatcher {
                 }
             } else if let Some((start, end)) = part.split_once('-') {
                 // Range: 1-5
-                let start: u8 = start.parse().map_err(|_| CronError::InvalidField {
-                    field,
-                    value: part.to_string(),
-                    reason: "invalid range start".to_string(),
-                })?;
-                let end: u8 = end.parse().map_err(|_| CronError::InvalidField {
+                let start =
+                    parse_value(start, names, min).ok_or_else(|| CronError::InvalidField {
+                        field,
+                        value: part.to_string(),
+                        reason: "invalid range start".to_string(),
+                    })?;
+                let end = parse_value(end, names, min).ok_or_else(|| CronError::InvalidField {
                     field,
                     value: part.to_string(),
                     reason: "invalid range end".to_string(),
@@ -125,7 +185,7 @@ impl FieldMatcher {
                 }
             } else {
                 // Single value
-                let v: u8 = part.parse().map_err(|_| CronError::InvalidField {
+                let v = parse_value(part, names, min).ok_or_else(|| CronError::InvalidField {
                     field,
                     value: part.to_string(),
                     reason: "invalid value".to_string(),
@@ -149,10 +209,44 @@ impl FieldMatcher {
         values.sort();
         Ok(Self::Values(values))
     }
+
+    /// Parse the day-of-week field, folding `7` into Sunday (`0`).
+    fn parse_weekday(s: &str) -> Result<Self, CronError> {
+        match Self::parse(s, "weekday", 0, 7, &WEEKDAY_NAMES)? {
+            Self::Any => Ok(Self::Any),
+            Self::Values(values) => {
+                let mut values: Vec<u8> = values.into_iter().map(|v| v % 7).collect();
+                values.sort();
+                values.dedup();
+                Ok(Self::Values(values))
+            }
+        }
+    }
+}
+
+/// Month names accepted in the month field, starting at 1.
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+
+/// Weekday names accepted in the day-of-week field, starting at 0 (Sunday).
+const WEEKDAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Parse a numeric field value or a case-insensitive name.
+///
+/// `names[0]` corresponds to `first`.
+fn parse_value(s: &str, names: &[&str], first: u8) -> Option<u8> {
+    if let Ok(v) = s.parse() {
+        return Some(v);
+    }
+    names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(s))
+        .map(|i| first + i as u8)
 }
 
 impl Cron {
-    fn parse_5_field(expr: &str) -> Result<Self, CronError> {
+    fn parse_5_field(expr: &str, day_matching: DayMatching) -> Result<Self, CronError> {
         let fields: Vec<&str> = expr.split_whitespace().collect();
         if fields.len() != 5 {
             return Err(CronError::InvalidFieldCount {
@@ -161,18 +255,11 @@ impl Cron {
             });
         }
 
-        Ok(Self {
-            expr: expr.to_string(),
-            seconds: FieldMatcher::Values(vec![0]), // Default to 0 seconds
-            minutes: FieldMatcher::parse(fields[0], "minute", 0, 59)?,
-            hours: FieldMatcher::parse(fields[1], "hour", 0, 23)?,
-            days: FieldMatcher::parse(fields[2], "day", 1, 31)?,
-            months: FieldMatcher::parse(fields[3], "month", 1, 12)?,
-            weekdays: FieldMatcher::parse(fields[4], "weekday", 0, 6)?,
-        })
+        // Default to 0 seconds
+        Self::from_fields(expr, None, &fields, day_matching)
     }
 
-    fn parse_6_field(expr: &str) -> Result<Self, CronError> {
+    fn parse_6_field(expr: &str, day_matching: DayMatching) -> Result<Self, CronError> {
         let fields: Vec<&str> = expr.split_whitespace().collect();
         if fields.len() != 6 {
             return Err(CronError::InvalidFieldCount {
@@ -181,16 +268,57 @@ impl Cron {
             });
         }
 
+        Self::from_fields(expr, Some(fields[0]), &fields[1..], day_matching)
+    }
+
+    /// Build from an optional seconds field and the five
+    /// `minute hour day month weekday` fields.
+    fn from_fields(
+        expr: &str,
+        seconds: Option<&str>,
+        fields: &[&str],
+        day_matching: DayMatching,
+    ) -> Result<Self, CronError> {
+        let seconds_wildcard = seconds.is_some_and(|s| s.starts_with('*'));
+        let seconds = match seconds {
+            Some(s) => FieldMatcher::parse(s, "second", 0, 59, &[])?,
+            None => FieldMatcher::Values(vec![0]),
+        };
+
         Ok(Self {
             expr: expr.to_string(),
-            seconds: FieldMatcher::parse(fields[0], "second", 0, 59)?,
-            minutes: FieldMatcher::parse(fields[1], "minute", 0, 59)?,
-            hours: FieldMatcher::parse(fields[2], "hour", 0, 23)?,
-            days: FieldMatcher::parse(fields[3], "day", 1, 31)?,
-            months: FieldMatcher::parse(fields[4], "month", 1, 12)?,
-            weekdays: FieldMatcher::parse(fields[5], "weekday", 0, 6)?,
+            seconds,
+            minutes: FieldMatcher::parse(fields[0], "minute", 0, 59, &[])?,
+            hours: FieldMatcher::parse(fields[1], "hour", 0, 23, &[])?,
+            days: FieldMatcher::parse(fields[2], "day", 1, 31, &[])?,
+            months: FieldMatcher::parse(fields[3], "month", 1, 12, &MONTH_NAMES)?,
+            weekdays: FieldMatcher::parse_weekday(fields[4])?,
+            // Like Vixie cron, a field starting with `*` (including `*/n`)
+            // counts as unrestricted for day matching purposes.
+            days_restricted: !fields[2].starts_with('*'),
+            weekdays_restricted: !fields[4].starts_with('*'),
+            fixed_time: !seconds_wildcard
+                && !fields[0].starts_with('*')
+                && !fields[1].starts_with('*'),
+            day_matching,
         })
     }
+
+    /// How the day-of-month and day-of-week fields are combined.
+    pub fn day_matching(&self) -> DayMatching {
+        self.day_matching
+    }
+
+    fn matches_day(&self, day: u8, weekday: u8) -> bool {
+        let day_ok = self.days.matches(day);
+        let weekday_ok = self.weekdays.matches(weekday);
+        match self.day_matching {
+            DayMatching::Vixie if self.days_restricted && self.weekdays_restricted => {
+                day_ok || weekday_ok
+            }
+            DayMatching::Vixie | DayMatching::StrictAnd => day_ok && weekday_ok,
+        }
+    }
 }
 
 impl CronExpr for Cron {
@@ -198,9 +326,8 @@ impl CronExpr for Cron {
         self.seconds.matches(second)
             && self.minutes.matches(minute)
             && self.hours.matches(hour)
-            && self.days.matches(day)
             && self.months.matches(month)
-            && self.weekdays.matches(weekday)
+            && self.matches_day(day, weekday)
     }
 
     fn as_str(&self) -> &str {
@@ -223,61 +350,402 @@ impl CronSchedule for Cron {
         hour: u8,
         minute: u8,
         second: u8,
-    ) -> Option<(i32, u8, u8, u8, u8, u8)> {
-        // Simple brute-force search with reasonable limit
-        let mut y = year;
-        let mut mo = month;
-        let mut d = day;
-        let mut h = hour;
-        let mut mi = minute;
-        let mut s = second + 1;
-
-        // Search up to 4 years ahead
-        let max_year = year + 4;
-
-        while y <= max_year {
-            // Normalize overflow
-            if s > 59 {
-                s = 0;
-                mi += 1;
+    ) -> Option<CronDateTime> {
+        let start = Cursor::new(year, month, day, hour, minute, second as i32 + 1);
+        self.search_forward(start)
+    }
+
+    fn prev_before(
+        &self,
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Option<CronDateTime> {
+        let start = Cursor::new(year, month, day, hour, minute, second as i32 - 1);
+        self.search_backward(start)
+    }
+}
+
+/// How many years [`CronSchedule`] lookups search before giving up.
+///
+/// The Gregorian calendar, weekdays included, repeats every 400 years, so an
+/// expression with no match within that span never matches.
+const SEARCH_YEARS: i32 = 400;
+
+/// A datetime under construction during a search.
+///
+/// Fields may temporarily over- or underflow; [`Cursor::carry`] and
+/// [`Cursor::borrow`] bring them back into range.
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    year: i32,
+    month: i32,
+    day: i32,
+    hour: i32,
+    minute: i32,
+    second: i32,
+}
+
+impl Cursor {
+    fn new(year: i32, month: u8, day: u8, hour: u8, minute: u8, second: i32) -> Self {
+        Self {
+            year,
+            month: month as i32,
+            day: day as i32,
+            hour: hour as i32,
+            minute: minute as i32,
+            second,
+        }
+    }
+
+    fn days_in_month(&self) -> i32 {
+        days_in_month(self.year, self.month as u8) as i32
+    }
+
+    /// Normalize overflowing fields into the next larger unit.
+    fn carry(&mut self) {
+        if self.second > 59 {
+            self.second = 0;
+            self.minute += 1;
+        }
+        if self.minute > 59 {
+            self.minute = 0;
+            self.hour += 1;
+        }
+        if self.hour > 23 {
+            self.hour = 0;
+            self.day += 1;
+        }
+        if self.month > 12 {
+            self.month = 1;
+            self.year += 1;
+        }
+        if self.day > self.days_in_month() {
+            self.day = 1;
+            self.month += 1;
+        }
+        if self.month > 12 {
+            self.month = 1;
+            self.year += 1;
+        }
+    }
+
+    /// Normalize underflowing fields by borrowing from the next larger unit.
+    fn borrow(&mut self) {
+        if self.second < 0 {
+            self.second = 59;
+            self.minute -= 1;
+        }
+        if self.minute < 0 {
+            self.minute = 59;
+            self.hour -= 1;
+        }
+        if self.hour < 0 {
+            self.hour = 23;
+            self.day -= 1;
+        }
+        if self.month < 1 {
+            self.month = 12;
+            self.year -= 1;
+        }
+        if self.day < 1 {
+            self.month -= 1;
+            if self.month < 1 {
+                self.month = 12;
+                self.year -= 1;
             }
-            if mi > 59 {
-                mi = 0;
-                h += 1;
+            self.day = self.days_in_month();
+        }
+    }
+
+    fn get(&self) -> CronDateTime {
+        (
+            self.year,
+            self.month as u8,
+            self.day as u8,
+            self.hour as u8,
+            self.minute as u8,
+            self.second as u8,
+        )
+    }
+}
+
+impl Cron {
+    fn matches_date(&self, year: i32, month: i32, day: i32) -> bool {
+        let weekday = day_of_week(year, month as u8, day as u8);
+        self.matches_day(day as u8, weekday)
+    }
+
+    /// Find the first match at or after `c`.
+    ///
+    /// Rather than stepping one second at a time, each field jumps straight
+    /// to its next matching value, resetting the smaller fields.
+    fn search_forward(&self, mut c: Cursor) -> Option<CronDateTime> {
+        let max_year = c.year + SEARCH_YEARS;
+
+        loop {
+            c.carry();
+            if c.year > max_year {
+                return None;
             }
-            if h > 23 {
-                h = 0;
-                d += 1;
+
+            match self.months.next_from(c.month as u8) {
+                Some(m) if m as i32 == c.month => {}
+                Some(m) => c = Cursor::new(c.year, m, 1, 0, 0, 0),
+                None => {
+                    c = Cursor::new(c.year + 1, 1, 1, 0, 0, 0);
+                    continue;
+                }
             }
 
-            let days_in_month = days_in_month(y, mo);
-            if d > days_in_month {
-                d = 1;
-                mo += 1;
+            let dim = c.days_in_month();
+            match (c.day..=dim).find(|&d| self.matches_date(c.year, c.month, d)) {
+                Some(d) if d == c.day => {}
+                Some(d) => c = Cursor::new(c.year, c.month as u8, d as u8, 0, 0, 0),
+                None => {
+                    c = Cursor::new(c.year, c.month as u8, dim as u8 + 1, 0, 0, 0);
+                    continue;
+                }
             }
-            if mo > 12 {
-                mo = 1;
-                y += 1;
+
+            match self.hours.next_from(c.hour as u8) {
+                Some(h) if h as i32 == c.hour => {}
+                Some(h) => {
+                    c.hour = h as i32;
+                    c.minute = 0;
+                    c.second = 0;
+                }
+                None => {
+                    c.hour = 24;
+                    c.minute = 0;
+                    c.second = 0;
+                    continue;
+                }
+            }
+
+            match self.minutes.next_from(c.minute as u8) {
+                Some(m) if m as i32 == c.minute => {}
+                Some(m) => {
+                    c.minute = m as i32;
+                    c.second = 0;
+                }
+                None => {
+                    c.minute = 60;
+                    c.second = 0;
+                    continue;
+                }
             }
 
-            if y > max_year {
+            match self.seconds.next_from(c.second as u8) {
+                Some(s) => c.second = s as i32,
+                None => {
+                    c.second = 60;
+                    continue;
+                }
+            }
+
+            return Some(c.get());
+        }
+    }
+
+    /// Find the last match at or before `c`.
+    ///
+    /// Mirrors [`Cron::search_forward`], jumping each field to its previous
+    /// matching value and maximizing the smaller fields.
+    fn search_backward(&self, mut c: Cursor) -> Option<CronDateTime> {
+        let min_year = c.year - SEARCH_YEARS;
+        let last = |year, month| Cursor {
+            year,
+            month,
+            day: days_in_month(year, month as u8) as i32,
+            hour: 23,
+            minute: 59,
+            second: 59,
+        };
+
+        loop {
+            c.borrow();
+            if c.year < min_year {
                 return None;
             }
 
-            let weekday = day_of_week(y, mo, d);
+            match self.months.prev_from(c.month as u8) {
+                Some(m) if m as i32 == c.month => {}
+                Some(m) => c = last(c.year, m as i32),
+                None => {
+                    c = last(c.year - 1, 12);
+                    continue;
+                }
+            }
+
+            if c.day > c.days_in_month() {
+                c = last(c.year, c.month);
+            }
+            match (1..=c.day)
+                .rev()
+                .find(|&d| self.matches_date(c.year, c.month, d))
+            {
+                Some(d) if d == c.day => {}
+                Some(d) => {
+                    c.day = d;
+                    c.hour = 23;
+                    c.minute = 59;
+                    c.second = 59;
+                }
+                None => {
+                    c.day = 0;
+                    c.hour = 23;
+                    c.minute = 59;
+                    c.second = 59;
+                    continue;
+                }
+            }
 
-            if self.matches(s, mi, h, d, mo, weekday) {
-                return Some((y, mo, d, h, mi, s));
+            match self.hours.prev_from(c.hour as u8) {
+                Some(h) if h as i32 == c.hour => {}
+                Some(h) => {
+                    c.hour = h as i32;
+                    c.minute = 59;
+                    c.second = 59;
+                }
+                None => {
+                    c.hour = -1;
+                    c.minute = 59;
+                    c.second = 59;
+                    continue;
+                }
             }
 
-            // Increment by one second
-            s += 1;
+            match self.minutes.prev_from(c.minute as u8) {
+                Some(m) if m as i32 == c.minute => {}
+                Some(m) => {
+                    c.minute = m as i32;
+                    c.second = 59;
+                }
+                None => {
+                    c.minute = -1;
+                    c.second = 59;
+                    continue;
+                }
+            }
+
+            match self.seconds.prev_from(c.second as u8) {
+                Some(s) => c.second = s as i32,
+                None => {
+                    c.second = -1;
+                    continue;
+                }
+            }
+
+            return Some(c.get());
         }
+    }
+}
+
+#[cfg(feature = "timezone")]
+impl portals_cron::ZonedCronSchedule for Cron {
+    fn next_after_zoned(&self, after: &Zoned) -> Option<Zoned> {
+        let tz = after.time_zone();
+        // Cron has second resolution, so search from the start of the second.
+        let mut cursor = Timestamp::from_second(after.timestamp().as_second()).ok()?;
+        let mut inclusive = false;
+
+        if self.fixed_time
+            && let Some(end) = fold_end(tz, cursor)
+            && cursor < end
+        {
+            // Starting inside a repeated stretch of civil time: fixed-time
+            // expressions already fired on the first pass.
+            cursor = end;
+            inclusive = true;
+        }
+
+        // Each iteration searches the stretch of time up to the next offset
+        // transition, where civil time maps linearly onto instants.
+        loop {
+            let offset = tz.to_offset(cursor);
+            let civil = offset.to_datetime(cursor);
+            let candidate = if inclusive && self.matches_civil(civil) {
+                civil
+            } else {
+                self.next_civil_after(civil)?
+            };
+            let instant = offset.to_timestamp(candidate).ok()?;
+
+            let transition = match tz.following(cursor).next() {
+                Some(t) if instant >= t.timestamp() => t,
+                _ => return Some(instant.to_zoned(tz.clone())),
+            };
+
+            let at = transition.timestamp();
+            let civil_before = offset.to_datetime(at);
+            let civil_after = transition.offset().to_datetime(at);
 
+            if civil_after > civil_before {
+                // Gap: civil times in [civil_before, civil_after) never occur.
+                if self.fixed_time && candidate < civil_after {
+                    return Some(at.to_zoned(tz.clone()));
+                }
+                cursor = at;
+            } else if self.fixed_time {
+                // Fold: civil times in [civil_after, civil_before) repeat.
+                // Fixed-time expressions already fired on the first pass.
+                cursor = transition.offset().to_timestamp(civil_before).ok()?;
+            } else {
+                cursor = at;
+            }
+            inclusive = true;
+        }
+    }
+}
+
+/// If the most recent transition at or before `at` repeated civil time,
+/// the instant at which the repetition ends.
+#[cfg(feature = "timezone")]
+fn fold_end(tz: &TimeZone, at: Timestamp) -> Option<Timestamp> {
+    let transition = tz
+        .preceding(Timestamp::from_second(at.as_second() + 1).ok()?)
+        .next()?;
+    let t = transition.timestamp();
+    let before = tz.to_offset(Timestamp::from_second(t.as_second() - 1).ok()?);
+    let civil_before = before.to_datetime(t);
+    if transition.offset().to_datetime(t) < civil_before {
+        transition.offset().to_timestamp(civil_before).ok()
+    } else {
         None
     }
 }
 
+#[cfg(feature = "timezone")]
+impl Cron {
+    fn matches_civil(&self, dt: DateTime) -> bool {
+        self.matches(
+            dt.second() as u8,
+            dt.minute() as u8,
+            dt.hour() as u8,
+            dt.day() as u8,
+            dt.month() as u8,
+            dt.weekday().to_sunday_zero_offset() as u8,
+        )
+    }
+
+    /// Next matching civil time strictly after `after`.
+    fn next_civil_after(&self, after: DateTime) -> Option<DateTime> {
+        let (y, mo, d, h, mi, s) = self.next_after(
+            after.year() as i32,
+            after.month() as u8,
+            after.day() as u8,
+            after.hour() as u8,
+            after.minute() as u8,
+            after.second() as u8,
+        )?;
+        DateTime::new(y as i16, mo as i8, d as i8, h as i8, mi as i8, s as i8, 0).ok()
+    }
+}
+
 /// Calculate day of week (0 = Sunday).
 fn day_of_week(year: i32, month: u8, day: u8) -> u8 {
     // Zeller's congruence for Gregorian calendar
@@ -316,12 +784,21 @@ fn is_leap_year(year: i32) -> bool {
 }
 
 /// Default cron parser.
+///
+/// Uses Vixie cron day matching unless configured otherwise.
 #[derive(Debug, Default, Clone, Copy)]
-pub struct CronParserImpl;
+pub struct CronParserImpl {
+    day_matching: DayMatching,
+}
 
 impl CronParserImpl {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// Create a parser with the given day-of-month / day-of-week semantics.
+    pub fn with_day_matching(day_matching: DayMatching) -> Self {
+        Self { day_matching }
     }
 }
 
@@ -329,11 +806,11 @@ impl CronParser for CronParserImpl {
     type Expr = Cron;
 
     fn parse(&self, expr: &str) -> Result<Self::Expr, CronError> {
-        Cron::parse_5_field(expr)
+        Cron::parse_5_field(expr, self.day_matching)
     }
 
     fn parse_with_seconds(&self, expr: &str) -> Result<Self::Expr, CronError> {
-        Cron::parse_6_field(expr)
+        Cron::parse_6_field(expr, self.day_matching)
     }
 }
 
@@ -464,4 +941,491 @@ mod tests {
         let next = cron.next_after(2024, 1, 1, 12, 0, 0);
         assert_eq!(next, Some((2024, 1, 2, 12, 0, 0)));
     }
+
+    #[test]
+    fn parse_names() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 0 * JAN-MAR mon,Fri").unwrap();
+        assert!(cron.matches(0, 0, 0, 1, 2, 1));
+        assert!(cron.matches(0, 0, 0, 1, 3, 5));
+        assert!(!cron.matches(0, 0, 0, 1, 4, 1));
+        assert!(!cron.matches(0, 0, 0, 1, 2, 2));
+        assert!(parser.parse("0 0 * * MONDAY").is_err());
+    }
+
+    #[test]
+    fn sunday_as_seven() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 0 * * 7").unwrap();
+        assert!(cron.matches(0, 0, 0, 1, 1, 0));
+        let cron = parser.parse("0 0 * * 5-7").unwrap();
+        assert!(cron.matches(0, 0, 0, 1, 1, 0));
+        assert!(cron.matches(0, 0, 0, 1, 1, 6));
+        assert!(!cron.matches(0, 0, 0, 1, 1, 1));
+    }
+
+    #[test]
+    fn day_fields_or_when_both_restricted() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 0 1 * MON").unwrap();
+        assert!(cron.matches(0, 0, 0, 1, 2, 4)); // 1st, Thursday
+        assert!(cron.matches(0, 0, 0, 5, 2, 1)); // 5th, Monday
+        assert!(!cron.matches(0, 0, 0, 6, 2, 2)); // 6th, Tuesday
+    }
+
+    #[test]
+    fn day_fields_and_when_one_starred() {
+        let parser = CronParserImpl::new();
+        // A leading `*` makes the field unrestricted, even with a step.
+        let cron = parser.parse("0 0 */2 * MON").unwrap();
+        assert!(cron.matches(0, 0, 0, 15, 1, 1)); // odd day, Monday
+        assert!(!cron.matches(0, 0, 0, 8, 1, 1)); // even day, Monday
+        assert!(!cron.matches(0, 0, 0, 3, 1, 3)); // odd day, Wednesday
+    }
+
+    #[test]
+    fn day_fields_strict_and() {
+        let parser = CronParserImpl::with_day_matching(DayMatching::StrictAnd);
+        let cron = parser.parse("0 0 13 * FRI").unwrap();
+        assert_eq!(cron.day_matching(), DayMatching::StrictAnd);
+        assert!(cron.matches(0, 0, 0, 13, 9, 5));
+        assert!(!cron.matches(0, 0, 0, 13, 1, 6));
+        assert!(!cron.matches(0, 0, 0, 5, 1, 5));
+    }
+
+    /// `(year, month, day, hour, minute)`
+    type Minute = (i32, u8, u8, u8, u8);
+
+    /// Expected schedules under Vixie cron (and cronie) semantics. Each entry
+    /// lists the occurrences following 2024-01-01 00:00, a Monday.
+    const VIXIE_CORPUS: &[(&str, &[Minute])] = &[
+        (
+            "0 0 1 * MON",
+            &[
+                (2024, 1, 8, 0, 0),
+                (2024, 1, 15, 0, 0),
+                (2024, 1, 22, 0, 0),
+                (2024, 1, 29, 0, 0),
+                (2024, 2, 1, 0, 0),
+                (2024, 2, 5, 0, 0),
+            ],
+        ),
+        (
+            "0 0 13 * 5",
+            &[
+                (2024, 1, 5, 0, 0),
+                (2024, 1, 12, 0, 0),
+                (2024, 1, 13, 0, 0),
+                (2024, 1, 19, 0, 0),
+                (2024, 1, 26, 0, 0),
+                (2024, 2, 2, 0, 0),
+            ],
+        ),
+        (
+            "0 0 */2 * 1",
+            &[
+                (2024, 1, 15, 0, 0),
+                (2024, 1, 29, 0, 0),
+                (2024, 2, 5, 0, 0),
+                (2024, 2, 19, 0, 0),
+                (2024, 3, 11, 0, 0),
+                (2024, 3, 25, 0, 0),
+            ],
+        ),
+        (
+            "0 0 1,15 * WED",
+            &[
+                (2024, 1, 3, 0, 0),
+                (2024, 1, 10, 0, 0),
+                (2024, 1, 15, 0, 0),
+                (2024, 1, 17, 0, 0),
+                (2024, 1, 24, 0, 0),
+                (2024, 1, 31, 0, 0),
+            ],
+        ),
+        (
+            "30 9 * * sat,sun",
+            &[
+                (2024, 1, 6, 9, 30),
+                (2024, 1, 7, 9, 30),
+                (2024, 1, 13, 9, 30),
+                (2024, 1, 14, 9, 30),
+                (2024, 1, 20, 9, 30),
+                (2024, 1, 21, 9, 30),
+            ],
+        ),
+        (
+            "0 0 * * 7",
+            &[
+                (2024, 1, 7, 0, 0),
+                (2024, 1, 14, 0, 0),
+                (2024, 1, 21, 0, 0),
+                (2024, 1, 28, 0, 0),
+                (2024, 2, 4, 0, 0),
+                (2024, 2, 11, 0, 0),
+            ],
+        ),
+        (
+            "0 12 29 FEB MON",
+            &[
+                (2024, 2, 5, 12, 0),
+                (2024, 2, 12, 12, 0),
+                (2024, 2, 19, 12, 0),
+                (2024, 2, 26, 12, 0),
+                (2024, 2, 29, 12, 0),
+                (2025, 2, 3, 12, 0),
+            ],
+        ),
+        (
+            "0 0 31 * *",
+            &[
+                (2024, 1, 31, 0, 0),
+                (2024, 3, 31, 0, 0),
+                (2024, 5, 31, 0, 0),
+                (2024, 7, 31, 0, 0),
+                (2024, 8, 31, 0, 0),
+                (2024, 10, 31, 0, 0),
+            ],
+        ),
+    ];
+
+    fn upcoming(cron: &Cron, n: usize) -> Vec<Minute> {
+        let mut out = Vec::new();
+        let mut cur = (2024, 1, 1, 0, 0, 0);
+        while out.len() < n {
+            cur = cron
+                .next_after(cur.0, cur.1, cur.2, cur.3, cur.4, cur.5)
+                .expect("schedule ended early");
+            out.push((cur.0, cur.1, cur.2, cur.3, cur.4));
+        }
+        out
+    }
+
+    #[test]
+    fn vixie_conformance() {
+        let parser = CronParserImpl::new();
+        for (expr, expected) in VIXIE_CORPUS {
+            let cron = parser.parse(expr).unwrap();
+            assert_eq!(&upcoming(&cron, expected.len()), expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn strict_and_conformance() {
+        let parser = CronParserImpl::with_day_matching(DayMatching::StrictAnd);
+
+        // Friday the 13th
+        let cron = parser.parse("0 0 13 * FRI").unwrap();
+        assert_eq!(
+            upcoming(&cron, 3),
+            [
+                (2024, 9, 13, 0, 0),
+                (2024, 12, 13, 0, 0),
+                (2025, 6, 13, 0, 0)
+            ]
+        );
+
+        // A month starting on Monday
+        let cron = parser.parse("0 0 1 * MON").unwrap();
+        assert_eq!(
+            upcoming(&cron, 3),
+            [(2024, 4, 1, 0, 0), (2024, 7, 1, 0, 0), (2025, 9, 1, 0, 0)]
+        );
+    }
+
+    #[test]
+    fn next_after_sparse() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            cron.next_after(2025, 1, 1, 0, 0, 0),
+            Some((2028, 2, 29, 0, 0, 0))
+        );
+
+        // Beyond the old four-year search window
+        let parser = CronParserImpl::with_day_matching(DayMatching::StrictAnd);
+        let cron = parser.parse("0 0 29 2 MON").unwrap();
+        assert_eq!(
+            cron.next_after(2024, 3, 1, 0, 0, 0),
+            Some((2044, 2, 29, 0, 0, 0))
+        );
+        assert_eq!(
+            cron.prev_before(2044, 2, 29, 0, 0, 0),
+            Some((2016, 2, 29, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn never_matches() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 0 30 2 *").unwrap();
+        assert_eq!(cron.next_after(2024, 1, 1, 0, 0, 0), None);
+        assert_eq!(cron.prev_before(2024, 1, 1, 0, 0, 0), None);
+    }
+
+    #[test]
+    fn next_after_with_seconds_rolls_over_year() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse_with_seconds("*/20 * * * * *").unwrap();
+        assert_eq!(
+            cron.next_after(2024, 12, 31, 23, 59, 50),
+            Some((2025, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            cron.prev_before(2025, 1, 1, 0, 0, 0),
+            Some((2024, 12, 31, 23, 59, 40))
+        );
+    }
+
+    #[test]
+    fn prev_occurrence() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 12 * * *").unwrap();
+        assert_eq!(
+            cron.prev_before(2024, 1, 1, 8, 0, 0),
+            Some((2023, 12, 31, 12, 0, 0))
+        );
+        assert_eq!(
+            cron.prev_before(2024, 1, 1, 12, 0, 1),
+            Some((2024, 1, 1, 12, 0, 0))
+        );
+        assert_eq!(
+            cron.prev_before(2024, 1, 1, 12, 0, 0),
+            Some((2023, 12, 31, 12, 0, 0))
+        );
+
+        let cron = parser.parse("0 0 31 * *").unwrap();
+        assert_eq!(
+            cron.prev_before(2024, 5, 1, 0, 0, 0),
+            Some((2024, 3, 31, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn upcoming_iterator() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 9 * * MON-FRI").unwrap();
+        let next: Vec<_> = cron.upcoming((2024, 1, 5, 12, 0, 0)).take(3).collect();
+        assert_eq!(
+            next,
+            [
+                (2024, 1, 8, 9, 0, 0),
+                (2024, 1, 9, 9, 0, 0),
+                (2024, 1, 10, 9, 0, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_between_bounds() {
+        let parser = CronParserImpl::new();
+        let cron = parser.parse("0 */6 * * *").unwrap();
+        let all: Vec<_> = cron
+            .occurrences_between((2024, 1, 1, 0, 0, 0), (2024, 1, 2, 0, 0, 0))
+            .collect();
+        // Exclusive start, inclusive end
+        assert_eq!(
+            all,
+            [
+                (2024, 1, 1, 6, 0, 0),
+                (2024, 1, 1, 12, 0, 0),
+                (2024, 1, 1, 18, 0, 0),
+                (2024, 1, 2, 0, 0, 0),
+            ]
+        );
+        assert_eq!(
+            cron.occurrences_between((2024, 1, 1, 1, 0, 0), (2024, 1, 1, 5, 0, 0))
+                .count(),
+            0
+        );
+    }
+
+    /// Step minute by minute, checking `matches` directly.
+    fn brute_force(cron: &Cron, from: CronDateTime, forward: bool) -> CronDateTime {
+        let (y, mo, d, h, mi, _) = from;
+        let mut c = Cursor::new(y, mo, d, h, mi, 0);
+        loop {
+            if forward {
+                c.minute += 1;
+                c.carry();
+            } else {
+                c.minute -= 1;
+                c.borrow();
+            }
+            let (y, mo, d, h, mi, s) = c.get();
+            if cron.matches(s, mi, h, d, mo, day_of_week(y, mo, d)) {
+                return c.get();
+            }
+        }
+    }
+
+    #[test]
+    fn field_jumping_matches_brute_force() {
+        let parser = CronParserImpl::new();
+        let exprs = [
+            "*/7 * * * *",
+            "5 4 * * SUN",
+            "0 22 * * 1-5",
+            "23 0-20/2 * * *",
+            "0 0,12 1 */2 *",
+            "0 0 1 * MON",
+            "15 14 1,31 * *",
+            "59 23 28-31 * *",
+        ];
+        for expr in exprs {
+            let cron = parser.parse(expr).unwrap();
+            for start in [(2023, 12, 31, 23, 59, 0), (2024, 2, 28, 12, 30, 0)] {
+                let mut next = start;
+                let mut prev = start;
+                for _ in 0..25 {
+                    let (y, mo, d, h, mi, s) = next;
+                    let expected = brute_force(&cron, next, true);
+                    next = cron.next_after(y, mo, d, h, mi, s).unwrap();
+                    assert_eq!(next, expected, "next {} from {:?}", expr, start);
+
+                    let (y, mo, d, h, mi, s) = prev;
+                    let expected = brute_force(&cron, prev, false);
+                    prev = cron.prev_before(y, mo, d, h, mi, s).unwrap();
+                    assert_eq!(prev, expected, "prev {} from {:?}", expr, start);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "timezone")]
+    mod zoned {
+        use super::*;
+        use portals_cron::ZonedCronSchedule;
+
+        fn zoned(s: &str) -> Zoned {
+            s.parse().unwrap()
+        }
+
+        fn upcoming_zoned(expr: &str, after: &str, n: usize) -> Vec<String> {
+            let cron = CronParserImpl::new().parse(expr).unwrap();
+            let mut cur = zoned(after);
+            let mut out = Vec::new();
+            for _ in 0..n {
+                cur = cron.next_after_zoned(&cur).unwrap();
+                out.push(cur.to_string());
+            }
+            out
+        }
+
+        #[test]
+        fn next_in_zone() {
+            assert_eq!(
+                upcoming_zoned(
+                    "0 12 * * *",
+                    "2024-06-01T08:00:00-04:00[America/New_York]",
+                    2
+                ),
+                [
+                    "2024-06-01T12:00:00-04:00[America/New_York]",
+                    "2024-06-02T12:00:00-04:00[America/New_York]",
+                ]
+            );
+        }
+
+        #[test]
+        fn next_after_instant() {
+            let cron = CronParserImpl::new().parse("0 9 * * *").unwrap();
+            let after = zoned("2024-06-01T12:00:00+00:00[UTC]").timestamp();
+            let tz = portals_timezone::get("Asia/Tokyo").unwrap();
+            let next = cron.next_after_in(after, &tz).unwrap();
+            assert_eq!(next.to_string(), "2024-06-02T09:00:00+09:00[Asia/Tokyo]");
+        }
+
+        #[test]
+        fn spring_forward_fixed_time_fires_at_gap_end() {
+            assert_eq!(
+                upcoming_zoned(
+                    "30 2 * * *",
+                    "2024-03-09T12:00:00-05:00[America/New_York]",
+                    2
+                ),
+                [
+                    "2024-03-10T03:00:00-04:00[America/New_York]",
+                    "2024-03-11T02:30:00-04:00[America/New_York]",
+                ]
+            );
+        }
+
+        #[test]
+        fn spring_forward_wildcard_skips_gap() {
+            assert_eq!(
+                upcoming_zoned(
+                    "15 * * * *",
+                    "2024-03-10T01:45:00-05:00[America/New_York]",
+                    2
+                ),
+                [
+                    "2024-03-10T03:15:00-04:00[America/New_York]",
+                    "2024-03-10T04:15:00-04:00[America/New_York]",
+                ]
+            );
+            assert_eq!(
+                upcoming_zoned(
+                    "*/30 * * * *",
+                    "2024-03-10T01:45:00-05:00[America/New_York]",
+                    1
+                ),
+                ["2024-03-10T03:00:00-04:00[America/New_York]"]
+            );
+        }
+
+        #[test]
+        fn fall_back_fixed_time_fires_once() {
+            assert_eq!(
+                upcoming_zoned(
+                    "30 1 * * *",
+                    "2024-11-03T00:00:00-04:00[America/New_York]",
+                    2
+                ),
+                [
+                    "2024-11-03T01:30:00-04:00[America/New_York]",
+                    "2024-11-04T01:30:00-05:00[America/New_York]",
+                ]
+            );
+        }
+
+        #[test]
+        fn fall_back_wildcard_fires_twice() {
+            assert_eq!(
+                upcoming_zoned(
+                    "0,30 * * * *",
+                    "2024-11-03T00:45:00-04:00[America/New_York]",
+                    5
+                ),
+                [
+                    "2024-11-03T01:00:00-04:00[America/New_York]",
+                    "2024-11-03T01:30:00-04:00[America/New_York]",
+                    "2024-11-03T01:00:00-05:00[America/New_York]",
+                    "2024-11-03T01:30:00-05:00[America/New_York]",
+                    "2024-11-03T02:00:00-05:00[America/New_York]",
+                ]
+            );
+        }
+
+        #[test]
+        fn fall_back_second_pass_start() {
+            // Starting inside the repeated hour only yields later times.
+            assert_eq!(
+                upcoming_zoned(
+                    "30 1 * * *",
+                    "2024-11-03T01:10:00-05:00[America/New_York]",
+                    1
+                ),
+                ["2024-11-04T01:30:00-05:00[America/New_York]"]
+            );
+            assert_eq!(
+                upcoming_zoned(
+                    "*/20 * * * *",
+                    "2024-11-03T01:10:00-05:00[America/New_York]",
+                    1
+                ),
+                ["2024-11-03T01:20:00-05:00[America/New_York]"]
+            );
+        }
+    }
 }
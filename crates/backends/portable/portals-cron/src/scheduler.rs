@@ -0,0 +1,873 @@
+//! Async job runner driven by cron schedules.
+//!
+//! A [`Scheduler`] owns a set of named jobs, each pairing a
+//! [`CronSchedule`] with an async closure. Time comes from an injected
+//! [`WallClock`] and [`MonotonicClock`], so with `portals-clocks-mock` every
+//! decision the scheduler makes is deterministic.
+//!
+//! Schedules are evaluated in UTC unless a time zone is set with
+//! [`Scheduler::with_time_zone`], in which case daylight saving transitions
+//! are handled as described on [`ZonedCronSchedule`].
+//!
+//! [`ZonedCronSchedule`]: portals_cron::ZonedCronSchedule
+
+use portals_clocks::{MonotonicClock, WallClock};
+use portals_cron::{CronDateTime, CronError, CronSchedule};
+use std::collections::VecDeque;
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+#[cfg(feature = "timezone")]
+use portals_timezone::{DateTime, TimeZone, Timestamp, Zoned};
+
+/// The longest [`Scheduler::run`] sleeps before re-reading the wall clock,
+/// so that clock adjustments and suspends are noticed promptly.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+type Task = Box<dyn FnMut(Duration) -> Run + Send>;
+type Run = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The time zone schedules are evaluated in, with the schedule's zoned
+/// lookup captured where its `ZonedCronSchedule` bound is known.
+#[cfg(feature = "timezone")]
+type Zone<E> = Option<(TimeZone, fn(&E, &Zoned) -> Option<Zoned>)>;
+#[cfg(not(feature = "timezone"))]
+type Zone<E> = std::marker::PhantomData<E>;
+
+/// What to do when a job comes due while a previous run is still going.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// Drop the new run.
+    #[default]
+    Skip,
+    /// Start the new run once the previous one finishes.
+    Queue,
+    /// Start the new run alongside the previous one.
+    Concurrent,
+}
+
+/// What to do with occurrences that were missed, e.g. during downtime.
+///
+/// An occurrence counts as missed once it is more than
+/// [`JobOptions::grace`] overdue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Drop missed occurrences.
+    #[default]
+    Skip,
+    /// Run once, however many occurrences were missed.
+    Once,
+    /// Run once per missed occurrence, oldest first, up to
+    /// [`JobOptions::catch_up_limit`] of the most recent ones.
+    ///
+    /// Combine with [`Overlap::Queue`] to run them one after another;
+    /// with [`Overlap::Skip`] only the first would start.
+    All,
+}
+
+/// Options for a scheduled job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobOptions {
+    pub overlap: Overlap,
+    pub catch_up: CatchUp,
+    /// Maximum delay added to each run to spread load.
+    ///
+    /// The delay is derived from a hash of the job name and occurrence, so
+    /// it is stable across restarts and fully reproducible in tests.
+    pub jitter: Duration,
+    /// How late a run may start before its occurrence counts as missed.
+    pub grace: Duration,
+    /// The most missed occurrences [`CatchUp::All`] runs after downtime.
+    /// Older ones are dropped.
+    pub catch_up_limit: usize,
+    /// Treat occurrences after this time (since the Unix epoch) as due, for
+    /// example the last recorded run before a restart.
+    ///
+    /// Defaults to the time the job is added.
+    pub since: Option<Duration>,
+}
+
+impl Default for JobOptions {
+    fn default() -> Self {
+        Self {
+            overlap: Overlap::default(),
+            catch_up: CatchUp::default(),
+            jitter: Duration::ZERO,
+            grace: Duration::from_secs(60),
+            catch_up_limit: 100,
+            since: None,
+        }
+    }
+}
+
+/// Runs named jobs at each occurrence of their cron schedule.
+///
+/// [`Scheduler::run`] drives everything from the injected clocks. Tests can
+/// instead call [`Scheduler::tick`] after moving a mock clock, then
+/// [`Scheduler::run_until_idle`].
+pub struct Scheduler<E, W, M> {
+    wall: W,
+    monotonic: M,
+    zone: Zone<E>,
+    jobs: Jobs<E>,
+}
+
+#[cfg(feature = "timezone")]
+impl<E: portals_cron::ZonedCronSchedule, W, M> Scheduler<E, W, M> {
+    /// Evaluate schedules in `tz` rather than UTC.
+    ///
+    /// Times passed to tasks and returned by [`Scheduler::next_run`] are
+    /// still durations since the Unix epoch.
+    pub fn with_time_zone(mut self, tz: TimeZone) -> Self {
+        self.zone = Some((tz, E::next_after_zoned));
+        self
+    }
+}
+
+impl<E: CronSchedule, W: WallClock, M: MonotonicClock> Scheduler<E, W, M> {
+    /// Create a scheduler with no jobs.
+    pub fn new(wall: W, monotonic: M) -> Self {
+        Self {
+            wall,
+            monotonic,
+            zone: Default::default(),
+            jobs: Jobs {
+                jobs: Vec::new(),
+                running: Vec::new(),
+                next_id: 0,
+            },
+        }
+    }
+
+    /// Register a job.
+    ///
+    /// `task` is called with the scheduled occurrence time (since the Unix
+    /// epoch) each time the job runs. Fails if a job with the same name
+    /// already exists.
+    ///
+    /// `task` and the futures it returns must be `Send`, so a scheduler
+    /// whose schedule and clocks are `Send` can run on a multi-threaded
+    /// executor.
+    pub fn add<F, Fut>(
+        &mut self,
+        name: impl Into<String>,
+        schedule: E,
+        options: JobOptions,
+        mut task: F,
+    ) -> Result<(), CronError>
+    where
+        F: FnMut(Duration) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.into();
+        if self.jobs.find(&name).is_some() {
+            return Err(CronError::Other(format!("job '{}' already exists", name)));
+        }
+
+        let since = options.since.unwrap_or_else(|| self.now());
+        let id = self.jobs.next_id;
+        self.jobs.next_id += 1;
+        self.jobs.jobs.push(Job {
+            id,
+            name,
+            schedule,
+            options,
+            task: Box::new(move |at| Box::pin(task(at))),
+            cursor: Duration::from_secs(since.as_secs()),
+            last_run: None,
+            running: 0,
+            queued: VecDeque::new(),
+        });
+        Ok(())
+    }
+
+    /// Remove a job. Runs already in progress are left to finish.
+    ///
+    /// Returns `false` if no job has that name.
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.jobs.jobs.len();
+        self.jobs.jobs.retain(|job| job.name != name);
+        self.jobs.jobs.len() != before
+    }
+
+    /// The scheduled time of the job's most recent run.
+    pub fn last_run(&self, name: &str) -> Option<Duration> {
+        self.jobs.find(name)?.last_run
+    }
+
+    /// When the job will next start, jitter included.
+    pub fn next_run(&self, name: &str) -> Option<Duration> {
+        let job = self.jobs.find(name)?;
+        let at = job.next_occurrence(job.cursor, &self.zone)?;
+        Some(job.due_at(at))
+    }
+
+    /// Number of runs currently in progress.
+    pub fn running(&self) -> usize {
+        self.jobs.running.len()
+    }
+
+    /// Start every run that is due at the current wall-clock time.
+    ///
+    /// Returns the number of runs started. Started runs make progress while
+    /// [`Scheduler::run`] or [`Scheduler::run_until_idle`] is awaited.
+    pub fn tick(&mut self) -> usize {
+        let now = self.now();
+        let mut started = 0;
+        for job in &mut self.jobs.jobs {
+            for at in job.take_due(now, &self.zone) {
+                if job.submit(at, &mut self.jobs.running) {
+                    started += 1;
+                }
+            }
+        }
+        started
+    }
+
+    /// Drive in-progress runs, including queued ones, until none remain.
+    ///
+    /// Does not start newly due runs; call [`Scheduler::tick`] for that.
+    pub async fn run_until_idle(&mut self) {
+        poll_fn(|cx| self.jobs.poll_running(cx)).await
+    }
+
+    /// Run jobs forever.
+    ///
+    /// Sleeps on the monotonic clock until the next run is due, re-reading
+    /// the wall clock at least every minute.
+    pub async fn run(&mut self) {
+        loop {
+            self.tick();
+
+            let now = self.now();
+            let next = self
+                .jobs
+                .jobs
+                .iter()
+                .filter_map(|job| Some(job.due_at(job.next_occurrence(job.cursor, &self.zone)?)))
+                .min();
+            let sleep = next.map_or(MAX_SLEEP, |at| at.saturating_sub(now).min(MAX_SLEEP));
+
+            let mut timer = std::pin::pin!(self.monotonic.subscribe_duration(sleep));
+            let jobs = &mut self.jobs;
+            poll_fn(|cx| {
+                let _ = jobs.poll_running(cx);
+                timer.as_mut().poll(cx)
+            })
+            .await;
+        }
+    }
+
+    fn now(&self) -> Duration {
+        let (secs, nanos) = self.wall.now();
+        Duration::new(secs, nanos)
+    }
+}
+
+/// Job list and in-progress runs, kept apart from the clocks so runs can be
+/// polled while a timer borrows the monotonic clock.
+struct Jobs<E> {
+    jobs: Vec<Job<E>>,
+    running: Vec<(u64, Run)>,
+    next_id: u64,
+}
+
+impl<E: CronSchedule> Jobs<E> {
+    fn find(&self, name: &str) -> Option<&Job<E>> {
+        self.jobs.iter().find(|job| job.name == name)
+    }
+
+    /// Poll every in-progress run, starting queued runs as others finish.
+    ///
+    /// Ready once nothing is left running.
+    fn poll_running(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut i = 0;
+        while i < self.running.len() {
+            if self.running[i].1.as_mut().poll(cx).is_pending() {
+                i += 1;
+                continue;
+            }
+
+            // The slot is refilled from the end, so poll index `i` again.
+            let (id, _) = self.running.swap_remove(i);
+            if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                job.running -= 1;
+                if let Some(at) = job.queued.pop_front() {
+                    job.start(at, &mut self.running);
+                }
+            }
+        }
+
+        if self.running.is_empty() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+struct Job<E> {
+    id: u64,
+    name: String,
+    schedule: E,
+    options: JobOptions,
+    task: Task,
+    /// Occurrences at or before this time have been handled.
+    cursor: Duration,
+    last_run: Option<Duration>,
+    running: usize,
+    queued: VecDeque<Duration>,
+}
+
+impl<E: CronSchedule> Job<E> {
+    fn next_occurrence(&self, after: Duration, zone: &Zone<E>) -> Option<Duration> {
+        #[cfg(feature = "timezone")]
+        if let Some((tz, next_after)) = zone {
+            let after = instant(after)?.to_zoned(tz.clone());
+            return since_epoch(next_after(&self.schedule, &after)?.timestamp());
+        }
+        #[cfg(not(feature = "timezone"))]
+        let _ = zone;
+
+        let (y, mo, d, h, mi, s) = to_civil(after.as_secs());
+        let next = self.schedule.next_after(y, mo, d, h, mi, s)?;
+        Some(Duration::from_secs(from_civil(next)))
+    }
+
+    /// The latest occurrence before `before` and after the cursor.
+    fn prev_occurrence(&self, before: Duration, zone: &Zone<E>) -> Option<Duration> {
+        #[cfg(feature = "timezone")]
+        if let Some((tz, next_after)) = zone {
+            return self.prev_zoned(before, tz, *next_after);
+        }
+        #[cfg(not(feature = "timezone"))]
+        let _ = zone;
+
+        let (y, mo, d, h, mi, s) = to_civil(before.as_secs());
+        let prev = self.schedule.prev_before(y, mo, d, h, mi, s)?;
+        Some(Duration::from_secs(from_civil(prev))).filter(|&at| at > self.cursor)
+    }
+
+    /// [`Job::prev_occurrence`] in a time zone.
+    ///
+    /// Works back one stretch of constant UTC offset at a time. Within a
+    /// stretch the schedule follows civil time, so the civil lookup finds
+    /// the candidate and `next_after` confirms it against the DST rules.
+    #[cfg(feature = "timezone")]
+    fn prev_zoned(
+        &self,
+        before: Duration,
+        tz: &TimeZone,
+        next_after: fn(&E, &Zoned) -> Option<Zoned>,
+    ) -> Option<Duration> {
+        let floor = instant(self.cursor)?;
+        let mut end = shift(instant(before)?, -1)?;
+
+        while end > floor {
+            let start = tz
+                .preceding(shift(end, 1)?)
+                .next()
+                .map_or(floor, |t| t.timestamp().max(floor));
+            let offset = tz.to_offset(end);
+            let civil = offset.to_datetime(shift(end, 1)?);
+            let candidate = self
+                .schedule
+                .prev_before(
+                    civil.year() as i32,
+                    civil.month() as u8,
+                    civil.day() as u8,
+                    civil.hour() as u8,
+                    civil.minute() as u8,
+                    civil.second() as u8,
+                )
+                .and_then(|(y, mo, d, h, mi, s)| {
+                    let dt =
+                        DateTime::new(y as i16, mo as i8, d as i8, h as i8, mi as i8, s as i8, 0);
+                    offset.to_timestamp(dt.ok()?).ok()
+                })
+                .filter(|&at| at > start);
+
+            // Search from just before the candidate, or from the start of
+            // the stretch, where a fixed-time job skipped by a gap fires.
+            let mut at = match candidate {
+                Some(at) => shift(at, -1)?,
+                None if start > floor => shift(start, -1)?,
+                None => floor,
+            };
+            let mut latest = None;
+            while let Some(next) = next_after(&self.schedule, &at.to_zoned(tz.clone()))
+                && next.timestamp() <= end
+            {
+                at = next.timestamp();
+                latest = Some(at);
+            }
+            if let Some(at) = latest {
+                return since_epoch(at);
+            }
+            end = shift(start, -1)?;
+        }
+        None
+    }
+
+    fn due_at(&self, at: Duration) -> Duration {
+        at + jitter(&self.name, at, self.options.jitter)
+    }
+
+    /// Advance past every occurrence due at `now`, returning those to run
+    /// under the job's catch-up policy.
+    fn take_due(&mut self, now: Duration, zone: &Zone<E>) -> Vec<Duration> {
+        // Only the latest due occurrences matter, so walk back from now
+        // rather than forward through a long downtime.
+        let mut latest = self.prev_occurrence(now + Duration::from_secs(1), zone);
+        while let Some(at) = latest
+            && self.due_at(at) > now
+        {
+            latest = self.prev_occurrence(at, zone);
+        }
+        let Some(at) = latest else {
+            return Vec::new();
+        };
+
+        let due = match self.options.catch_up {
+            CatchUp::Skip if now > self.due_at(at) + self.options.grace => Vec::new(),
+            CatchUp::Skip | CatchUp::Once => vec![at],
+            CatchUp::All => {
+                let mut due = vec![at];
+                while due.len() < self.options.catch_up_limit
+                    && let Some(prev) = self.prev_occurrence(due[due.len() - 1], zone)
+                {
+                    due.push(prev);
+                }
+                due.reverse();
+                due
+            }
+        };
+        self.cursor = at;
+        due
+    }
+
+    /// Apply the overlap policy to a due run. Returns whether it started.
+    fn submit(&mut self, at: Duration, running: &mut Vec<(u64, Run)>) -> bool {
+        let busy = self.running > 0 || !self.queued.is_empty();
+        match self.options.overlap {
+            Overlap::Skip if busy => false,
+            Overlap::Queue if busy => {
+                self.queued.push_back(at);
+                false
+            }
+            _ => {
+                self.start(at, running);
+                true
+            }
+        }
+    }
+
+    fn start(&mut self, at: Duration, running: &mut Vec<(u64, Run)>) {
+        self.running += 1;
+        self.last_run = Some(at);
+        running.push((self.id, (self.task)(at)));
+    }
+}
+
+/// Deterministic per-run delay below `max`, from an FNV-1a hash of the job
+/// name and occurrence.
+fn jitter(name: &str, at: Duration, max: Duration) -> Duration {
+    let max = max.as_nanos() as u64;
+    if max == 0 {
+        return Duration::ZERO;
+    }
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in name.bytes().chain(at.as_secs().to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    Duration::from_nanos(hash % max)
+}
+
+#[cfg(feature = "timezone")]
+fn instant(since_epoch: Duration) -> Option<Timestamp> {
+    Timestamp::from_second(i64::try_from(since_epoch.as_secs()).ok()?).ok()
+}
+
+#[cfg(feature = "timezone")]
+fn shift(at: Timestamp, seconds: i64) -> Option<Timestamp> {
+    Timestamp::from_second(at.as_second() + seconds).ok()
+}
+
+#[cfg(feature = "timezone")]
+fn since_epoch(at: Timestamp) -> Option<Duration> {
+    Some(Duration::from_secs(u64::try_from(at.as_second()).ok()?))
+}
+
+/// Convert seconds since the Unix epoch to a UTC civil datetime.
+fn to_civil(secs: u64) -> CronDateTime {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+    (
+        year,
+        month,
+        day,
+        (rem / 3600) as u8,
+        (rem / 60 % 60) as u8,
+        (rem % 60) as u8,
+    )
+}
+
+/// Convert a UTC civil datetime to seconds since the Unix epoch.
+fn from_civil((year, month, day, hour, minute, second): CronDateTime) -> u64 {
+    // Howard Hinnant's days_from_civil
+    let y = year as i64 - i64::from(month <= 2);
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let m = month as i64;
+    let doy = (153 * if m > 2 { m - 3 } else { m + 9 } + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    days as u64 * 86_400 + hour as u64 * 3600 + minute as u64 * 60 + second as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cron, CronParserImpl};
+    use portals_clocks_mock::{MockMonotonicClock, MockWallClock};
+    use portals_cron::CronParser;
+    use portals_testing::now;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::Waker;
+
+    type MockScheduler = Scheduler<Cron, MockWallClock, MockMonotonicClock>;
+
+    /// Scheduled times of each run, relative to `T0`.
+    type Log = Arc<Mutex<Vec<u64>>>;
+
+    /// 2024-01-01 00:00:00 UTC
+    const T0: u64 = 1_704_067_200;
+
+    fn cron(expr: &str) -> Cron {
+        CronParserImpl::new().parse(expr).unwrap()
+    }
+
+    fn scheduler(clock: &MockWallClock) -> MockScheduler {
+        Scheduler::new(clock.clone(), MockMonotonicClock::new())
+    }
+
+    /// Poll in-progress runs once without waiting for them.
+    fn poll_once(s: &mut MockScheduler) {
+        let _ = s.jobs.poll_running(&mut Context::from_waker(Waker::noop()));
+    }
+
+    /// Records the scheduled time of every run.
+    fn recorder() -> (Log, impl FnMut(Duration) -> std::future::Ready<()>) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let log2 = log.clone();
+        (log, move |at: Duration| {
+            log2.lock().unwrap().push(at.as_secs() - T0);
+            std::future::ready(())
+        })
+    }
+
+    /// A run that stays pending until the gate opens.
+    fn gated(gate: Arc<AtomicBool>) -> impl Future<Output = ()> {
+        poll_fn(move |_| {
+            if gate.load(Ordering::Relaxed) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+    }
+
+    #[test]
+    fn scheduler_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let mut s = scheduler(&MockWallClock::new(T0, 0));
+        assert_send(&s);
+        assert_send(&s.run());
+    }
+
+    #[test]
+    fn civil_round_trip() {
+        assert_eq!(to_civil(0), (1970, 1, 1, 0, 0, 0));
+        assert_eq!(to_civil(T0), (2024, 1, 1, 0, 0, 0));
+        assert_eq!(to_civil(1_709_210_096), (2024, 2, 29, 12, 34, 56));
+        for secs in [0, T0, 951_782_400, 4_102_444_799, 1_709_210_096] {
+            assert_eq!(from_civil(to_civil(secs)), secs);
+        }
+    }
+
+    #[test]
+    fn runs_at_each_occurrence() {
+        let clock = MockWallClock::new(T0, 0);
+        let mut s = scheduler(&clock);
+        let (log, task) = recorder();
+        s.add("minutely", cron("* * * * *"), JobOptions::default(), task)
+            .unwrap();
+
+        assert_eq!(s.tick(), 0);
+        assert_eq!(s.next_run("minutely"), Some(Duration::from_secs(T0 + 60)));
+
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(s.tick(), 0);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(s.tick(), 1);
+        assert_eq!(s.tick(), 0);
+        now(s.run_until_idle());
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 1);
+        now(s.run_until_idle());
+
+        assert_eq!(*log.lock().unwrap(), [60, 120]);
+        assert_eq!(s.last_run("minutely"), Some(Duration::from_secs(T0 + 120)));
+    }
+
+    #[test]
+    fn duplicate_and_remove() {
+        let clock = MockWallClock::new(T0, 0);
+        let mut s = scheduler(&clock);
+        let opts = JobOptions::default();
+        s.add("a", cron("* * * * *"), opts, |_| async {}).unwrap();
+        assert!(s.add("a", cron("* * * * *"), opts, |_| async {}).is_err());
+
+        assert!(s.remove("a"));
+        assert!(!s.remove("a"));
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 0);
+    }
+
+    fn overlap_job(overlap: Overlap) -> (MockWallClock, MockScheduler, Arc<AtomicBool>, Log) {
+        let clock = MockWallClock::new(T0, 0);
+        let mut s = scheduler(&clock);
+        let gate = Arc::new(AtomicBool::new(false));
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (g, l) = (gate.clone(), log.clone());
+        let opts = JobOptions {
+            overlap,
+            ..Default::default()
+        };
+        s.add("slow", cron("* * * * *"), opts, move |at| {
+            l.lock().unwrap().push(at.as_secs() - T0);
+            gated(g.clone())
+        })
+        .unwrap();
+        (clock, s, gate, log)
+    }
+
+    #[test]
+    fn overlap_skip() {
+        let (clock, mut s, gate, log) = overlap_job(Overlap::Skip);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 1);
+        poll_once(&mut s);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 0);
+        assert_eq!(s.running(), 1);
+
+        gate.store(true, Ordering::Relaxed);
+        now(s.run_until_idle());
+        assert_eq!(*log.lock().unwrap(), [60]);
+    }
+
+    #[test]
+    fn overlap_queue() {
+        let (clock, mut s, gate, log) = overlap_job(Overlap::Queue);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 1);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 0);
+        poll_once(&mut s);
+        assert_eq!(s.running(), 1);
+        assert_eq!(*log.lock().unwrap(), [60]);
+
+        // The queued run starts when the first finishes.
+        gate.store(true, Ordering::Relaxed);
+        now(s.run_until_idle());
+        assert_eq!(*log.lock().unwrap(), [60, 120]);
+    }
+
+    #[test]
+    fn overlap_concurrent() {
+        let (clock, mut s, gate, log) = overlap_job(Overlap::Concurrent);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 1);
+        poll_once(&mut s);
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(s.tick(), 1);
+        assert_eq!(s.running(), 2);
+
+        gate.store(true, Ordering::Relaxed);
+        now(s.run_until_idle());
+        assert_eq!(s.running(), 0);
+        assert_eq!(*log.lock().unwrap(), [60, 120]);
+    }
+
+    fn catch_up(catch_up: CatchUp, downtime: u64) -> Vec<u64> {
+        let clock = MockWallClock::new(T0 + downtime, 0);
+        let mut s = scheduler(&clock);
+        let (log, task) = recorder();
+        let opts = JobOptions {
+            overlap: Overlap::Queue,
+            catch_up,
+            since: Some(Duration::from_secs(T0)),
+            ..Default::default()
+        };
+        s.add("hourly", cron("0 * * * *"), opts, task).unwrap();
+        s.tick();
+        now(s.run_until_idle());
+        std::mem::take(&mut *log.lock().unwrap())
+    }
+
+    #[test]
+    fn catch_up_after_downtime() {
+        // Down for 5.5 hours: occurrences at +1h..+5h were missed.
+        let downtime = 5 * 3600 + 1800;
+        assert_eq!(catch_up(CatchUp::Skip, downtime), [] as [u64; 0]);
+        assert_eq!(catch_up(CatchUp::Once, downtime), [5 * 3600]);
+        assert_eq!(
+            catch_up(CatchUp::All, downtime),
+            [3600, 7200, 10800, 14400, 18000]
+        );
+
+        // Within the grace period the latest run is on time.
+        assert_eq!(catch_up(CatchUp::Skip, 5 * 3600 + 30), [5 * 3600]);
+    }
+
+    #[test]
+    fn catch_up_all_is_capped() {
+        let clock = MockWallClock::new(T0 + 30 * 86_400, 0);
+        let mut s = scheduler(&clock);
+        let (log, task) = recorder();
+        let opts = JobOptions {
+            overlap: Overlap::Queue,
+            catch_up: CatchUp::All,
+            catch_up_limit: 3,
+            since: Some(Duration::from_secs(T0)),
+            ..Default::default()
+        };
+        s.add("minutely", cron("* * * * *"), opts, task).unwrap();
+        s.tick();
+        now(s.run_until_idle());
+
+        // A month of missed minutes, of which only the last three run.
+        let end = 30 * 86_400;
+        assert_eq!(*log.lock().unwrap(), [end - 120, end - 60, end]);
+    }
+
+    #[test]
+    fn jitter_is_bounded_and_deterministic() {
+        let jitter = Duration::from_secs(30);
+        let opts = JobOptions {
+            jitter,
+            ..Default::default()
+        };
+        let clock = MockWallClock::new(T0, 0);
+        let mut a = scheduler(&clock);
+        let mut b = scheduler(&clock);
+        a.add("report", cron("0 * * * *"), opts, |_| async {})
+            .unwrap();
+        b.add("report", cron("0 * * * *"), opts, |_| async {})
+            .unwrap();
+
+        let next = a.next_run("report").unwrap();
+        assert_eq!(b.next_run("report"), Some(next));
+        let hour = Duration::from_secs(T0 + 3600);
+        assert!(next >= hour && next < hour + jitter);
+
+        // Not started before the jittered time.
+        let early = next - Duration::from_nanos(1);
+        clock.set(early.as_secs(), early.subsec_nanos());
+        assert_eq!(a.tick(), 0);
+        clock.set(next.as_secs(), next.subsec_nanos());
+        assert_eq!(a.tick(), 1);
+    }
+
+    #[cfg(feature = "timezone")]
+    mod zoned {
+        use super::*;
+
+        fn new_york(clock: &MockWallClock) -> MockScheduler {
+            let tz = portals_timezone::get("America/New_York").unwrap();
+            scheduler(clock).with_time_zone(tz)
+        }
+
+        /// Scheduled times of every run of `expr` missed between `since`
+        /// and `until`, in seconds since the Unix epoch.
+        fn missed(expr: &str, since: u64, until: u64) -> Vec<u64> {
+            let clock = MockWallClock::new(until, 0);
+            let mut s = new_york(&clock);
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let l = log.clone();
+            let opts = JobOptions {
+                overlap: Overlap::Queue,
+                catch_up: CatchUp::All,
+                since: Some(Duration::from_secs(since)),
+                ..Default::default()
+            };
+            s.add("job", cron(expr), opts, move |at: Duration| {
+                l.lock().unwrap().push(at.as_secs());
+                std::future::ready(())
+            })
+            .unwrap();
+            s.tick();
+            now(s.run_until_idle());
+            std::mem::take(&mut *log.lock().unwrap())
+        }
+
+        #[test]
+        fn runs_in_local_time() {
+            let clock = MockWallClock::new(T0, 0);
+            let mut s = new_york(&clock);
+            let (log, task) = recorder();
+            s.add("morning", cron("0 9 * * *"), JobOptions::default(), task)
+                .unwrap();
+
+            // 09:00 EST is 14:00 UTC.
+            let next = Duration::from_secs(T0 + 14 * 3600);
+            assert_eq!(s.next_run("morning"), Some(next));
+            clock.set(next.as_secs(), 0);
+            assert_eq!(s.tick(), 1);
+            now(s.run_until_idle());
+            assert_eq!(*log.lock().unwrap(), [14 * 3600]);
+        }
+
+        #[test]
+        fn catch_up_follows_spring_forward() {
+            // 02:30 does not exist on 2024-03-10, so the fixed-time job
+            // runs at the end of the gap, 03:00 EDT.
+            assert_eq!(
+                missed("30 2 * * *", 1_709_942_400, 1_710_158_400),
+                [1_709_969_400, 1_710_054_000, 1_710_138_600]
+            );
+        }
+
+        #[test]
+        fn catch_up_follows_fall_back() {
+            // 01:00 happens twice on 2024-11-03: hourly jobs run on both
+            // passes, fixed-time jobs only on the first.
+            let (since, now) = (1_730_608_200, 1_730_619_000);
+            assert_eq!(
+                missed("0 * * * *", since, now),
+                [1_730_610_000, 1_730_613_600, 1_730_617_200]
+            );
+            assert_eq!(missed("30 1 * * *", since, now), [1_730_611_800]);
+        }
+    }
+}
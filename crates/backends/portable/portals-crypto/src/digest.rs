@@ -0,0 +1,155 @@
+//! Hashing data as it is written to a stream.
+
+use portals_crypto::Hash;
+use portals_io::{OutputStream, StreamError};
+
+/// An output stream that discards everything written to it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Discard;
+
+impl OutputStream for Discard {
+    fn check_write(&self) -> Result<usize, StreamError> {
+        Ok(usize::MAX)
+    }
+
+    fn write(&mut self, _bytes: &[u8]) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn blocking_write(&mut self, _bytes: &[u8]) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn blocking_flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        std::future::ready(())
+    }
+}
+
+/// Hashes everything written to it.
+///
+/// On its own a `Digest` is a sink; [`tee`](Digest::tee) also passes the
+/// data through to another stream, e.g. to hash a file while copying it.
+/// Only bytes the inner stream accepted are hashed.
+pub struct Digest<H, W = Discard> {
+    hasher: H,
+    output: W,
+}
+
+impl<H: Hash> Digest<H> {
+    pub fn new() -> Self {
+        Self {
+            hasher: H::new(),
+            output: Discard,
+        }
+    }
+
+    /// Return the hash of everything written.
+    pub fn finalize(self) -> Vec<u8> {
+        self.hasher.finalize()
+    }
+}
+
+impl<H: Hash> Default for Digest<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: Hash, W: OutputStream> Digest<H, W> {
+    /// Hash data on its way to `output`.
+    pub fn tee(output: W) -> Self {
+        Self {
+            hasher: H::new(),
+            output,
+        }
+    }
+
+    /// Flush the inner stream, returning the hash and the stream.
+    pub fn finish(mut self) -> Result<(Vec<u8>, W), StreamError> {
+        self.output.blocking_flush()?;
+        Ok((self.hasher.finalize(), self.output))
+    }
+}
+
+impl<H: Hash, W: OutputStream> OutputStream for Digest<H, W> {
+    fn check_write(&self) -> Result<usize, StreamError> {
+        self.output.check_write()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.output.write(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.output.blocking_write(bytes)?;
+        self.hasher.update(bytes);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.output.flush()
+    }
+
+    fn blocking_flush(&mut self) -> Result<(), StreamError> {
+        self.output.blocking_flush()
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        self.output.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_crypto_native::Sha256;
+
+    #[test]
+    fn digest_matches_one_shot_hash() {
+        let mut digest = Digest::<Sha256>::new();
+        digest.write(b"hello ").unwrap();
+        digest.blocking_write(b"world").unwrap();
+        assert_eq!(digest.finalize(), Sha256::hash(b"hello world"));
+    }
+
+    #[test]
+    fn failed_writes_are_not_hashed() {
+        struct Full;
+
+        impl OutputStream for Full {
+            fn check_write(&self) -> Result<usize, StreamError> {
+                Ok(0)
+            }
+            fn write(&mut self, _bytes: &[u8]) -> Result<(), StreamError> {
+                Err(StreamError::Closed)
+            }
+            fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+                self.write(bytes)
+            }
+            fn flush(&mut self) -> Result<(), StreamError> {
+                Ok(())
+            }
+            fn blocking_flush(&mut self) -> Result<(), StreamError> {
+                Ok(())
+            }
+            fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+                std::future::ready(())
+            }
+        }
+
+        let mut digest = Digest::<Sha256, _>::tee(Full);
+        assert!(digest.write(b"lost").is_err());
+        let (hash, _) = digest.finish().unwrap();
+        assert_eq!(hash, Sha256::hash(b""));
+    }
+}
@@ -0,0 +1,382 @@
+//! Sealing and opening messages without managing nonces.
+//!
+//! # Format
+//!
+//! Sealed messages are self-describing and the layout below is stable:
+//! messages sealed by any release can be opened by later ones.
+//!
+//! ```text
+//! version      u8     always 1
+//! mode         u8     0 = direct, 1 = envelope
+//! key ID       u8 length (1-255), then UTF-8 bytes
+//! nonce        u8 length, then bytes
+//! data key     envelope mode only: u16 big-endian length, then the
+//!              wrapping nonce followed by the encrypted data key
+//! ciphertext   the rest, including the authentication tag
+//! ```
+//!
+//! Ciphers whose nonces or wrapped data keys do not fit these lengths
+//! cannot seal messages.
+//!
+//! The header (everything before the ciphertext) is authenticated as
+//! associated data, followed by the caller's own associated data. In
+//! envelope mode the data key is encrypted under the key ID's key, with the
+//! header up to and including the nonce as associated data.
+
+use crate::Keyring;
+use portals_crypto::{Cipher, CryptoError, SymmetricKey, Zeroizing};
+use portals_random::SecureRandom;
+
+/// Current format version.
+const VERSION: u8 = 1;
+
+const MODE_DIRECT: u8 = 0;
+const MODE_ENVELOPE: u8 = 1;
+
+/// Seals and opens messages with a keyring.
+///
+/// Nonces are generated from `R` for every message. Messages are sealed
+/// with the keyring's active key and opened with whichever retained key
+/// they name, so keys can be rotated without re-encrypting old data.
+///
+/// Nonces are random, so a single key should not seal more than about 2^32
+/// messages with a 96-bit nonce cipher. Envelope mode sidesteps this by
+/// encrypting each message under a fresh data key.
+#[derive(Debug)]
+pub struct Sealer<C, R> {
+    keyring: Keyring<SymmetricKey<C>>,
+    random: R,
+    envelope: bool,
+}
+
+impl<C: Cipher, R: SecureRandom> Sealer<C, R> {
+    pub fn new(keyring: Keyring<SymmetricKey<C>>, random: R) -> Self {
+        Self {
+            keyring,
+            random,
+            envelope: false,
+        }
+    }
+
+    /// Encrypt each message under a fresh random data key, stored in the
+    /// message wrapped by the keyring's key. Opening handles both modes
+    /// regardless of this setting.
+    pub fn with_envelope(mut self, envelope: bool) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    pub fn keyring(&self) -> &Keyring<SymmetricKey<C>> {
+        &self.keyring
+    }
+
+    /// Access the keyring, e.g. to rotate keys.
+    pub fn keyring_mut(&mut self) -> &mut Keyring<SymmetricKey<C>> {
+        &mut self.keyring
+    }
+
+    /// Encrypt and authenticate a message with the active key.
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (kid, key) = self.keyring.active();
+        if kid.is_empty() || kid.len() > u8::MAX as usize {
+            return Err(CryptoError::Other(format!(
+                "key ID must be 1-255 bytes: {:?}",
+                kid
+            )));
+        }
+        let mode = if self.envelope {
+            MODE_ENVELOPE
+        } else {
+            MODE_DIRECT
+        };
+
+        let nonce_len = u8::try_from(C::NONCE_SIZE).map_err(|_| CryptoError::InvalidNonceSize)?;
+
+        let mut out = vec![VERSION, mode, kid.len() as u8];
+        out.extend_from_slice(kid.as_bytes());
+        out.push(nonce_len);
+        let nonce_start = out.len();
+        out.resize(nonce_start + C::NONCE_SIZE, 0);
+        self.random.fill(&mut out[nonce_start..]);
+
+        let data_key = if self.envelope {
+            let data_key = Zeroizing::new(self.random.bytes(C::KEY_SIZE));
+            let mut wrap_nonce = vec![0; C::NONCE_SIZE];
+            self.random.fill(&mut wrap_nonce);
+            let wrapped = key.encrypt(&wrap_nonce, &data_key, &out)?;
+
+            let len = u16::try_from(wrap_nonce.len() + wrapped.len())
+                .map_err(|_| CryptoError::InvalidKeySize)?;
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(&wrap_nonce);
+            out.extend_from_slice(&wrapped);
+            Some(SymmetricKey::<C>::from_bytes(&data_key)?)
+        } else {
+            None
+        };
+
+        let nonce = &out[nonce_start..nonce_start + C::NONCE_SIZE];
+        let full_aad = [out.as_slice(), aad].concat();
+        let ciphertext = data_key
+            .as_ref()
+            .unwrap_or(key)
+            .encrypt(nonce, plaintext, &full_aad)?;
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Verify and decrypt a sealed message.
+    pub fn open(&self, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let header = Header::parse(sealed)?;
+        let key = self
+            .keyring
+            .get(header.kid)
+            .ok_or_else(|| CryptoError::Other(format!("unknown key ID: {}", header.kid)))?;
+        if header.nonce.len() != C::NONCE_SIZE {
+            return Err(CryptoError::InvalidNonceSize);
+        }
+
+        let data_key = match header.wrapped_key {
+            Some(wrapped) => {
+                if wrapped.len() != C::NONCE_SIZE + C::KEY_SIZE + C::TAG_SIZE {
+                    return Err(malformed("wrapped data key has the wrong length"));
+                }
+                let (wrap_nonce, wrapped) = wrapped.split_at(C::NONCE_SIZE);
+                let data_key = Zeroizing::new(key.decrypt(
+                    wrap_nonce,
+                    wrapped,
+                    &sealed[..header.nonce_end],
+                )?);
+                Some(SymmetricKey::<C>::from_bytes(&data_key)?)
+            }
+            None => None,
+        };
+
+        let (header_bytes, ciphertext) = sealed.split_at(header.len);
+        let full_aad = [header_bytes, aad].concat();
+        data_key
+            .as_ref()
+            .unwrap_or(key)
+            .decrypt(header.nonce, ciphertext, &full_aad)
+    }
+}
+
+/// The key ID a sealed message was sealed with, without decrypting it.
+pub fn sealed_key_id(sealed: &[u8]) -> Result<&str, CryptoError> {
+    Ok(Header::parse(sealed)?.kid)
+}
+
+fn malformed(msg: &str) -> CryptoError {
+    CryptoError::InvalidCiphertext(msg.to_string())
+}
+
+/// A parsed sealed-message header.
+struct Header<'a> {
+    kid: &'a str,
+    nonce: &'a [u8],
+    /// Offset just past the nonce.
+    nonce_end: usize,
+    wrapped_key: Option<&'a [u8]>,
+    /// Total header length; the ciphertext follows.
+    len: usize,
+}
+
+impl<'a> Header<'a> {
+    fn parse(sealed: &'a [u8]) -> Result<Self, CryptoError> {
+        let mut reader = Reader {
+            data: sealed,
+            pos: 0,
+        };
+
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(CryptoError::InvalidCiphertext(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+        let mode = reader.u8()?;
+        if mode != MODE_DIRECT && mode != MODE_ENVELOPE {
+            return Err(CryptoError::InvalidCiphertext(format!(
+                "unknown mode {}",
+                mode
+            )));
+        }
+
+        let kid_len = reader.u8()? as usize;
+        if kid_len == 0 {
+            return Err(malformed("empty key ID"));
+        }
+        let kid = std::str::from_utf8(reader.take(kid_len)?)
+            .map_err(|_| malformed("key ID is not UTF-8"))?;
+
+        let nonce_len = reader.u8()? as usize;
+        let nonce = reader.take(nonce_len)?;
+        let nonce_end = reader.pos;
+
+        let wrapped_key = if mode == MODE_ENVELOPE {
+            let len = u16::from_be_bytes([reader.u8()?, reader.u8()?]) as usize;
+            Some(reader.take(len)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            kid,
+            nonce,
+            nonce_end,
+            wrapped_key,
+            len: reader.pos,
+        })
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CryptoError> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| malformed("truncated header"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, CryptoError> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Toy;
+    use portals_random_mock::MockSecureRandom;
+
+    const SEED: u64 = 42;
+
+    fn sealer() -> Sealer<Toy, MockSecureRandom> {
+        let key = SymmetricKey::from_bytes(&[0xa0, 0xa1, 0xa2, 0xa3]).unwrap();
+        Sealer::new(Keyring::new("k1", key), MockSecureRandom::new(SEED))
+    }
+
+    #[test]
+    fn format_is_stable() {
+        const KEY: [u8; 4] = [0xa0, 0xa1, 0xa2, 0xa3];
+
+        // The same draws the sealer makes: nonce, then data key and
+        // wrapping nonce in envelope mode.
+        let random = MockSecureRandom::new(SEED);
+        let nonce = random.bytes(12);
+        let sealed = sealer().seal(b"hi", b"").unwrap();
+        let mut expected = vec![1, 0, 2, b'k', b'1', 12];
+        expected.extend(&nonce);
+        expected.extend(<Toy>::encrypt(&KEY, &nonce, b"hi", &expected).unwrap());
+        assert_eq!(sealed, expected);
+
+        let (data_key, wrap_nonce) = (random.bytes(4), random.bytes(12));
+        let sealed = sealer().with_envelope(true).seal(b"hi", b"").unwrap();
+        assert_eq!(sealed[..6], [1, 1, 2, b'k', b'1', 12]);
+        assert_eq!(sealed[6..18], nonce);
+        assert_eq!(sealed[18..20], [0, 20]);
+        assert_eq!(sealed[20..32], wrap_nonce);
+        let wrapped = <Toy>::encrypt(&KEY, &wrap_nonce, &data_key, &sealed[..18]).unwrap();
+        assert_eq!(sealed[32..40], wrapped);
+        assert_eq!(sealed.len(), 40 + 2 + 4);
+    }
+
+    #[test]
+    fn seal_open_roundtrip() {
+        for envelope in [false, true] {
+            let sealer = sealer().with_envelope(envelope);
+            let sealed = sealer.seal(b"hello world", b"context").unwrap();
+            assert_eq!(sealed_key_id(&sealed).unwrap(), "k1");
+            assert_eq!(sealer.open(&sealed, b"context").unwrap(), b"hello world");
+            assert!(matches!(
+                sealer.open(&sealed, b"other"),
+                Err(CryptoError::AuthenticationFailed)
+            ));
+        }
+    }
+
+    #[test]
+    fn nonces_are_fresh() {
+        let sealer = sealer();
+        let a = sealer.seal(b"same", b"").unwrap();
+        let b = sealer.seal(b"same", b"").unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn header_is_authenticated() {
+        for envelope in [false, true] {
+            let sealer = sealer().with_envelope(envelope);
+            let sealed = sealer.seal(b"hello", b"").unwrap();
+            for i in 0..sealed.len() {
+                let mut tampered = sealed.clone();
+                tampered[i] ^= 0x01;
+                assert!(
+                    sealer.open(&tampered, b"").is_err(),
+                    "byte {} (envelope: {})",
+                    i,
+                    envelope
+                );
+            }
+            for len in 0..sealed.len() {
+                assert!(sealer.open(&sealed[..len], b"").is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn opens_with_rotated_keys() {
+        let mut sealer = sealer().with_envelope(true);
+        let old = sealer.seal(b"old", b"").unwrap();
+        let key = SymmetricKey::from_bytes(&[0xb0; 4]).unwrap();
+        sealer.keyring_mut().rotate("k2", key).unwrap();
+        let new = sealer.seal(b"new", b"").unwrap();
+
+        assert_eq!(sealed_key_id(&new).unwrap(), "k2");
+        assert_eq!(sealer.open(&old, b"").unwrap(), b"old");
+        assert_eq!(sealer.open(&new, b"").unwrap(), b"new");
+
+        sealer.keyring_mut().remove("k1").unwrap();
+        assert!(sealer.open(&old, b"").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let mut sealed = sealer().seal(b"hi", b"").unwrap();
+        sealed[0] = 2;
+        assert!(matches!(
+            sealed_key_id(&sealed),
+            Err(CryptoError::InvalidCiphertext(_))
+        ));
+    }
+
+    fn resized_sealer<const KEY: usize, const NONCE: usize>()
+    -> Sealer<Toy<KEY, NONCE>, MockSecureRandom> {
+        let key = SymmetricKey::from_bytes(&[0xa0; KEY]).unwrap();
+        Sealer::new(Keyring::new("k1", key), MockSecureRandom::new(SEED))
+    }
+
+    #[test]
+    fn refuses_sizes_the_format_cannot_hold() {
+        assert!(matches!(
+            resized_sealer::<4, 256>().seal(b"hi", b""),
+            Err(CryptoError::InvalidNonceSize)
+        ));
+
+        // A 65,528-byte data key wraps to 65,536 bytes with its nonce and tag.
+        let sealer = resized_sealer::<65_528, 4>();
+        assert!(sealer.seal(b"hi", b"").is_ok());
+        assert!(matches!(
+            sealer.with_envelope(true).seal(b"hi", b""),
+            Err(CryptoError::InvalidKeySize)
+        ));
+    }
+}
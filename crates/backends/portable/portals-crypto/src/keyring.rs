@@ -0,0 +1,205 @@
+use portals_crypto::{Cipher, CryptoError, KeyPair, PublicKey, Signature, SymmetricKey};
+use std::fmt;
+
+/// Keys identified by key ID, with one active key.
+///
+/// New data is encrypted or signed with the active key; decryption and
+/// verification accept any retained key. To rotate, add a new key with
+/// [`rotate`](Self::rotate) and [`remove`](Self::remove) old ones once
+/// nothing depends on them.
+#[derive(Clone)]
+pub struct Keyring<K> {
+    keys: Vec<(String, K)>,
+    active: usize,
+}
+
+impl<K> Keyring<K> {
+    /// Create a keyring with a single, active key.
+    pub fn new(kid: impl Into<String>, key: K) -> Self {
+        Self {
+            keys: vec![(kid.into(), key)],
+            active: 0,
+        }
+    }
+
+    /// Retain a key without making it active.
+    pub fn add(&mut self, kid: impl Into<String>, key: K) -> Result<(), CryptoError> {
+        let kid = kid.into();
+        if self.position(&kid).is_some() {
+            return Err(CryptoError::Other(format!("duplicate key ID: {}", kid)));
+        }
+        self.keys.push((kid, key));
+        Ok(())
+    }
+
+    /// Add a key and make it active. The previous key is retained.
+    pub fn rotate(&mut self, kid: impl Into<String>, key: K) -> Result<(), CryptoError> {
+        self.add(kid, key)?;
+        self.active = self.keys.len() - 1;
+        Ok(())
+    }
+
+    /// Make a retained key active.
+    pub fn set_active(&mut self, kid: &str) -> Result<(), CryptoError> {
+        self.active = self.position(kid).ok_or_else(|| unknown_key(kid))?;
+        Ok(())
+    }
+
+    /// Stop retaining a key. The active key cannot be removed.
+    pub fn remove(&mut self, kid: &str) -> Result<K, CryptoError> {
+        let index = self.position(kid).ok_or_else(|| unknown_key(kid))?;
+        if index == self.active {
+            return Err(CryptoError::Other(format!(
+                "cannot remove active key: {}",
+                kid
+            )));
+        }
+        if index < self.active {
+            self.active -= 1;
+        }
+        Ok(self.keys.remove(index).1)
+    }
+
+    /// The active key ID and key.
+    pub fn active(&self) -> (&str, &K) {
+        let (kid, key) = &self.keys[self.active];
+        (kid, key)
+    }
+
+    /// Look up a retained key.
+    pub fn get(&self, kid: &str) -> Option<&K> {
+        self.position(kid).map(|i| &self.keys[i].1)
+    }
+
+    /// IDs of all retained keys, in the order they were added.
+    pub fn key_ids(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(|(kid, _)| kid.as_str())
+    }
+
+    fn position(&self, kid: &str) -> Option<usize> {
+        self.keys.iter().position(|(k, _)| k == kid)
+    }
+
+    fn require(&self, kid: &str) -> Result<&K, CryptoError> {
+        self.get(kid).ok_or_else(|| unknown_key(kid))
+    }
+}
+
+fn unknown_key(kid: &str) -> CryptoError {
+    CryptoError::Other(format!("unknown key ID: {}", kid))
+}
+
+impl<K> fmt::Debug for Keyring<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring")
+            .field("keys", &self.key_ids().collect::<Vec<_>>())
+            .field("active", &self.active().0)
+            .finish()
+    }
+}
+
+impl<C: Cipher> Keyring<SymmetricKey<C>> {
+    /// Encrypt with the active key, returning its ID with the ciphertext.
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<(&str, Vec<u8>), CryptoError> {
+        let (kid, key) = self.active();
+        Ok((kid, key.encrypt(nonce, plaintext, aad)?))
+    }
+
+    /// Decrypt with the key that encrypted the data.
+    pub fn decrypt(
+        &self,
+        kid: &str,
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.require(kid)?.decrypt(nonce, ciphertext, aad)
+    }
+}
+
+impl<S: Signature> Keyring<KeyPair<S>> {
+    /// Sign with the active key, returning its ID with the signature.
+    pub fn sign(&self, message: &[u8]) -> Result<(&str, Vec<u8>), CryptoError> {
+        let (kid, key) = self.active();
+        Ok((kid, key.sign(message)?))
+    }
+
+    /// Verify a signature made by the given key.
+    pub fn verify(&self, kid: &str, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        self.require(kid)?.verify(message, signature)
+    }
+
+    /// The public keys, for distributing to verifiers.
+    pub fn public_keys(&self) -> Keyring<PublicKey<S>> {
+        Keyring {
+            keys: self
+                .keys
+                .iter()
+                .map(|(kid, pair)| (kid.clone(), pair.public().clone()))
+                .collect(),
+            active: self.active,
+        }
+    }
+}
+
+impl<S: Signature> Keyring<PublicKey<S>> {
+    /// Verify a signature made by the given key.
+    pub fn verify(&self, kid: &str, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        self.require(kid)?.verify(message, signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Toy;
+
+    const NONCE: [u8; 12] = [0; 12];
+
+    fn key(byte: u8) -> SymmetricKey<Toy> {
+        SymmetricKey::from_bytes(&[byte; 4]).unwrap()
+    }
+
+    #[test]
+    fn keyring_rotation() {
+        let mut ring = Keyring::new("2024", key(1));
+        let (kid, old) = ring.encrypt(&NONCE, b"hello", &[]).unwrap();
+        assert_eq!(kid, "2024");
+
+        ring.rotate("2025", key(2)).unwrap();
+        let (kid, new) = ring.encrypt(&NONCE, b"hello", &[]).unwrap();
+        assert_eq!(kid, "2025");
+        assert_ne!(new, old);
+
+        // Both keys still decrypt.
+        assert_eq!(ring.decrypt("2024", &NONCE, &old, &[]).unwrap(), b"hello");
+        assert_eq!(ring.decrypt("2025", &NONCE, &new, &[]).unwrap(), b"hello");
+
+        // Retire the old key.
+        assert!(ring.remove("2025").is_err());
+        ring.remove("2024").unwrap();
+        assert!(ring.decrypt("2024", &NONCE, &old, &[]).is_err());
+        assert_eq!(ring.key_ids().collect::<Vec<_>>(), ["2025"]);
+        assert_eq!(ring.active().0, "2025");
+    }
+
+    #[test]
+    fn keyring_bookkeeping() {
+        let mut ring = Keyring::new("a", key(1));
+        ring.add("b", key(2)).unwrap();
+        ring.add("c", key(3)).unwrap();
+        assert!(ring.add("b", key(4)).is_err());
+        assert_eq!(ring.active().0, "a");
+
+        ring.set_active("c").unwrap();
+        ring.remove("a").unwrap();
+        assert_eq!(ring.active().0, "c");
+        assert!(ring.set_active("a").is_err());
+        assert_eq!(ring.get("b"), Some(&key(2)));
+    }
+}
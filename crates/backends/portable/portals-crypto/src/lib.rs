@@ -0,0 +1,17 @@
+//! Portable implementations built on the portals-crypto traits.
+//!
+//! Works on both native and WASM targets. Nothing here implements a
+//! primitive; the algorithms come from whichever backend supplies the
+//! `portals-crypto` trait implementations.
+
+mod digest;
+mod envelope;
+mod keyring;
+mod stream;
+#[cfg(test)]
+mod testing;
+
+pub use digest::{Digest, Discard};
+pub use envelope::{Sealer, sealed_key_id};
+pub use keyring::Keyring;
+pub use stream::{DEFAULT_CHUNK_SIZE, MAX_CHUNK_SIZE, StreamDecryptor, StreamEncryptor};
@@ -0,0 +1,635 @@
+//! Streaming authenticated encryption.
+//!
+//! Plaintext is split into fixed-size chunks, each sealed separately with the
+//! STREAM construction (Hoang, Reyhanitabar, Rogaway and Vizár, 2015). Every
+//! chunk's nonce is a random per-stream prefix, a 32-bit big-endian chunk
+//! counter and a flag byte that is 1 for the final chunk and 0 otherwise.
+//! Reordered or dropped chunks fail authentication because the counter no
+//! longer matches, and a stream truncated at a chunk boundary fails because
+//! its last chunk was not sealed as final.
+//!
+//! # Format
+//!
+//! ```text
+//! version      u8       always 1
+//! chunk size   u32      big-endian plaintext bytes per chunk
+//! prefix       NONCE_SIZE - 5 random bytes
+//! chunks       each chunk_size plaintext bytes plus a tag; the final chunk
+//!              may be shorter, or empty
+//! ```
+//!
+//! The header is authenticated as associated data of every chunk. With a
+//! 96-bit nonce cipher the prefix is 56 bits, so a single key should not
+//! encrypt more than about 2^24 streams.
+
+use portals_crypto::{Cipher, CryptoError, SymmetricKey};
+use portals_io::{InputStream, OutputStream, StreamError};
+use portals_random::SecureRandom;
+
+/// Current format version.
+const VERSION: u8 = 1;
+
+/// Default plaintext bytes per chunk.
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Largest chunk size accepted when decrypting.
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+fn nonce<C: Cipher>(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut nonce = Vec::with_capacity(C::NONCE_SIZE);
+    nonce.extend_from_slice(prefix);
+    nonce.extend_from_slice(&counter.to_be_bytes());
+    nonce.push(last as u8);
+    nonce
+}
+
+fn stream_error(err: CryptoError) -> StreamError {
+    StreamError::Other(err.to_string())
+}
+
+/// Encrypts everything written to it into an output stream.
+///
+/// Chunks are sealed as they fill up. The final chunk is only sealed by
+/// [`finish`](Self::finish); a stream dropped without finishing cannot be
+/// decrypted.
+///
+/// Non-blocking writes only stage sealed output. [`flush`](OutputStream::flush)
+/// hands it to the inner stream no faster than the inner
+/// [`check_write`](OutputStream::check_write) allows, and until all of it
+/// is written, this stream's `check_write` returns 0.
+pub struct StreamEncryptor<C, W> {
+    key: SymmetricKey<C>,
+    output: W,
+    /// Version, chunk size and nonce prefix.
+    header: Vec<u8>,
+    chunk_size: usize,
+    /// Plaintext not yet sealed.
+    buf: Vec<u8>,
+    /// Sealed output not yet written.
+    pending: Vec<u8>,
+    counter: u32,
+    started: bool,
+}
+
+impl<C: Cipher, W: OutputStream> StreamEncryptor<C, W> {
+    /// Create an encryptor with a fresh nonce prefix from `random`.
+    ///
+    /// Fails with [`CryptoError::InvalidNonceSize`] for ciphers with nonces
+    /// shorter than 96 bits.
+    pub fn new(
+        key: &SymmetricKey<C>,
+        random: &impl SecureRandom,
+        output: W,
+    ) -> Result<Self, CryptoError> {
+        if C::NONCE_SIZE < 12 {
+            return Err(CryptoError::InvalidNonceSize);
+        }
+        let mut header = vec![VERSION, 0, 0, 0, 0];
+        header.resize(C::NONCE_SIZE, 0);
+        random.fill(&mut header[5..]);
+        Ok(Self {
+            key: key.clone(),
+            output,
+            header,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buf: Vec::new(),
+            pending: Vec::new(),
+            counter: 0,
+            started: false,
+        })
+    }
+
+    /// Set the plaintext bytes per chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero or above [`MAX_CHUNK_SIZE`], or if output
+    /// has already been written.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(
+            size > 0 && size <= MAX_CHUNK_SIZE,
+            "chunk size out of range"
+        );
+        assert!(!self.started, "chunk size set after writing");
+        self.chunk_size = size;
+        self
+    }
+
+    /// Seal the final chunk, write everything out, flush, and return the
+    /// output stream.
+    pub fn finish(mut self) -> Result<W, StreamError> {
+        self.seal(self.buf.len(), true)?;
+        self.drain(true)?;
+        self.output.blocking_flush()?;
+        Ok(self.output)
+    }
+
+    /// Seal the first `len` bytes of buffered plaintext into `pending`.
+    /// The plaintext is only dropped once sealing succeeds.
+    fn seal(&mut self, len: usize, last: bool) -> Result<(), StreamError> {
+        if !self.started {
+            self.header[1..5].copy_from_slice(&(self.chunk_size as u32).to_be_bytes());
+        }
+        let nonce = nonce::<C>(&self.header[5..], self.counter, last);
+        let sealed = self
+            .key
+            .encrypt(&nonce, &self.buf[..len], &self.header)
+            .map_err(stream_error)?;
+        if !last {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| StreamError::Other("too many chunks".to_string()))?;
+        }
+        if !self.started {
+            self.pending.extend_from_slice(&self.header);
+            self.started = true;
+        }
+        self.pending.extend(sealed);
+        self.buf.drain(..len);
+        Ok(())
+    }
+
+    /// Write staged output, all of it if `blocking`, otherwise as much as
+    /// the inner stream permits. Output is only dropped once written.
+    fn drain(&mut self, blocking: bool) -> Result<(), StreamError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let len = if blocking {
+            self.output.blocking_write(&self.pending)?;
+            self.pending.len()
+        } else {
+            let len = self.output.check_write()?.min(self.pending.len());
+            if len == 0 {
+                return Ok(());
+            }
+            self.output.write(&self.pending[..len])?;
+            len
+        };
+        self.pending.drain(..len);
+        Ok(())
+    }
+
+    fn write_chunks(&mut self, bytes: &[u8], blocking: bool) -> Result<(), StreamError> {
+        self.buf.extend_from_slice(bytes);
+        // Hold back a full chunk until more data arrives, since it may turn
+        // out to be the last one.
+        while self.buf.len() > self.chunk_size {
+            self.seal(self.chunk_size, false)?;
+            if blocking {
+                self.drain(true)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<C: Cipher, W: OutputStream> OutputStream for StreamEncryptor<C, W> {
+    /// Plaintext bytes that can be written: 0 while sealed output is
+    /// staged, otherwise enough to fill at most one more chunk.
+    fn check_write(&self) -> Result<usize, StreamError> {
+        self.output.check_write()?;
+        if !self.pending.is_empty() {
+            return Ok(0);
+        }
+        Ok(2 * self.chunk_size - self.buf.len())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.write_chunks(bytes, false)
+    }
+
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.write_chunks(bytes, true)
+    }
+
+    /// Write out as much staged output as the inner stream permits, then
+    /// flush it. Buffered plaintext stays buffered until it fills a chunk
+    /// or the stream is finished.
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.drain(false)?;
+        self.output.flush()
+    }
+
+    fn blocking_flush(&mut self) -> Result<(), StreamError> {
+        self.drain(true)?;
+        self.output.blocking_flush()
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        self.output.subscribe()
+    }
+}
+
+/// Decrypts an input stream produced by [`StreamEncryptor`].
+///
+/// Plaintext is only returned once its chunk has been authenticated. Any
+/// failure, including truncation, is reported as an error rather than
+/// [`StreamError::Closed`], and every later read fails the same way.
+pub struct StreamDecryptor<C, R> {
+    key: SymmetricKey<C>,
+    input: R,
+    header: Option<Vec<u8>>,
+    chunk_size: usize,
+    /// Ciphertext read but not yet decrypted.
+    raw: Vec<u8>,
+    plain: Vec<u8>,
+    plain_pos: usize,
+    counter: u32,
+    eof: bool,
+    done: bool,
+    failed: Option<StreamError>,
+}
+
+impl<C: Cipher, R: InputStream> StreamDecryptor<C, R> {
+    pub fn new(key: &SymmetricKey<C>, input: R) -> Self {
+        Self {
+            key: key.clone(),
+            input,
+            header: None,
+            chunk_size: 0,
+            raw: Vec::new(),
+            plain: Vec::new(),
+            plain_pos: 0,
+            counter: 0,
+            eof: false,
+            done: false,
+            failed: None,
+        }
+    }
+
+    /// Read until plaintext is available. Returns false if a non-blocking
+    /// read came up empty.
+    fn fill(&mut self, blocking: bool) -> Result<bool, StreamError> {
+        while self.plain_pos == self.plain.len() {
+            if self.done {
+                return Err(StreamError::Closed);
+            }
+            // One byte past a full chunk tells us it is not the last.
+            let need = match self.header {
+                None => C::NONCE_SIZE,
+                Some(_) => self.chunk_size + C::TAG_SIZE + 1,
+            };
+            if !self.eof && self.raw.len() < need {
+                let start = self.raw.len();
+                self.raw.resize(need, 0);
+                let read = if blocking {
+                    self.input.blocking_read_into(&mut self.raw[start..])
+                } else {
+                    self.input.read_into(&mut self.raw[start..])
+                };
+                match read {
+                    Ok(n) => {
+                        self.raw.truncate(start + n);
+                        if n == 0 && !blocking {
+                            return Ok(false);
+                        }
+                    }
+                    Err(StreamError::Closed) => {
+                        self.raw.truncate(start);
+                        self.eof = true;
+                    }
+                    Err(e) => {
+                        self.raw.truncate(start);
+                        return Err(e);
+                    }
+                }
+                continue;
+            }
+            match self.header {
+                None => self.read_header()?,
+                Some(_) => self.decrypt_chunk()?,
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_header(&mut self) -> Result<(), StreamError> {
+        if self.raw.len() < C::NONCE_SIZE {
+            return Err(stream_error(CryptoError::InvalidCiphertext(
+                "truncated header".to_string(),
+            )));
+        }
+        let header: Vec<u8> = self.raw.drain(..C::NONCE_SIZE).collect();
+        if header[0] != VERSION {
+            return Err(stream_error(CryptoError::InvalidCiphertext(format!(
+                "unsupported version {}",
+                header[0]
+            ))));
+        }
+        let chunk_size = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(stream_error(CryptoError::InvalidCiphertext(format!(
+                "invalid chunk size {}",
+                chunk_size
+            ))));
+        }
+        self.chunk_size = chunk_size;
+        self.header = Some(header);
+        Ok(())
+    }
+
+    fn decrypt_chunk(&mut self) -> Result<(), StreamError> {
+        let header = self.header.as_deref().unwrap_or_default();
+        let chunk_len = self.chunk_size + C::TAG_SIZE;
+        let last = self.raw.len() <= chunk_len;
+        let len = self.raw.len().min(chunk_len);
+        let nonce = nonce::<C>(&header[5..], self.counter, last);
+        let plain = self
+            .key
+            .decrypt(&nonce, &self.raw[..len], header)
+            .map_err(stream_error)?;
+        self.raw.drain(..len);
+        self.plain = plain;
+        self.plain_pos = 0;
+        if last {
+            self.done = true;
+        } else {
+            self.counter = self
+                .counter
+                .checked_add(1)
+                .ok_or_else(|| StreamError::Other("too many chunks".to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn read_chunked(&mut self, buf: &mut [u8], blocking: bool) -> Result<usize, StreamError> {
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+        match self.fill(blocking) {
+            Ok(true) => {}
+            Ok(false) => return Ok(0),
+            Err(StreamError::Closed) => return Err(StreamError::Closed),
+            Err(err) => {
+                self.failed = Some(err.clone());
+                return Err(err);
+            }
+        }
+        let available = &self.plain[self.plain_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.plain_pos += n;
+        Ok(n)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+impl<C: Cipher, R: InputStream> InputStream for StreamDecryptor<C, R> {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+        self.read_chunked(buf, false)
+    }
+
+    fn blocking_read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+        self.read_chunked(buf, true)
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        self.input.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Toy;
+
+    struct Fixed;
+
+    impl SecureRandom for Fixed {
+        fn fill(&self, buf: &mut [u8]) {
+            buf.fill(0x42);
+        }
+    }
+
+    #[derive(Default)]
+    struct Sink(Vec<u8>);
+
+    impl OutputStream for Sink {
+        fn check_write(&self) -> Result<usize, StreamError> {
+            Ok(usize::MAX)
+        }
+        fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.0.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.write(bytes)
+        }
+        fn flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn blocking_flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    /// Permits `step` bytes per write, and fails the next write if `fail`
+    /// is set.
+    struct Trickle {
+        out: Vec<u8>,
+        step: usize,
+        fail: bool,
+    }
+
+    impl OutputStream for Trickle {
+        fn check_write(&self) -> Result<usize, StreamError> {
+            Ok(self.step)
+        }
+        fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            assert!(bytes.len() <= self.step, "wrote past the permit");
+            if std::mem::take(&mut self.fail) {
+                return Err(StreamError::LastOperationFailed);
+            }
+            self.out.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.out.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn blocking_flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    /// Hands out at most `step` bytes per read.
+    struct Source {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+    }
+
+    impl InputStream for Source {
+        fn read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+            if self.pos == self.data.len() {
+                return Err(StreamError::Closed);
+            }
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+        fn blocking_read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+            self.read_into(buf)
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    fn key() -> SymmetricKey<Toy> {
+        SymmetricKey::from_bytes(&[1, 2, 3, 4]).unwrap()
+    }
+
+    fn encrypt(plaintext: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut enc = StreamEncryptor::new(&key(), &Fixed, Sink::default())
+            .unwrap()
+            .chunk_size(chunk_size);
+        for piece in plaintext.chunks(7) {
+            enc.write(piece).unwrap();
+        }
+        enc.finish().unwrap().0
+    }
+
+    fn decrypt(ciphertext: &[u8], step: usize) -> Result<Vec<u8>, StreamError> {
+        let source = Source {
+            data: ciphertext.to_vec(),
+            pos: 0,
+            step,
+        };
+        let mut dec = StreamDecryptor::new(&key(), source);
+        let mut out = Vec::new();
+        let mut buf = [0u8; 5];
+        loop {
+            match dec.blocking_read_into(&mut buf) {
+                Ok(n) => out.extend_from_slice(&buf[..n]),
+                Err(StreamError::Closed) => return Ok(out),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrips_at_chunk_boundaries() {
+        let data: Vec<u8> = (0..100u8).collect();
+        for len in [0, 1, 15, 16, 17, 32, 33, 100] {
+            let ciphertext = encrypt(&data[..len], 16);
+            let chunks = len.div_ceil(16).max(1);
+            assert_eq!(ciphertext.len(), 12 + len + chunks * 4, "length {}", len);
+            for step in [1, 3, 64] {
+                assert_eq!(decrypt(&ciphertext, step).unwrap(), &data[..len]);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_within_the_inner_permit() {
+        let data: Vec<u8> = (0..100u8).collect();
+        let sink = Trickle {
+            out: Vec::new(),
+            step: 5,
+            fail: false,
+        };
+        let mut enc = StreamEncryptor::new(&key(), &Fixed, sink)
+            .unwrap()
+            .chunk_size(16);
+        let mut rest = &data[..];
+        let mut failed = false;
+        while !rest.is_empty() {
+            let n = enc.check_write().unwrap().min(rest.len());
+            if n == 0 {
+                // A failed write loses nothing; the output is retried.
+                enc.output.fail = !failed;
+                assert_eq!(enc.flush().is_err(), !failed);
+                failed = true;
+                continue;
+            }
+            enc.write(&rest[..n]).unwrap();
+            rest = &rest[n..];
+        }
+        assert!(failed);
+        let ciphertext = enc.finish().unwrap().out;
+        assert_eq!(ciphertext, encrypt(&data, 16));
+        assert_eq!(decrypt(&ciphertext, 64).unwrap(), data);
+    }
+
+    #[test]
+    fn header_layout() {
+        let ciphertext = encrypt(b"", 16);
+        assert_eq!(
+            ciphertext[..12],
+            [1, 0, 0, 0, 16, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42]
+        );
+    }
+
+    #[test]
+    fn detects_truncation() {
+        let data = [7u8; 48];
+        let ciphertext = encrypt(&data, 16);
+        // Header, then three chunks of 20 bytes, the last sealed as final.
+        assert_eq!(ciphertext.len(), 12 + 3 * 20);
+        for len in 0..ciphertext.len() {
+            assert!(decrypt(&ciphertext[..len], 64).is_err(), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn detects_reordering() {
+        let data: Vec<u8> = (0..40u8).collect();
+        let ciphertext = encrypt(&data, 16);
+        let mut swapped = ciphertext[..12].to_vec();
+        swapped.extend_from_slice(&ciphertext[32..52]);
+        swapped.extend_from_slice(&ciphertext[12..32]);
+        swapped.extend_from_slice(&ciphertext[52..]);
+        assert!(decrypt(&swapped, 64).is_err());
+
+        let mut dropped = ciphertext[..12].to_vec();
+        dropped.extend_from_slice(&ciphertext[32..]);
+        assert!(decrypt(&dropped, 64).is_err());
+    }
+
+    #[test]
+    fn detects_tampering() {
+        let ciphertext = encrypt(b"hello, streaming world", 8);
+        for i in 0..ciphertext.len() {
+            let mut tampered = ciphertext.clone();
+            tampered[i] ^= 0x80;
+            assert!(decrypt(&tampered, 64).is_err(), "byte {}", i);
+        }
+    }
+
+    #[test]
+    fn errors_are_sticky() {
+        let mut ciphertext = encrypt(&[0u8; 40], 16);
+        ciphertext[20] ^= 1;
+        let source = Source {
+            data: ciphertext,
+            pos: 0,
+            step: 64,
+        };
+        let mut dec = StreamDecryptor::new(&key(), source);
+        let mut buf = [0u8; 64];
+        assert!(matches!(
+            dec.blocking_read_into(&mut buf),
+            Err(StreamError::Other(_))
+        ));
+        assert!(matches!(
+            dec.blocking_read_into(&mut buf),
+            Err(StreamError::Other(_))
+        ));
+    }
+}
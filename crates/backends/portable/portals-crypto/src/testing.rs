@@ -0,0 +1,66 @@
+//! Fakes shared by the unit tests.
+
+use portals_crypto::{Cipher, CryptoError};
+
+/// A toy AEAD: XOR keystream and a checksum tag. Enough to exercise
+/// formats and framing, not to protect anything.
+///
+/// Defaults to 96-bit nonces like the real ciphers; other sizes test
+/// ciphers a format cannot hold.
+pub(crate) struct Toy<const KEY: usize = 4, const NONCE: usize = 12>;
+
+impl<const KEY: usize, const NONCE: usize> Toy<KEY, NONCE> {
+    fn tag(key: &[u8], nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> [u8; 4] {
+        let mut tag = [0u8; 4];
+        for (i, b) in key
+            .iter()
+            .chain(nonce)
+            .chain(aad)
+            .chain(ciphertext)
+            .enumerate()
+        {
+            tag[i % 4] = tag[i % 4].wrapping_mul(31).wrapping_add(*b);
+        }
+        tag
+    }
+
+    fn xor(key: &[u8], nonce: &[u8], data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .enumerate()
+            .map(|(i, b)| b ^ key[i % KEY] ^ nonce[i % NONCE])
+            .collect()
+    }
+}
+
+impl<const KEY: usize, const NONCE: usize> Cipher for Toy<KEY, NONCE> {
+    const KEY_SIZE: usize = KEY;
+    const NONCE_SIZE: usize = NONCE;
+    const TAG_SIZE: usize = 4;
+
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let mut out = Self::xor(key, nonce, plaintext);
+        let tag = Self::tag(key, nonce, aad, &out);
+        out.extend_from_slice(&tag);
+        Ok(out)
+    }
+
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let (body, tag) = ciphertext
+            .split_at_checked(ciphertext.len().wrapping_sub(4))
+            .ok_or(CryptoError::AuthenticationFailed)?;
+        if Self::tag(key, nonce, aad, body) != tag {
+            return Err(CryptoError::AuthenticationFailed);
+        }
+        Ok(Self::xor(key, nonce, body))
+    }
+}
@@ -0,0 +1,608 @@
+//! A caching layer for any resolver.
+
+use portals_clocks::MonotonicClock;
+use portals_dns::{Error, LookupFailure, Record, RecordData, RecordType, Resolver, reverse_name};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::Duration;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// A cached answer.
+#[derive(Debug, Clone)]
+enum Answer {
+    Records(Vec<Record>),
+    NoRecords,
+    NxDomain,
+}
+
+#[derive(Debug)]
+struct Entry {
+    answer: Answer,
+    stored_at: u64,
+    expires_at: u64,
+}
+
+/// Cache key: a normalized name and a record type. `None` stands for every
+/// type, which is how NXDOMAIN is cached.
+type Key = (String, Option<RecordType>);
+
+/// Normalize a name for the cache: names are case-insensitive and
+/// `example.com` and `example.com.` are the same name.
+fn key_name(name: &str) -> String {
+    name.strip_suffix('.').unwrap_or(name).to_ascii_lowercase()
+}
+
+/// A resolver that caches the answers of another.
+///
+/// Answers are kept for the smallest TTL among their records, clamped to
+/// [`min_ttl`](Self::min_ttl) and [`max_ttl`](Self::max_ttl). Cached records
+/// are returned with their TTLs counted down. `NoRecords` is cached per name
+/// and type and `NxDomain` per name, both for the negative TTL of the SOA
+/// record that came with them (RFC 2308), capped at
+/// [`negative_ttl`](Self::negative_ttl). Server failures and timeouts are
+/// never cached.
+///
+/// Every lookup goes through [`Resolver::lookup_with_soa`] so that TTLs are
+/// known:
+/// [`lookup_ip`](Resolver::lookup_ip) makes A and AAAA lookups, and
+/// [`reverse_lookup`](Resolver::reverse_lookup) a PTR lookup of the
+/// reverse name.
+pub struct CachingResolver<R, C> {
+    inner: R,
+    clock: C,
+    entries: Mutex<HashMap<Key, Entry>>,
+    capacity: usize,
+    min_ttl: u32,
+    max_ttl: u32,
+    negative_ttl: u32,
+}
+
+impl<R: Resolver, C: MonotonicClock> CachingResolver<R, C> {
+    /// Cache the answers of a resolver, timed by a clock.
+    ///
+    /// Holds up to 1024 answers, keeps them for at most a day and caches
+    /// negative answers for at most five minutes.
+    pub fn new(inner: R, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            entries: Mutex::new(HashMap::new()),
+            capacity: 1024,
+            min_ttl: 0,
+            max_ttl: 86400,
+            negative_ttl: 300,
+        }
+    }
+
+    /// Set the maximum number of cached answers. When full, expired answers
+    /// are dropped first, then the one closest to expiry.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Keep answers for at least this long, even if their TTL is shorter.
+    pub fn min_ttl(mut self, ttl: Duration) -> Self {
+        self.min_ttl = seconds(ttl);
+        self
+    }
+
+    /// Keep answers for at most this long, even if their TTL is longer.
+    pub fn max_ttl(mut self, ttl: Duration) -> Self {
+        self.max_ttl = seconds(ttl);
+        self
+    }
+
+    /// The longest to cache `NoRecords` and `NxDomain`, and how long to
+    /// cache them when they came without an SOA record. Zero disables
+    /// negative caching.
+    pub fn negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = seconds(ttl);
+        self
+    }
+
+    /// The wrapped resolver.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// The number of cached answers, including expired ones not yet dropped.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached answer.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Drop the cached answers for a name.
+    pub fn evict(&self, name: &str) {
+        let name = key_name(name);
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|(key, _), _| *key != name);
+    }
+
+    /// Clamp a TTL to the configured bounds. The minimum wins over the
+    /// maximum.
+    fn clamp_ttl(&self, ttl: u32) -> u32 {
+        ttl.min(self.max_ttl).max(self.min_ttl)
+    }
+
+    /// How long to cache a negative answer: the smaller of the SOA record's
+    /// TTL and its MINIMUM field (RFC 2308 section 5), capped at the
+    /// configured negative TTL.
+    fn negative_ttl_for(&self, soa: Option<&Record>) -> u32 {
+        match soa {
+            Some(Record {
+                ttl,
+                data: RecordData::Soa(soa),
+                ..
+            }) => (*ttl).min(soa.minimum).min(self.negative_ttl),
+            _ => self.negative_ttl,
+        }
+    }
+
+    /// Look up a cached answer, dropping it if it has expired.
+    fn cached(&self, name: &str, record_type: RecordType) -> Option<Result<Vec<Record>, Error>> {
+        let now = self.clock.now();
+        let mut entries = self.entries.lock().unwrap();
+        for key in [
+            (name.to_string(), Some(record_type)),
+            (name.to_string(), None),
+        ] {
+            let Some(entry) = entries.get(&key) else {
+                continue;
+            };
+            if entry.expires_at <= now {
+                entries.remove(&key);
+                continue;
+            }
+            let elapsed = ((now - entry.stored_at) / NANOS_PER_SEC) as u32;
+            return Some(match &entry.answer {
+                Answer::Records(records) => Ok(records
+                    .iter()
+                    .map(|record| Record {
+                        ttl: record.ttl.saturating_sub(elapsed),
+                        ..record.clone()
+                    })
+                    .collect()),
+                Answer::NoRecords => Err(Error::NoRecords),
+                Answer::NxDomain => Err(Error::NxDomain),
+            });
+        }
+        None
+    }
+
+    /// Cache an answer for `ttl` seconds.
+    fn store(&self, key: Key, answer: Answer, ttl: u32) {
+        if ttl == 0 || self.capacity == 0 {
+            return;
+        }
+        let now = self.clock.now();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.expires_at > now);
+        }
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let soonest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(soonest) = soonest {
+                entries.remove(&soonest);
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                answer,
+                stored_at: now,
+                expires_at: now.saturating_add(ttl as u64 * NANOS_PER_SEC),
+            },
+        );
+    }
+
+    /// Answer from the cache, or ask the inner resolver and cache the result.
+    async fn resolve(&self, name: &str, record_type: RecordType) -> Result<Vec<Record>, Error> {
+        let key = key_name(name);
+        if let Some(answer) = self.cached(&key, record_type) {
+            return answer;
+        }
+        match self.inner.lookup_with_soa(name, record_type).await {
+            Ok(records) => {
+                let records: Vec<Record> = records
+                    .into_iter()
+                    .map(|record| Record {
+                        ttl: self.clamp_ttl(record.ttl),
+                        ..record
+                    })
+                    .collect();
+                let ttl = records.iter().map(|record| record.ttl).min().unwrap_or(0);
+                self.store(
+                    (key, Some(record_type)),
+                    Answer::Records(records.clone()),
+                    ttl,
+                );
+                Ok(records)
+            }
+            Err(LookupFailure {
+                error: Error::NoRecords,
+                soa,
+            }) => {
+                self.store(
+                    (key, Some(record_type)),
+                    Answer::NoRecords,
+                    self.negative_ttl_for(soa.as_ref()),
+                );
+                Err(Error::NoRecords)
+            }
+            Err(LookupFailure {
+                error: Error::NxDomain,
+                soa,
+            }) => {
+                self.store(
+                    (key, None),
+                    Answer::NxDomain,
+                    self.negative_ttl_for(soa.as_ref()),
+                );
+                Err(Error::NxDomain)
+            }
+            Err(LookupFailure { error, .. }) => Err(error),
+        }
+    }
+
+    /// Resolve and pick the data of one record type.
+    async fn select<T>(
+        &self,
+        name: &str,
+        record_type: RecordType,
+        select: impl Fn(RecordData) -> Option<T>,
+    ) -> Result<Vec<T>, Error> {
+        let values: Vec<T> = self
+            .resolve(name, record_type)
+            .await?
+            .into_iter()
+            .filter_map(|record| select(record.data))
+            .collect();
+        if values.is_empty() {
+            return Err(Error::NoRecords);
+        }
+        Ok(values)
+    }
+}
+
+/// Whole seconds in a duration, saturating at `u32::MAX`.
+fn seconds(duration: Duration) -> u32 {
+    duration.as_secs().try_into().unwrap_or(u32::MAX)
+}
+
+impl<R: Resolver, C: MonotonicClock> Resolver for CachingResolver<R, C> {
+    async fn lookup_ipv4(&self, host: &str) -> Result<Vec<Ipv4Addr>, Error> {
+        self.select(host, RecordType::A, |data| match data {
+            RecordData::A(addr) => Some(addr),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn lookup_ipv6(&self, host: &str) -> Result<Vec<Ipv6Addr>, Error> {
+        self.select(host, RecordType::Aaaa, |data| match data {
+            RecordData::Aaaa(addr) => Some(addr),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, Error> {
+        let v4 = self.lookup_ipv4(host).await;
+        let v6 = match v4 {
+            // Already known not to exist, whatever the type.
+            Err(Error::NxDomain) => return Err(Error::NxDomain),
+            _ => self.lookup_ipv6(host).await,
+        };
+        match (v4, v6) {
+            (Err(Error::NoRecords), Err(Error::NoRecords)) => Err(Error::NoRecords),
+            (Err(e), _) | (_, Err(e)) if !matches!(e, Error::NoRecords) => Err(e),
+            (v4, v6) => {
+                let v4 = v4.unwrap_or_default().into_iter().map(IpAddr::V4);
+                let v6 = v6.unwrap_or_default().into_iter().map(IpAddr::V6);
+                Ok(v4.chain(v6).collect())
+            }
+        }
+    }
+
+    async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, Error> {
+        self.select(host, RecordType::Txt, |data| match data {
+            RecordData::Txt(text) => Some(text),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn lookup_mx(&self, domain: &str) -> Result<Vec<(u16, String)>, Error> {
+        self.select(domain, RecordType::Mx, |data| match data {
+            RecordData::Mx(mx) => Some((mx.preference, mx.exchange)),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn reverse_lookup(&self, addr: IpAddr) -> Result<Vec<String>, Error> {
+        self.select(&reverse_name(addr), RecordType::Ptr, |data| match data {
+            RecordData::Ptr(name) => Some(name),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn lookup(&self, name: &str, record_type: RecordType) -> Result<Vec<Record>, Error> {
+        self.resolve(name, record_type).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_clocks_mock::MockMonotonicClock;
+    use portals_dns::Soa;
+    use portals_testing::now;
+    use std::cell::RefCell;
+    use std::future::{Future, ready};
+
+    /// Answers `a.test` with two A records and counts the queries that reach
+    /// it. `empty.test` has no records, `down.test` fails and anything else
+    /// does not exist. Only `soa.test` comes with an SOA record, with a TTL
+    /// of 60 and a MINIMUM of 20.
+    #[derive(Default)]
+    struct Upstream {
+        queries: RefCell<Vec<(String, RecordType)>>,
+    }
+
+    impl Upstream {
+        fn count(&self) -> usize {
+            self.queries.borrow().len()
+        }
+    }
+
+    impl Resolver for Upstream {
+        fn lookup_ipv4(&self, _host: &str) -> impl Future<Output = Result<Vec<Ipv4Addr>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn lookup_ipv6(&self, _host: &str) -> impl Future<Output = Result<Vec<Ipv6Addr>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn lookup_ip(&self, _host: &str) -> impl Future<Output = Result<Vec<IpAddr>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn lookup_txt(&self, _host: &str) -> impl Future<Output = Result<Vec<String>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn lookup_mx(
+            &self,
+            _domain: &str,
+        ) -> impl Future<Output = Result<Vec<(u16, String)>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn reverse_lookup(
+            &self,
+            _addr: IpAddr,
+        ) -> impl Future<Output = Result<Vec<String>, Error>> {
+            ready(Err(Error::Other("unused".to_string())))
+        }
+
+        fn lookup(
+            &self,
+            name: &str,
+            record_type: RecordType,
+        ) -> impl Future<Output = Result<Vec<Record>, Error>> {
+            self.queries
+                .borrow_mut()
+                .push((name.to_string(), record_type));
+            let record = |ttl, last| Record {
+                name: "a.test.".to_string(),
+                ttl,
+                data: RecordData::A(Ipv4Addr::new(192, 0, 2, last)),
+            };
+            ready(match (name, record_type) {
+                ("a.test", RecordType::A) => Ok(vec![record(60, 1), record(30, 2)]),
+                ("a.test" | "empty.test", _) => Err(Error::NoRecords),
+                ("down.test", _) => Err(Error::ServFail),
+                _ => Err(Error::NxDomain),
+            })
+        }
+
+        fn lookup_with_soa(
+            &self,
+            name: &str,
+            record_type: RecordType,
+        ) -> impl Future<Output = Result<Vec<Record>, LookupFailure>> {
+            let soa = (name == "soa.test").then(|| Record {
+                name: "test.".to_string(),
+                ttl: 60,
+                data: RecordData::Soa(Soa {
+                    mname: "ns.test.".to_string(),
+                    rname: "hostmaster.test.".to_string(),
+                    serial: 1,
+                    refresh: 7200,
+                    retry: 3600,
+                    expire: 1209600,
+                    minimum: 20,
+                }),
+            });
+            let lookup = self.lookup(name, record_type);
+            async move { lookup.await.map_err(|error| LookupFailure { error, soa }) }
+        }
+    }
+
+    #[test]
+    fn caches_for_the_smallest_ttl() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone());
+
+        assert_eq!(now(cache.lookup_ipv4("a.test")).unwrap().len(), 2);
+        clock.advance(Duration::from_secs(20));
+        let records = now(cache.lookup("A.TEST", RecordType::A)).unwrap();
+        assert_eq!(cache.inner().count(), 1);
+        assert_eq!(records[0].ttl, 40);
+        assert_eq!(records[1].ttl, 10);
+
+        clock.advance(Duration::from_secs(10));
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        assert_eq!(cache.inner().count(), 2);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone());
+
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        now(cache.lookup_ipv4("A.Test.")).unwrap();
+        assert_eq!(cache.inner().count(), 1);
+
+        cache.evict("a.TEST.");
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn clamps_ttls() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone())
+            .min_ttl(Duration::from_secs(45))
+            .max_ttl(Duration::from_secs(50));
+
+        let records = now(cache.lookup("a.test", RecordType::A)).unwrap();
+        assert_eq!((records[0].ttl, records[1].ttl), (50, 45));
+        clock.advance(Duration::from_secs(44));
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        assert_eq!(cache.inner().count(), 1);
+        clock.advance(Duration::from_secs(1));
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        assert_eq!(cache.inner().count(), 2);
+    }
+
+    #[test]
+    fn caches_negative_answers() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone())
+            .negative_ttl(Duration::from_secs(10));
+
+        assert!(matches!(
+            now(cache.lookup_txt("empty.test")),
+            Err(Error::NoRecords)
+        ));
+        assert!(matches!(
+            now(cache.lookup_txt("empty.test")),
+            Err(Error::NoRecords)
+        ));
+        assert_eq!(cache.inner().count(), 1);
+
+        // NXDOMAIN covers every type.
+        assert!(matches!(
+            now(cache.lookup_mx("gone.test")),
+            Err(Error::NxDomain)
+        ));
+        assert!(matches!(
+            now(cache.lookup_ip("gone.test")),
+            Err(Error::NxDomain)
+        ));
+        assert_eq!(cache.inner().count(), 2);
+
+        clock.advance(Duration::from_secs(10));
+        assert!(matches!(
+            now(cache.lookup_txt("empty.test")),
+            Err(Error::NoRecords)
+        ));
+        assert_eq!(cache.inner().count(), 3);
+    }
+
+    #[test]
+    fn negative_ttl_follows_the_soa() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone());
+
+        let _ = now(cache.lookup_txt("soa.test"));
+        clock.advance(Duration::from_secs(19));
+        let _ = now(cache.lookup_txt("soa.test"));
+        assert_eq!(cache.inner().count(), 1);
+        clock.advance(Duration::from_secs(1));
+        let _ = now(cache.lookup_txt("soa.test"));
+        assert_eq!(cache.inner().count(), 2);
+
+        // The configured negative TTL caps the SOA's.
+        let cache = cache.negative_ttl(Duration::from_secs(5));
+        cache.clear();
+        let _ = now(cache.lookup_txt("soa.test"));
+        clock.advance(Duration::from_secs(5));
+        let _ = now(cache.lookup_txt("soa.test"));
+        assert_eq!(cache.inner().count(), 4);
+    }
+
+    #[test]
+    fn does_not_cache_failures() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone());
+
+        assert!(matches!(
+            now(cache.lookup_ipv4("down.test")),
+            Err(Error::ServFail)
+        ));
+        assert!(matches!(
+            now(cache.lookup_ipv4("down.test")),
+            Err(Error::ServFail)
+        ));
+        assert_eq!(cache.inner().count(), 2);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn combines_address_families() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone());
+
+        let addrs = now(cache.lookup_ip("a.test")).unwrap();
+        assert_eq!(addrs.len(), 2);
+        now(cache.lookup_ip("a.test")).unwrap();
+        assert_eq!(
+            *cache.inner().queries.borrow(),
+            vec![
+                ("a.test".to_string(), RecordType::A),
+                ("a.test".to_string(), RecordType::Aaaa),
+            ]
+        );
+    }
+
+    #[test]
+    fn evicts_when_full() {
+        let clock = MockMonotonicClock::new();
+        let cache = CachingResolver::new(Upstream::default(), clock.clone()).capacity(2);
+
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        let _ = now(cache.lookup_txt("empty.test"));
+        let _ = now(cache.lookup_txt("gone.test"));
+        assert_eq!(cache.len(), 2);
+        // The A records expire first, so they went.
+        now(cache.lookup_ipv4("a.test")).unwrap();
+        assert_eq!(cache.inner().count(), 4);
+
+        cache.evict("A.test");
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+//! Portable resolver layers for portals-dns.
+//!
+//! These wrap any [`portals_dns::Resolver`], so they work with every
+//! backend, on native and WASM targets alike.
+
+mod cache;
+
+pub use cache::CachingResolver;
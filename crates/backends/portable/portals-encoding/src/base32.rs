@@ -0,0 +1,256 @@
+//! Base32 variants: RFC 4648 base32 and base32hex, and Crockford's.
+
+use portals_encoding::{Base32, Base32Hex, Crockford, DecodeError};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE32HEX_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+const CROCKFORD_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Encode as a 5-bit stream, without padding.
+fn encode_bits(data: &[u8], alphabet: &[u8; 32]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(alphabet[(buffer >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        result.push(alphabet[(buffer << (5 - bits)) as usize & 31] as char);
+    }
+
+    result
+}
+
+fn pad(mut encoded: String) -> String {
+    while !encoded.len().is_multiple_of(8) {
+        encoded.push('=');
+    }
+    encoded
+}
+
+/// Decode a 5-bit stream. `value` maps a byte to its digit, `None` if it
+/// is invalid, or `Some(None)` if it is to be skipped.
+fn decode_bits(
+    encoded: &str,
+    data_len: usize,
+    value: impl Fn(u8) -> Option<Option<u8>>,
+) -> Result<Vec<u8>, DecodeError> {
+    let data = &encoded.as_bytes()[..data_len];
+    let mut result = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    let mut digits = 0;
+
+    for (i, &b) in data.iter().enumerate() {
+        let Some(digit) = value(b).ok_or_else(|| DecodeError::invalid_character(encoded, i))?
+        else {
+            continue;
+        };
+        digits += 1;
+        buffer = (buffer << 5) | digit as u16;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+    // 2, 4, 5 and 7 trailing digits carry whole bytes; 1, 3 and 6 cannot.
+    if matches!(digits % 8, 1 | 3 | 6) {
+        return Err(DecodeError::InvalidLength);
+    }
+    if buffer & ((1 << bits) - 1) != 0 {
+        return Err(DecodeError::InvalidPadding);
+    }
+
+    Ok(result)
+}
+
+/// Check RFC 4648 padding and return the length of the data before it.
+fn unpadded_len(encoded: &str) -> Result<usize, DecodeError> {
+    let data = encoded.trim_end_matches('=');
+    let padding = encoded.len() - data.len();
+    if padding > 0 && (!encoded.len().is_multiple_of(8) || padding > 6) {
+        return Err(DecodeError::InvalidPadding);
+    }
+    Ok(data.len())
+}
+
+/// Map a digit through an alphabet, ignoring case.
+fn rfc4648_value(alphabet: &[u8; 32], b: u8) -> Option<Option<u8>> {
+    let b = b.to_ascii_uppercase();
+    alphabet.iter().position(|&c| c == b).map(|i| Some(i as u8))
+}
+
+/// Base32 encoding (RFC 4648).
+pub struct StdBase32;
+
+impl Base32 for StdBase32 {
+    fn encode(data: &[u8]) -> String {
+        pad(Self::encode_unpadded(data))
+    }
+
+    fn encode_unpadded(data: &[u8]) -> String {
+        encode_bits(data, BASE32_ALPHABET)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        let len = unpadded_len(encoded)?;
+        decode_bits(encoded, len, |b| rfc4648_value(BASE32_ALPHABET, b))
+    }
+}
+
+/// Base32 with the extended hex alphabet (RFC 4648).
+pub struct StdBase32Hex;
+
+impl Base32Hex for StdBase32Hex {
+    fn encode(data: &[u8]) -> String {
+        pad(Self::encode_unpadded(data))
+    }
+
+    fn encode_unpadded(data: &[u8]) -> String {
+        encode_bits(data, BASE32HEX_ALPHABET)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        let len = unpadded_len(encoded)?;
+        decode_bits(encoded, len, |b| rfc4648_value(BASE32HEX_ALPHABET, b))
+    }
+}
+
+/// Crockford's Base32.
+pub struct StdCrockford;
+
+impl Crockford for StdCrockford {
+    fn encode(data: &[u8]) -> String {
+        encode_bits(data, CROCKFORD_ALPHABET)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        decode_bits(encoded, encoded.len(), |b| match b.to_ascii_uppercase() {
+            b'-' => Some(None),
+            b'O' => Some(Some(0)),
+            b'I' | b'L' => Some(Some(1)),
+            b => rfc4648_value(CROCKFORD_ALPHABET, b),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base32_matches_rfc_4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(StdBase32::encode(data.as_bytes()), encoded);
+            assert_eq!(StdBase32::decode(encoded).unwrap(), data.as_bytes());
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(StdBase32::encode_unpadded(data.as_bytes()), unpadded);
+            assert_eq!(StdBase32::decode(unpadded).unwrap(), data.as_bytes());
+        }
+        assert_eq!(StdBase32::decode("mzxw6ytboi").unwrap(), b"foobar");
+    }
+
+    #[test]
+    fn base32_rejects_malformed_input() {
+        assert!(matches!(
+            StdBase32::decode("MZXW1==="),
+            Err(DecodeError::InvalidCharacter {
+                character: '1',
+                position: 4
+            })
+        ));
+        assert!(matches!(
+            StdBase32::decode("MZ=W6YTB"),
+            Err(DecodeError::InvalidCharacter {
+                character: '=',
+                position: 2
+            })
+        ));
+        assert!(matches!(
+            StdBase32::decode("MZX"),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(matches!(
+            StdBase32::decode("MZXW6=="),
+            Err(DecodeError::InvalidPadding)
+        ));
+        assert!(matches!(
+            StdBase32::decode("M======="),
+            Err(DecodeError::InvalidPadding)
+        ));
+        // Trailing bits must be zero.
+        assert!(matches!(
+            StdBase32::decode("MZ======"),
+            Err(DecodeError::InvalidPadding)
+        ));
+    }
+
+    #[test]
+    fn base32hex_matches_rfc_4648() {
+        let vectors = [
+            ("", ""),
+            ("f", "CO======"),
+            ("fo", "CPNG===="),
+            ("foo", "CPNMU==="),
+            ("foob", "CPNMUOG="),
+            ("fooba", "CPNMUOJ1"),
+            ("foobar", "CPNMUOJ1E8======"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(StdBase32Hex::encode(data.as_bytes()), encoded);
+            assert_eq!(StdBase32Hex::decode(encoded).unwrap(), data.as_bytes());
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(StdBase32Hex::encode_unpadded(data.as_bytes()), unpadded);
+        }
+        assert!(matches!(
+            StdBase32Hex::decode("CPNW"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'W',
+                position: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn crockford_reads_like_people_write() {
+        let vectors = [
+            ("", ""),
+            ("f", "CR"),
+            ("foo", "CSQPY"),
+            ("foobar", "CSQPYRK1E8"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(StdCrockford::encode(data.as_bytes()), encoded);
+            assert_eq!(StdCrockford::decode(encoded).unwrap(), data.as_bytes());
+        }
+        assert_eq!(StdCrockford::encode(&[0xff; 5]), "ZZZZZZZZ");
+        assert_eq!(StdCrockford::decode("csqpy-rkie8").unwrap(), b"foobar");
+        assert_eq!(
+            StdCrockford::decode("0o1iLl00").unwrap(),
+            StdCrockford::decode("00111100").unwrap()
+        );
+        assert!(matches!(
+            StdCrockford::decode("CS-QU"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'U',
+                position: 4
+            })
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+//! Base58 and Base58Check with the Bitcoin alphabet.
+
+use portals_encoding::{Base58, DecodeError};
+use sha2::{Digest, Sha256};
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn checksum(data: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(Sha256::digest(data));
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Base58 encoding (Bitcoin alphabet).
+pub struct StdBase58;
+
+impl Base58 for StdBase58 {
+    fn encode(data: &[u8]) -> String {
+        let zeros = data.iter().take_while(|&&b| b == 0).count();
+        // Base-58 digits, least significant first.
+        let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+        for &byte in &data[zeros..] {
+            let mut carry = byte as u32;
+            for digit in digits.iter_mut() {
+                carry += (*digit as u32) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+
+        let mut result = String::with_capacity(zeros + digits.len());
+        result.extend(std::iter::repeat_n('1', zeros));
+        result.extend(digits.iter().rev().map(|&d| ALPHABET[d as usize] as char));
+        result
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        let input = encoded.as_bytes();
+        let zeros = input.iter().take_while(|&&b| b == b'1').count();
+        // Bytes, least significant first.
+        let mut bytes: Vec<u8> = Vec::with_capacity(input.len() * 733 / 1000 + 1);
+        for (i, &c) in input.iter().enumerate().skip(zeros) {
+            let value = ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or_else(|| DecodeError::invalid_character(encoded, i))?;
+            let mut carry = value as u32;
+            for byte in bytes.iter_mut() {
+                carry += *byte as u32 * 58;
+                *byte = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+
+        let mut result = vec![0u8; zeros];
+        result.extend(bytes.iter().rev());
+        Ok(result)
+    }
+
+    fn encode_check(data: &[u8]) -> String {
+        let mut payload = data.to_vec();
+        payload.extend_from_slice(&checksum(data));
+        Self::encode(&payload)
+    }
+
+    fn decode_check(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut data = Self::decode(encoded)?;
+        if data.len() < 4 {
+            return Err(DecodeError::InvalidLength);
+        }
+        let sum = data.split_off(data.len() - 4);
+        if sum != checksum(&data) {
+            return Err(DecodeError::InvalidChecksum);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StdHex;
+    use portals_encoding::Hex;
+
+    #[test]
+    fn base58_matches_bitcoin_vectors() {
+        let vectors = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            (
+                "73696d706c792061206c6f6e6720737472696e67",
+                "2cFupjhnEsSn59qHXstmK2ffpLv2",
+            ),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+        for (hex, encoded) in vectors {
+            let data = StdHex::decode(hex).unwrap();
+            assert_eq!(StdBase58::encode(&data), encoded);
+            assert_eq!(StdBase58::decode(encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn base58_rejects_ambiguous_characters() {
+        for (input, character, position) in [("0", '0', 0), ("1O", 'O', 1), ("abIl", 'I', 2)] {
+            match StdBase58::decode(input) {
+                Err(DecodeError::InvalidCharacter {
+                    character: c,
+                    position: p,
+                }) => assert_eq!((c, p), (character, position)),
+                other => panic!("{:?}: unexpected {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn base58check_verifies_checksums() {
+        // A pay-to-pubkey-hash address: version 0 and a HASH160.
+        let data = StdHex::decode("00010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        let address = "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM";
+        assert_eq!(StdBase58::encode_check(&data), address);
+        assert_eq!(StdBase58::decode_check(address).unwrap(), data);
+
+        assert!(matches!(
+            StdBase58::decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN"),
+            Err(DecodeError::InvalidChecksum)
+        ));
+        assert!(matches!(
+            StdBase58::decode_check("2g"),
+            Err(DecodeError::InvalidLength)
+        ));
+    }
+}
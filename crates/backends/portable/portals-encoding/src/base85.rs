@@ -0,0 +1,256 @@
+//! Base85 variants: Z85 and Ascii85.
+
+use portals_encoding::{Ascii85, DecodeError, Z85};
+
+const Z85_ALPHABET: &[u8; 85] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// What a byte of base85 input stands for.
+enum Symbol {
+    Digit(u8),
+    /// `z` in Ascii85: a whole group of zero bytes.
+    Zeros,
+    /// Ignored, like whitespace in Ascii85.
+    Skip,
+}
+
+/// Encode 4-byte groups as 5 digits, most significant first. A final
+/// partial group of `n` bytes is zero-padded and cut to `n + 1` digits.
+fn encode_groups(data: &[u8], digit: impl Fn(u32) -> char, zeros: Option<char>) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(4) * 5);
+
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = u32::from_be_bytes(group);
+        if let Some(z) = zeros
+            && chunk.len() == 4
+            && value == 0
+        {
+            result.push(z);
+            continue;
+        }
+
+        let mut digits = [0u32; 5];
+        let mut v = value;
+        for d in digits.iter_mut().rev() {
+            *d = v % 85;
+            v /= 85;
+        }
+        result.extend(digits[..chunk.len() + 1].iter().map(|&d| digit(d)));
+    }
+
+    result
+}
+
+/// Decode `encoded[start..end]` as 5-digit groups. A final partial group
+/// of `n` digits is padded with the highest digit and yields `n - 1` bytes.
+fn decode_groups(
+    encoded: &str,
+    start: usize,
+    end: usize,
+    symbol: impl Fn(u8) -> Option<Symbol>,
+) -> Result<Vec<u8>, DecodeError> {
+    let input = encoded.as_bytes();
+    let mut result = Vec::with_capacity((end - start) * 4 / 5);
+    let mut group = [0u8; 5];
+    let mut len = 0;
+    // Where the current group starts, for reporting overflow.
+    let mut group_start = start;
+
+    for (i, &b) in input.iter().enumerate().take(end).skip(start) {
+        match symbol(b).ok_or_else(|| DecodeError::invalid_character(encoded, i))? {
+            Symbol::Skip => {}
+            Symbol::Zeros if len == 0 => result.extend_from_slice(&[0; 4]),
+            Symbol::Zeros => return Err(DecodeError::invalid_character(encoded, i)),
+            Symbol::Digit(d) => {
+                if len == 0 {
+                    group_start = i;
+                }
+                group[len] = d;
+                len += 1;
+                if len == 5 {
+                    result.extend_from_slice(&group_value(&group, encoded, group_start)?);
+                    len = 0;
+                }
+            }
+        }
+    }
+
+    match len {
+        0 => {}
+        1 => return Err(DecodeError::InvalidLength),
+        _ => {
+            group[len..].fill(84);
+            let bytes = group_value(&group, encoded, group_start)?;
+            result.extend_from_slice(&bytes[..len - 1]);
+        }
+    }
+
+    Ok(result)
+}
+
+/// The four bytes of a 5-digit group. Groups above `u32::MAX` are
+/// reported at their first character.
+fn group_value(group: &[u8; 5], encoded: &str, position: usize) -> Result<[u8; 4], DecodeError> {
+    let value = group.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+    u32::try_from(value)
+        .map(u32::to_be_bytes)
+        .map_err(|_| DecodeError::invalid_character(encoded, position))
+}
+
+/// Z85 encoding (ZeroMQ RFC 32).
+///
+/// Extends the specification to any length: a final group of `n` bytes is
+/// encoded as `n + 1` characters, so decoding accepts input of any length
+/// except one more than a multiple of 5.
+pub struct StdZ85;
+
+impl Z85 for StdZ85 {
+    fn encode(data: &[u8]) -> String {
+        encode_groups(data, |d| Z85_ALPHABET[d as usize] as char, None)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        decode_groups(encoded, 0, encoded.len(), |b| {
+            Z85_ALPHABET
+                .iter()
+                .position(|&c| c == b)
+                .map(|d| Symbol::Digit(d as u8))
+        })
+    }
+}
+
+/// Ascii85 encoding (Adobe variant, without delimiters).
+pub struct StdAscii85;
+
+impl Ascii85 for StdAscii85 {
+    fn encode(data: &[u8]) -> String {
+        encode_groups(data, |d| (b'!' + d as u8) as char, Some('z'))
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut start = 0;
+        let mut end = encoded.len();
+        let trimmed = encoded.trim_start();
+        if trimmed.starts_with("<~") {
+            start = end - trimmed.len() + 2;
+        }
+        let trimmed = encoded[start..].trim_end();
+        if trimmed.ends_with("~>") {
+            end = start + trimmed.len() - 2;
+        }
+
+        decode_groups(encoded, start, end, |b| match b {
+            b'!'..=b'u' => Some(Symbol::Digit(b - b'!')),
+            b'z' => Some(Symbol::Zeros),
+            b if b.is_ascii_whitespace() => Some(Symbol::Skip),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z85_matches_the_specification() {
+        let data = [0x86, 0x4f, 0xd2, 0x6f, 0xb5, 0x59, 0xf7, 0x5b];
+        assert_eq!(StdZ85::encode(&data), "HelloWorld");
+        assert_eq!(StdZ85::decode("HelloWorld").unwrap(), data);
+        assert_eq!(StdZ85::encode(&[]), "");
+        assert_eq!(StdZ85::encode(&[0xff; 4]), "%nSc0");
+    }
+
+    #[test]
+    fn z85_round_trips_partial_groups() {
+        let data: Vec<u8> = (0..=255).collect();
+        for len in 0..12 {
+            let encoded = StdZ85::encode(&data[100..100 + len]);
+            assert_eq!(
+                encoded.len(),
+                len / 4 * 5 + len % 4 + usize::from(len % 4 > 0)
+            );
+            assert_eq!(StdZ85::decode(&encoded).unwrap(), &data[100..100 + len]);
+        }
+    }
+
+    #[test]
+    fn z85_rejects_malformed_input() {
+        assert!(matches!(
+            StdZ85::decode("Hello World"),
+            Err(DecodeError::InvalidCharacter {
+                character: ' ',
+                position: 5
+            })
+        ));
+        assert!(matches!(
+            StdZ85::decode("HelloW"),
+            Err(DecodeError::InvalidLength)
+        ));
+        // 85^5 - 1 does not fit in 32 bits.
+        assert!(matches!(
+            StdZ85::decode("Hello#####"),
+            Err(DecodeError::InvalidCharacter {
+                character: '#',
+                position: 5
+            })
+        ));
+    }
+
+    #[test]
+    fn ascii85_matches_known_vectors() {
+        let vectors: [(&[u8], &str); 6] = [
+            (b"", ""),
+            (b"Man is distinguished", "9jqo^BlbD-BleB1DJ+*+F(f,q"),
+            (b"hello world", "BOu!rD]j7BEbo7"),
+            (&[0, 0, 0, 0], "z"),
+            (&[0, 0, 0], "!!!!"),
+            (&[0, 0, 0, 0, 0xff], "zrr"),
+        ];
+        for (data, encoded) in vectors {
+            assert_eq!(StdAscii85::encode(data), encoded);
+            assert_eq!(StdAscii85::decode(encoded).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn ascii85_accepts_delimiters_and_whitespace() {
+        assert_eq!(
+            StdAscii85::decode(" <~BOu!rD]j7\n BEbo7~> ").unwrap(),
+            b"hello world"
+        );
+        assert_eq!(StdAscii85::decode("<~z~>").unwrap(), [0; 4]);
+    }
+
+    #[test]
+    fn ascii85_rejects_malformed_input() {
+        // `z` is only valid between groups.
+        assert!(matches!(
+            StdAscii85::decode("BOzu!rD"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'z',
+                position: 2
+            })
+        ));
+        assert!(matches!(
+            StdAscii85::decode("<~BOu!v~>"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'v',
+                position: 6
+            })
+        ));
+        assert!(matches!(
+            StdAscii85::decode("BOu!rD"),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(matches!(
+            StdAscii85::decode("uuuuu"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'u',
+                position: 0
+            })
+        ));
+    }
+}
@@ -0,0 +1,581 @@
+//! CBOR (RFC 8949) for dynamic values.
+
+use portals_encoding::{Cbor, CborValue, DecodeError, SimpleValue};
+
+/// How deeply arrays, maps and tags may nest before decoding gives up.
+const MAX_DEPTH: usize = 256;
+
+const BREAK: u8 = 0xff;
+
+/// Append an initial byte and its argument in the shortest form.
+fn head(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.extend_from_slice(&[major | 24, n as u8]);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn f16_to_f64(half: u16) -> f64 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = (half & 0x3ff) as f64;
+    match exponent {
+        0 => sign * mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => sign * f64::INFINITY,
+        31 => f64::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f64.powi(exponent as i32 - 15),
+    }
+}
+
+/// The half-precision form of `value`, if it has one without loss.
+fn f64_to_f16(value: f64) -> Option<u16> {
+    if value.is_nan() {
+        return Some(0x7e00);
+    }
+    let single = value as f32;
+    if single as f64 != value {
+        return None;
+    }
+
+    let bits = single.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+    let half = match exponent {
+        0xff => sign | 0x7c00,
+        0 => sign,
+        _ => match exponent - 127 {
+            e @ -14..=15 => sign | (((e + 15) as u16) << 10) | (mantissa >> 13) as u16,
+            e @ -24..=-15 => sign | ((0x80_0000 | mantissa) >> -(e + 1)) as u16,
+            _ => return None,
+        },
+    };
+    // Candidates that dropped bits decode to something else.
+    (f16_to_f64(half) == value).then_some(half)
+}
+
+fn encode_float(value: f64, out: &mut Vec<u8>) {
+    if let Some(half) = f64_to_f16(value) {
+        out.push(0xf9);
+        out.extend_from_slice(&half.to_be_bytes());
+    } else if (value as f32) as f64 == value {
+        out.push(0xfa);
+        out.extend_from_slice(&(value as f32).to_be_bytes());
+    } else {
+        out.push(0xfb);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+fn encode_bignum(tag: u64, n: u128, out: &mut Vec<u8>) {
+    let bytes = n.to_be_bytes();
+    let start = bytes.iter().take_while(|&&b| b == 0).count();
+    head(6, tag, out);
+    head(2, (bytes.len() - start) as u64, out);
+    out.extend_from_slice(&bytes[start..]);
+}
+
+fn encode_into(value: &CborValue, out: &mut Vec<u8>) {
+    match value {
+        CborValue::Integer(i) if *i >= 0 => match u64::try_from(*i) {
+            Ok(n) => head(0, n, out),
+            Err(_) => encode_bignum(2, *i as u128, out),
+        },
+        CborValue::Integer(i) => {
+            let n = -1 - *i;
+            match u64::try_from(n) {
+                Ok(n) => head(1, n, out),
+                Err(_) => encode_bignum(3, n as u128, out),
+            }
+        }
+        CborValue::Bytes(bytes) => {
+            head(2, bytes.len() as u64, out);
+            out.extend_from_slice(bytes);
+        }
+        CborValue::Text(text) => {
+            head(3, text.len() as u64, out);
+            out.extend_from_slice(text.as_bytes());
+        }
+        CborValue::Array(items) => {
+            head(4, items.len() as u64, out);
+            for item in items {
+                encode_into(item, out);
+            }
+        }
+        CborValue::Map(pairs) => {
+            head(5, pairs.len() as u64, out);
+            for (key, value) in pairs {
+                encode_into(key, out);
+                encode_into(value, out);
+            }
+        }
+        CborValue::Tag(tag, inner) => {
+            head(6, *tag, out);
+            encode_into(inner, out);
+        }
+        CborValue::Bool(false) => out.push(0xf4),
+        CborValue::Bool(true) => out.push(0xf5),
+        CborValue::Null => out.push(0xf6),
+        CborValue::Undefined => out.push(0xf7),
+        CborValue::Float(f) => encode_float(*f, out),
+        CborValue::Simple(n) => head(7, n.get() as u64, out),
+    }
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn byte(&mut self) -> Result<u8, DecodeError> {
+        let byte = *self.input.get(self.pos).ok_or(DecodeError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, n: u64) -> Result<&'a [u8], DecodeError> {
+        let remaining = self.input.len() - self.pos;
+        match usize::try_from(n) {
+            Ok(n) if n <= remaining => {
+                self.pos += n;
+                Ok(&self.input[self.pos - n..self.pos])
+            }
+            _ => Err(DecodeError::UnexpectedEnd),
+        }
+    }
+
+    /// Consume a break if one is next.
+    fn at_break(&mut self) -> bool {
+        let found = self.input.get(self.pos) == Some(&BREAK);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Capacity for `n` items, bounded by what the input could hold.
+    fn capacity(&self, n: u64) -> usize {
+        n.min((self.input.len() - self.pos) as u64) as usize
+    }
+
+    fn invalid(&self, position: usize) -> DecodeError {
+        DecodeError::InvalidByte {
+            byte: self.input[position],
+            position,
+        }
+    }
+
+    /// The argument of the initial byte at `at`, or `None` if it marks an
+    /// indefinite length.
+    fn argument(&mut self, at: usize) -> Result<Option<u64>, DecodeError> {
+        let n = match self.input[at] & 0x1f {
+            n @ 0..=23 => n as u64,
+            24 => self.byte()? as u64,
+            25 => u16::from_be_bytes(self.take(2)?.try_into().unwrap()) as u64,
+            26 => u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+            27 => u64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            31 => return Ok(None),
+            _ => return Err(self.invalid(at)),
+        };
+        Ok(Some(n))
+    }
+
+    fn definite(&mut self, at: usize) -> Result<u64, DecodeError> {
+        self.argument(at)?.ok_or_else(|| self.invalid(at))
+    }
+
+    /// A byte or text string, joining the chunks of an indefinite one.
+    fn string(&mut self, at: usize, major: u8) -> Result<Vec<u8>, DecodeError> {
+        if let Some(n) = self.argument(at)? {
+            return Ok(self.string_bytes(n, major)?.to_vec());
+        }
+        let mut result = Vec::new();
+        while !self.at_break() {
+            let chunk = self.pos;
+            if self.byte()? >> 5 != major {
+                return Err(self.invalid(chunk));
+            }
+            let n = self.definite(chunk)?;
+            result.extend_from_slice(self.string_bytes(n, major)?);
+        }
+        Ok(result)
+    }
+
+    /// The `n` bytes of a definite-length string or chunk. Each chunk of
+    /// a text string must be valid UTF-8 by itself.
+    fn string_bytes(&mut self, n: u64, major: u8) -> Result<&'a [u8], DecodeError> {
+        let bytes = self.take(n)?;
+        if major == 3 {
+            std::str::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?;
+        }
+        Ok(bytes)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<CborValue, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::Other("CBOR nesting too deep".into()));
+        }
+        let at = self.pos;
+        let initial = self.byte()?;
+        if initial == BREAK {
+            return Err(self.invalid(at));
+        }
+
+        let value = match initial >> 5 {
+            0 => CborValue::Integer(self.definite(at)? as i128),
+            1 => CborValue::Integer(-1 - self.definite(at)? as i128),
+            2 => CborValue::Bytes(self.string(at, 2)?),
+            3 => CborValue::Text(String::from_utf8(self.string(at, 3)?).unwrap()),
+            4 => match self.argument(at)? {
+                Some(n) => {
+                    let mut items = Vec::with_capacity(self.capacity(n));
+                    for _ in 0..n {
+                        items.push(self.value(depth + 1)?);
+                    }
+                    CborValue::Array(items)
+                }
+                None => {
+                    let mut items = Vec::new();
+                    while !self.at_break() {
+                        items.push(self.value(depth + 1)?);
+                    }
+                    CborValue::Array(items)
+                }
+            },
+            5 => match self.argument(at)? {
+                Some(n) => {
+                    let mut pairs = Vec::with_capacity(self.capacity(n));
+                    for _ in 0..n {
+                        pairs.push((self.value(depth + 1)?, self.value(depth + 1)?));
+                    }
+                    CborValue::Map(pairs)
+                }
+                None => {
+                    let mut pairs = Vec::new();
+                    while !self.at_break() {
+                        pairs.push((self.value(depth + 1)?, self.value(depth + 1)?));
+                    }
+                    CborValue::Map(pairs)
+                }
+            },
+            6 => {
+                let tag = self.definite(at)?;
+                bignum(tag, self.value(depth + 1)?)
+            }
+            _ => match initial & 0x1f {
+                20 => CborValue::Bool(false),
+                21 => CborValue::Bool(true),
+                22 => CborValue::Null,
+                23 => CborValue::Undefined,
+                n @ 0..=19 => simple(n),
+                24 => match self.byte()? {
+                    n @ 32.. => simple(n),
+                    _ => return Err(self.invalid(self.pos - 1)),
+                },
+                25 => {
+                    let half = u16::from_be_bytes(self.take(2)?.try_into().unwrap());
+                    CborValue::Float(f16_to_f64(half))
+                }
+                26 => {
+                    let single = f32::from_be_bytes(self.take(4)?.try_into().unwrap());
+                    CborValue::Float(single as f64)
+                }
+                27 => CborValue::Float(f64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+                _ => return Err(self.invalid(at)),
+            },
+        };
+        Ok(value)
+    }
+}
+
+/// A simple value the decoder has already checked is not 20 to 31.
+fn simple(n: u8) -> CborValue {
+    CborValue::Simple(SimpleValue::new(n).expect("simple value out of range"))
+}
+
+/// Fold bignums (tags 2 and 3) that fit in an `i128` into integers.
+fn bignum(tag: u64, inner: CborValue) -> CborValue {
+    if let (2 | 3, CborValue::Bytes(bytes)) = (tag, &inner) {
+        let start = bytes.iter().take_while(|&&b| b == 0).count();
+        if bytes.len() - start <= 16 {
+            let n = bytes[start..]
+                .iter()
+                .fold(0u128, |acc, &b| (acc << 8) | b as u128);
+            if let Ok(n) = i128::try_from(n) {
+                return CborValue::Integer(if tag == 2 { n } else { -1 - n });
+            }
+        }
+    }
+    CborValue::Tag(tag, Box::new(inner))
+}
+
+/// CBOR encoding.
+pub struct StdCbor;
+
+impl Cbor for StdCbor {
+    fn encode(value: &CborValue) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_into(value, &mut out);
+        out
+    }
+
+    fn decode(input: &[u8]) -> Result<CborValue, DecodeError> {
+        let (value, len) = Self::decode_prefix(input)?;
+        if len < input.len() {
+            return Err(DecodeError::InvalidByte {
+                byte: input[len],
+                position: len,
+            });
+        }
+        Ok(value)
+    }
+
+    fn decode_prefix(input: &[u8]) -> Result<(CborValue, usize), DecodeError> {
+        let mut decoder = Decoder { input, pos: 0 };
+        let value = decoder.value(0)?;
+        Ok((value, decoder.pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StdHex;
+    use portals_encoding::Hex;
+
+    fn check(value: CborValue, hex: &str) {
+        assert_eq!(StdHex::encode(&StdCbor::encode(&value)), hex, "{:?}", value);
+        assert_eq!(
+            StdCbor::decode(&StdHex::decode(hex).unwrap()).unwrap(),
+            value
+        );
+    }
+
+    fn text(s: &str) -> CborValue {
+        CborValue::from(s)
+    }
+
+    fn int(i: i128) -> CborValue {
+        CborValue::Integer(i)
+    }
+
+    // Appendix A of RFC 8949.
+    #[test]
+    fn integers_match_rfc_8949() {
+        let vectors = [
+            (0, "00"),
+            (23, "17"),
+            (24, "1818"),
+            (100, "1864"),
+            (1000, "1903e8"),
+            (1000000, "1a000f4240"),
+            (1000000000000, "1b000000e8d4a51000"),
+            (18446744073709551615, "1bffffffffffffffff"),
+            (18446744073709551616, "c249010000000000000000"),
+            (-18446744073709551616, "3bffffffffffffffff"),
+            (-18446744073709551617, "c349010000000000000000"),
+            (-1, "20"),
+            (-10, "29"),
+            (-100, "3863"),
+            (-1000, "3903e7"),
+        ];
+        for (i, hex) in vectors {
+            check(int(i), hex);
+        }
+        check(int(i128::MIN), "c3507fffffffffffffffffffffffffffffff");
+    }
+
+    #[test]
+    fn floats_take_their_shortest_form() {
+        let vectors = [
+            (0.0, "f90000"),
+            (-0.0, "f98000"),
+            (1.0, "f93c00"),
+            (1.1, "fb3ff199999999999a"),
+            (1.5, "f93e00"),
+            (65504.0, "f97bff"),
+            (100000.0, "fa47c35000"),
+            (3.4028234663852886e38, "fa7f7fffff"),
+            (1.0e300, "fb7e37e43c8800759c"),
+            (5.960464477539063e-8, "f90001"),
+            (0.00006103515625, "f90400"),
+            (-4.0, "f9c400"),
+            (-4.1, "fbc010666666666666"),
+            (f64::INFINITY, "f97c00"),
+            (f64::NEG_INFINITY, "f9fc00"),
+        ];
+        for (f, hex) in vectors {
+            check(CborValue::Float(f), hex);
+        }
+        assert_eq!(
+            StdCbor::encode(&CborValue::Float(f64::NAN)),
+            [0xf9, 0x7e, 0x00]
+        );
+        for hex in ["f97e00", "fa7fc00000", "fb7ff8000000000000"] {
+            let value = StdCbor::decode(&StdHex::decode(hex).unwrap()).unwrap();
+            assert!(matches!(value, CborValue::Float(f) if f.is_nan()));
+        }
+        // Wider encodings of short values still decode.
+        let value = StdCbor::decode(&StdHex::decode("fb3ff8000000000000").unwrap());
+        assert_eq!(value.unwrap(), CborValue::Float(1.5));
+    }
+
+    #[test]
+    fn simple_values_and_tags() {
+        check(CborValue::Bool(false), "f4");
+        check(CborValue::Bool(true), "f5");
+        check(CborValue::Null, "f6");
+        check(CborValue::Undefined, "f7");
+        check(CborValue::Simple(SimpleValue::new(16).unwrap()), "f0");
+        check(CborValue::Simple(SimpleValue::new(255).unwrap()), "f8ff");
+        check(
+            CborValue::Tag(0, Box::new(text("2013-03-21T20:04:00Z"))),
+            "c074323031332d30332d32315432303a30343a30305a",
+        );
+        check(
+            CborValue::Tag(32, Box::new(text("http://www.example.com"))),
+            "d82076687474703a2f2f7777772e6578616d706c652e636f6d",
+        );
+    }
+
+    #[test]
+    fn strings_arrays_and_maps() {
+        check(CborValue::Bytes(vec![]), "40");
+        check(CborValue::Bytes(vec![1, 2, 3, 4]), "4401020304");
+        check(text(""), "60");
+        check(text("a"), "6161");
+        check(text("IETF"), "6449455446");
+        check(text("\"\\"), "62225c");
+        check(text("\u{00fc}"), "62c3bc");
+        check(text("\u{6c34}"), "63e6b0b4");
+        check(CborValue::Array(vec![]), "80");
+        check(CborValue::Array(vec![int(1), int(2), int(3)]), "83010203");
+        let nested = CborValue::Array(vec![
+            int(1),
+            CborValue::Array(vec![int(2), int(3)]),
+            CborValue::Array(vec![int(4), int(5)]),
+        ]);
+        check(nested.clone(), "8301820203820405");
+        check(
+            CborValue::Array((1..=25).map(int).collect()),
+            "98190102030405060708090a0b0c0d0e0f101112131415161718181819",
+        );
+        check(CborValue::Map(vec![]), "a0");
+        check(
+            CborValue::Map(vec![(int(1), int(2)), (int(3), int(4))]),
+            "a201020304",
+        );
+        let map = CborValue::Map(vec![
+            (text("a"), int(1)),
+            (text("b"), CborValue::Array(vec![int(2), int(3)])),
+        ]);
+        check(map.clone(), "a26161016162820203");
+        assert_eq!(map.get("a"), Some(&int(1)));
+        assert_eq!(map.get("c"), None);
+
+        // Indefinite lengths decode to the same values.
+        let vectors = [
+            ("5f42010243030405ff", CborValue::Bytes(vec![1, 2, 3, 4, 5])),
+            ("7f657374726561646d696e67ff", text("streaming")),
+            ("9fff", CborValue::Array(vec![])),
+            ("9f018202039f0405ffff", nested),
+            ("bf61610161629f0203ffff", map),
+        ];
+        for (hex, value) in vectors {
+            assert_eq!(
+                StdCbor::decode(&StdHex::decode(hex).unwrap()).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn decode_prefix_leaves_trailing_bytes() {
+        let input = [0x83, 0x01, 0x02, 0x03, 0xf6];
+        let (value, len) = StdCbor::decode_prefix(&input).unwrap();
+        assert_eq!(value, CborValue::Array(vec![int(1), int(2), int(3)]));
+        assert_eq!(len, 4);
+        assert!(matches!(
+            StdCbor::decode(&input),
+            Err(DecodeError::InvalidByte {
+                byte: 0xf6,
+                position: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let invalid = [
+            // Reserved additional information.
+            ("1c", 0x1c, 0),
+            // A break outside an indefinite item.
+            ("ff", 0xff, 0),
+            ("8201ff", 0xff, 2),
+            // Indefinite integers and tags are not a thing.
+            ("1f", 0x1f, 0),
+            ("df00", 0xdf, 0),
+            // A chunk of the wrong type in an indefinite string.
+            ("5f6161ff", 0x61, 1),
+            // A two-byte simple value below 32.
+            ("f818", 0x18, 1),
+        ];
+        for (hex, byte, position) in invalid {
+            match StdCbor::decode(&StdHex::decode(hex).unwrap()) {
+                Err(DecodeError::InvalidByte {
+                    byte: b,
+                    position: p,
+                }) => assert_eq!((b, p), (byte, position), "{}", hex),
+                other => panic!("{}: unexpected {:?}", hex, other),
+            }
+        }
+
+        for hex in [
+            "", "18", "1900", "62c3", "8301", "a16161", "5f4101", "fa0000",
+        ] {
+            assert!(
+                matches!(
+                    StdCbor::decode(&StdHex::decode(hex).unwrap()),
+                    Err(DecodeError::UnexpectedEnd)
+                ),
+                "{}",
+                hex
+            );
+        }
+
+        assert!(matches!(
+            StdCbor::decode(&[0x62, 0xc3, 0x28]),
+            Err(DecodeError::InvalidUtf8)
+        ));
+        // A huge declared length fails without allocating for it.
+        assert!(matches!(
+            StdCbor::decode(&StdHex::decode("9bffffffffffffffff").unwrap()),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            StdCbor::decode(&[0x81; 1000]),
+            Err(DecodeError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn simple_values_exclude_named_and_reserved_ones() {
+        for n in 20..=31 {
+            assert_eq!(SimpleValue::new(n), None);
+        }
+        assert_eq!(SimpleValue::new(19).map(SimpleValue::get), Some(19));
+        assert_eq!(SimpleValue::new(32).map(SimpleValue::get), Some(32));
+    }
+}
@@ -0,0 +1,372 @@
+//! Constant-time hex and base64 for secret material.
+//!
+//! Characters are mapped with arithmetic on masks instead of branches or
+//! table lookups, and invalid input is only acted on once all of it has
+//! been read.
+
+use portals_encoding::{Base64, Base64Url, ConstantTime, DecodeError, Hex};
+
+/// All ones if `lo <= c <= hi`, otherwise zero. Arguments are bytes, so
+/// the differences fit in an `i16` and only their signs matter.
+fn in_range(c: i16, lo: i16, hi: i16) -> i16 {
+    ((lo - 1 - c) & (c - hi - 1)) >> 8
+}
+
+/// Records where the first invalid character was, without branching on
+/// the characters.
+struct FirstInvalid(usize);
+
+impl FirstInvalid {
+    /// Positions never have the top bit set, so it marks "none yet".
+    const NONE: usize = usize::MAX;
+
+    fn new() -> Self {
+        Self(Self::NONE)
+    }
+
+    /// Note position `i`, whose decoded value is negative if invalid.
+    fn check(&mut self, i: usize, value: i16) {
+        let invalid = (value as u16 >> 15) as usize;
+        let unset = self.0 >> (usize::BITS - 1);
+        let mask = (invalid & unset).wrapping_neg();
+        self.0 = (self.0 & !mask) | (i & mask);
+    }
+
+    fn into_result(self, encoded: &str) -> Result<(), DecodeError> {
+        match self.0 {
+            Self::NONE => Ok(()),
+            position => Err(DecodeError::invalid_character(encoded, position)),
+        }
+    }
+}
+
+fn encode_nibble(n: u8, upper: bool) -> char {
+    let n = n as i16;
+    // Past 9, jump from just after '9' to 'a' or 'A'.
+    let letters = if upper { 0x41 - 0x3a } else { 0x61 - 0x3a };
+    (n + 0x30 + (((9 - n) >> 8) & letters)) as u8 as char
+}
+
+/// The value of a hex digit, or -1.
+fn decode_nibble(c: u8) -> i16 {
+    let c = c as i16;
+    let mut value = -1;
+    value += in_range(c, 0x30, 0x39) & (c - 0x30 + 1);
+    value += in_range(c, 0x41, 0x46) & (c - 0x41 + 10 + 1);
+    value += in_range(c, 0x61, 0x66) & (c - 0x61 + 10 + 1);
+    value
+}
+
+fn encode_hex(data: &[u8], upper: bool) -> String {
+    let mut result = String::with_capacity(data.len() * 2);
+    for &b in data {
+        result.push(encode_nibble(b >> 4, upper));
+        result.push(encode_nibble(b & 15, upper));
+    }
+    result
+}
+
+/// Base64 alphabets, differing in the last two characters.
+#[derive(Clone, Copy)]
+enum Alphabet {
+    Standard,
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(self) -> (u8, u8) {
+        match self {
+            Alphabet::Standard => (b'+', b'/'),
+            Alphabet::UrlSafe => (b'-', b'_'),
+        }
+    }
+
+    fn encode(self, n: u8) -> char {
+        let (s62, s63) = self.symbols();
+        let n = n as i16;
+        let mut c = n + 0x41;
+        c += ((25 - n) >> 8) & (0x61 - 26 - 0x41);
+        c += ((51 - n) >> 8) & (0x30 - 52 - (0x61 - 26));
+        c += ((61 - n) >> 8) & (s62 as i16 - 62 - (0x30 - 52));
+        c += ((62 - n) >> 8) & (s63 as i16 - s62 as i16 - 1);
+        c as u8 as char
+    }
+
+    /// The value of a base64 character, or -1.
+    fn decode(self, c: u8) -> i16 {
+        let (s62, s63) = self.symbols();
+        let c = c as i16;
+        let mut value = -1;
+        value += in_range(c, 0x41, 0x5a) & (c - 0x41 + 1);
+        value += in_range(c, 0x61, 0x7a) & (c - 0x61 + 26 + 1);
+        value += in_range(c, 0x30, 0x39) & (c - 0x30 + 52 + 1);
+        value += in_range(c, s62 as i16, s62 as i16) & (62 + 1);
+        value += in_range(c, s63 as i16, s63 as i16) & (63 + 1);
+        value
+    }
+}
+
+fn encode_base64(data: &[u8], alphabet: Alphabet, pad: bool) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let sextets = [
+            b[0] >> 2,
+            ((b[0] & 0x03) << 4) | (b[1] >> 4),
+            ((b[1] & 0x0f) << 2) | (b[2] >> 6),
+            b[2] & 0x3f,
+        ];
+        for &s in &sextets[..chunk.len() + 1] {
+            result.push(alphabet.encode(s));
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+/// Decode unpadded base64. The length has been checked by the caller.
+fn decode_base64(encoded: &str, len: usize, alphabet: Alphabet) -> Result<Vec<u8>, DecodeError> {
+    let input = &encoded.as_bytes()[..len];
+    let mut result = Vec::with_capacity(len * 3 / 4);
+    let mut first_invalid = FirstInvalid::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for (i, &c) in input.iter().enumerate() {
+        let value = alphabet.decode(c);
+        first_invalid.check(i, value);
+        buffer = (buffer << 6) | (value as u32 & 0x3f);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+        }
+    }
+    // Unused trailing bits must be zero, so each input has one encoding.
+    let trailing = buffer & ((1 << bits) - 1);
+
+    first_invalid.into_result(encoded)?;
+    if trailing != 0 {
+        return Err(DecodeError::InvalidPadding);
+    }
+    Ok(result)
+}
+
+/// Constant-time hexadecimal encoding.
+pub struct CtHex;
+
+impl ConstantTime for CtHex {}
+
+impl Hex for CtHex {
+    fn encode(data: &[u8]) -> String {
+        encode_hex(data, false)
+    }
+
+    fn encode_upper(data: &[u8]) -> String {
+        encode_hex(data, true)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        if !encoded.len().is_multiple_of(2) {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let input = encoded.as_bytes();
+        let mut result = Vec::with_capacity(input.len() / 2);
+        let mut first_invalid = FirstInvalid::new();
+        for (i, pair) in input.chunks_exact(2).enumerate() {
+            let hi = decode_nibble(pair[0]);
+            let lo = decode_nibble(pair[1]);
+            first_invalid.check(2 * i, hi);
+            first_invalid.check(2 * i + 1, lo);
+            result.push(((hi << 4) | (lo & 15)) as u8);
+        }
+
+        first_invalid.into_result(encoded)?;
+        Ok(result)
+    }
+}
+
+/// Constant-time standard Base64 encoding.
+pub struct CtBase64;
+
+impl ConstantTime for CtBase64 {}
+
+impl Base64 for CtBase64 {
+    fn encode(data: &[u8]) -> String {
+        encode_base64(data, Alphabet::Standard, true)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        match encoded.len() % 4 {
+            0 => {}
+            1 => return Err(DecodeError::InvalidLength),
+            _ => return Err(DecodeError::InvalidPadding),
+        }
+        // Padding only reveals the length of the output, which is public.
+        let bytes = encoded.as_bytes();
+        let padding = bytes
+            .iter()
+            .rev()
+            .take(2)
+            .take_while(|&&c| c == b'=')
+            .count();
+        decode_base64(encoded, bytes.len() - padding, Alphabet::Standard)
+    }
+}
+
+/// Constant-time URL-safe Base64 encoding (no padding).
+pub struct CtBase64Url;
+
+impl ConstantTime for CtBase64Url {}
+
+impl Base64Url for CtBase64Url {
+    fn encode(data: &[u8]) -> String {
+        encode_base64(data, Alphabet::UrlSafe, false)
+    }
+
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
+        if encoded.ends_with('=') {
+            return Err(DecodeError::InvalidPadding);
+        }
+        if encoded.len() % 4 == 1 {
+            return Err(DecodeError::InvalidLength);
+        }
+        decode_base64(encoded, encoded.len(), Alphabet::UrlSafe)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StdBase64, StdBase64Url, StdHex};
+
+    /// Deterministic test data of every length up to `max`.
+    fn samples(max: usize) -> impl Iterator<Item = Vec<u8>> {
+        let mut state = 0x2545_f491_u32;
+        (0..=max).map(move |len| {
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    state as u8
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn maps_every_symbol_like_the_tables() {
+        for n in 0..16 {
+            assert_eq!(
+                encode_nibble(n, false),
+                StdHex::encode(&[n]).chars().nth(1).unwrap()
+            );
+            assert_eq!(
+                encode_nibble(n, true),
+                StdHex::encode_upper(&[n]).chars().nth(1).unwrap()
+            );
+        }
+        for c in 0..=255u8 {
+            let expected = (c as char).to_digit(16).map_or(-1, |d| d as i16);
+            assert_eq!(decode_nibble(c), expected, "{:?}", c as char);
+        }
+
+        let standard = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let url_safe = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        for (alphabet, table) in [
+            (Alphabet::Standard, standard),
+            (Alphabet::UrlSafe, url_safe),
+        ] {
+            for n in 0..64 {
+                assert_eq!(alphabet.encode(n), table[n as usize] as char);
+            }
+            for c in 0..=255u8 {
+                let expected = table.iter().position(|&t| t == c).map_or(-1, |n| n as i16);
+                assert_eq!(alphabet.decode(c), expected, "{:?}", c as char);
+            }
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_standard_implementations() {
+        for data in samples(40) {
+            let hex = StdHex::encode(&data);
+            assert_eq!(CtHex::encode(&data), hex);
+            assert_eq!(CtHex::encode_upper(&data), StdHex::encode_upper(&data));
+            assert_eq!(CtHex::decode(&hex).unwrap(), data);
+            assert_eq!(CtHex::decode(&hex.to_uppercase()).unwrap(), data);
+
+            let base64 = StdBase64::encode(&data);
+            assert_eq!(CtBase64::encode(&data), base64);
+            assert_eq!(CtBase64::decode(&base64).unwrap(), data);
+
+            let base64url = StdBase64Url::encode(&data);
+            assert_eq!(CtBase64Url::encode(&data), base64url);
+            assert_eq!(CtBase64Url::decode(&base64url).unwrap(), data);
+        }
+    }
+
+    /// The error, with the position for invalid characters.
+    fn describe(result: Result<Vec<u8>, DecodeError>) -> String {
+        format!("{}", result.unwrap_err())
+    }
+
+    #[test]
+    fn errors_match_the_standard_implementations() {
+        for input in ["abc", "0g", "zz00", "00ff0x", "é0", "00é0"] {
+            assert_eq!(
+                describe(CtHex::decode(input)),
+                describe(StdHex::decode(input)),
+                "{}",
+                input
+            );
+        }
+        for input in [
+            "aGVsbG8", "aGVsb", "a", "aGV*bG8=", "a=Vs", "aGVsbG8*", "aGVsbG9=", "aGVsbA==",
+            "aGVsbB==", "aG==",
+        ] {
+            let ct = CtBase64::decode(input);
+            let std = StdBase64::decode(input);
+            assert_eq!(ct.is_ok(), std.is_ok(), "{}", input);
+            if let (Err(ct), Err(std)) = (ct, std) {
+                assert_eq!(ct.to_string(), std.to_string(), "{}", input);
+            }
+        }
+        for input in ["aGVsbG8", "a", "aGV*bG8", "aGVsbG8=", "aGVsbB", "_-_-"] {
+            let ct = CtBase64Url::decode(input);
+            let std = StdBase64Url::decode(input);
+            assert_eq!(ct.is_ok(), std.is_ok(), "{}", input);
+            if let (Err(ct), Err(std)) = (ct, std) {
+                assert_eq!(ct.to_string(), std.to_string(), "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn reports_the_first_invalid_character() {
+        assert!(matches!(
+            CtHex::decode("00zz"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'z',
+                position: 2
+            })
+        ));
+        assert!(matches!(
+            CtBase64::decode("ab!d?fgh"),
+            Err(DecodeError::InvalidCharacter {
+                character: '!',
+                position: 2
+            })
+        ));
+    }
+}
@@ -0,0 +1,234 @@
+//! Length-prefixed framing.
+
+use crate::StdLeb128;
+use portals_encoding::{DecodeError, Leb128};
+
+/// Largest payload a [`FrameCodec`] accepts by default: 16 MiB.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// How a frame's payload length is written ahead of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    /// One byte.
+    U8,
+    /// Two bytes, big-endian.
+    U16Be,
+    /// Two bytes, little-endian.
+    U16Le,
+    /// Four bytes, big-endian.
+    U32Be,
+    /// Four bytes, little-endian.
+    U32Le,
+    /// Eight bytes, big-endian.
+    U64Be,
+    /// Eight bytes, little-endian.
+    U64Le,
+    /// Unsigned LEB128, as in protobuf's delimited messages.
+    Varint,
+}
+
+impl LengthPrefix {
+    /// The largest length this prefix can hold.
+    pub fn limit(self) -> u64 {
+        match self {
+            LengthPrefix::U8 => u8::MAX as u64,
+            LengthPrefix::U16Be | LengthPrefix::U16Le => u16::MAX as u64,
+            LengthPrefix::U32Be | LengthPrefix::U32Le => u32::MAX as u64,
+            LengthPrefix::U64Be | LengthPrefix::U64Le | LengthPrefix::Varint => u64::MAX,
+        }
+    }
+
+    fn write(self, len: u64, out: &mut Vec<u8>) {
+        match self {
+            LengthPrefix::U8 => out.push(len as u8),
+            LengthPrefix::U16Be => out.extend_from_slice(&(len as u16).to_be_bytes()),
+            LengthPrefix::U16Le => out.extend_from_slice(&(len as u16).to_le_bytes()),
+            LengthPrefix::U32Be => out.extend_from_slice(&(len as u32).to_be_bytes()),
+            LengthPrefix::U32Le => out.extend_from_slice(&(len as u32).to_le_bytes()),
+            LengthPrefix::U64Be => out.extend_from_slice(&len.to_be_bytes()),
+            LengthPrefix::U64Le => out.extend_from_slice(&len.to_le_bytes()),
+            LengthPrefix::Varint => StdLeb128::encode_u64(len, out),
+        }
+    }
+
+    /// Read a length, or `None` if `input` does not hold all of it yet.
+    fn read(self, input: &[u8]) -> Result<Option<(u64, usize)>, DecodeError> {
+        fn fixed<const N: usize>(input: &[u8]) -> Option<[u8; N]> {
+            input.get(..N).map(|bytes| bytes.try_into().unwrap())
+        }
+
+        let read = match self {
+            LengthPrefix::U8 => input.first().map(|&b| (b as u64, 1)),
+            LengthPrefix::U16Be => fixed(input).map(|b| (u16::from_be_bytes(b) as u64, 2)),
+            LengthPrefix::U16Le => fixed(input).map(|b| (u16::from_le_bytes(b) as u64, 2)),
+            LengthPrefix::U32Be => fixed(input).map(|b| (u32::from_be_bytes(b) as u64, 4)),
+            LengthPrefix::U32Le => fixed(input).map(|b| (u32::from_le_bytes(b) as u64, 4)),
+            LengthPrefix::U64Be => fixed(input).map(|b| (u64::from_be_bytes(b), 8)),
+            LengthPrefix::U64Le => fixed(input).map(|b| (u64::from_le_bytes(b), 8)),
+            LengthPrefix::Varint => match StdLeb128::decode_u64(input) {
+                Ok(read) => Some(read),
+                Err(DecodeError::UnexpectedEnd) => None,
+                Err(e) => return Err(e),
+            },
+        };
+        Ok(read)
+    }
+}
+
+/// Splits a byte stream into frames, each a length prefix followed by
+/// that many payload bytes.
+///
+/// Decoding works on whatever has arrived so far, so it suits buffers
+/// filled from a socket: it reports an incomplete frame rather than
+/// failing, and rejects an oversized length as soon as the prefix is in.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameCodec {
+    prefix: LengthPrefix,
+    max_len: usize,
+}
+
+impl FrameCodec {
+    /// Create a codec with the given prefix and the default maximum
+    /// payload length.
+    pub fn new(prefix: LengthPrefix) -> Self {
+        Self {
+            prefix,
+            max_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Set the largest payload to accept. The prefix may impose a
+    /// smaller limit of its own.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    fn limit(&self) -> u64 {
+        (self.max_len as u64).min(self.prefix.limit())
+    }
+
+    /// Append `payload` to `out` as one frame.
+    ///
+    /// A payload longer than the maximum length or than the prefix can
+    /// hold is [`DecodeError::InvalidLength`], and leaves `out` untouched.
+    pub fn encode(&self, payload: &[u8], out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let len = payload.len() as u64;
+        if len > self.limit() {
+            return Err(DecodeError::InvalidLength);
+        }
+        self.prefix.write(len, out);
+        out.extend_from_slice(payload);
+        Ok(())
+    }
+
+    /// Decode the frame at the start of `input`, returning its payload
+    /// and the number of bytes the whole frame took, or `None` if the
+    /// frame is not complete yet.
+    ///
+    /// A length above the maximum is [`DecodeError::InvalidLength`].
+    pub fn decode<'a>(&self, input: &'a [u8]) -> Result<Option<(&'a [u8], usize)>, DecodeError> {
+        let Some((len, header)) = self.prefix.read(input)? else {
+            return Ok(None);
+        };
+        if len > self.limit() {
+            return Err(DecodeError::InvalidLength);
+        }
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| header.checked_add(len))
+            .ok_or(DecodeError::InvalidLength)?;
+        Ok(input.get(header..end).map(|payload| (payload, end)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_are_written_as_configured() {
+        let vectors: [(LengthPrefix, &[u8]); 8] = [
+            (LengthPrefix::U8, &[3]),
+            (LengthPrefix::U16Be, &[0, 3]),
+            (LengthPrefix::U16Le, &[3, 0]),
+            (LengthPrefix::U32Be, &[0, 0, 0, 3]),
+            (LengthPrefix::U32Le, &[3, 0, 0, 0]),
+            (LengthPrefix::U64Be, &[0, 0, 0, 0, 0, 0, 0, 3]),
+            (LengthPrefix::U64Le, &[3, 0, 0, 0, 0, 0, 0, 0]),
+            (LengthPrefix::Varint, &[3]),
+        ];
+        for (prefix, header) in vectors {
+            let codec = FrameCodec::new(prefix);
+            let mut out = Vec::new();
+            codec.encode(b"abc", &mut out).unwrap();
+            assert_eq!(&out[..header.len()], header, "{:?}", prefix);
+            assert_eq!(&out[header.len()..], b"abc");
+            assert_eq!(codec.decode(&out).unwrap(), Some((&b"abc"[..], out.len())));
+        }
+    }
+
+    #[test]
+    fn decodes_frames_as_they_arrive() {
+        let codec = FrameCodec::new(LengthPrefix::Varint);
+        let mut stream = Vec::new();
+        codec.encode(&[7; 200], &mut stream).unwrap();
+        codec.encode(b"", &mut stream).unwrap();
+        codec.encode(b"last", &mut stream).unwrap();
+
+        // Every strict prefix of the first frame is incomplete.
+        for end in 0..202 {
+            assert_eq!(codec.decode(&stream[..end]).unwrap(), None);
+        }
+
+        let mut frames = Vec::new();
+        let mut rest = &stream[..];
+        while let Some((payload, len)) = codec.decode(rest).unwrap() {
+            frames.push(payload.to_vec());
+            rest = &rest[len..];
+        }
+        assert!(rest.is_empty());
+        assert_eq!(frames, [vec![7; 200], vec![], b"last".to_vec()]);
+    }
+
+    #[test]
+    fn rejects_oversized_frames_before_the_payload() {
+        let codec = FrameCodec::new(LengthPrefix::U32Be).max_len(16);
+        assert!(matches!(
+            codec.decode(&[0, 0, 0, 17]),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert_eq!(codec.decode(&[0, 0, 0, 16]).unwrap(), None);
+
+        let codec = FrameCodec::new(LengthPrefix::Varint);
+        assert!(matches!(
+            codec.decode(&[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            Err(DecodeError::InvalidLength)
+        ));
+    }
+
+    #[test]
+    fn encode_rejects_payloads_over_the_limit() {
+        let mut out = Vec::new();
+        assert!(matches!(
+            FrameCodec::new(LengthPrefix::U8).encode(&[0; 256], &mut out),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(matches!(
+            FrameCodec::new(LengthPrefix::Varint)
+                .max_len(3)
+                .encode(b"abcd", &mut out),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn decode_rejects_lengths_past_the_address_space() {
+        let codec = FrameCodec::new(LengthPrefix::U64Be).max_len(usize::MAX);
+        assert!(matches!(
+            codec.decode(&[0xff; 8]),
+            Err(DecodeError::InvalidLength)
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! LEB128 variable-length integers.
+
+use portals_encoding::{DecodeError, Leb128};
+
+/// A 64-bit value takes at most ten 7-bit groups.
+const MAX_BYTES: usize = 10;
+
+/// LEB128 encoding.
+pub struct StdLeb128;
+
+impl Leb128 for StdLeb128 {
+    fn encode_u64(mut value: u64, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn encode_i64(mut value: i64, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let sign = byte & 0x40 != 0;
+            if (value == 0 && !sign) || (value == -1 && sign) {
+                out.push(byte);
+                return;
+            }
+            out.push(byte | 0x80);
+        }
+    }
+
+    fn decode_u64(input: &[u8]) -> Result<(u64, usize), DecodeError> {
+        let mut result = 0u64;
+        for i in 0..MAX_BYTES {
+            let byte = *input.get(i).ok_or(DecodeError::UnexpectedEnd)?;
+            // The last group holds the single remaining bit.
+            if i == MAX_BYTES - 1 && byte > 1 {
+                return Err(DecodeError::InvalidByte { byte, position: i });
+            }
+            result |= ((byte & 0x7f) as u64) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok((result, i + 1));
+            }
+        }
+        unreachable!("the last group has no continuation bit")
+    }
+
+    fn decode_i64(input: &[u8]) -> Result<(i64, usize), DecodeError> {
+        let mut result = 0i64;
+        for i in 0..MAX_BYTES {
+            let byte = *input.get(i).ok_or(DecodeError::UnexpectedEnd)?;
+            // The last group holds the sign bit, extended.
+            if i == MAX_BYTES - 1 && byte != 0x00 && byte != 0x7f {
+                return Err(DecodeError::InvalidByte { byte, position: i });
+            }
+            result |= ((byte & 0x7f) as i64) << (7 * i);
+            if byte & 0x80 == 0 {
+                let shift = 7 * (i + 1);
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+                return Ok((result, i + 1));
+            }
+        }
+        unreachable!("the last group has no continuation bit")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_u64(value: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        StdLeb128::encode_u64(value, &mut out);
+        out
+    }
+
+    fn encode_i64(value: i64) -> Vec<u8> {
+        let mut out = Vec::new();
+        StdLeb128::encode_i64(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn unsigned_round_trips() {
+        let vectors: [(u64, &[u8]); 6] = [
+            (0, &[0x00]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xac, 0x02]),
+            (624485, &[0xe5, 0x8e, 0x26]),
+            (
+                u64::MAX,
+                &[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01],
+            ),
+        ];
+        for (value, encoded) in vectors {
+            assert_eq!(encode_u64(value), encoded);
+            assert_eq!(
+                StdLeb128::decode_u64(encoded).unwrap(),
+                (value, encoded.len())
+            );
+        }
+        // Trailing bytes are left for the caller.
+        assert_eq!(
+            StdLeb128::decode_u64(&[0xac, 0x02, 0xff]).unwrap(),
+            (300, 2)
+        );
+    }
+
+    #[test]
+    fn signed_round_trips() {
+        let vectors: [(i64, &[u8]); 8] = [
+            (0, &[0x00]),
+            (-1, &[0x7f]),
+            (63, &[0x3f]),
+            (64, &[0xc0, 0x00]),
+            (-64, &[0x40]),
+            (-65, &[0xbf, 0x7f]),
+            (-123456, &[0xc0, 0xbb, 0x78]),
+            (
+                i64::MIN,
+                &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x7f],
+            ),
+        ];
+        for (value, encoded) in vectors {
+            assert_eq!(encode_i64(value), encoded);
+            assert_eq!(
+                StdLeb128::decode_i64(encoded).unwrap(),
+                (value, encoded.len())
+            );
+        }
+        let max = encode_i64(i64::MAX);
+        assert_eq!(StdLeb128::decode_i64(&max).unwrap(), (i64::MAX, 10));
+    }
+
+    #[test]
+    fn rejects_truncated_and_oversized_input() {
+        assert!(matches!(
+            StdLeb128::decode_u64(&[]),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            StdLeb128::decode_u64(&[0x80, 0x80]),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            StdLeb128::decode_u64(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]),
+            Err(DecodeError::InvalidByte {
+                byte: 0x02,
+                position: 9
+            })
+        ));
+        assert!(matches!(
+            StdLeb128::decode_i64(&[0x80; 11]),
+            Err(DecodeError::InvalidByte {
+                byte: 0x80,
+                position: 9
+            })
+        ));
+    }
+}
@@ -2,7 +2,45 @@
 //!
 //! Works on both native and WASM targets.
 
-use portals_encoding::{Base64, Base64Url, DecodeError, Hex, UrlEncoding};
+mod base32;
+mod base58;
+mod base85;
+mod cbor;
+mod ct;
+mod frame;
+mod leb128;
+mod stream;
+mod url;
+
+pub use base32::{StdBase32, StdBase32Hex, StdCrockford};
+pub use base58::StdBase58;
+pub use base85::{StdAscii85, StdZ85};
+pub use cbor::StdCbor;
+pub use ct::{CtBase64, CtBase64Url, CtHex};
+pub use frame::{DEFAULT_MAX_FRAME_LEN, FrameCodec, LengthPrefix};
+pub use leb128::StdLeb128;
+pub use stream::{Encoding, LineWrap, StreamDecoder, StreamEncoder};
+pub use url::{StdFormUrlEncoding, StdUrlEncoding};
+
+use portals_encoding::{Base64, Base64Url, DecodeError, Hex};
+
+/// Map a `base64` crate error onto ours, keeping the offending position.
+fn base64_error(encoded: &str, error: base64::DecodeError) -> DecodeError {
+    match error {
+        base64::DecodeError::InvalidByte(position, _) => {
+            DecodeError::invalid_character(encoded, position)
+        }
+        base64::DecodeError::InvalidLength(_) => DecodeError::InvalidLength,
+        base64::DecodeError::InvalidLastSymbol(_, _) | base64::DecodeError::InvalidPadding => {
+            DecodeError::InvalidPadding
+        }
+    }
+}
+
+/// Value of an ASCII hex digit.
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
 
 /// Standard Base64 encoding.
 pub struct StdBase64;
@@ -17,7 +55,7 @@ impl Base64 for StdBase64 {
         use base64::Engine;
         base64::engine::general_purpose::STANDARD
             .decode(encoded)
-            .map_err(|_| DecodeError::InvalidCharacter('?'))
+            .map_err(|e| base64_error(encoded, e))
     }
 }
 
@@ -34,7 +72,7 @@ impl Base64Url for StdBase64Url {
         use base64::Engine;
         base64::engine::general_purpose::URL_SAFE_NO_PAD
             .decode(encoded)
-            .map_err(|_| DecodeError::InvalidCharacter('?'))
+            .map_err(|e| base64_error(encoded, e))
     }
 }
 
@@ -51,20 +89,17 @@ impl Hex for StdHex {
     }
 
     fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError> {
-        if encoded.len() % 2 != 0 {
+        if !encoded.len().is_multiple_of(2) {
             return Err(DecodeError::InvalidLength);
         }
 
-        let mut result = Vec::with_capacity(encoded.len() / 2);
-        let mut chars = encoded.chars();
-
-        while let (Some(hi), Some(lo)) = (chars.next(), chars.next()) {
-            let hi = hi
-                .to_digit(16)
-                .ok_or(DecodeError::InvalidCharacter(hi))? as u8;
-            let lo = lo
-                .to_digit(16)
-                .ok_or(DecodeError::InvalidCharacter(lo))? as u8;
+        let input = encoded.as_bytes();
+        let mut result = Vec::with_capacity(input.len() / 2);
+        for (i, pair) in input.chunks_exact(2).enumerate() {
+            let hi =
+                hex_value(pair[0]).ok_or_else(|| DecodeError::invalid_character(encoded, 2 * i))?;
+            let lo = hex_value(pair[1])
+                .ok_or_else(|| DecodeError::invalid_character(encoded, 2 * i + 1))?;
             result.push((hi << 4) | lo);
         }
 
@@ -72,56 +107,10 @@ impl Hex for StdHex {
     }
 }
 
-/// URL percent encoding.
-pub struct StdUrlEncoding;
-
-impl UrlEncoding for StdUrlEncoding {
-    fn encode(input: &str) -> String {
-        let mut result = String::new();
-        for c in input.chars() {
-            match c {
-                'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
-                    result.push(c);
-                }
-                _ => {
-                    for b in c.to_string().as_bytes() {
-                        result.push_str(&format!("%{:02X}", b));
-                    }
-                }
-            }
-        }
-        result
-    }
-
-    fn decode(encoded: &str) -> Result<String, DecodeError> {
-        let mut result = Vec::new();
-        let mut chars = encoded.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '%' {
-                let hi = chars.next().ok_or(DecodeError::InvalidLength)?;
-                let lo = chars.next().ok_or(DecodeError::InvalidLength)?;
-                let hi = hi
-                    .to_digit(16)
-                    .ok_or(DecodeError::InvalidCharacter(hi))? as u8;
-                let lo = lo
-                    .to_digit(16)
-                    .ok_or(DecodeError::InvalidCharacter(lo))? as u8;
-                result.push((hi << 4) | lo);
-            } else if c == '+' {
-                result.push(b' ');
-            } else {
-                result.push(c as u8);
-            }
-        }
-
-        String::from_utf8(result).map_err(|_| DecodeError::InvalidUtf8)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use portals_encoding::UrlEncoding;
 
     #[test]
     fn base64_roundtrip() {
@@ -160,4 +149,58 @@ mod tests {
         let decoded = StdUrlEncoding::decode(&encoded).unwrap();
         assert_eq!(decoded, input);
     }
+
+    #[test]
+    fn decode_errors_carry_positions() {
+        assert!(matches!(
+            StdBase64::decode("aGVs*G8="),
+            Err(DecodeError::InvalidCharacter {
+                character: '*',
+                position: 4
+            })
+        ));
+        assert!(matches!(
+            StdBase64::decode("aGVsbG8"),
+            Err(DecodeError::InvalidPadding)
+        ));
+        assert!(matches!(
+            StdHex::decode("dEaDbEgf"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'g',
+                position: 6
+            })
+        ));
+        assert!(matches!(
+            StdHex::decode("abc"),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(matches!(
+            StdUrlEncoding::decode("caf%C3%Q9"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'Q',
+                position: 7
+            })
+        ));
+        assert!(matches!(
+            StdUrlEncoding::decode("100%"),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert_eq!(StdUrlEncoding::decode("caf%C3%A9").unwrap(), "café");
+        assert_eq!(StdUrlEncoding::decode("café").unwrap(), "café");
+        // Offsets that are not character boundaries must not panic.
+        assert!(matches!(
+            DecodeError::invalid_character("é", 1),
+            DecodeError::InvalidCharacter {
+                character: '\u{a9}',
+                position: 1
+            }
+        ));
+        assert!(matches!(
+            DecodeError::invalid_character("é", 3),
+            DecodeError::InvalidCharacter {
+                character: '\0',
+                position: 3
+            }
+        ));
+    }
 }
@@ -0,0 +1,664 @@
+//! Streaming encoders and decoders over `portals-io` streams.
+//!
+//! Input is processed in whole blocks of the encoding (3 bytes for base64,
+//! 5 for base32, 1 for hex), so output is produced as data arrives and only
+//! a partial block is ever held back.
+
+use crate::{StdBase32, StdBase64, StdBase64Url, StdHex};
+use portals_encoding::{Base32, Base64, Base64Url, DecodeError, Hex};
+use portals_io::{InputStream, OutputStream, StreamError};
+
+/// Bytes read from the input per decoding step.
+const READ_SIZE: usize = 4096;
+
+/// An encoding that can be streamed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Standard base64 with padding (RFC 4648 section 4).
+    Base64,
+    /// URL-safe base64 without padding (RFC 4648 section 5). Padding is
+    /// accepted when decoding.
+    Base64Url,
+    /// Base32 with padding (RFC 4648 section 6).
+    Base32,
+    /// Lowercase hexadecimal. Either case is accepted when decoding.
+    Hex,
+    /// Uppercase hexadecimal. Either case is accepted when decoding.
+    HexUpper,
+}
+
+impl Encoding {
+    /// Bytes per block and characters per encoded block.
+    fn block(self) -> (usize, usize) {
+        match self {
+            Encoding::Base64 | Encoding::Base64Url => (3, 4),
+            Encoding::Base32 => (5, 8),
+            Encoding::Hex | Encoding::HexUpper => (1, 2),
+        }
+    }
+
+    fn encode(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => StdBase64::encode(data),
+            Encoding::Base64Url => StdBase64Url::encode(data),
+            Encoding::Base32 => StdBase32::encode(data),
+            Encoding::Hex => StdHex::encode(data),
+            Encoding::HexUpper => StdHex::encode_upper(data),
+        }
+    }
+
+    fn decode(self, text: &str) -> Result<Vec<u8>, DecodeError> {
+        match self {
+            Encoding::Base64 => StdBase64::decode(text),
+            Encoding::Base64Url => StdBase64Url::decode(text.trim_end_matches('=')),
+            Encoding::Base32 => StdBase32::decode(text),
+            Encoding::Hex | Encoding::HexUpper => StdHex::decode(text),
+        }
+    }
+}
+
+/// How to break encoded output into lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineWrap {
+    width: usize,
+    ending: &'static str,
+}
+
+impl LineWrap {
+    /// 76 columns with CRLF endings, as in MIME (RFC 2045).
+    pub const MIME: LineWrap = LineWrap {
+        width: 76,
+        ending: "\r\n",
+    };
+
+    /// 64 columns with LF endings, as in PEM (RFC 7468).
+    pub const PEM: LineWrap = LineWrap {
+        width: 64,
+        ending: "\n",
+    };
+
+    /// Lines of `width` characters with LF endings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    pub fn new(width: usize) -> Self {
+        assert!(width > 0, "line width must be positive");
+        LineWrap {
+            width,
+            ending: "\n",
+        }
+    }
+
+    /// End lines with CRLF instead.
+    pub fn crlf(mut self) -> Self {
+        self.ending = "\r\n";
+        self
+    }
+}
+
+/// Encodes everything written to it into an output stream.
+///
+/// The final partial block, its padding and the last line ending are only
+/// written by [`finish`](Self::finish). Encoded text the output stream has
+/// no room for is kept and written by the next write or flush.
+pub struct StreamEncoder<W> {
+    encoding: Encoding,
+    output: W,
+    wrap: Option<LineWrap>,
+    /// Characters on the current line.
+    column: usize,
+    /// Input not yet making up a whole block.
+    buf: Vec<u8>,
+    /// Encoded text not yet written to the output.
+    pending: Vec<u8>,
+    started: bool,
+}
+
+impl<W: OutputStream> StreamEncoder<W> {
+    /// Encode into `output`, without line breaks.
+    pub fn new(encoding: Encoding, output: W) -> Self {
+        Self {
+            encoding,
+            output,
+            wrap: None,
+            column: 0,
+            buf: Vec::new(),
+            pending: Vec::new(),
+            started: false,
+        }
+    }
+
+    /// Break the output into lines.
+    ///
+    /// # Panics
+    ///
+    /// Panics if output has already been written.
+    pub fn wrap(mut self, wrap: LineWrap) -> Self {
+        assert!(!self.started, "line wrapping set after writing");
+        self.wrap = Some(wrap);
+        self
+    }
+
+    /// Encode the final partial block, end the last line, flush, and return
+    /// the output stream.
+    pub fn finish(mut self) -> Result<W, StreamError> {
+        let rest = std::mem::take(&mut self.buf);
+        let mut text = self.encoding.encode(&rest);
+        if let Some(wrap) = self.wrap {
+            text = self.wrapped(&text);
+            if self.column > 0 {
+                text.push_str(wrap.ending);
+            }
+        }
+        self.pending.extend_from_slice(text.as_bytes());
+        self.drain(true)?;
+        self.output.blocking_flush()?;
+        Ok(self.output)
+    }
+
+    /// Insert line endings into encoded text, tracking the column.
+    fn wrapped(&mut self, text: &str) -> String {
+        let Some(wrap) = self.wrap else {
+            return text.to_string();
+        };
+        let mut out = String::with_capacity(text.len() + text.len() / wrap.width * 2 + 2);
+        let mut rest = text;
+        while !rest.is_empty() {
+            if self.column == wrap.width {
+                out.push_str(wrap.ending);
+                self.column = 0;
+            }
+            let n = rest.len().min(wrap.width - self.column);
+            out.push_str(&rest[..n]);
+            self.column += n;
+            rest = &rest[n..];
+        }
+        out
+    }
+
+    /// Write pending text to the output, within its permit unless
+    /// blocking. Text is only dropped once written.
+    fn drain(&mut self, blocking: bool) -> Result<(), StreamError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let len = if blocking {
+            self.output.blocking_write(&self.pending)?;
+            self.pending.len()
+        } else {
+            let len = self.output.check_write()?.min(self.pending.len());
+            if len == 0 {
+                return Ok(());
+            }
+            self.output.write(&self.pending[..len])?;
+            len
+        };
+        self.pending.drain(..len);
+        Ok(())
+    }
+
+    fn write_blocks(&mut self, bytes: &[u8], blocking: bool) -> Result<(), StreamError> {
+        self.buf.extend_from_slice(bytes);
+        let (block, _) = self.encoding.block();
+        let whole = self.buf.len() / block * block;
+        if whole > 0 {
+            self.started = true;
+            let text = self.encoding.encode(&self.buf[..whole]);
+            let text = self.wrapped(&text);
+            self.pending.extend_from_slice(text.as_bytes());
+            self.buf.drain(..whole);
+        }
+        self.drain(blocking)
+    }
+}
+
+impl<W: OutputStream> OutputStream for StreamEncoder<W> {
+    /// The input the output stream has room for once encoded, but at least
+    /// the rest of a block, or 0 while earlier text is still pending.
+    fn check_write(&self) -> Result<usize, StreamError> {
+        let permit = self.output.check_write()?;
+        if !self.pending.is_empty() {
+            return Ok(0);
+        }
+        let (block, chars) = self.encoding.block();
+        // Leave room for a line ending per line, plus one for a line
+        // already at full width.
+        let room = match self.wrap {
+            Some(wrap) => {
+                let ending = wrap.ending.len();
+                permit.saturating_sub(permit / (wrap.width + ending) * ending + ending)
+            }
+            None => permit,
+        };
+        // Always allow one block, so a small permit cannot stall a
+        // partial one; its text waits in `pending` if need be.
+        Ok((room / chars).max(1).saturating_mul(block) - self.buf.len())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.write_blocks(bytes, false)
+    }
+
+    fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.write_blocks(bytes, true)
+    }
+
+    /// Flush output written so far. A partial block stays buffered until
+    /// more data arrives or the stream is finished.
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.drain(false)?;
+        self.output.flush()
+    }
+
+    fn blocking_flush(&mut self) -> Result<(), StreamError> {
+        self.drain(true)?;
+        self.output.blocking_flush()
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        self.output.subscribe()
+    }
+}
+
+/// Decodes an encoded input stream.
+///
+/// ASCII whitespace, including line breaks, is skipped anywhere in the
+/// input. Malformed input is reported as [`StreamError::Other`], and every
+/// later read fails the same way.
+pub struct StreamDecoder<R> {
+    encoding: Encoding,
+    input: R,
+    /// Encoded characters not yet decoded, without whitespace.
+    text: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+    eof: bool,
+    failed: Option<StreamError>,
+}
+
+impl<R: InputStream> StreamDecoder<R> {
+    /// Decode `input`.
+    pub fn new(encoding: Encoding, input: R) -> Self {
+        Self {
+            encoding,
+            input,
+            text: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            eof: false,
+            failed: None,
+        }
+    }
+
+    /// Read until decoded bytes are available. Returns false if a
+    /// non-blocking read came up empty.
+    fn fill(&mut self, blocking: bool) -> Result<bool, StreamError> {
+        while self.out_pos == self.out.len() {
+            if self.eof {
+                if self.text.is_empty() {
+                    return Err(StreamError::Closed);
+                }
+                let text = std::mem::take(&mut self.text);
+                self.decode(&text)?;
+                continue;
+            }
+
+            let mut raw = [0u8; READ_SIZE];
+            let read = if blocking {
+                self.input.blocking_read_into(&mut raw)
+            } else {
+                self.input.read_into(&mut raw)
+            };
+            match read {
+                Ok(0) if !blocking => return Ok(false),
+                Ok(n) => self
+                    .text
+                    .extend(raw[..n].iter().filter(|b| !b.is_ascii_whitespace())),
+                Err(StreamError::Closed) => self.eof = true,
+                Err(e) => return Err(e),
+            }
+
+            // Decode whole blocks, keeping any padding for the end so that
+            // data after it is rejected.
+            let (_, block) = self.encoding.block();
+            let end = self
+                .text
+                .iter()
+                .position(|&b| b == b'=')
+                .unwrap_or(self.text.len());
+            if self.text[end..].iter().any(|&b| b != b'=') {
+                return Err(StreamError::Other(DecodeError::InvalidPadding.to_string()));
+            }
+            let whole = end / block * block;
+            if whole > 0 && !self.eof {
+                let text: Vec<u8> = self.text.drain(..whole).collect();
+                self.decode(&text)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn decode(&mut self, text: &[u8]) -> Result<(), StreamError> {
+        let text = std::str::from_utf8(text)
+            .map_err(|_| StreamError::Other(DecodeError::InvalidUtf8.to_string()))?;
+        self.out = self
+            .encoding
+            .decode(text)
+            .map_err(|e| StreamError::Other(e.to_string()))?;
+        self.out_pos = 0;
+        Ok(())
+    }
+
+    fn read_decoded(&mut self, buf: &mut [u8], blocking: bool) -> Result<usize, StreamError> {
+        if let Some(err) = &self.failed {
+            return Err(err.clone());
+        }
+        match self.fill(blocking) {
+            Ok(true) => {}
+            Ok(false) => return Ok(0),
+            Err(StreamError::Closed) => return Err(StreamError::Closed),
+            Err(err) => {
+                self.failed = Some(err.clone());
+                return Err(err);
+            }
+        }
+        let available = &self.out[self.out_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+
+    /// Return the input stream. Anything read from it but not yet returned
+    /// by a read is discarded.
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+}
+
+impl<R: InputStream> InputStream for StreamDecoder<R> {
+    fn read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+        self.read_decoded(buf, false)
+    }
+
+    fn blocking_read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+        self.read_decoded(buf, true)
+    }
+
+    fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+        self.input.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Sink(Vec<u8>);
+
+    impl OutputStream for Sink {
+        fn check_write(&self) -> Result<usize, StreamError> {
+            Ok(usize::MAX)
+        }
+        fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.0.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.write(bytes)
+        }
+        fn flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn blocking_flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    /// Permits `step` bytes per write, and fails the next write if `fail`
+    /// is set.
+    struct Trickle {
+        out: Vec<u8>,
+        step: usize,
+        fail: bool,
+    }
+
+    impl OutputStream for Trickle {
+        fn check_write(&self) -> Result<usize, StreamError> {
+            Ok(self.step)
+        }
+        fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            assert!(bytes.len() <= self.step, "wrote past the permit");
+            if std::mem::take(&mut self.fail) {
+                return Err(StreamError::LastOperationFailed);
+            }
+            self.out.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn blocking_write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+            self.out.extend_from_slice(bytes);
+            Ok(())
+        }
+        fn flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn blocking_flush(&mut self) -> Result<(), StreamError> {
+            Ok(())
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    /// Hands out at most `step` bytes per read.
+    struct Source {
+        data: Vec<u8>,
+        pos: usize,
+        step: usize,
+    }
+
+    impl InputStream for Source {
+        fn read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+            if self.pos == self.data.len() {
+                return Err(StreamError::Closed);
+            }
+            let n = buf.len().min(self.step).min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+        fn blocking_read_into(&mut self, buf: &mut [u8]) -> Result<usize, StreamError> {
+            self.read_into(buf)
+        }
+        fn subscribe(&self) -> impl std::future::Future<Output = ()> {
+            std::future::ready(())
+        }
+    }
+
+    fn encode(encoding: Encoding, data: &[u8], wrap: Option<LineWrap>) -> String {
+        let mut enc = StreamEncoder::new(encoding, Sink::default());
+        if let Some(wrap) = wrap {
+            enc = enc.wrap(wrap);
+        }
+        for piece in data.chunks(7) {
+            enc.write(piece).unwrap();
+        }
+        String::from_utf8(enc.finish().unwrap().0).unwrap()
+    }
+
+    fn decode(encoding: Encoding, text: &str, step: usize) -> Result<Vec<u8>, StreamError> {
+        let source = Source {
+            data: text.as_bytes().to_vec(),
+            pos: 0,
+            step,
+        };
+        let mut dec = StreamDecoder::new(encoding, source);
+        let mut out = Vec::new();
+        let mut buf = [0u8; 5];
+        loop {
+            match dec.blocking_read_into(&mut buf) {
+                Ok(n) => out.extend_from_slice(&buf[..n]),
+                Err(StreamError::Closed) => return Ok(out),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    #[test]
+    fn matches_one_shot_encoding() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for len in [0, 1, 2, 3, 4, 5, 6, 99, 1000] {
+            let data = &data[..len];
+            assert_eq!(
+                encode(Encoding::Base64, data, None),
+                StdBase64::encode(data)
+            );
+            assert_eq!(
+                encode(Encoding::Base64Url, data, None),
+                StdBase64Url::encode(data)
+            );
+            assert_eq!(
+                encode(Encoding::Base32, data, None),
+                StdBase32::encode(data)
+            );
+            assert_eq!(encode(Encoding::Hex, data, None), StdHex::encode(data));
+            assert_eq!(
+                encode(Encoding::HexUpper, data, None),
+                StdHex::encode_upper(data)
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrips_in_any_step() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(500).collect();
+        let encodings = [
+            Encoding::Base64,
+            Encoding::Base64Url,
+            Encoding::Base32,
+            Encoding::Hex,
+            Encoding::HexUpper,
+        ];
+        for encoding in encodings {
+            for len in [0, 1, 4, 5, 500] {
+                let text = encode(encoding, &data[..len], Some(LineWrap::new(10)));
+                for step in [1, 3, 4096] {
+                    assert_eq!(decode(encoding, &text, step).unwrap(), &data[..len]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wraps_lines() {
+        let data = [0u8; 60];
+        let mime = encode(Encoding::Base64, &data, Some(LineWrap::MIME));
+        let lines: Vec<&str> = mime.split_terminator("\r\n").collect();
+        assert_eq!(lines.iter().map(|l| l.len()).collect::<Vec<_>>(), [76, 4]);
+        assert!(mime.ends_with("AAAA\r\n"));
+
+        let pem = encode(Encoding::Base64, &data[..48], Some(LineWrap::PEM));
+        assert_eq!(pem, format!("{}\n", "A".repeat(64)));
+
+        assert_eq!(encode(Encoding::Hex, b"", Some(LineWrap::PEM)), "");
+        assert_eq!(
+            encode(
+                Encoding::Hex,
+                b"\x01\x02\x03",
+                Some(LineWrap::new(4).crlf())
+            ),
+            "0102\r\n03\r\n"
+        );
+    }
+
+    #[test]
+    fn writes_within_the_inner_permit() {
+        let data: Vec<u8> = (0..100u8).collect();
+        for (encoding, wrap) in [
+            (Encoding::Base64, None),
+            (Encoding::Base32, Some(LineWrap::new(6).crlf())),
+            (Encoding::Hex, Some(LineWrap::new(3))),
+        ] {
+            for step in [1, 5, 13] {
+                let sink = Trickle {
+                    out: Vec::new(),
+                    step,
+                    fail: false,
+                };
+                let mut enc = StreamEncoder::new(encoding, sink);
+                if let Some(wrap) = wrap {
+                    enc = enc.wrap(wrap);
+                }
+                let mut rest = &data[..];
+                let mut failed = false;
+                while !rest.is_empty() {
+                    let n = enc.check_write().unwrap().min(rest.len());
+                    if n == 0 {
+                        // A failed write loses nothing; the text is retried.
+                        enc.output.fail = !failed;
+                        assert_eq!(enc.flush().is_err(), !failed);
+                        failed = true;
+                        continue;
+                    }
+                    enc.write(&rest[..n]).unwrap();
+                    rest = &rest[n..];
+                }
+                let text = String::from_utf8(enc.finish().unwrap().out).unwrap();
+                assert_eq!(text, encode(encoding, &data, wrap), "step {}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn skips_whitespace() {
+        assert_eq!(
+            decode(Encoding::Base64, " aGVs\r\n bG8g\td29y bGQ=\n", 2).unwrap(),
+            b"hello world"
+        );
+        assert_eq!(decode(Encoding::Base32, "MZXW\n6===", 1).unwrap(), b"foo");
+        assert_eq!(
+            decode(Encoding::Hex, "de ad\nBE EF", 3).unwrap(),
+            b"\xde\xad\xbe\xef"
+        );
+        assert_eq!(decode(Encoding::Base64Url, "_-8=", 1).unwrap(), b"\xff\xef");
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(matches!(
+            decode(Encoding::Base64, "aGVs!G8=", 2),
+            Err(StreamError::Other(_))
+        ));
+        assert!(matches!(
+            decode(Encoding::Hex, "abc", 1),
+            Err(StreamError::Other(_))
+        ));
+        // Nothing may follow padding.
+        assert!(matches!(
+            decode(Encoding::Base64, "Zg==Zg==", 4),
+            Err(StreamError::Other(_))
+        ));
+        assert!(matches!(
+            decode(Encoding::Base32, "MY======MY", 1),
+            Err(StreamError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn failures_are_sticky() {
+        let source = Source {
+            data: b"zz00".to_vec(),
+            pos: 0,
+            step: 4,
+        };
+        let mut dec = StreamDecoder::new(Encoding::Hex, source);
+        let mut buf = [0u8; 4];
+        let err = dec.blocking_read_into(&mut buf).unwrap_err();
+        assert_eq!(dec.blocking_read_into(&mut buf).unwrap_err(), err);
+    }
+}
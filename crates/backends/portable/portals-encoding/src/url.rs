@@ -0,0 +1,256 @@
+//! Percent encoding, form bodies and query strings.
+
+use crate::hex_value;
+use portals_encoding::{DecodeError, EncodeSet, FormUrlEncoding, QueryMap, UrlEncoding};
+
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Whether `b` may appear unescaped in `set`.
+fn keeps(set: EncodeSet, b: u8) -> bool {
+    let unreserved = b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+    // Sub-delimiters, except `+`: decoding reads it as a space.
+    let sub_delim = matches!(
+        b,
+        b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b',' | b';' | b'='
+    );
+    match set {
+        EncodeSet::Unreserved => unreserved,
+        EncodeSet::Userinfo => unreserved || sub_delim || b == b':',
+        EncodeSet::PathSegment => unreserved || sub_delim || matches!(b, b':' | b'@'),
+        EncodeSet::Path => keeps(EncodeSet::PathSegment, b) || b == b'/',
+        EncodeSet::Query | EncodeSet::Fragment => keeps(EncodeSet::Path, b) || b == b'?',
+        EncodeSet::QueryComponent => keeps(EncodeSet::Query, b) && !matches!(b, b'&' | b'=' | b';'),
+        EncodeSet::Form => b.is_ascii_alphanumeric() || matches!(b, b'*' | b'-' | b'.' | b'_'),
+    }
+}
+
+fn percent_encode(input: &str, set: EncodeSet, out: &mut String) {
+    for b in input.bytes() {
+        if set == EncodeSet::Form && b == b' ' {
+            out.push('+');
+        } else if keeps(set, b) {
+            out.push(b as char);
+        } else {
+            out.push('%');
+            out.push(HEX_UPPER[(b >> 4) as usize] as char);
+            out.push(HEX_UPPER[(b & 15) as usize] as char);
+        }
+    }
+}
+
+/// Decode `encoded[start..end]`, reporting positions within `encoded`.
+fn percent_decode(encoded: &str, start: usize, end: usize) -> Result<String, DecodeError> {
+    let input = encoded.as_bytes();
+    let mut result = Vec::with_capacity(end - start);
+    let mut i = start;
+
+    while i < end {
+        match input[i] {
+            b'%' => {
+                if i + 2 >= end {
+                    return Err(DecodeError::InvalidLength);
+                }
+                let hi = hex_value(input[i + 1])
+                    .ok_or_else(|| DecodeError::invalid_character(encoded, i + 1))?;
+                let lo = hex_value(input[i + 2])
+                    .ok_or_else(|| DecodeError::invalid_character(encoded, i + 2))?;
+                result.push((hi << 4) | lo);
+                i += 3;
+            }
+            b'+' => {
+                result.push(b' ');
+                i += 1;
+            }
+            b => {
+                result.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(result).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+fn encode_pairs(pairs: &QueryMap, set: EncodeSet) -> String {
+    let mut result = String::new();
+    for (i, (key, value)) in pairs.iter().enumerate() {
+        if i > 0 {
+            result.push('&');
+        }
+        percent_encode(key, set, &mut result);
+        result.push('=');
+        percent_encode(value, set, &mut result);
+    }
+    result
+}
+
+/// URL percent encoding.
+pub struct StdUrlEncoding;
+
+impl UrlEncoding for StdUrlEncoding {
+    fn encode(input: &str) -> String {
+        Self::encode_set(input, EncodeSet::Unreserved)
+    }
+
+    fn encode_set(input: &str, set: EncodeSet) -> String {
+        let mut result = String::with_capacity(input.len());
+        percent_encode(input, set, &mut result);
+        result
+    }
+
+    fn decode(encoded: &str) -> Result<String, DecodeError> {
+        percent_decode(encoded, 0, encoded.len())
+    }
+}
+
+/// `application/x-www-form-urlencoded` and query strings.
+pub struct StdFormUrlEncoding;
+
+impl FormUrlEncoding for StdFormUrlEncoding {
+    fn encode(pairs: &QueryMap) -> String {
+        encode_pairs(pairs, EncodeSet::Form)
+    }
+
+    fn encode_query(pairs: &QueryMap) -> String {
+        encode_pairs(pairs, EncodeSet::QueryComponent)
+    }
+
+    fn decode(encoded: &str) -> Result<QueryMap, DecodeError> {
+        let mut map = QueryMap::new();
+        let mut start = usize::from(encoded.starts_with('?'));
+
+        for pair in encoded[start..].split('&') {
+            let end = start + pair.len();
+            if !pair.is_empty() {
+                let (key_end, value_start) = match pair.find('=') {
+                    Some(eq) => (start + eq, start + eq + 1),
+                    None => (end, end),
+                };
+                map.append(
+                    percent_decode(encoded, start, key_end)?,
+                    percent_decode(encoded, value_start, end)?,
+                );
+            }
+            start = end + 1;
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_sets_escape_per_component() {
+        let vectors = [
+            (EncodeSet::Unreserved, "a b/c?d", "a%20b%2Fc%3Fd"),
+            (EncodeSet::Userinfo, "user:p@ss", "user:p%40ss"),
+            (EncodeSet::Path, "/a b/c:d?e", "/a%20b/c:d%3Fe"),
+            (EncodeSet::PathSegment, "a/b@c", "a%2Fb@c"),
+            (EncodeSet::Query, "a=b&c=d/e?f#g", "a=b&c=d/e?f%23g"),
+            (
+                EncodeSet::QueryComponent,
+                "a=b&c+d;e/f?",
+                "a%3Db%26c%2Bd%3Be/f?",
+            ),
+            (EncodeSet::Fragment, "top #1", "top%20%231"),
+            (EncodeSet::Form, "a b~c*", "a+b%7Ec*"),
+        ];
+        for (set, input, encoded) in vectors {
+            assert_eq!(StdUrlEncoding::encode_set(input, set), encoded, "{:?}", set);
+        }
+        // `%` is always escaped, as input is never taken as already encoded.
+        assert_eq!(
+            StdUrlEncoding::encode_set("100%", EncodeSet::Query),
+            "100%25"
+        );
+        assert_eq!(StdUrlEncoding::encode_set("é", EncodeSet::Path), "%C3%A9");
+        assert_eq!(StdUrlEncoding::encode_set("1+1", EncodeSet::Path), "1%2B1");
+    }
+
+    #[test]
+    fn encode_sets_round_trip() {
+        let input = "a+b c%d&e=f;g/h?i#j@k:l~m!n'o(p)*q,r$s\u{e9}";
+        for set in [
+            EncodeSet::Unreserved,
+            EncodeSet::Userinfo,
+            EncodeSet::Path,
+            EncodeSet::PathSegment,
+            EncodeSet::Query,
+            EncodeSet::QueryComponent,
+            EncodeSet::Fragment,
+            EncodeSet::Form,
+        ] {
+            let encoded = StdUrlEncoding::encode_set(input, set);
+            assert_eq!(
+                StdUrlEncoding::decode(&encoded).unwrap(),
+                input,
+                "{:?}",
+                set
+            );
+        }
+    }
+
+    #[test]
+    fn form_and_query_serialization() {
+        let pairs = QueryMap::new()
+            .with("name", "Ada Lovelace")
+            .with("q", "a+b&c=d")
+            .with("city", "Zürich")
+            .with("q", "");
+        assert_eq!(
+            StdFormUrlEncoding::encode(&pairs),
+            "name=Ada+Lovelace&q=a%2Bb%26c%3Dd&city=Z%C3%BCrich&q="
+        );
+        assert_eq!(
+            StdFormUrlEncoding::encode_query(&pairs),
+            "name=Ada%20Lovelace&q=a%2Bb%26c%3Dd&city=Z%C3%BCrich&q="
+        );
+        for encoded in [
+            StdFormUrlEncoding::encode(&pairs),
+            StdFormUrlEncoding::encode_query(&pairs),
+        ] {
+            assert_eq!(StdFormUrlEncoding::decode(&encoded).unwrap(), pairs);
+        }
+        assert_eq!(StdFormUrlEncoding::encode(&QueryMap::new()), "");
+    }
+
+    #[test]
+    fn form_decode_is_lenient_about_shape() {
+        let map = StdFormUrlEncoding::decode("?a=1&&b&a=2&c=x%3Dy+z&=v&d==").unwrap();
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [
+                ("a", "1"),
+                ("b", ""),
+                ("a", "2"),
+                ("c", "x=y z"),
+                ("", "v"),
+                ("d", "=")
+            ]
+        );
+        assert!(StdFormUrlEncoding::decode("").unwrap().is_empty());
+        assert!(StdFormUrlEncoding::decode("?").unwrap().is_empty());
+    }
+
+    #[test]
+    fn form_decode_errors_point_into_the_input() {
+        assert!(matches!(
+            StdFormUrlEncoding::decode("?a=1&b=%zz"),
+            Err(DecodeError::InvalidCharacter {
+                character: 'z',
+                position: 8
+            })
+        ));
+        assert!(matches!(
+            StdFormUrlEncoding::decode("a=%4&b=1"),
+            Err(DecodeError::InvalidLength)
+        ));
+        assert!(matches!(
+            StdFormUrlEncoding::decode("a=%FF"),
+            Err(DecodeError::InvalidUtf8)
+        ));
+    }
+}
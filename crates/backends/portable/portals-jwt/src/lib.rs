@@ -0,0 +1,839 @@
+//! Portable implementation of portals-jwt.
+//!
+//! Works on both native and WASM targets. Signing and verification go
+//! through the `portals-crypto` traits, so any backend can supply the
+//! primitives:
+//!
+//! ```
+//! use portals_clocks_mock::MockWallClock;
+//! use portals_crypto_native::HmacSha256;
+//! use portals_jwt::{Claims, JwtSigner, JwtVerifier, Validation};
+//! use portals_jwt_portable::{Signer, SigningKey, Verifier, VerifyingKey};
+//!
+//! let secret = [7u8; 32];
+//! let signer = Signer::new(SigningKey::hs256::<HmacSha256>(&secret).unwrap());
+//! let token = signer.sign(&Claims::new().subject("alice").expires_at(2_000)).unwrap();
+//!
+//! let verifier = Verifier::new(MockWallClock::new(1_000, 0), Validation::new())
+//!     .with_key(VerifyingKey::hs256::<HmacSha256>(&secret).unwrap());
+//! let verified = verifier.verify(&token).unwrap();
+//! assert_eq!(verified.claims.sub.as_deref(), Some("alice"));
+//! ```
+
+use portals_clocks::WallClock;
+use portals_crypto::{CryptoError, Hmac, JwsAlgorithm, PublicKey, SecretKey};
+use portals_encoding::Base64Url;
+use portals_encoding_portable::StdBase64Url;
+use portals_jwt::{Algorithm, Claims, Header, JwtError, JwtSigner, JwtVerifier, Token, Validation};
+use serde_json::{Map, Value};
+use zeroize::Zeroizing;
+
+type SignFn = fn(&[u8], &[u8]) -> Result<Vec<u8>, CryptoError>;
+type VerifyFn = fn(&[u8], &[u8], &[u8]) -> Result<bool, CryptoError>;
+
+/// Minimum HS256 secret length (RFC 7518 section 3.2).
+const MIN_HMAC_KEY_SIZE: usize = 32;
+
+fn hmac_sign<H: Hmac>(key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut mac = H::new(key);
+    mac.update(message);
+    Ok(mac.finalize())
+}
+
+fn hmac_verify<H: Hmac>(key: &[u8], message: &[u8], tag: &[u8]) -> Result<bool, CryptoError> {
+    let mut mac = H::new(key);
+    mac.update(message);
+    Ok(mac.verify(tag))
+}
+
+/// The algorithm keys of a signature scheme are bound to.
+fn signature_algorithm<S: JwsAlgorithm>() -> Result<Algorithm, JwtError> {
+    match S::ALG.parse()? {
+        Algorithm::Hs256 => Err(JwtError::UnsupportedAlgorithm(S::ALG.to_string())),
+        alg => Ok(alg),
+    }
+}
+
+/// A key for signing tokens, bound to one algorithm.
+///
+/// Asymmetric keys take their algorithm from the scheme's
+/// [`JwsAlgorithm`] name, so an Ed25519 key only ever signs EdDSA tokens.
+#[derive(Clone)]
+pub struct SigningKey {
+    alg: Algorithm,
+    kid: Option<String>,
+    /// Cleared on drop, as it is secret for every algorithm.
+    key: Zeroizing<Vec<u8>>,
+    sign: SignFn,
+}
+
+impl SigningKey {
+    /// An HS256 key. The secret must be at least 32 bytes.
+    pub fn hs256<H: Hmac>(secret: &[u8]) -> Result<Self, JwtError> {
+        if secret.len() < MIN_HMAC_KEY_SIZE {
+            return Err(JwtError::InvalidKey);
+        }
+        Ok(Self::with_alg(Algorithm::Hs256, secret, hmac_sign::<H>))
+    }
+
+    /// A key for its scheme's algorithm: EdDSA for Ed25519, ES256 for
+    /// ECDSA P-256.
+    ///
+    /// Fails with [`JwtError::UnsupportedAlgorithm`] for other schemes.
+    pub fn new<S: JwsAlgorithm>(secret_key: &SecretKey<S>) -> Result<Self, JwtError> {
+        let alg = signature_algorithm::<S>()?;
+        Ok(Self::with_alg(alg, secret_key.as_bytes(), S::sign))
+    }
+
+    fn with_alg(alg: Algorithm, key: &[u8], sign: SignFn) -> Self {
+        Self {
+            alg,
+            kid: None,
+            key: Zeroizing::new(key.to_vec()),
+            sign,
+        }
+    }
+
+    /// Set the key ID, written to the `kid` header of signed tokens.
+    pub fn with_key_id(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.alg
+    }
+}
+
+impl std::fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKey")
+            .field("alg", &self.alg)
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A key for verifying tokens, bound to one algorithm.
+///
+/// Tokens are only checked against keys of the algorithm their header
+/// names, and a header naming a different algorithm than every candidate
+/// key is rejected outright. This prevents algorithm confusion, such as an
+/// HS256 token "signed" with a public key.
+#[derive(Clone)]
+pub struct VerifyingKey {
+    alg: Algorithm,
+    kid: Option<String>,
+    /// Cleared on drop, as for HS256 it is the shared secret.
+    key: Zeroizing<Vec<u8>>,
+    verify: VerifyFn,
+}
+
+impl VerifyingKey {
+    /// An HS256 key. The secret must be at least 32 bytes.
+    pub fn hs256<H: Hmac>(secret: &[u8]) -> Result<Self, JwtError> {
+        if secret.len() < MIN_HMAC_KEY_SIZE {
+            return Err(JwtError::InvalidKey);
+        }
+        Ok(Self::with_alg(Algorithm::Hs256, secret, hmac_verify::<H>))
+    }
+
+    /// A key for its scheme's algorithm: EdDSA for Ed25519, ES256 for
+    /// ECDSA P-256.
+    ///
+    /// Fails with [`JwtError::UnsupportedAlgorithm`] for other schemes.
+    pub fn new<S: JwsAlgorithm>(public_key: &PublicKey<S>) -> Result<Self, JwtError> {
+        let alg = signature_algorithm::<S>()?;
+        Ok(Self::with_alg(alg, public_key.as_bytes(), S::verify))
+    }
+
+    fn with_alg(alg: Algorithm, key: &[u8], verify: VerifyFn) -> Self {
+        Self {
+            alg,
+            kid: None,
+            key: Zeroizing::new(key.to_vec()),
+            verify,
+        }
+    }
+
+    /// Set the key ID. Tokens whose `kid` header names a different key are
+    /// not checked against this one.
+    pub fn with_key_id(mut self, kid: impl Into<String>) -> Self {
+        self.kid = Some(kid.into());
+        self
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.alg
+    }
+}
+
+impl std::fmt::Debug for VerifyingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerifyingKey")
+            .field("alg", &self.alg)
+            .field("kid", &self.kid)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Signs tokens with a single key.
+#[derive(Debug, Clone)]
+pub struct Signer {
+    key: SigningKey,
+}
+
+impl Signer {
+    pub fn new(key: SigningKey) -> Self {
+        Self { key }
+    }
+}
+
+impl JwtSigner for Signer {
+    fn algorithm(&self) -> Algorithm {
+        self.key.alg
+    }
+
+    fn sign(&self, claims: &Claims) -> Result<String, JwtError> {
+        let mut header = Header::new(self.key.alg);
+        header.kid = self.key.kid.clone();
+
+        let mut token = encode_json(&header_to_json(&header));
+        token.push('.');
+        token.push_str(&encode_json(&claims_to_json(claims)));
+
+        let signature = (self.key.sign)(&self.key.key, token.as_bytes())
+            .map_err(|e| JwtError::Other(e.to_string()))?;
+        token.push('.');
+        token.push_str(&StdBase64Url::encode(&signature));
+        Ok(token)
+    }
+}
+
+/// Verifies tokens against a set of keys and validates their claims
+/// against an injected wall clock.
+#[derive(Debug, Clone)]
+pub struct Verifier<C> {
+    clock: C,
+    validation: Validation,
+    keys: Vec<VerifyingKey>,
+}
+
+impl<C: WallClock> Verifier<C> {
+    pub fn new(clock: C, validation: Validation) -> Self {
+        Self {
+            clock,
+            validation,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Add a key tokens may be signed with.
+    pub fn with_key(mut self, key: VerifyingKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Get the validation rules.
+    pub fn validation(&self) -> &Validation {
+        &self.validation
+    }
+
+    fn verify_signature(
+        &self,
+        header: &Header,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), JwtError> {
+        let candidates: Vec<&VerifyingKey> = self
+            .keys
+            .iter()
+            .filter(|key| match (&header.kid, &key.kid) {
+                (Some(wanted), Some(kid)) => wanted == kid,
+                _ => true,
+            })
+            .collect();
+
+        let Some(first) = candidates.first() else {
+            return Err(JwtError::UnknownKey(header.kid.clone()));
+        };
+        if !candidates.iter().any(|key| key.alg == header.alg) {
+            return Err(JwtError::AlgorithmMismatch {
+                expected: first.alg,
+                found: header.alg,
+            });
+        }
+
+        for key in candidates.into_iter().filter(|key| key.alg == header.alg) {
+            match (key.verify)(&key.key, message, signature) {
+                Ok(true) => return Ok(()),
+                Ok(false) | Err(CryptoError::InvalidSignature) => {}
+                Err(e) => return Err(JwtError::Other(e.to_string())),
+            }
+        }
+        Err(JwtError::InvalidSignature)
+    }
+
+    fn validate(&self, claims: &Claims) -> Result<(), JwtError> {
+        let (now, _) = self.clock.now();
+        let validation = &self.validation;
+        let leeway = validation.leeway;
+
+        match claims.exp {
+            Some(exp) if now >= exp.saturating_add(leeway) => return Err(JwtError::Expired),
+            None if validation.require_exp => return Err(JwtError::MissingClaim("exp")),
+            _ => {}
+        }
+        if claims
+            .nbf
+            .is_some_and(|nbf| now.saturating_add(leeway) < nbf)
+        {
+            return Err(JwtError::NotYetValid);
+        }
+        if claims
+            .iat
+            .is_some_and(|iat| now.saturating_add(leeway) < iat)
+        {
+            return Err(JwtError::IssuedInFuture);
+        }
+
+        if let Some(expected) = &validation.issuer {
+            match &claims.iss {
+                None => return Err(JwtError::MissingClaim("iss")),
+                Some(iss) if iss != expected => return Err(JwtError::InvalidIssuer),
+                Some(_) => {}
+            }
+        }
+
+        if validation.audience.is_empty() {
+            if !claims.aud.is_empty() {
+                return Err(JwtError::InvalidAudience);
+            }
+        } else if claims.aud.is_empty() {
+            return Err(JwtError::MissingClaim("aud"));
+        } else if !claims
+            .aud
+            .iter()
+            .any(|aud| validation.audience.contains(aud))
+        {
+            return Err(JwtError::InvalidAudience);
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: WallClock> JwtVerifier for Verifier<C> {
+    fn verify(&self, token: &str) -> Result<Token, JwtError> {
+        let mut parts = token.split('.');
+        let (Some(header_b64), Some(claims_b64), Some(signature_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(JwtError::Malformed("expected three segments".to_string()));
+        };
+
+        let header = header_from_json(decode_json(header_b64, "header")?)?;
+        let signature = decode_segment(signature_b64, "signature")?;
+        let signed_len = header_b64.len() + 1 + claims_b64.len();
+        self.verify_signature(&header, &token.as_bytes()[..signed_len], &signature)?;
+
+        let claims = claims_from_json(decode_json(claims_b64, "claims")?)?;
+        self.validate(&claims)?;
+
+        Ok(Token { header, claims })
+    }
+}
+
+fn encode_json(value: &Map<String, Value>) -> String {
+    let json = serde_json::to_vec(value).expect("JSON maps always serialize");
+    StdBase64Url::encode(&json)
+}
+
+fn decode_segment(segment: &str, what: &str) -> Result<Vec<u8>, JwtError> {
+    StdBase64Url::decode(segment)
+        .map_err(|_| JwtError::Malformed(format!("invalid base64url in {}", what)))
+}
+
+fn decode_json(segment: &str, what: &str) -> Result<Map<String, Value>, JwtError> {
+    match serde_json::from_slice(&decode_segment(segment, what)?) {
+        Ok(Value::Object(map)) => Ok(map),
+        _ => Err(JwtError::Malformed(format!(
+            "{} is not a JSON object",
+            what
+        ))),
+    }
+}
+
+fn header_to_json(header: &Header) -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert("alg".to_string(), header.alg.as_str().into());
+    if let Some(typ) = &header.typ {
+        map.insert("typ".to_string(), typ.as_str().into());
+    }
+    if let Some(kid) = &header.kid {
+        map.insert("kid".to_string(), kid.as_str().into());
+    }
+    map
+}
+
+fn header_from_json(map: Map<String, Value>) -> Result<Header, JwtError> {
+    // No extensions are understood, so any critical one is fatal
+    // (RFC 7515 section 4.1.11).
+    if map.contains_key("crit") {
+        return Err(JwtError::Malformed(
+            "unsupported critical header".to_string(),
+        ));
+    }
+    let alg = match map.get("alg") {
+        Some(Value::String(alg)) => alg.parse()?,
+        Some(_) => return Err(JwtError::Malformed("\"alg\" is not a string".to_string())),
+        None => return Err(JwtError::Malformed("missing \"alg\" header".to_string())),
+    };
+    Ok(Header {
+        alg,
+        typ: string_field(&map, "typ")?,
+        kid: string_field(&map, "kid")?,
+    })
+}
+
+fn claims_to_json(claims: &Claims) -> Map<String, Value> {
+    let mut map = claims.custom.clone();
+    let strings = [
+        ("iss", &claims.iss),
+        ("sub", &claims.sub),
+        ("jti", &claims.jti),
+    ];
+    for (name, value) in strings {
+        if let Some(value) = value {
+            map.insert(name.to_string(), value.as_str().into());
+        }
+    }
+    match claims.aud.as_slice() {
+        [] => {}
+        [aud] => {
+            map.insert("aud".to_string(), aud.as_str().into());
+        }
+        auds => {
+            map.insert("aud".to_string(), auds.into());
+        }
+    }
+    let times = [
+        ("exp", claims.exp),
+        ("nbf", claims.nbf),
+        ("iat", claims.iat),
+    ];
+    for (name, value) in times {
+        if let Some(value) = value {
+            map.insert(name.to_string(), value.into());
+        }
+    }
+    map
+}
+
+fn claims_from_json(mut map: Map<String, Value>) -> Result<Claims, JwtError> {
+    let aud = match map.remove("aud") {
+        None => Vec::new(),
+        Some(Value::String(aud)) => vec![aud],
+        Some(Value::Array(auds)) => auds
+            .into_iter()
+            .map(|aud| match aud {
+                Value::String(aud) => Ok(aud),
+                _ => Err(invalid_claim("aud")),
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => return Err(invalid_claim("aud")),
+    };
+    Ok(Claims {
+        iss: string_field(&map, "iss")?,
+        sub: string_field(&map, "sub")?,
+        jti: string_field(&map, "jti")?,
+        aud,
+        exp: numeric_date(&map, "exp")?,
+        nbf: numeric_date(&map, "nbf")?,
+        iat: numeric_date(&map, "iat")?,
+        custom: {
+            for name in ["iss", "sub", "jti", "exp", "nbf", "iat"] {
+                map.remove(name);
+            }
+            map
+        },
+    })
+}
+
+fn invalid_claim(name: &str) -> JwtError {
+    JwtError::Malformed(format!("invalid {:?} claim", name))
+}
+
+fn string_field(map: &Map<String, Value>, name: &str) -> Result<Option<String>, JwtError> {
+    match map.get(name) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(invalid_claim(name)),
+    }
+}
+
+/// A NumericDate: seconds since the epoch, possibly fractional.
+fn numeric_date(map: &Map<String, Value>, name: &str) -> Result<Option<u64>, JwtError> {
+    match map.get(name) {
+        None => Ok(None),
+        Some(Value::Number(n)) => match (n.as_u64(), n.as_f64()) {
+            (Some(secs), _) => Ok(Some(secs)),
+            (None, Some(secs)) if secs >= 0.0 && secs.is_finite() => Ok(Some(secs as u64)),
+            _ => Err(invalid_claim(name)),
+        },
+        Some(_) => Err(invalid_claim(name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_clocks_mock::MockWallClock;
+    use portals_crypto::Signature;
+    use portals_crypto_native::{EcdsaP256, EcdsaSecp256k1, Ed25519, HmacSha256};
+
+    const SECRET: [u8; 32] = *b"0123456789abcdef0123456789abcdef";
+    const NOW: u64 = 1_700_000_000;
+
+    fn hs256_verifier(validation: Validation) -> Verifier<MockWallClock> {
+        Verifier::new(MockWallClock::new(NOW, 0), validation)
+            .with_key(VerifyingKey::hs256::<HmacSha256>(&SECRET).unwrap())
+    }
+
+    fn hs256_token(claims: &Claims) -> String {
+        Signer::new(SigningKey::hs256::<HmacSha256>(&SECRET).unwrap())
+            .sign(claims)
+            .unwrap()
+    }
+
+    /// Build a token with an arbitrary header, signed with HMAC-SHA256.
+    fn forge(header: &str, claims: &str, key: &[u8]) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            StdBase64Url::encode(header.as_bytes()),
+            StdBase64Url::encode(claims.as_bytes())
+        );
+        let mac = hmac_sign::<HmacSha256>(key, signing_input.as_bytes()).unwrap();
+        format!("{}.{}", signing_input, StdBase64Url::encode(&mac))
+    }
+
+    #[test]
+    fn hs256_rfc7515_example() {
+        // RFC 7515 appendix A.1.
+        let key = StdBase64Url::decode(
+            "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+        )
+        .unwrap();
+        let token = "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9\
+            .eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ\
+            .dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+        let verifier = Verifier::new(
+            MockWallClock::new(1_300_819_000, 0),
+            Validation::new().issuer("joe"),
+        )
+        .with_key(VerifyingKey::hs256::<HmacSha256>(&key).unwrap());
+        let verified = verifier.verify(token).unwrap();
+        assert_eq!(verified.header.alg, Algorithm::Hs256);
+        assert_eq!(verified.claims.exp, Some(1_300_819_380));
+        assert_eq!(
+            verified.claims.get("http://example.com/is_root"),
+            Some(&Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn roundtrip_all_algorithms() {
+        let claims = Claims::new()
+            .issuer("portals")
+            .subject("alice")
+            .audience("api")
+            .issued_at(NOW)
+            .expires_at(NOW + 300)
+            .claim("admin", true);
+        let validation = Validation::new().issuer("portals").audience("api");
+
+        let ed = Ed25519::generate_keypair();
+        let es = EcdsaP256::generate_keypair();
+        let pairs = [
+            (
+                SigningKey::hs256::<HmacSha256>(&SECRET).unwrap(),
+                VerifyingKey::hs256::<HmacSha256>(&SECRET).unwrap(),
+            ),
+            (
+                SigningKey::new(ed.secret()).unwrap(),
+                VerifyingKey::new(ed.public()).unwrap(),
+            ),
+            (
+                SigningKey::new(es.secret()).unwrap(),
+                VerifyingKey::new(es.public()).unwrap(),
+            ),
+        ];
+
+        for (signing, verifying) in pairs {
+            let alg = signing.algorithm();
+            let token = Signer::new(signing).sign(&claims).unwrap();
+            let verifier =
+                Verifier::new(MockWallClock::new(NOW, 0), validation.clone()).with_key(verifying);
+            let verified = verifier.verify(&token).unwrap();
+            assert_eq!(verified.header.alg, alg);
+            assert_eq!(verified.claims, claims);
+
+            // Flip a bit in the signature.
+            let mut tampered = token.into_bytes();
+            let last = tampered.len() - 2;
+            tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+            let tampered = String::from_utf8(tampered).unwrap();
+            assert!(matches!(
+                verifier.verify(&tampered),
+                Err(JwtError::InvalidSignature)
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_alg_none() {
+        let verifier = hs256_verifier(Validation::new().require_exp(false));
+        for alg in ["none", "None", "NONE", "hs256"] {
+            let header = StdBase64Url::encode(format!(r#"{{"alg":"{}"}}"#, alg).as_bytes());
+            let claims = StdBase64Url::encode(br#"{"sub":"admin"}"#);
+            let token = format!("{}.{}.", header, claims);
+            assert!(
+                matches!(verifier.verify(&token), Err(JwtError::UnsupportedAlgorithm(a)) if a == alg),
+                "{}",
+                alg
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_algorithm_confusion() {
+        // An attacker signs an HS256 token using the verifier's public key
+        // as the HMAC secret.
+        let es = EcdsaP256::generate_keypair();
+        let verifier = Verifier::new(
+            MockWallClock::new(NOW, 0),
+            Validation::new().require_exp(false),
+        )
+        .with_key(VerifyingKey::new(es.public()).unwrap());
+        let token = forge(
+            r#"{"alg":"HS256"}"#,
+            r#"{"sub":"admin"}"#,
+            es.public().as_bytes(),
+        );
+        assert!(matches!(
+            verifier.verify(&token),
+            Err(JwtError::AlgorithmMismatch {
+                expected: Algorithm::Es256,
+                found: Algorithm::Hs256
+            })
+        ));
+
+        // An HS256 verifier does not accept EdDSA tokens either.
+        let ed = Ed25519::generate_keypair();
+        let token = Signer::new(SigningKey::new(ed.secret()).unwrap())
+            .sign(&Claims::new())
+            .unwrap();
+        let verifier = hs256_verifier(Validation::new().require_exp(false));
+        assert!(matches!(
+            verifier.verify(&token),
+            Err(JwtError::AlgorithmMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn keys_take_their_algorithm_from_the_scheme() {
+        let ed = Ed25519::generate_keypair();
+        let es = EcdsaP256::generate_keypair();
+        assert_eq!(
+            SigningKey::new(ed.secret()).unwrap().algorithm(),
+            Algorithm::EdDsa
+        );
+        assert_eq!(
+            VerifyingKey::new(es.public()).unwrap().algorithm(),
+            Algorithm::Es256
+        );
+
+        // Schemes with a JWS name that this crate doesn't implement.
+        let k = EcdsaSecp256k1::generate_keypair();
+        assert!(matches!(
+            SigningKey::new(k.secret()),
+            Err(JwtError::UnsupportedAlgorithm(alg)) if alg == "ES256K"
+        ));
+        assert!(VerifyingKey::new(k.public()).is_err());
+    }
+
+    #[test]
+    fn expiry_with_leeway() {
+        let verifier = hs256_verifier(Validation::new().leeway(30));
+        let token = |exp| hs256_token(&Claims::new().expires_at(exp));
+
+        assert!(verifier.verify(&token(NOW + 1)).is_ok());
+        assert!(verifier.verify(&token(NOW - 29)).is_ok());
+        assert!(matches!(
+            verifier.verify(&token(NOW - 30)),
+            Err(JwtError::Expired)
+        ));
+        assert!(matches!(
+            verifier.verify(&hs256_token(&Claims::new())),
+            Err(JwtError::MissingClaim("exp"))
+        ));
+
+        let lenient = hs256_verifier(Validation::new().require_exp(false));
+        assert!(lenient.verify(&hs256_token(&Claims::new())).is_ok());
+    }
+
+    #[test]
+    fn not_before_and_issued_at() {
+        let verifier = hs256_verifier(Validation::new().leeway(30).require_exp(false));
+
+        assert!(
+            verifier
+                .verify(&hs256_token(&Claims::new().not_before(NOW + 30)))
+                .is_ok()
+        );
+        assert!(matches!(
+            verifier.verify(&hs256_token(&Claims::new().not_before(NOW + 31))),
+            Err(JwtError::NotYetValid)
+        ));
+        assert!(
+            verifier
+                .verify(&hs256_token(&Claims::new().issued_at(NOW + 30)))
+                .is_ok()
+        );
+        assert!(matches!(
+            verifier.verify(&hs256_token(&Claims::new().issued_at(NOW + 31))),
+            Err(JwtError::IssuedInFuture)
+        ));
+    }
+
+    #[test]
+    fn clock_is_injected() {
+        let clock = MockWallClock::new(NOW, 0);
+        let verifier = Verifier::new(clock.clone(), Validation::new().leeway(0))
+            .with_key(VerifyingKey::hs256::<HmacSha256>(&SECRET).unwrap());
+        let token = hs256_token(&Claims::new().expires_at(NOW + 60));
+
+        assert!(verifier.verify(&token).is_ok());
+        clock.advance(std::time::Duration::from_secs(60));
+        assert!(matches!(verifier.verify(&token), Err(JwtError::Expired)));
+    }
+
+    #[test]
+    fn issuer_and_audience() {
+        let validation = Validation::new()
+            .require_exp(false)
+            .issuer("portals")
+            .audience("api");
+        let verifier = hs256_verifier(validation);
+
+        let ok = Claims::new()
+            .issuer("portals")
+            .audience("web")
+            .audience("api");
+        assert!(verifier.verify(&hs256_token(&ok)).is_ok());
+
+        let wrong_issuer = Claims::new().issuer("evil").audience("api");
+        assert!(matches!(
+            verifier.verify(&hs256_token(&wrong_issuer)),
+            Err(JwtError::InvalidIssuer)
+        ));
+
+        let no_issuer = Claims::new().audience("api");
+        assert!(matches!(
+            verifier.verify(&hs256_token(&no_issuer)),
+            Err(JwtError::MissingClaim("iss"))
+        ));
+
+        let wrong_audience = Claims::new().issuer("portals").audience("web");
+        assert!(matches!(
+            verifier.verify(&hs256_token(&wrong_audience)),
+            Err(JwtError::InvalidAudience)
+        ));
+
+        // A verifier without an audience rejects tokens meant for someone.
+        let verifier = hs256_verifier(Validation::new().require_exp(false));
+        assert!(matches!(
+            verifier.verify(&hs256_token(&Claims::new().audience("api"))),
+            Err(JwtError::InvalidAudience)
+        ));
+    }
+
+    #[test]
+    fn selects_key_by_id() {
+        let other = [9u8; 32];
+        let verifier = Verifier::new(
+            MockWallClock::new(NOW, 0),
+            Validation::new().require_exp(false),
+        )
+        .with_key(
+            VerifyingKey::hs256::<HmacSha256>(&SECRET)
+                .unwrap()
+                .with_key_id("a"),
+        )
+        .with_key(
+            VerifyingKey::hs256::<HmacSha256>(&other)
+                .unwrap()
+                .with_key_id("b"),
+        );
+
+        let sign = |key: &[u8], kid: &str| {
+            Signer::new(
+                SigningKey::hs256::<HmacSha256>(key)
+                    .unwrap()
+                    .with_key_id(kid),
+            )
+            .sign(&Claims::new())
+            .unwrap()
+        };
+
+        let verified = verifier.verify(&sign(&other, "b")).unwrap();
+        assert_eq!(verified.header.kid.as_deref(), Some("b"));
+        assert!(matches!(
+            verifier.verify(&sign(&other, "a")),
+            Err(JwtError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verifier.verify(&sign(&SECRET, "c")),
+            Err(JwtError::UnknownKey(Some(kid))) if kid == "c"
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        let verifier = hs256_verifier(Validation::new().require_exp(false));
+        let valid = hs256_token(&Claims::new());
+
+        for token in ["", "a.b", "a.b.c.d", "!!.e30.", &format!("{}.extra", valid)] {
+            assert!(
+                matches!(verifier.verify(token), Err(JwtError::Malformed(_))),
+                "{:?}",
+                token
+            );
+        }
+
+        let crit = forge(r#"{"alg":"HS256","crit":["exp"]}"#, "{}", &SECRET);
+        assert!(matches!(
+            verifier.verify(&crit),
+            Err(JwtError::Malformed(_))
+        ));
+
+        let bad_exp = forge(r#"{"alg":"HS256"}"#, r#"{"exp":"tomorrow"}"#, &SECRET);
+        assert!(matches!(
+            verifier.verify(&bad_exp),
+            Err(JwtError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_short_hmac_secrets() {
+        assert!(matches!(
+            SigningKey::hs256::<HmacSha256>(b"secret"),
+            Err(JwtError::InvalidKey)
+        ));
+        assert!(matches!(
+            VerifyingKey::hs256::<HmacSha256>(&[0; 31]),
+            Err(JwtError::InvalidKey)
+        ));
+    }
+}
@@ -0,0 +1,833 @@
+//! WASM implementation of portals-crypto.
+//!
+//! Uses the Web Crypto API (`crypto.subtle`), which is async-only, so the
+//! types here implement the async crypto traits. Algorithms SubtleCrypto
+//! lacks are pure Rust: [`ChaCha20Poly1305`] is a synchronous [`Cipher`] and
+//! so also an [`AsyncCipher`], and [`Ed25519`] falls back to `ed25519-dalek`
+//! in browsers without Ed25519 support.
+//!
+//! This covers a subset of the native backend. SHA-3, BLAKE2b, BLAKE3,
+//! HMAC-SHA1, secp256k1 and RSA signatures, key agreement, HKDF, password
+//! hashing, OTP and key encodings are not implemented here yet; see
+//! `docs/WASM.md`.
+
+use js_sys::{Array, Object, Promise, Reflect, Uint8Array};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use portals_crypto::{
+    AsyncCipher, AsyncHash, AsyncHmac, AsyncSignature, Cipher, CryptoError, KeyPair, KeyScheme,
+    PublicKey, SecretKey, Zeroizing,
+};
+use portals_encoding::Base64Url;
+use portals_encoding_portable::StdBase64Url;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Crypto, CryptoKey, SubtleCrypto};
+
+// ============================================================================
+// SubtleCrypto plumbing
+// ============================================================================
+
+/// Get `crypto.subtle` from the global scope (window or worker).
+fn subtle() -> Result<SubtleCrypto, CryptoError> {
+    let crypto = Reflect::get(&js_sys::global(), &JsValue::from_str("crypto"))
+        .ok()
+        .and_then(|crypto| crypto.dyn_into::<Crypto>().ok())
+        .ok_or_else(|| CryptoError::Other("Web Crypto API is not available".to_string()))?;
+    Ok(crypto.subtle())
+}
+
+/// Await a SubtleCrypto call.
+async fn call(promise: Result<Promise, JsValue>) -> Result<JsValue, JsValue> {
+    JsFuture::from(promise?).await
+}
+
+/// The `name` of a thrown DOMException, e.g. `NotSupportedError`.
+fn error_name(error: &JsValue) -> Option<String> {
+    Reflect::get(error, &JsValue::from_str("name"))
+        .ok()
+        .and_then(|name| name.as_string())
+}
+
+fn js_error(error: JsValue) -> CryptoError {
+    let message = Reflect::get(&error, &JsValue::from_str("message"))
+        .ok()
+        .and_then(|message| message.as_string())
+        .unwrap_or_default();
+    match error_name(&error) {
+        Some(name) => CryptoError::Other(format!("Web Crypto: {}: {}", name, message)),
+        None => CryptoError::Other(format!("Web Crypto: {:?}", error)),
+    }
+}
+
+/// Build a plain object, e.g. an algorithm description.
+fn object(fields: &[(&str, &JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in fields {
+        Reflect::set(&object, &JsValue::from_str(key), value)
+            .expect("plain objects accept new properties");
+    }
+    object
+}
+
+fn bytes(data: &[u8]) -> JsValue {
+    Uint8Array::from(data).into()
+}
+
+fn string(value: &str) -> JsValue {
+    JsValue::from_str(value)
+}
+
+/// Copy the contents of a returned `ArrayBuffer`.
+fn to_vec(buffer: &JsValue) -> Vec<u8> {
+    Uint8Array::new(buffer).to_vec()
+}
+
+fn usages(usages: &[&str]) -> JsValue {
+    usages
+        .iter()
+        .map(|usage| JsValue::from_str(usage))
+        .collect::<Array>()
+        .into()
+}
+
+async fn import_key(
+    subtle: &SubtleCrypto,
+    format: &str,
+    key_data: &Object,
+    algorithm: &Object,
+    usage: &str,
+) -> Result<CryptoKey, JsValue> {
+    let key =
+        call(subtle.import_key_with_object(format, key_data, algorithm, false, &usages(&[usage])))
+            .await?;
+    Ok(key.unchecked_into())
+}
+
+// ============================================================================
+// Hashing
+// ============================================================================
+
+async fn digest(algorithm: &str, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let subtle = subtle()?;
+    let hash = call(subtle.digest_with_str_and_u8_array(algorithm, data))
+        .await
+        .map_err(js_error)?;
+    Ok(to_vec(&hash))
+}
+
+/// SHA-256 hash.
+pub struct Sha256;
+
+impl AsyncHash for Sha256 {
+    const OUTPUT_SIZE: usize = 32;
+
+    async fn digest(data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        digest("SHA-256", data).await
+    }
+}
+
+/// SHA-384 hash.
+pub struct Sha384;
+
+impl AsyncHash for Sha384 {
+    const OUTPUT_SIZE: usize = 48;
+
+    async fn digest(data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        digest("SHA-384", data).await
+    }
+}
+
+/// SHA-512 hash.
+pub struct Sha512;
+
+impl AsyncHash for Sha512 {
+    const OUTPUT_SIZE: usize = 64;
+
+    async fn digest(data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        digest("SHA-512", data).await
+    }
+}
+
+// ============================================================================
+// HMAC
+// ============================================================================
+
+async fn hmac_key(
+    subtle: &SubtleCrypto,
+    hash: &str,
+    key: &[u8],
+    usage: &str,
+) -> Result<CryptoKey, CryptoError> {
+    // Web Crypto rejects empty HMAC keys. HMAC pads keys with zeros to the
+    // block size, so a single zero byte is the same key.
+    let key = if key.is_empty() { &[0u8][..] } else { key };
+    let algorithm = object(&[("name", &string("HMAC")), ("hash", &string(hash))]);
+    import_key(subtle, "raw", &Uint8Array::from(key), &algorithm, usage)
+        .await
+        .map_err(js_error)
+}
+
+async fn hmac_sign(hash: &str, key: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let subtle = subtle()?;
+    let key = hmac_key(&subtle, hash, key, "sign").await?;
+    let tag = call(subtle.sign_with_str_and_u8_array("HMAC", &key, data))
+        .await
+        .map_err(js_error)?;
+    Ok(to_vec(&tag))
+}
+
+async fn hmac_verify(hash: &str, key: &[u8], data: &[u8], tag: &[u8]) -> Result<bool, CryptoError> {
+    let subtle = subtle()?;
+    let key = hmac_key(&subtle, hash, key, "verify").await?;
+    let valid = call(subtle.verify_with_str_and_u8_array_and_u8_array("HMAC", &key, tag, data))
+        .await
+        .map_err(js_error)?;
+    Ok(valid.as_bool().unwrap_or(false))
+}
+
+/// HMAC-SHA256.
+pub struct HmacSha256;
+
+impl AsyncHmac for HmacSha256 {
+    async fn sign(key: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        hmac_sign("SHA-256", key, data).await
+    }
+
+    async fn verify(key: &[u8], data: &[u8], tag: &[u8]) -> Result<bool, CryptoError> {
+        hmac_verify("SHA-256", key, data, tag).await
+    }
+}
+
+/// HMAC-SHA512.
+pub struct HmacSha512;
+
+impl AsyncHmac for HmacSha512 {
+    async fn sign(key: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        hmac_sign("SHA-512", key, data).await
+    }
+
+    async fn verify(key: &[u8], data: &[u8], tag: &[u8]) -> Result<bool, CryptoError> {
+        hmac_verify("SHA-512", key, data, tag).await
+    }
+}
+
+// ============================================================================
+// Encryption
+// ============================================================================
+
+/// AES-256-GCM.
+pub struct Aes256Gcm;
+
+impl Aes256Gcm {
+    async fn key(subtle: &SubtleCrypto, key: &[u8], usage: &str) -> Result<CryptoKey, CryptoError> {
+        if key.len() != <Self as AsyncCipher>::KEY_SIZE {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        let algorithm = object(&[("name", &string("AES-GCM"))]);
+        import_key(subtle, "raw", &Uint8Array::from(key), &algorithm, usage)
+            .await
+            .map_err(js_error)
+    }
+
+    fn params(nonce: &[u8], aad: &[u8]) -> Result<Object, CryptoError> {
+        if nonce.len() != <Self as AsyncCipher>::NONCE_SIZE {
+            return Err(CryptoError::InvalidNonceSize);
+        }
+        Ok(object(&[
+            ("name", &string("AES-GCM")),
+            ("iv", &bytes(nonce)),
+            ("additionalData", &bytes(aad)),
+            ("tagLength", &JsValue::from(128)),
+        ]))
+    }
+}
+
+impl AsyncCipher for Aes256Gcm {
+    const KEY_SIZE: usize = 32;
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    async fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let params = Self::params(nonce, aad)?;
+        let subtle = subtle()?;
+        let key = Self::key(&subtle, key, "encrypt").await?;
+        let ciphertext = call(subtle.encrypt_with_object_and_u8_array(&params, &key, plaintext))
+            .await
+            .map_err(js_error)?;
+        Ok(to_vec(&ciphertext))
+    }
+
+    async fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let params = Self::params(nonce, aad)?;
+        let subtle = subtle()?;
+        let key = Self::key(&subtle, key, "decrypt").await?;
+        let plaintext = call(subtle.decrypt_with_object_and_u8_array(&params, &key, ciphertext))
+            .await
+            .map_err(|_| CryptoError::AuthenticationFailed)?;
+        Ok(to_vec(&plaintext))
+    }
+}
+
+/// ChaCha20-Poly1305, in pure Rust; SubtleCrypto does not offer it.
+pub struct ChaCha20Poly1305;
+
+impl Cipher for ChaCha20Poly1305 {
+    const KEY_SIZE: usize = 32;
+    const NONCE_SIZE: usize = 12;
+    const TAG_SIZE: usize = 16;
+
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use chacha20poly1305::{
+            ChaCha20Poly1305 as ChaCha, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
+
+        if key.len() != <Self as Cipher>::KEY_SIZE {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        if nonce.len() != <Self as Cipher>::NONCE_SIZE {
+            return Err(CryptoError::InvalidNonceSize);
+        }
+
+        let cipher = ChaCha::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
+        cipher
+            .encrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|_| CryptoError::AuthenticationFailed)
+    }
+
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        use chacha20poly1305::{
+            ChaCha20Poly1305 as ChaCha, KeyInit, Nonce,
+            aead::{Aead, Payload},
+        };
+
+        if key.len() != <Self as Cipher>::KEY_SIZE {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        if nonce.len() != <Self as Cipher>::NONCE_SIZE {
+            return Err(CryptoError::InvalidNonceSize);
+        }
+
+        let cipher = ChaCha::new_from_slice(key).map_err(|_| CryptoError::InvalidKeySize)?;
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad,
+                },
+            )
+            .map_err(|_| CryptoError::AuthenticationFailed)
+    }
+}
+
+// ============================================================================
+// Signatures
+// ============================================================================
+
+/// Check that key bytes have the expected length.
+fn check_size(bytes: &[u8], size: usize) -> Result<(), CryptoError> {
+    if bytes.len() == size {
+        Ok(())
+    } else {
+        Err(CryptoError::InvalidKeySize)
+    }
+}
+
+/// Ed25519 signatures.
+///
+/// Keys are encoded as by the native backend: 32-byte public keys and
+/// 32-byte seeds. Uses SubtleCrypto where the browser supports Ed25519 and
+/// `ed25519-dalek` otherwise.
+pub struct Ed25519;
+
+impl Ed25519 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 32;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+
+    /// The signature size in bytes.
+    pub const SIGNATURE_SIZE: usize = 64;
+
+    /// PKCS#8 `PrivateKeyInfo` for an Ed25519 seed, minus the seed.
+    const PKCS8_PREFIX: [u8; 16] = [
+        0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04,
+        0x20,
+    ];
+
+    /// Import a key, or `None` if the browser does not support Ed25519.
+    async fn key(
+        format: &str,
+        key_data: &[u8],
+        usage: &str,
+    ) -> Result<Option<(SubtleCrypto, CryptoKey)>, CryptoError> {
+        let subtle = subtle()?;
+        let algorithm = object(&[("name", &string("Ed25519"))]);
+        match import_key(
+            &subtle,
+            format,
+            &Uint8Array::from(key_data),
+            &algorithm,
+            usage,
+        )
+        .await
+        {
+            Ok(key) => Ok(Some((subtle, key))),
+            Err(error) if error_name(&error).as_deref() == Some("NotSupportedError") => Ok(None),
+            Err(error) => Err(js_error(error)),
+        }
+    }
+
+    /// Generate a keypair in Rust; SubtleCrypto keys cannot be exported as
+    /// seeds everywhere.
+    fn keypair() -> Result<KeyPair<Self>, CryptoError> {
+        use ed25519_dalek::SigningKey;
+
+        let mut seed = Zeroizing::new([0u8; 32]);
+        getrandom::fill(seed.as_mut_slice()).map_err(|e| CryptoError::Other(e.to_string()))?;
+        let signing_key = SigningKey::from_bytes(&seed);
+
+        Ok(KeyPair::new(
+            PublicKey::from_bytes(signing_key.verifying_key().as_bytes())?,
+            SecretKey::from_bytes(seed.as_slice())?,
+        ))
+    }
+
+    /// Sign with `ed25519-dalek`, for browsers without Ed25519.
+    fn fallback_sign(seed: &[u8; 32], message: &[u8]) -> Vec<u8> {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        SigningKey::from_bytes(seed)
+            .sign(message)
+            .to_bytes()
+            .to_vec()
+    }
+
+    /// Verify with `ed25519-dalek`, for browsers without Ed25519.
+    fn fallback_verify(
+        public_key: &[u8; 32],
+        message: &[u8],
+        signature: &[u8; 64],
+    ) -> Result<bool, CryptoError> {
+        use ed25519_dalek::{Signature as EdSig, Verifier, VerifyingKey};
+
+        let verifying_key =
+            VerifyingKey::from_bytes(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(verifying_key
+            .verify(message, &EdSig::from_bytes(signature))
+            .is_ok())
+    }
+}
+
+impl KeyScheme for Ed25519 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)
+    }
+}
+
+impl AsyncSignature for Ed25519 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        Self::check_public_key(public_key)?;
+        Ok(Self::SIGNATURE_SIZE)
+    }
+
+    async fn generate_keypair() -> Result<KeyPair<Self>, CryptoError> {
+        Self::keypair()
+    }
+
+    async fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let seed: [u8; 32] = secret_key
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+
+        let mut pkcs8 = Zeroizing::new(Self::PKCS8_PREFIX.to_vec());
+        pkcs8.extend_from_slice(&seed);
+
+        match Self::key("pkcs8", &pkcs8, "sign").await? {
+            Some((subtle, key)) => {
+                let signature = call(subtle.sign_with_str_and_u8_array("Ed25519", &key, message))
+                    .await
+                    .map_err(js_error)?;
+                Ok(to_vec(&signature))
+            }
+            None => Ok(Self::fallback_sign(&seed, message)),
+        }
+    }
+
+    async fn verify(
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, CryptoError> {
+        let public_bytes: [u8; 32] = public_key
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeySize)?;
+        let sig_bytes: [u8; 64] = signature
+            .try_into()
+            .map_err(|_| CryptoError::InvalidSignature)?;
+
+        match Self::key("raw", &public_bytes, "verify").await? {
+            Some((subtle, key)) => {
+                let valid = call(subtle.verify_with_str_and_u8_array_and_u8_array(
+                    "Ed25519", &key, &sig_bytes, message,
+                ))
+                .await
+                .map_err(js_error)?;
+                Ok(valid.as_bool().unwrap_or(false))
+            }
+            None => Self::fallback_verify(&public_bytes, message, &sig_bytes),
+        }
+    }
+}
+
+/// ECDSA over NIST P-256 with SHA-256 (ES256).
+///
+/// Keys and signatures are encoded as by the native backend: SEC1
+/// uncompressed public keys (65 bytes), 32-byte secret scalars and `r || s`
+/// signatures (64 bytes). Unlike the native backend, signing is randomized.
+pub struct EcdsaP256;
+
+impl EcdsaP256 {
+    /// The public key size in bytes.
+    pub const PUBLIC_KEY_SIZE: usize = 65;
+
+    /// The secret key size in bytes.
+    pub const SECRET_KEY_SIZE: usize = 32;
+
+    /// The signature size in bytes.
+    pub const SIGNATURE_SIZE: usize = 64;
+
+    fn algorithm() -> Object {
+        object(&[("name", &string("ECDSA")), ("namedCurve", &string("P-256"))])
+    }
+
+    fn params() -> Object {
+        object(&[("name", &string("ECDSA")), ("hash", &string("SHA-256"))])
+    }
+
+    /// Import a secret scalar as a JWK, which needs the public point too.
+    async fn secret_key(
+        subtle: &SubtleCrypto,
+        secret_key: &[u8],
+    ) -> Result<CryptoKey, CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)?;
+        let secret =
+            p256::SecretKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        let point = secret.public_key().to_encoded_point(false);
+        let (x, y) = match (point.x(), point.y()) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(CryptoError::InvalidKeySize),
+        };
+
+        let d = Zeroizing::new(StdBase64Url::encode(secret_key));
+        let jwk = object(&[
+            ("kty", &string("EC")),
+            ("crv", &string("P-256")),
+            ("x", &string(&StdBase64Url::encode(x))),
+            ("y", &string(&StdBase64Url::encode(y))),
+            ("d", &string(&d)),
+        ]);
+        import_key(subtle, "jwk", &jwk, &Self::algorithm(), "sign")
+            .await
+            .map_err(js_error)
+    }
+
+    /// Generate a keypair in Rust, in the native backend's encodings.
+    fn keypair() -> Result<KeyPair<Self>, CryptoError> {
+        // Rejection sampling; a random 32-byte string is out of range with
+        // probability about 2^-32.
+        loop {
+            let mut scalar = Zeroizing::new([0u8; 32]);
+            getrandom::fill(scalar.as_mut_slice())
+                .map_err(|e| CryptoError::Other(e.to_string()))?;
+            if let Ok(secret) = p256::SecretKey::from_slice(scalar.as_slice()) {
+                let public = secret.public_key().to_encoded_point(false);
+                return Ok(KeyPair::new(
+                    PublicKey::from_bytes(public.as_bytes())?,
+                    SecretKey::from_bytes(scalar.as_slice())?,
+                ));
+            }
+        }
+    }
+}
+
+impl KeyScheme for EcdsaP256 {
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(public_key, Self::PUBLIC_KEY_SIZE)?;
+        p256::PublicKey::from_sec1_bytes(public_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError> {
+        check_size(secret_key, Self::SECRET_KEY_SIZE)?;
+        p256::SecretKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidKeySize)?;
+        Ok(())
+    }
+}
+
+impl AsyncSignature for EcdsaP256 {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        Self::check_public_key(public_key)?;
+        Ok(Self::SIGNATURE_SIZE)
+    }
+
+    async fn generate_keypair() -> Result<KeyPair<Self>, CryptoError> {
+        Self::keypair()
+    }
+
+    async fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let subtle = subtle()?;
+        let key = Self::secret_key(&subtle, secret_key).await?;
+        let signature = call(subtle.sign_with_object_and_u8_array(&Self::params(), &key, message))
+            .await
+            .map_err(js_error)?;
+        Ok(to_vec(&signature))
+    }
+
+    async fn verify(
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, CryptoError> {
+        Self::check_public_key(public_key)?;
+        if signature.len() != Self::SIGNATURE_SIZE {
+            return Err(CryptoError::InvalidSignature);
+        }
+
+        let subtle = subtle()?;
+        let key = import_key(
+            &subtle,
+            "raw",
+            &Uint8Array::from(public_key),
+            &Self::algorithm(),
+            "verify",
+        )
+        .await
+        .map_err(js_error)?;
+        let valid = call(subtle.verify_with_object_and_u8_array_and_u8_array(
+            &Self::params(),
+            &key,
+            signature,
+            message,
+        ))
+        .await
+        .map_err(js_error)?;
+        Ok(valid.as_bool().unwrap_or(false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_testing::unhex;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn sha256_matches_known_answer() {
+        assert_eq!(
+            Sha256::digest(b"abc").await.unwrap(),
+            unhex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn hmac_sha256_matches_rfc_4231() {
+        let tag = HmacSha256::sign(b"Jefe", b"what do ya want for nothing?")
+            .await
+            .unwrap();
+        assert_eq!(
+            tag,
+            unhex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert!(
+            HmacSha256::verify(b"Jefe", b"what do ya want for nothing?", &tag)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !HmacSha256::verify(b"Jeff", b"what do ya want for nothing?", &tag)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn hmac_accepts_empty_keys() {
+        let tag = HmacSha256::sign(b"", b"data").await.unwrap();
+        assert!(HmacSha256::verify(b"", b"data", &tag).await.unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    async fn aes_gcm_round_trips_and_authenticates() {
+        let key = [7u8; 32];
+        let nonce = [1u8; 12];
+        let sealed = Aes256Gcm::encrypt(&key, &nonce, b"secret", b"aad")
+            .await
+            .unwrap();
+        assert_eq!(sealed.len(), 6 + Aes256Gcm::TAG_SIZE);
+        assert_eq!(
+            Aes256Gcm::decrypt(&key, &nonce, &sealed, b"aad")
+                .await
+                .unwrap(),
+            b"secret"
+        );
+        assert!(matches!(
+            Aes256Gcm::decrypt(&key, &nonce, &sealed, b"other").await,
+            Err(CryptoError::AuthenticationFailed)
+        ));
+        assert!(matches!(
+            Aes256Gcm::encrypt(&key[..16], &nonce, b"", b"").await,
+            Err(CryptoError::InvalidKeySize)
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn chacha_falls_back_to_rust() {
+        let key = [7u8; 32];
+        let nonce = [1u8; 12];
+        let sealed = <ChaCha20Poly1305 as AsyncCipher>::encrypt(&key, &nonce, b"secret", b"")
+            .await
+            .unwrap();
+        let opened = <ChaCha20Poly1305 as AsyncCipher>::decrypt(&key, &nonce, &sealed, b"")
+            .await
+            .unwrap();
+        assert_eq!(opened, b"secret");
+    }
+
+    #[wasm_bindgen_test]
+    async fn ed25519_matches_rfc_8032() {
+        let secret = unhex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+        let public = unhex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let signature = Ed25519::sign(&secret, b"").await.unwrap();
+        assert_eq!(
+            signature,
+            unhex(
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+            )
+        );
+        assert!(Ed25519::verify(&public, b"", &signature).await.unwrap());
+        assert!(!Ed25519::verify(&public, b"x", &signature).await.unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    async fn ecdsa_p256_signs_and_verifies() {
+        let pair = EcdsaP256::generate_keypair().await.unwrap();
+        let public = pair.public().as_bytes();
+
+        let signature = EcdsaP256::sign(pair.secret().as_bytes(), b"message")
+            .await
+            .unwrap();
+        assert_eq!(signature.len(), EcdsaP256::SIGNATURE_SIZE);
+        assert!(
+            EcdsaP256::verify(public, b"message", &signature)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !EcdsaP256::verify(public, b"tampered", &signature)
+                .await
+                .unwrap()
+        );
+    }
+
+    // The pure-Rust paths need no browser, so they also run on the host.
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn chacha_matches_rfc_8439() {
+        let key = unhex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f");
+        let nonce = unhex("070000004041424344454647");
+        let aad = unhex("50515253c0c1c2c3c4c5c6c7");
+        let plaintext = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let sealed = <ChaCha20Poly1305 as Cipher>::encrypt(&key, &nonce, plaintext, &aad).unwrap();
+        assert_eq!(
+            sealed,
+            unhex(
+                "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691"
+            )
+        );
+        assert_eq!(
+            <ChaCha20Poly1305 as Cipher>::decrypt(&key, &nonce, &sealed, &aad).unwrap(),
+            plaintext
+        );
+        assert!(matches!(
+            <ChaCha20Poly1305 as Cipher>::decrypt(&key, &nonce, &sealed, b""),
+            Err(CryptoError::AuthenticationFailed)
+        ));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn ed25519_fallback_matches_rfc_8032() {
+        let secret: [u8; 32] =
+            unhex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                .try_into()
+                .unwrap();
+        let public: [u8; 32] =
+            unhex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                .try_into()
+                .unwrap();
+        let signature: [u8; 64] = Ed25519::fallback_sign(&secret, b"").try_into().unwrap();
+        assert_eq!(
+            signature.to_vec(),
+            unhex(
+                "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+            )
+        );
+        assert!(Ed25519::fallback_verify(&public, b"", &signature).unwrap());
+        assert!(!Ed25519::fallback_verify(&public, b"x", &signature).unwrap());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn generated_keypairs_match() {
+        let pair = Ed25519::keypair().unwrap();
+        let seed: [u8; 32] = pair.secret().as_bytes().try_into().unwrap();
+        let public: [u8; 32] = pair.public().as_bytes().try_into().unwrap();
+        let signature: [u8; 64] = Ed25519::fallback_sign(&seed, b"message")
+            .try_into()
+            .unwrap();
+        assert!(Ed25519::fallback_verify(&public, b"message", &signature).unwrap());
+
+        let pair = EcdsaP256::keypair().unwrap();
+        let secret = p256::SecretKey::from_slice(pair.secret().as_bytes()).unwrap();
+        assert_eq!(
+            secret.public_key().to_encoded_point(false).as_bytes(),
+            pair.public().as_bytes()
+        );
+    }
+}
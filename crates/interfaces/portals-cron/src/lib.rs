@@ -18,6 +18,10 @@ pub trait CronExpr: fmt::Display {
     /// - `day`: 1-31
     /// - `month`: 1-12
     /// - `weekday`: 0-6 (Sunday = 0)
+    ///
+    /// Implementations follow standard cron semantics unless documented
+    /// otherwise: when both the day-of-month and day-of-week fields are
+    /// restricted, a date matches if either of them does.
     fn matches(&self, second: u8, minute: u8, hour: u8, day: u8, month: u8, weekday: u8) -> bool;
 
     /// Get the original expression string.
@@ -99,6 +103,9 @@ pub trait CronParser {
     fn parse_with_seconds(&self, expr: &str) -> Result<Self::Expr, CronError>;
 }
 
+/// A civil datetime as `(year, month, day, hour, minute, second)`.
+pub type CronDateTime = (i32, u8, u8, u8, u8, u8);
+
 /// Iterator over upcoming cron occurrences.
 pub trait CronSchedule: CronExpr {
     /// Find the next occurrence after the given datetime.
@@ -113,5 +120,96 @@ pub trait CronSchedule: CronExpr {
         hour: u8,
         minute: u8,
         second: u8,
-    ) -> Option<(i32, u8, u8, u8, u8, u8)>;
+    ) -> Option<CronDateTime>;
+
+    /// Find the most recent occurrence before the given datetime.
+    ///
+    /// Returns `(year, month, day, hour, minute, second)` or `None` if no
+    /// occurrence exists within a reasonable search window.
+    ///
+    /// The default returns `None`, for schedules that can only search
+    /// forwards.
+    fn prev_before(
+        &self,
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Option<CronDateTime> {
+        let _ = (year, month, day, hour, minute, second);
+        None
+    }
+
+    /// Iterate over occurrences after the given datetime.
+    fn upcoming(&self, after: CronDateTime) -> Occurrences<'_, Self> {
+        Occurrences {
+            schedule: self,
+            cursor: Some(after),
+            end: None,
+        }
+    }
+
+    /// Iterate over occurrences after `start`, up to and including `end`.
+    fn occurrences_between(&self, start: CronDateTime, end: CronDateTime) -> Occurrences<'_, Self> {
+        Occurrences {
+            schedule: self,
+            cursor: Some(start),
+            end: Some(end),
+        }
+    }
+}
+
+/// Iterator over the occurrences of a [`CronSchedule`].
+///
+/// Returned by [`CronSchedule::upcoming`] and
+/// [`CronSchedule::occurrences_between`].
+#[derive(Debug)]
+pub struct Occurrences<'a, S: ?Sized> {
+    schedule: &'a S,
+    cursor: Option<CronDateTime>,
+    end: Option<CronDateTime>,
+}
+
+impl<S: CronSchedule + ?Sized> Iterator for Occurrences<'_, S> {
+    type Item = CronDateTime;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (year, month, day, hour, minute, second) = self.cursor?;
+        let next = self
+            .schedule
+            .next_after(year, month, day, hour, minute, second)
+            .filter(|next| self.end.is_none_or(|end| *next <= end));
+        self.cursor = next;
+        next
+    }
+}
+
+/// Timezone-aware cron scheduling.
+///
+/// Expressions are evaluated against local civil time in the given zone.
+/// Daylight saving transitions are handled like Vixie cron:
+///
+/// - **Skipped times** (spring-forward): fixed-time expressions (no `*` in
+///   the second, minute or hour fields) whose time falls in the gap fire
+///   once, at the end of the gap. Other expressions skip the missing times.
+/// - **Repeated times** (fall-back): fixed-time expressions fire once, on the
+///   first pass. Other expressions fire on both passes.
+#[cfg(feature = "timezone")]
+pub trait ZonedCronSchedule: CronSchedule {
+    /// Find the next occurrence strictly after the given zoned time.
+    ///
+    /// The result is in the same time zone as `after`, or `None` if no
+    /// occurrence exists within the search window.
+    fn next_after_zoned(&self, after: &portals_timezone::Zoned) -> Option<portals_timezone::Zoned>;
+
+    /// Find the next occurrence strictly after the given instant, in `tz`.
+    fn next_after_in(
+        &self,
+        after: portals_timezone::Timestamp,
+        tz: &portals_timezone::TimeZone,
+    ) -> Option<portals_timezone::Zoned> {
+        self.next_after_zoned(&after.to_zoned(tz.clone()))
+    }
 }
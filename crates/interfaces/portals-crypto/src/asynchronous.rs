@@ -0,0 +1,194 @@
+//! Async variants of the crypto traits.
+//!
+//! Some platforms only expose cryptography through async APIs, most notably
+//! the browser's SubtleCrypto. Code that should run there is written against
+//! these traits instead. Every synchronous implementation is also an async
+//! one, so pure-Rust backends fill in whatever the platform lacks.
+
+use crate::{Cipher, CryptoError, Hash, Hmac, KeyPair, KeyScheme, Signature};
+use std::future::{Future, ready};
+
+/// A cryptographic hash function with an async one-shot API.
+pub trait AsyncHash {
+    /// The output size in bytes.
+    const OUTPUT_SIZE: usize;
+
+    /// Hash data in one shot.
+    fn digest(data: &[u8]) -> impl Future<Output = Result<Vec<u8>, CryptoError>>;
+}
+
+/// HMAC with an async one-shot API.
+pub trait AsyncHmac {
+    /// Compute the MAC of data under a key.
+    fn sign(key: &[u8], data: &[u8]) -> impl Future<Output = Result<Vec<u8>, CryptoError>>;
+
+    /// Verify a MAC in constant time.
+    fn verify(
+        key: &[u8],
+        data: &[u8],
+        tag: &[u8],
+    ) -> impl Future<Output = Result<bool, CryptoError>>;
+}
+
+/// Symmetric encryption with an async API.
+///
+/// Same contract as [`Cipher`]: ciphertext carries the appended tag.
+pub trait AsyncCipher {
+    /// The key size in bytes.
+    const KEY_SIZE: usize;
+
+    /// The nonce size in bytes.
+    const NONCE_SIZE: usize;
+
+    /// The authentication tag size in bytes.
+    const TAG_SIZE: usize;
+
+    /// Encrypt data with the given key and nonce.
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>>;
+
+    /// Decrypt data with the given key and nonce.
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>>;
+}
+
+/// Signature scheme with an async API.
+///
+/// Key checks come from [`KeyScheme`] and stay synchronous; they only
+/// parse bytes.
+pub trait AsyncSignature: KeyScheme + Sized {
+    /// The size in bytes of signatures verified by a public key.
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError>;
+
+    /// Generate a new keypair.
+    fn generate_keypair() -> impl Future<Output = Result<KeyPair<Self>, CryptoError>>;
+
+    /// Sign a message.
+    fn sign(
+        secret_key: &[u8],
+        message: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>>;
+
+    /// Verify a signature.
+    fn verify(
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> impl Future<Output = Result<bool, CryptoError>>;
+}
+
+impl<T: Hash> AsyncHash for T {
+    const OUTPUT_SIZE: usize = <T as Hash>::OUTPUT_SIZE;
+
+    fn digest(data: &[u8]) -> impl Future<Output = Result<Vec<u8>, CryptoError>> {
+        ready(Ok(T::hash(data)))
+    }
+}
+
+impl<T: Hmac> AsyncHmac for T {
+    fn sign(key: &[u8], data: &[u8]) -> impl Future<Output = Result<Vec<u8>, CryptoError>> {
+        let mut mac = T::new(key);
+        mac.update(data);
+        ready(Ok(mac.finalize()))
+    }
+
+    fn verify(
+        key: &[u8],
+        data: &[u8],
+        tag: &[u8],
+    ) -> impl Future<Output = Result<bool, CryptoError>> {
+        let mut mac = T::new(key);
+        mac.update(data);
+        ready(Ok(Hmac::verify(mac, tag)))
+    }
+}
+
+impl<T: Cipher> AsyncCipher for T {
+    const KEY_SIZE: usize = <T as Cipher>::KEY_SIZE;
+    const NONCE_SIZE: usize = <T as Cipher>::NONCE_SIZE;
+    const TAG_SIZE: usize = <T as Cipher>::TAG_SIZE;
+
+    fn encrypt(
+        key: &[u8],
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>> {
+        ready(<T as Cipher>::encrypt(key, nonce, plaintext, aad))
+    }
+
+    fn decrypt(
+        key: &[u8],
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>> {
+        ready(<T as Cipher>::decrypt(key, nonce, ciphertext, aad))
+    }
+}
+
+impl<T: Signature> AsyncSignature for T {
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError> {
+        <T as Signature>::signature_size(public_key)
+    }
+
+    fn generate_keypair() -> impl Future<Output = Result<KeyPair<Self>, CryptoError>> {
+        ready(Ok(<T as Signature>::generate_keypair()))
+    }
+
+    fn sign(
+        secret_key: &[u8],
+        message: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, CryptoError>> {
+        ready(<T as Signature>::sign(secret_key, message))
+    }
+
+    fn verify(
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> impl Future<Output = Result<bool, CryptoError>> {
+        ready(<T as Signature>::verify(public_key, message, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{KeyedSum, Sum, Xor};
+    use portals_testing::now;
+
+    #[test]
+    fn sync_hashes_are_async_hashes() {
+        assert_eq!(<Sum as AsyncHash>::OUTPUT_SIZE, 8);
+        assert_eq!(now(Sum::digest(b"ab")).unwrap(), Sum::hash(b"ab"));
+    }
+
+    #[test]
+    fn sync_macs_are_async_macs() {
+        let tag = now(<KeyedSum as AsyncHmac>::sign(b"k", b"data")).unwrap();
+        assert!(now(<KeyedSum as AsyncHmac>::verify(b"k", b"data", &tag)).unwrap());
+        assert!(!now(<KeyedSum as AsyncHmac>::verify(b"k", b"date", &tag)).unwrap());
+    }
+
+    #[test]
+    fn sync_ciphers_are_async_ciphers() {
+        let sealed = now(<Xor as AsyncCipher>::encrypt(&[7; 4], &[], b"hi", &[])).unwrap();
+        assert_eq!(
+            now(<Xor as AsyncCipher>::decrypt(&[7; 4], &[], &sealed, &[])).unwrap(),
+            b"hi"
+        );
+        assert!(matches!(
+            now(<Xor as AsyncCipher>::decrypt(&[8; 4], &[], &sealed, &[])),
+            Err(CryptoError::AuthenticationFailed)
+        ));
+    }
+}
@@ -0,0 +1,337 @@
+//! Typed keys, key encodings and keyrings.
+
+use crate::{Cipher, CryptoError, KeyAgreement, KeyScheme, Signature, constant_time_eq};
+use std::fmt;
+use std::marker::PhantomData;
+use zeroize::Zeroizing;
+
+/// A symmetric key for the cipher `C`.
+///
+/// The key bytes are zeroed on drop and never printed by `Debug`.
+pub struct SymmetricKey<C> {
+    bytes: Zeroizing<Vec<u8>>,
+    _cipher: PhantomData<fn() -> C>,
+}
+
+impl<C: Cipher> SymmetricKey<C> {
+    /// Wrap key bytes, checking their length against `C::KEY_SIZE`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() != C::KEY_SIZE {
+            return Err(CryptoError::InvalidKeySize);
+        }
+        Ok(Self {
+            bytes: Zeroizing::new(bytes.to_vec()),
+            _cipher: PhantomData,
+        })
+    }
+
+    /// Encrypt with this key. See [`Cipher::encrypt`].
+    pub fn encrypt(
+        &self,
+        nonce: &[u8],
+        plaintext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        C::encrypt(&self.bytes, nonce, plaintext, aad)
+    }
+
+    /// Decrypt with this key. See [`Cipher::decrypt`].
+    pub fn decrypt(
+        &self,
+        nonce: &[u8],
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        C::decrypt(&self.bytes, nonce, ciphertext, aad)
+    }
+}
+
+impl<C> SymmetricKey<C> {
+    /// The raw key bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A secret key for the signature or key agreement scheme `S`.
+///
+/// The key bytes are zeroed on drop and never printed by `Debug`.
+pub struct SecretKey<S> {
+    bytes: Zeroizing<Vec<u8>>,
+    _scheme: PhantomData<fn() -> S>,
+}
+
+impl<S: KeyScheme> SecretKey<S> {
+    /// Wrap key bytes, checking them with [`KeyScheme::check_secret_key`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        S::check_secret_key(bytes)?;
+        Ok(Self {
+            bytes: Zeroizing::new(bytes.to_vec()),
+            _scheme: PhantomData,
+        })
+    }
+}
+
+impl<S: Signature> SecretKey<S> {
+    /// Sign a message with this key. See [`Signature::sign`].
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        S::sign(&self.bytes, message)
+    }
+}
+
+impl<S: KeyAgreement> SecretKey<S> {
+    /// Compute the secret shared with the owner of `peer`. See
+    /// [`KeyAgreement::agree`].
+    pub fn agree(&self, peer: &PublicKey<S>) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        S::agree(self, peer)
+    }
+}
+
+impl<S> SecretKey<S> {
+    /// The raw key bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A public key for the signature or key agreement scheme `S`.
+pub struct PublicKey<S> {
+    bytes: Vec<u8>,
+    _scheme: PhantomData<fn() -> S>,
+}
+
+impl<S: KeyScheme> PublicKey<S> {
+    /// Wrap key bytes, checking them with [`KeyScheme::check_public_key`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        S::check_public_key(bytes)?;
+        Ok(Self {
+            bytes: bytes.to_vec(),
+            _scheme: PhantomData,
+        })
+    }
+}
+
+impl<S: Signature> PublicKey<S> {
+    /// Verify a signature with this key. See [`Signature::verify`].
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        S::verify(&self.bytes, message, signature)
+    }
+}
+
+impl<S> PublicKey<S> {
+    /// The raw key bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// A matching public and secret key.
+pub struct KeyPair<S> {
+    public: PublicKey<S>,
+    secret: SecretKey<S>,
+}
+
+impl<S: KeyScheme> KeyPair<S> {
+    /// Pair up a public and secret key. The caller is responsible for
+    /// them matching; use [`KeyEncoding::public_key`] to derive one.
+    pub fn new(public: PublicKey<S>, secret: SecretKey<S>) -> Self {
+        Self { public, secret }
+    }
+}
+
+impl<S: Signature> KeyPair<S> {
+    /// Sign a message with the secret key.
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.secret.sign(message)
+    }
+
+    /// Verify a signature with the public key.
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, CryptoError> {
+        self.public.verify(message, signature)
+    }
+}
+
+impl<S: KeyAgreement> KeyPair<S> {
+    /// Compute the secret shared with the owner of `peer`.
+    pub fn agree(&self, peer: &PublicKey<S>) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        self.secret.agree(peer)
+    }
+}
+
+impl<S> KeyPair<S> {
+    pub fn public(&self) -> &PublicKey<S> {
+        &self.public
+    }
+
+    pub fn secret(&self) -> &SecretKey<S> {
+        &self.secret
+    }
+
+    pub fn into_parts(self) -> (PublicKey<S>, SecretKey<S>) {
+        (self.public, self.secret)
+    }
+}
+
+impl<S: KeyEncoding> KeyPair<S> {
+    /// Build a key pair from a secret key, deriving the public key.
+    pub fn from_secret(secret: SecretKey<S>) -> Result<Self, CryptoError> {
+        Ok(Self {
+            public: S::public_key(&secret)?,
+            secret,
+        })
+    }
+}
+
+impl<C> Clone for SymmetricKey<C> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            _cipher: PhantomData,
+        }
+    }
+}
+
+impl<S> Clone for SecretKey<S> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            _scheme: PhantomData,
+        }
+    }
+}
+
+impl<S> Clone for PublicKey<S> {
+    fn clone(&self) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            _scheme: PhantomData,
+        }
+    }
+}
+
+impl<S> Clone for KeyPair<S> {
+    fn clone(&self) -> Self {
+        Self {
+            public: self.public.clone(),
+            secret: self.secret.clone(),
+        }
+    }
+}
+
+impl<C> PartialEq for SymmetricKey<C> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.bytes, &other.bytes)
+    }
+}
+
+impl<C> Eq for SymmetricKey<C> {}
+
+impl<S> PartialEq for SecretKey<S> {
+    fn eq(&self, other: &Self) -> bool {
+        constant_time_eq(&self.bytes, &other.bytes)
+    }
+}
+
+impl<S> Eq for SecretKey<S> {}
+
+impl<S> PartialEq for PublicKey<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<S> Eq for PublicKey<S> {}
+
+impl<C> fmt::Debug for SymmetricKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SymmetricKey(..)")
+    }
+}
+
+impl<S> fmt::Debug for SecretKey<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+impl<S> fmt::Debug for PublicKey<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PublicKey").field(&self.bytes).finish()
+    }
+}
+
+impl<S> fmt::Debug for KeyPair<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyPair")
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Import and export of signature and key agreement keys in standard
+/// formats.
+///
+/// Secret keys use PKCS#8, public keys use SubjectPublicKeyInfo; PEM is the
+/// base64 armoring of the DER form (`PRIVATE KEY` / `PUBLIC KEY`). JWK
+/// follows RFC 7517 and its per-algorithm key types.
+pub trait KeyEncoding: KeyScheme + Sized {
+    /// Derive the public key for a secret key.
+    fn public_key(secret: &SecretKey<Self>) -> Result<PublicKey<Self>, CryptoError>;
+
+    /// Encode a secret key as PKCS#8 DER.
+    fn secret_to_der(secret: &SecretKey<Self>) -> Result<Zeroizing<Vec<u8>>, CryptoError>;
+
+    /// Decode a secret key from PKCS#8 DER.
+    fn secret_from_der(der: &[u8]) -> Result<SecretKey<Self>, CryptoError>;
+
+    /// Encode a secret key as PKCS#8 PEM.
+    fn secret_to_pem(secret: &SecretKey<Self>) -> Result<Zeroizing<String>, CryptoError>;
+
+    /// Decode a secret key from PKCS#8 PEM.
+    fn secret_from_pem(pem: &str) -> Result<SecretKey<Self>, CryptoError>;
+
+    /// Encode a secret key as a JWK, including its public part.
+    fn secret_to_jwk(secret: &SecretKey<Self>) -> Result<Zeroizing<String>, CryptoError>;
+
+    /// Decode a secret key from a JWK.
+    fn secret_from_jwk(jwk: &str) -> Result<SecretKey<Self>, CryptoError>;
+
+    /// Encode a public key as SubjectPublicKeyInfo DER.
+    fn public_to_der(public: &PublicKey<Self>) -> Result<Vec<u8>, CryptoError>;
+
+    /// Decode a public key from SubjectPublicKeyInfo DER.
+    fn public_from_der(der: &[u8]) -> Result<PublicKey<Self>, CryptoError>;
+
+    /// Encode a public key as SubjectPublicKeyInfo PEM.
+    fn public_to_pem(public: &PublicKey<Self>) -> Result<String, CryptoError>;
+
+    /// Decode a public key from SubjectPublicKeyInfo PEM.
+    fn public_from_pem(pem: &str) -> Result<PublicKey<Self>, CryptoError>;
+
+    /// Encode a public key as a JWK.
+    fn public_to_jwk(public: &PublicKey<Self>) -> Result<String, CryptoError>;
+
+    /// Decode a public key from a JWK.
+    fn public_from_jwk(jwk: &str) -> Result<PublicKey<Self>, CryptoError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Xor;
+
+    fn key(byte: u8) -> SymmetricKey<Xor> {
+        SymmetricKey::from_bytes(&[byte; 4]).unwrap()
+    }
+
+    #[test]
+    fn typed_keys_check_size() {
+        assert!(matches!(
+            SymmetricKey::<Xor>::from_bytes(&[0; 3]),
+            Err(CryptoError::InvalidKeySize)
+        ));
+        assert_eq!(key(1), key(1));
+        assert_ne!(key(1), key(2));
+        assert_eq!(format!("{:?}", key(7)), "SymmetricKey(..)");
+    }
+}
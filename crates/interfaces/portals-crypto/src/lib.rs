@@ -2,6 +2,15 @@
 
 use std::fmt;
 
+mod asynchronous;
+mod keys;
+#[cfg(test)]
+mod testing;
+
+pub use asynchronous::{AsyncCipher, AsyncHash, AsyncHmac, AsyncSignature};
+pub use keys::{KeyEncoding, KeyPair, PublicKey, SecretKey, SymmetricKey};
+pub use zeroize::Zeroizing;
+
 /// A cryptographic hash function.
 pub trait Hash {
     /// The output size in bytes.
@@ -67,19 +76,28 @@ pub trait Cipher {
     fn decrypt(key: &[u8], nonce: &[u8], ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError>;
 }
 
-/// Cryptographic signature scheme.
-pub trait Signature {
-    /// The public key size in bytes.
-    const PUBLIC_KEY_SIZE: usize;
-
-    /// The secret key size in bytes.
-    const SECRET_KEY_SIZE: usize;
+/// A scheme whose keys are passed around as encoded bytes.
+///
+/// Key sizes can vary between keys of the same scheme (RSA moduli, for
+/// one), so schemes check encoded keys instead of declaring fixed sizes.
+/// [`PublicKey`] and [`SecretKey`] only hold bytes that passed these checks.
+pub trait KeyScheme {
+    /// Check that bytes are a well-formed public key for this scheme.
+    fn check_public_key(public_key: &[u8]) -> Result<(), CryptoError>;
+
+    /// Check that bytes are a well-formed secret key for this scheme.
+    fn check_secret_key(secret_key: &[u8]) -> Result<(), CryptoError>;
+}
 
-    /// The signature size in bytes.
-    const SIGNATURE_SIZE: usize;
+/// Cryptographic signature scheme.
+pub trait Signature: KeyScheme {
+    /// The size in bytes of signatures verified by a public key.
+    fn signature_size(public_key: &[u8]) -> Result<usize, CryptoError>;
 
     /// Generate a new keypair.
-    fn generate_keypair() -> (Vec<u8>, Vec<u8>);
+    fn generate_keypair() -> KeyPair<Self>
+    where
+        Self: Sized;
 
     /// Sign a message.
     fn sign(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, CryptoError>;
@@ -88,12 +106,88 @@ pub trait Signature {
     fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, CryptoError>;
 }
 
+/// A signature scheme registered as a JSON Web Signature algorithm
+/// (RFC 7518, RFC 8037, RFC 8812).
+///
+/// Lets JOSE implementations take the `alg` header value from the key's
+/// scheme, so a key cannot be used under another algorithm's name.
+pub trait JwsAlgorithm: Signature {
+    /// The `alg` header value, e.g. `"ES256"`.
+    const ALG: &'static str;
+}
+
 /// Key derivation function.
 pub trait Kdf {
     /// Derive a key from a password and salt.
     fn derive(password: &[u8], salt: &[u8], output_len: usize) -> Vec<u8>;
 }
 
+/// HKDF key derivation (RFC 5869).
+///
+/// For deriving keys from high-entropy secrets such as key agreement output.
+/// Passwords need a deliberately slow [`Kdf`] instead.
+pub trait Hkdf {
+    /// The underlying hash's output size in bytes.
+    const HASH_SIZE: usize;
+
+    /// Extract a pseudorandom key from input keying material. An empty salt
+    /// is treated as `HASH_SIZE` zero bytes.
+    fn extract(salt: &[u8], ikm: &[u8]) -> Zeroizing<Vec<u8>>;
+
+    /// Expand a pseudorandom key into `output_len` bytes, bound to `info`.
+    ///
+    /// Fails if the key is shorter than `HASH_SIZE` or `output_len` exceeds
+    /// `255 * HASH_SIZE`.
+    fn expand(prk: &[u8], info: &[u8], output_len: usize) -> Result<Zeroizing<Vec<u8>>, CryptoError>;
+
+    /// Extract and expand in one step.
+    fn derive(salt: &[u8], ikm: &[u8], info: &[u8], output_len: usize) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        Self::expand(&Self::extract(salt, ikm), info, output_len)
+    }
+}
+
+/// Hashes passwords for storage.
+///
+/// Hashes are self-describing strings, normally in the PHC string format
+/// (`$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`), that carry their own
+/// salt and cost parameters. Salts are generated per hash.
+pub trait PasswordHasher {
+    /// Hash a password with a fresh salt and the configured parameters.
+    fn hash(&self, password: &[u8]) -> Result<String, CryptoError>;
+
+    /// Check a password against a stored hash, using the hash's own
+    /// parameters.
+    ///
+    /// Fails with [`CryptoError::InvalidPasswordHash`] if the hash is
+    /// malformed or from another algorithm.
+    fn verify(&self, password: &[u8], hash: &str) -> Result<bool, CryptoError>;
+
+    /// Whether a stored hash was made with different parameters than the
+    /// configured ones and should be replaced after the next successful
+    /// [`verify`](Self::verify).
+    fn needs_rehash(&self, hash: &str) -> Result<bool, CryptoError>;
+}
+
+/// Diffie-Hellman style key agreement.
+///
+/// Shared secrets are not uniformly random; run them through [`Hkdf`]
+/// before using them as keys.
+///
+/// Public keys are derived with [`KeyEncoding::public_key`].
+pub trait KeyAgreement: KeyEncoding {
+    /// The shared secret size in bytes.
+    const SHARED_SECRET_SIZE: usize;
+
+    /// Generate a new keypair.
+    fn generate_keypair() -> KeyPair<Self>;
+
+    /// Compute the secret shared with the owner of `peer`.
+    ///
+    /// Fails with [`CryptoError::InvalidSharedSecret`] for peer keys that
+    /// would force a predictable shared secret.
+    fn agree(secret: &SecretKey<Self>, peer: &PublicKey<Self>) -> Result<Zeroizing<Vec<u8>>, CryptoError>;
+}
+
 /// Cryptographic errors.
 #[derive(Debug)]
 pub enum CryptoError {
@@ -105,6 +199,14 @@ pub enum CryptoError {
     AuthenticationFailed,
     /// Invalid signature.
     InvalidSignature,
+    /// Malformed or unsupported key encoding (DER, PEM or JWK).
+    InvalidKeyFormat(String),
+    /// Malformed or unsupported sealed message.
+    InvalidCiphertext(String),
+    /// Malformed or unsupported password hash string.
+    InvalidPasswordHash(String),
+    /// Key agreement produced a predictable shared secret.
+    InvalidSharedSecret,
     /// Other error.
     Other(String),
 }
@@ -116,6 +218,10 @@ impl fmt::Display for CryptoError {
             CryptoError::InvalidNonceSize => write!(f, "invalid nonce size"),
             CryptoError::AuthenticationFailed => write!(f, "authentication failed"),
             CryptoError::InvalidSignature => write!(f, "invalid signature"),
+            CryptoError::InvalidKeyFormat(msg) => write!(f, "invalid key format: {}", msg),
+            CryptoError::InvalidCiphertext(msg) => write!(f, "invalid ciphertext: {}", msg),
+            CryptoError::InvalidPasswordHash(msg) => write!(f, "invalid password hash: {}", msg),
+            CryptoError::InvalidSharedSecret => write!(f, "invalid shared secret"),
             CryptoError::Other(msg) => write!(f, "{}", msg),
         }
     }
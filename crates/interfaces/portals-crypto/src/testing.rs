@@ -0,0 +1,83 @@
+//! Fakes shared by the unit tests.
+
+use crate::{Cipher, CryptoError, Hash, Hmac};
+
+/// Adds up the input bytes.
+pub(crate) struct Sum(u64);
+
+impl Hash for Sum {
+    const OUTPUT_SIZE: usize = 8;
+
+    fn new() -> Self {
+        Sum(0)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0 += data.iter().map(|&b| b as u64).sum::<u64>();
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+/// Keyed sum: the key's bytes are added to the data's.
+pub(crate) struct KeyedSum(Sum);
+
+impl Hmac for KeyedSum {
+    fn new(key: &[u8]) -> Self {
+        let mut sum = Sum::new();
+        sum.update(key);
+        KeyedSum(sum)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        self.0.finalize()
+    }
+}
+
+/// XORs with a repeating 4-byte key and appends the first key byte as a
+/// "tag", enough to exercise typed keys and wrappers.
+pub(crate) struct Xor;
+
+impl Xor {
+    fn apply(key: &[u8], data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .zip(key.iter().cycle())
+            .map(|(d, k)| d ^ k)
+            .collect()
+    }
+}
+
+impl Cipher for Xor {
+    const KEY_SIZE: usize = 4;
+    const NONCE_SIZE: usize = 0;
+    const TAG_SIZE: usize = 1;
+
+    fn encrypt(
+        key: &[u8],
+        _nonce: &[u8],
+        plaintext: &[u8],
+        _aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let mut out = Self::apply(key, plaintext);
+        out.push(key[0]);
+        Ok(out)
+    }
+
+    fn decrypt(
+        key: &[u8],
+        _nonce: &[u8],
+        ciphertext: &[u8],
+        _aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        match ciphertext.split_last() {
+            Some((&tag, body)) if tag == key[0] => Ok(Self::apply(key, body)),
+            _ => Err(CryptoError::AuthenticationFailed),
+        }
+    }
+}
@@ -8,7 +8,14 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 #[derive(Debug)]
 pub enum Error {
     Lookup(String),
+    /// The name exists but has no records of the requested type.
     NoRecords,
+    /// The name does not exist (NXDOMAIN).
+    NxDomain,
+    /// The server failed to answer (SERVFAIL).
+    ServFail,
+    /// No answer arrived in time.
+    Timeout,
     Other(String),
 }
 
@@ -17,6 +24,9 @@ impl fmt::Display for Error {
         match self {
             Error::Lookup(msg) => write!(f, "lookup failed: {}", msg),
             Error::NoRecords => write!(f, "no records found"),
+            Error::NxDomain => write!(f, "domain does not exist"),
+            Error::ServFail => write!(f, "server failure"),
+            Error::Timeout => write!(f, "lookup timed out"),
             Error::Other(msg) => write!(f, "{}", msg),
         }
     }
@@ -24,6 +34,191 @@ impl fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// A failed [`Resolver::lookup_with_soa`].
+#[derive(Debug)]
+pub struct LookupFailure {
+    pub error: Error,
+    /// For [`Error::NoRecords`] and [`Error::NxDomain`], the SOA record
+    /// the server sent with the answer, which bounds how long it may be
+    /// cached (RFC 2308).
+    pub soa: Option<Record>,
+}
+
+impl From<Error> for LookupFailure {
+    fn from(error: Error) -> Self {
+        Self { error, soa: None }
+    }
+}
+
+/// A DNS record type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Cname,
+    Mx,
+    Ns,
+    Ptr,
+    Soa,
+    Srv,
+    Txt,
+    Caa,
+    Tlsa,
+    Svcb,
+    Https,
+}
+
+impl fmt::Display for RecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RecordType::A => "A",
+            RecordType::Aaaa => "AAAA",
+            RecordType::Cname => "CNAME",
+            RecordType::Mx => "MX",
+            RecordType::Ns => "NS",
+            RecordType::Ptr => "PTR",
+            RecordType::Soa => "SOA",
+            RecordType::Srv => "SRV",
+            RecordType::Txt => "TXT",
+            RecordType::Caa => "CAA",
+            RecordType::Tlsa => "TLSA",
+            RecordType::Svcb => "SVCB",
+            RecordType::Https => "HTTPS",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A resource record returned by [`Resolver::lookup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The owner name.
+    pub name: String,
+    /// Time to live, in seconds.
+    pub ttl: u32,
+    pub data: RecordData,
+}
+
+impl Record {
+    pub fn record_type(&self) -> RecordType {
+        self.data.record_type()
+    }
+}
+
+/// Typed record data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Cname(String),
+    Mx(Mx),
+    Ns(String),
+    Ptr(String),
+    Soa(Soa),
+    Srv(Srv),
+    /// The record's character strings, concatenated.
+    Txt(String),
+    Caa(Caa),
+    Tlsa(Tlsa),
+    Svcb(Svcb),
+    Https(Svcb),
+}
+
+impl RecordData {
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            RecordData::A(_) => RecordType::A,
+            RecordData::Aaaa(_) => RecordType::Aaaa,
+            RecordData::Cname(_) => RecordType::Cname,
+            RecordData::Mx(_) => RecordType::Mx,
+            RecordData::Ns(_) => RecordType::Ns,
+            RecordData::Ptr(_) => RecordType::Ptr,
+            RecordData::Soa(_) => RecordType::Soa,
+            RecordData::Srv(_) => RecordType::Srv,
+            RecordData::Txt(_) => RecordType::Txt,
+            RecordData::Caa(_) => RecordType::Caa,
+            RecordData::Tlsa(_) => RecordType::Tlsa,
+            RecordData::Svcb(_) => RecordType::Svcb,
+            RecordData::Https(_) => RecordType::Https,
+        }
+    }
+}
+
+/// A mail exchange (MX) record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mx {
+    pub preference: u16,
+    pub exchange: String,
+}
+
+/// A start of authority (SOA) record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Soa {
+    /// The primary nameserver.
+    pub mname: String,
+    /// The administrator's mailbox, with the `@` written as a dot.
+    pub rname: String,
+    pub serial: u32,
+    pub refresh: u32,
+    pub retry: u32,
+    pub expire: u32,
+    /// The TTL for negative answers (RFC 2308).
+    pub minimum: u32,
+}
+
+/// A service locator (SRV) record (RFC 2782).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Srv {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+/// A certification authority authorization (CAA) record (RFC 8659).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caa {
+    /// Whether the issuer-critical flag is set.
+    pub critical: bool,
+    /// The property tag, e.g. `issue`, `issuewild` or `iodef`.
+    pub tag: String,
+    pub value: String,
+}
+
+/// A TLS certificate association (TLSA) record (RFC 6698).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tlsa {
+    pub cert_usage: u8,
+    pub selector: u8,
+    pub matching_type: u8,
+    pub data: Vec<u8>,
+}
+
+/// A service binding (SVCB or HTTPS) record (RFC 9460).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Svcb {
+    /// 0 for alias form, otherwise the service priority.
+    pub priority: u16,
+    pub target: String,
+    pub params: Vec<SvcParam>,
+}
+
+/// A service binding parameter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SvcParam {
+    /// Keys the client must understand, by number.
+    Mandatory(Vec<u16>),
+    Alpn(Vec<String>),
+    NoDefaultAlpn,
+    Port(u16),
+    Ipv4Hint(Vec<Ipv4Addr>),
+    /// An encoded `ECHConfigList`.
+    Ech(Vec<u8>),
+    Ipv6Hint(Vec<Ipv6Addr>),
+    /// Any other key, with its wire-format value.
+    Unknown(u16, Vec<u8>),
+}
+
 /// A DNS resolver.
 pub trait Resolver {
     /// Lookup IPv4 addresses for a hostname.
@@ -43,4 +238,230 @@ pub trait Resolver {
 
     /// Reverse lookup - get hostname for an IP address.
     fn reverse_lookup(&self, addr: IpAddr) -> impl Future<Output = Result<Vec<String>, Error>>;
+
+    /// Lookup records of any supported type, with their TTLs.
+    ///
+    /// Only records of the requested type are returned, not the CNAMEs
+    /// followed to reach them.
+    fn lookup(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> impl Future<Output = Result<Vec<Record>, Error>>;
+
+    /// Lookup records as [`lookup`](Self::lookup) does, keeping the SOA
+    /// record that came with a negative answer.
+    ///
+    /// The default reports no SOA.
+    fn lookup_with_soa(
+        &self,
+        name: &str,
+        record_type: RecordType,
+    ) -> impl Future<Output = Result<Vec<Record>, LookupFailure>> {
+        let lookup = self.lookup(name, record_type);
+        async move { lookup.await.map_err(LookupFailure::from) }
+    }
+
+    /// Lookup SRV records, e.g. for `_imaps._tcp.example.com`.
+    fn lookup_srv(&self, name: &str) -> impl Future<Output = Result<Vec<Srv>, Error>> {
+        typed(self.lookup(name, RecordType::Srv), |data| match data {
+            RecordData::Srv(srv) => Some(srv),
+            _ => None,
+        })
+    }
+
+    /// Lookup the CNAME target for a name.
+    fn lookup_cname(&self, name: &str) -> impl Future<Output = Result<Vec<String>, Error>> {
+        typed(self.lookup(name, RecordType::Cname), |data| match data {
+            RecordData::Cname(target) => Some(target),
+            _ => None,
+        })
+    }
+
+    /// Lookup the nameservers for a zone.
+    fn lookup_ns(&self, zone: &str) -> impl Future<Output = Result<Vec<String>, Error>> {
+        typed(self.lookup(zone, RecordType::Ns), |data| match data {
+            RecordData::Ns(ns) => Some(ns),
+            _ => None,
+        })
+    }
+
+    /// Lookup the SOA record for a zone.
+    fn lookup_soa(&self, zone: &str) -> impl Future<Output = Result<Soa, Error>> {
+        let records = typed(self.lookup(zone, RecordType::Soa), |data| match data {
+            RecordData::Soa(soa) => Some(soa),
+            _ => None,
+        });
+        async move { records.await?.into_iter().next().ok_or(Error::NoRecords) }
+    }
+
+    /// Lookup CAA records for a domain.
+    fn lookup_caa(&self, domain: &str) -> impl Future<Output = Result<Vec<Caa>, Error>> {
+        typed(self.lookup(domain, RecordType::Caa), |data| match data {
+            RecordData::Caa(caa) => Some(caa),
+            _ => None,
+        })
+    }
+
+    /// Lookup TLSA records, e.g. for `_443._tcp.example.com`.
+    fn lookup_tlsa(&self, name: &str) -> impl Future<Output = Result<Vec<Tlsa>, Error>> {
+        typed(self.lookup(name, RecordType::Tlsa), |data| match data {
+            RecordData::Tlsa(tlsa) => Some(tlsa),
+            _ => None,
+        })
+    }
+
+    /// Lookup SVCB records.
+    fn lookup_svcb(&self, name: &str) -> impl Future<Output = Result<Vec<Svcb>, Error>> {
+        typed(self.lookup(name, RecordType::Svcb), |data| match data {
+            RecordData::Svcb(svcb) => Some(svcb),
+            _ => None,
+        })
+    }
+
+    /// Lookup HTTPS records.
+    fn lookup_https(&self, name: &str) -> impl Future<Output = Result<Vec<Svcb>, Error>> {
+        typed(self.lookup(name, RecordType::Https), |data| match data {
+            RecordData::Https(https) => Some(https),
+            _ => None,
+        })
+    }
+}
+
+/// The `in-addr.arpa` or `ip6.arpa` name used to look up PTR records for an
+/// address.
+pub fn reverse_name(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, c, d] = v4.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa.", d, c, b, a)
+        }
+        IpAddr::V6(v6) => {
+            let mut name = String::new();
+            for byte in v6.octets().iter().rev() {
+                name.push_str(&format!("{:x}.{:x}.", byte & 0x0f, byte >> 4));
+            }
+            name + "ip6.arpa."
+        }
+    }
+}
+
+/// Pick typed data out of a generic lookup, failing if there is none.
+async fn typed<T>(
+    lookup: impl Future<Output = Result<Vec<Record>, Error>>,
+    select: impl Fn(RecordData) -> Option<T>,
+) -> Result<Vec<T>, Error> {
+    let values: Vec<T> = lookup
+        .await?
+        .into_iter()
+        .filter_map(|record| select(record.data))
+        .collect();
+    if values.is_empty() {
+        return Err(Error::NoRecords);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use portals_testing::now;
+    use std::future::ready;
+
+    /// Answers every query with one SRV and one SOA record.
+    struct Fixed;
+
+    impl Resolver for Fixed {
+        fn lookup_ipv4(&self, _host: &str) -> impl Future<Output = Result<Vec<Ipv4Addr>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn lookup_ipv6(&self, _host: &str) -> impl Future<Output = Result<Vec<Ipv6Addr>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn lookup_ip(&self, _host: &str) -> impl Future<Output = Result<Vec<IpAddr>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn lookup_txt(&self, _host: &str) -> impl Future<Output = Result<Vec<String>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn lookup_mx(
+            &self,
+            _domain: &str,
+        ) -> impl Future<Output = Result<Vec<(u16, String)>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn reverse_lookup(
+            &self,
+            _addr: IpAddr,
+        ) -> impl Future<Output = Result<Vec<String>, Error>> {
+            ready(Err(Error::NoRecords))
+        }
+
+        fn lookup(
+            &self,
+            name: &str,
+            _record_type: RecordType,
+        ) -> impl Future<Output = Result<Vec<Record>, Error>> {
+            let srv = Srv {
+                priority: 10,
+                weight: 0,
+                port: 993,
+                target: "imap.example.com.".to_string(),
+            };
+            let soa = Soa {
+                mname: "ns1.example.com.".to_string(),
+                rname: "hostmaster.example.com.".to_string(),
+                serial: 1,
+                refresh: 7200,
+                retry: 3600,
+                expire: 1209600,
+                minimum: 300,
+            };
+            ready(Ok(vec![
+                Record {
+                    name: name.to_string(),
+                    ttl: 60,
+                    data: RecordData::Srv(srv),
+                },
+                Record {
+                    name: name.to_string(),
+                    ttl: 60,
+                    data: RecordData::Soa(soa),
+                },
+            ]))
+        }
+    }
+
+    #[test]
+    fn typed_lookups_select_their_type() {
+        let srv = now(Fixed.lookup_srv("_imaps._tcp.example.com")).unwrap();
+        assert_eq!(srv.len(), 1);
+        assert_eq!(srv[0].port, 993);
+        assert_eq!(now(Fixed.lookup_soa("example.com")).unwrap().minimum, 300);
+    }
+
+    #[test]
+    fn reverse_names() {
+        assert_eq!(
+            reverse_name(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+            "1.2.0.192.in-addr.arpa."
+        );
+        assert_eq!(
+            reverse_name("2001:db8::1".parse().unwrap()),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+        );
+    }
+
+    #[test]
+    fn typed_lookups_without_matches_find_no_records() {
+        assert!(matches!(
+            now(Fixed.lookup_tlsa("_443._tcp.example.com")),
+            Err(Error::NoRecords)
+        ));
+    }
 }
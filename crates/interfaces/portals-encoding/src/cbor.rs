@@ -0,0 +1,113 @@
+//! Dynamic CBOR values.
+
+/// A CBOR data item (RFC 8949).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CborValue {
+    /// An integer. Values outside the 64-bit CBOR range are encoded as
+    /// bignums (tags 2 and 3), and bignums that fit decode back to this.
+    Integer(i128),
+    /// A byte string.
+    Bytes(Vec<u8>),
+    /// A UTF-8 text string.
+    Text(String),
+    /// An array.
+    Array(Vec<CborValue>),
+    /// A map, with its pairs in order.
+    Map(Vec<(CborValue, CborValue)>),
+    /// A tagged value.
+    Tag(u64, Box<CborValue>),
+    /// `false` or `true`.
+    Bool(bool),
+    /// `null`.
+    Null,
+    /// `undefined`.
+    Undefined,
+    /// A floating-point number.
+    Float(f64),
+    /// A simple value other than `false`, `true`, `null` and `undefined`.
+    Simple(SimpleValue),
+}
+
+/// A CBOR simple value that has no variant of its own in [`CborValue`]:
+/// 0 to 19, or 32 to 255.
+///
+/// 20 to 23 are `false`, `true`, `null` and `undefined`, and 24 to 31 are
+/// reserved, so those cannot be built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SimpleValue(u8);
+
+impl SimpleValue {
+    /// The simple value `n`, or `None` if it is 20 to 31.
+    pub const fn new(n: u8) -> Option<Self> {
+        match n {
+            20..=31 => None,
+            n => Some(SimpleValue(n)),
+        }
+    }
+
+    /// The number of this simple value.
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl CborValue {
+    /// Look up a text key in a map.
+    pub fn get(&self, key: &str) -> Option<&CborValue> {
+        match self {
+            CborValue::Map(pairs) => pairs
+                .iter()
+                .find(|(k, _)| matches!(k, CborValue::Text(t) if t == key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl From<i64> for CborValue {
+    fn from(value: i64) -> Self {
+        CborValue::Integer(value.into())
+    }
+}
+
+impl From<u64> for CborValue {
+    fn from(value: u64) -> Self {
+        CborValue::Integer(value.into())
+    }
+}
+
+impl From<f64> for CborValue {
+    fn from(value: f64) -> Self {
+        CborValue::Float(value)
+    }
+}
+
+impl From<bool> for CborValue {
+    fn from(value: bool) -> Self {
+        CborValue::Bool(value)
+    }
+}
+
+impl From<&str> for CborValue {
+    fn from(value: &str) -> Self {
+        CborValue::Text(value.to_string())
+    }
+}
+
+impl From<String> for CborValue {
+    fn from(value: String) -> Self {
+        CborValue::Text(value)
+    }
+}
+
+impl From<Vec<u8>> for CborValue {
+    fn from(value: Vec<u8>) -> Self {
+        CborValue::Bytes(value)
+    }
+}
+
+impl From<Vec<CborValue>> for CborValue {
+    fn from(value: Vec<CborValue>) -> Self {
+        CborValue::Array(value)
+    }
+}
@@ -2,6 +2,12 @@
 
 use std::fmt;
 
+mod cbor;
+mod query;
+
+pub use cbor::{CborValue, SimpleValue};
+pub use query::QueryMap;
+
 /// Base64 encoding/decoding.
 pub trait Base64 {
     /// Encode bytes to base64 string.
@@ -20,6 +26,86 @@ pub trait Base64Url {
     fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
 }
 
+/// Base32 encoding/decoding (RFC 4648).
+pub trait Base32 {
+    /// Encode bytes to a padded base32 string.
+    fn encode(data: &[u8]) -> String;
+
+    /// Encode bytes to base32 without trailing `=` padding, as used in
+    /// `otpauth://` URIs.
+    fn encode_unpadded(data: &[u8]) -> String;
+
+    /// Decode a base32 string to bytes. Padding is optional and letters
+    /// may be either case.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Base32 with the extended hex alphabet (RFC 4648 section 7), which
+/// preserves sort order.
+pub trait Base32Hex {
+    /// Encode bytes to a padded base32hex string.
+    fn encode(data: &[u8]) -> String;
+
+    /// Encode bytes to base32hex without trailing `=` padding.
+    fn encode_unpadded(data: &[u8]) -> String;
+
+    /// Decode a base32hex string to bytes. Padding is optional and letters
+    /// may be either case.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Crockford's Base32, for identifiers people read and type.
+///
+/// Encodes without padding. Decoding ignores case and hyphens, and reads
+/// `O` as `0` and `I` and `L` as `1`.
+pub trait Crockford {
+    /// Encode bytes to an uppercase Crockford string.
+    fn encode(data: &[u8]) -> String;
+
+    /// Decode a Crockford string to bytes.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Base58 with the Bitcoin alphabet, which leaves out `0`, `O`, `I` and `l`.
+pub trait Base58 {
+    /// Encode bytes to base58. Each leading zero byte becomes a `1`.
+    fn encode(data: &[u8]) -> String;
+
+    /// Decode a base58 string to bytes.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+
+    /// Encode bytes with a 4-byte double SHA-256 checksum (Base58Check).
+    /// Any version byte is part of `data`.
+    fn encode_check(data: &[u8]) -> String;
+
+    /// Decode a Base58Check string, verifying and removing its checksum.
+    fn decode_check(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Z85, the ZeroMQ base85 encoding (ZeroMQ RFC 32).
+///
+/// The specification only covers multiples of 4 bytes. Other lengths end
+/// with a partial group of one character more than its bytes, as in
+/// Ascii85.
+pub trait Z85 {
+    /// Encode bytes to Z85.
+    fn encode(data: &[u8]) -> String;
+
+    /// Decode a Z85 string to bytes.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
+/// Ascii85, as used by PostScript and PDF.
+pub trait Ascii85 {
+    /// Encode bytes to Ascii85 without `<~` `~>` delimiters. All-zero
+    /// groups are abbreviated to `z`.
+    fn encode(data: &[u8]) -> String;
+
+    /// Decode Ascii85. The `<~` `~>` delimiters are optional and
+    /// whitespace is ignored.
+    fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
+}
+
 /// Hexadecimal encoding/decoding.
 pub trait Hex {
     /// Encode bytes to hex string.
@@ -32,26 +118,125 @@ pub trait Hex {
     fn decode(encoded: &str) -> Result<Vec<u8>, DecodeError>;
 }
 
+/// Marker for implementations whose running time depends only on the
+/// length of their input, never on its content.
+///
+/// Use these for keys, tokens and other secrets, where branches and table
+/// lookups on the data would leak it through timing. Errors are the same
+/// as for any other implementation; only whether decoding failed, and
+/// where, shows once it has.
+pub trait ConstantTime {}
+
+/// Which characters a part of a URL may hold unescaped (RFC 3986).
+///
+/// Every set escapes `%`, spaces, controls and non-ASCII, so input is
+/// always treated as raw text rather than as already encoded. Every set
+/// also escapes `+`, which [`UrlEncoding::decode`] reads as a space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeSet {
+    /// Only unreserved characters: letters, digits, `-`, `.`, `_`, `~`.
+    Unreserved,
+    /// The user information before `@`: also sub-delimiters and `:`.
+    Userinfo,
+    /// A whole path: also sub-delimiters, `:`, `@` and `/`.
+    Path,
+    /// One path segment: like [`Path`](Self::Path) but escaping `/`.
+    PathSegment,
+    /// A whole query: also sub-delimiters, `:`, `@`, `/` and `?`.
+    Query,
+    /// One key or value within a query: like [`Query`](Self::Query) but
+    /// escaping `&`, `=`, `+` and `;`.
+    QueryComponent,
+    /// A fragment: the same characters as [`Query`](Self::Query).
+    Fragment,
+    /// `application/x-www-form-urlencoded`: letters, digits, `*`, `-`,
+    /// `.` and `_`, with spaces written as `+`.
+    Form,
+}
+
 /// URL encoding/decoding (percent encoding).
 pub trait UrlEncoding {
-    /// Encode a string for use in URLs.
+    /// Encode a string for use in URLs, escaping everything but
+    /// [unreserved](EncodeSet::Unreserved) characters.
     fn encode(input: &str) -> String;
 
-    /// Decode a URL-encoded string.
+    /// Encode a string for one part of a URL.
+    ///
+    /// By default this escapes everything but unreserved characters, which
+    /// is valid in any part.
+    fn encode_set(input: &str, set: EncodeSet) -> String {
+        let _ = set;
+        Self::encode(input)
+    }
+
+    /// Decode a URL-encoded string. `+` decodes to a space.
     fn decode(encoded: &str) -> Result<String, DecodeError>;
 }
 
+/// `application/x-www-form-urlencoded` bodies and URL query strings.
+pub trait FormUrlEncoding {
+    /// Serialize pairs as a form body: `key=value` joined with `&`, with
+    /// spaces written as `+`.
+    fn encode(pairs: &QueryMap) -> String;
+
+    /// Serialize pairs as a URL query, without the leading `?`. Keys and
+    /// values are escaped with [`EncodeSet::QueryComponent`].
+    fn encode_query(pairs: &QueryMap) -> String;
+
+    /// Parse a form body or query string. A leading `?` is skipped, `+`
+    /// decodes to a space, empty pairs are ignored and a key without `=`
+    /// has an empty value.
+    fn decode(encoded: &str) -> Result<QueryMap, DecodeError>;
+}
+
+/// LEB128 variable-length integers, as used by WebAssembly, DWARF and
+/// protobuf (unsigned).
+pub trait Leb128 {
+    /// Append an unsigned LEB128 integer to `out`.
+    fn encode_u64(value: u64, out: &mut Vec<u8>);
+
+    /// Append a signed LEB128 integer to `out`.
+    fn encode_i64(value: i64, out: &mut Vec<u8>);
+
+    /// Decode an unsigned LEB128 integer from the start of `input`,
+    /// returning it and the number of bytes it took.
+    fn decode_u64(input: &[u8]) -> Result<(u64, usize), DecodeError>;
+
+    /// Decode a signed LEB128 integer from the start of `input`,
+    /// returning it and the number of bytes it took.
+    fn decode_i64(input: &[u8]) -> Result<(i64, usize), DecodeError>;
+}
+
+/// CBOR (RFC 8949) for a dynamic [`CborValue`] tree.
+pub trait Cbor {
+    /// Encode a value. Integers and floats take their shortest form.
+    fn encode(value: &CborValue) -> Vec<u8>;
+
+    /// Decode exactly one value; bytes after it are an error.
+    fn decode(input: &[u8]) -> Result<CborValue, DecodeError>;
+
+    /// Decode one value from the start of `input`, returning it and the
+    /// number of bytes it took.
+    fn decode_prefix(input: &[u8]) -> Result<(CborValue, usize), DecodeError>;
+}
+
 /// Decoding errors.
 #[derive(Debug)]
 pub enum DecodeError {
-    /// Invalid character in input.
-    InvalidCharacter(char),
+    /// Invalid character in input, at a byte offset into the input.
+    InvalidCharacter { character: char, position: usize },
     /// Invalid length.
     InvalidLength,
     /// Invalid padding.
     InvalidPadding,
     /// Invalid UTF-8.
     InvalidUtf8,
+    /// The embedded checksum does not match the data.
+    InvalidChecksum,
+    /// Invalid byte in binary input, at an offset into the input.
+    InvalidByte { byte: u8, position: usize },
+    /// Binary input ended in the middle of a value.
+    UnexpectedEnd,
     /// Other error.
     Other(String),
 }
@@ -59,13 +244,45 @@ pub enum DecodeError {
 impl fmt::Display for DecodeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DecodeError::InvalidCharacter(c) => write!(f, "invalid character: {:?}", c),
+            DecodeError::InvalidCharacter {
+                character,
+                position,
+            } => write!(
+                f,
+                "invalid character {:?} at position {}",
+                character, position
+            ),
             DecodeError::InvalidLength => write!(f, "invalid length"),
             DecodeError::InvalidPadding => write!(f, "invalid padding"),
             DecodeError::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            DecodeError::InvalidChecksum => write!(f, "invalid checksum"),
+            DecodeError::InvalidByte { byte, position } => {
+                write!(f, "invalid byte 0x{:02x} at position {}", byte, position)
+            }
+            DecodeError::UnexpectedEnd => write!(f, "unexpected end of input"),
             DecodeError::Other(msg) => write!(f, "{}", msg),
         }
     }
 }
 
+impl DecodeError {
+    /// The invalid character at a byte offset into `input`.
+    ///
+    /// An offset inside a multi-byte character reports that byte as a
+    /// `char`, and one past the end reports `'\0'`.
+    pub fn invalid_character(input: &str, position: usize) -> Self {
+        let character = match input.get(position..) {
+            Some(rest) => rest.chars().next().unwrap_or('\0'),
+            None => input
+                .as_bytes()
+                .get(position)
+                .map_or('\0', |&b| char::from(b)),
+        };
+        DecodeError::InvalidCharacter {
+            character,
+            position,
+        }
+    }
+}
+
 impl std::error::Error for DecodeError {}
@@ -0,0 +1,148 @@
+//! Ordered multimap of query and form parameters.
+
+/// Query string or form parameters, in order, with repeated keys allowed.
+///
+/// Lookups compare keys exactly; `a=1&a=2` holds two values for `a`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryMap {
+    pairs: Vec<(String, String)>,
+}
+
+impl QueryMap {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a pair, builder style.
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.append(key, value);
+        self
+    }
+
+    /// Append a pair after any existing ones, even with the same key.
+    pub fn append(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    /// Replace every value of `key` with `value`. The pair takes the place
+    /// of the first existing one, or goes at the end if there was none.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let key = key.into();
+        let value = value.into();
+        match self.pairs.iter().position(|(k, _)| *k == key) {
+            Some(first) => {
+                self.pairs[first].1 = value;
+                let rest = self.pairs.split_off(first + 1);
+                self.pairs
+                    .extend(rest.into_iter().filter(|(k, _)| *k != key));
+            }
+            None => self.pairs.push((key, value)),
+        }
+    }
+
+    /// The first value of `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of `key`, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.pairs
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether any pair has this key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == key)
+    }
+
+    /// Remove every pair with this key, returning their values in order.
+    pub fn remove(&mut self, key: &str) -> Vec<String> {
+        let mut removed = Vec::new();
+        self.pairs.retain_mut(|(k, v)| {
+            if k == key {
+                removed.push(std::mem::take(v));
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Number of pairs.
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Whether there are no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Iterate over the pairs in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for QueryMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for QueryMap {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.append(k, v);
+        }
+    }
+}
+
+impl IntoIterator for QueryMap {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_order_and_repeated_keys() {
+        let map = QueryMap::new().with("b", "1").with("a", "2").with("b", "3");
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [("b", "1"), ("a", "2"), ("b", "3")]
+        );
+        assert_eq!(map.get("b"), Some("1"));
+        assert_eq!(map.get_all("b").collect::<Vec<_>>(), ["1", "3"]);
+        assert_eq!(map.get("c"), None);
+        assert!(map.contains_key("a"));
+    }
+
+    #[test]
+    fn set_replaces_in_place() {
+        let mut map: QueryMap = [("a", "1"), ("b", "2"), ("a", "3")].into_iter().collect();
+        map.set("a", "4");
+        assert_eq!(map.iter().collect::<Vec<_>>(), [("a", "4"), ("b", "2")]);
+        map.set("c", "5");
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.remove("a"), ["4"]);
+        assert_eq!(map.remove("a"), Vec::<String>::new());
+        assert_eq!(map.iter().collect::<Vec<_>>(), [("b", "2"), ("c", "5")]);
+    }
+}
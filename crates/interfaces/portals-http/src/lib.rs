@@ -5,6 +5,8 @@
 use std::collections::HashMap;
 use std::future::Future;
 
+pub use portals_encoding::{DecodeError, FormUrlEncoding, QueryMap};
+
 /// HTTP errors.
 #[derive(Debug)]
 pub enum Error {
@@ -58,6 +60,68 @@ pub struct Request {
     pub body: Option<Vec<u8>>,
 }
 
+impl Request {
+    /// A request without headers or body.
+    pub fn new(method: Method, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: HashMap::new(),
+            body: None,
+        }
+    }
+
+    /// Append query parameters to the URL, after any it already has and
+    /// before its fragment.
+    pub fn with_query<E: FormUrlEncoding>(mut self, query: &QueryMap) -> Self {
+        if query.is_empty() {
+            return self;
+        }
+        let fragment = self.url.find('#').unwrap_or(self.url.len());
+        let separator = match self.url[..fragment].find('?') {
+            Some(q) if q + 1 < fragment && !self.url[..fragment].ends_with('&') => "&",
+            Some(_) => "",
+            None => "?",
+        };
+        self.url.insert_str(
+            fragment,
+            &format!("{}{}", separator, E::encode_query(query)),
+        );
+        self
+    }
+
+    /// Send pairs as an `application/x-www-form-urlencoded` body.
+    pub fn with_form<E: FormUrlEncoding>(mut self, form: &QueryMap) -> Self {
+        self.headers.insert(
+            "content-type".to_string(),
+            "application/x-www-form-urlencoded".to_string(),
+        );
+        self.body = Some(E::encode(form).into_bytes());
+        self
+    }
+
+    /// Parse the query parameters of the URL. A URL without a query has
+    /// none.
+    pub fn query<E: FormUrlEncoding>(&self) -> Result<QueryMap, DecodeError> {
+        let url = self.url.split('#').next().unwrap_or_default();
+        match url.split_once('?') {
+            Some((_, query)) => E::decode(query),
+            None => Ok(QueryMap::new()),
+        }
+    }
+
+    /// Parse an `application/x-www-form-urlencoded` body. A request without
+    /// a body has no pairs; the content type is not checked.
+    pub fn form<E: FormUrlEncoding>(&self) -> Result<QueryMap, DecodeError> {
+        match &self.body {
+            Some(body) => {
+                E::decode(std::str::from_utf8(body).map_err(|_| DecodeError::InvalidUtf8)?)
+            }
+            None => Ok(QueryMap::new()),
+        }
+    }
+}
+
 /// An HTTP response.
 #[derive(Debug, Clone)]
 pub struct Response {
@@ -77,3 +141,85 @@ pub trait HttpHandler {
     /// Handle an incoming HTTP request.
     fn handle(&self, request: Request) -> impl Future<Output = Response>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Joins and splits pairs without escaping anything.
+    struct Plain;
+
+    impl FormUrlEncoding for Plain {
+        fn encode(pairs: &QueryMap) -> String {
+            Self::encode_query(pairs)
+        }
+
+        fn encode_query(pairs: &QueryMap) -> String {
+            pairs
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join("&")
+        }
+
+        fn decode(encoded: &str) -> Result<QueryMap, DecodeError> {
+            Ok(encoded
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+                .collect())
+        }
+    }
+
+    fn url_with(url: &str, query: &QueryMap) -> String {
+        Request::new(Method::Get, url)
+            .with_query::<Plain>(query)
+            .url
+    }
+
+    #[test]
+    fn with_query_appends_to_the_url() {
+        let query = QueryMap::new().with("a", "1").with("b", "2");
+        assert_eq!(url_with("/x", &query), "/x?a=1&b=2");
+        assert_eq!(url_with("/x?", &query), "/x?a=1&b=2");
+        assert_eq!(url_with("/x?c=3", &query), "/x?c=3&a=1&b=2");
+        assert_eq!(url_with("/x?c=3&", &query), "/x?c=3&a=1&b=2");
+        assert_eq!(url_with("/x#top", &query), "/x?a=1&b=2#top");
+        assert_eq!(url_with("/x?c=3#top", &query), "/x?c=3&a=1&b=2#top");
+        assert_eq!(url_with("/x?c=3", &QueryMap::new()), "/x?c=3");
+    }
+
+    #[test]
+    fn query_reads_the_url() {
+        let request = Request::new(Method::Get, "/x?a=1&a=2#b=3");
+        let query = request.query::<Plain>().unwrap();
+        assert_eq!(query.get_all("a").collect::<Vec<_>>(), ["1", "2"]);
+        assert!(!query.contains_key("b"));
+        assert!(
+            Request::new(Method::Get, "/x#a=1")
+                .query::<Plain>()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn form_round_trips_through_the_body() {
+        let form = QueryMap::new().with("name", "value");
+        let request = Request::new(Method::Post, "/x").with_form::<Plain>(&form);
+        assert_eq!(
+            request.headers.get("content-type").map(String::as_str),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(request.body.as_deref(), Some(&b"name=value"[..]));
+        assert_eq!(request.form::<Plain>().unwrap(), form);
+
+        let mut request = Request::new(Method::Post, "/x");
+        assert!(request.form::<Plain>().unwrap().is_empty());
+        request.body = Some(vec![0xff]);
+        assert!(matches!(
+            request.form::<Plain>(),
+            Err(DecodeError::InvalidUtf8)
+        ));
+    }
+}
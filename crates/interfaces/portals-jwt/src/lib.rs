@@ -0,0 +1,289 @@
+//! JSON Web Token interfaces.
+//!
+//! Covers compact JWS tokens (RFC 7519) signed with HS256, EdDSA or ES256.
+//! Unsecured tokens (`alg: none`) are never accepted.
+
+use serde_json::{Map, Value};
+use std::fmt;
+use std::str::FromStr;
+
+/// A JWS signing algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// HMAC with SHA-256.
+    Hs256,
+    /// Ed25519 signatures.
+    EdDsa,
+    /// ECDSA over P-256 with SHA-256.
+    Es256,
+}
+
+impl Algorithm {
+    /// The `alg` header value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hs256 => "HS256",
+            Self::EdDsa => "EdDSA",
+            Self::Es256 => "ES256",
+        }
+    }
+}
+
+/// Parses an `alg` header value. Matching is case-sensitive, and `none` is
+/// always rejected.
+impl FromStr for Algorithm {
+    type Err = JwtError;
+
+    fn from_str(s: &str) -> Result<Self, JwtError> {
+        match s {
+            "HS256" => Ok(Self::Hs256),
+            "EdDSA" => Ok(Self::EdDsa),
+            "ES256" => Ok(Self::Es256),
+            _ => Err(JwtError::UnsupportedAlgorithm(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The JOSE header of a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub alg: Algorithm,
+    /// Media type, normally `JWT`.
+    pub typ: Option<String>,
+    /// Identifies the key the token was signed with.
+    pub kid: Option<String>,
+}
+
+impl Header {
+    /// Create a header for the given algorithm with `typ: JWT`.
+    pub fn new(alg: Algorithm) -> Self {
+        Self {
+            alg,
+            typ: Some("JWT".to_string()),
+            kid: None,
+        }
+    }
+}
+
+/// The claims set of a token.
+///
+/// Registered claims have their own fields; times are seconds since the
+/// Unix epoch. Anything else goes in `custom`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Claims {
+    pub iss: Option<String>,
+    pub sub: Option<String>,
+    /// Audiences. A single audience is serialized as a string.
+    pub aud: Vec<String>,
+    pub exp: Option<u64>,
+    pub nbf: Option<u64>,
+    pub iat: Option<u64>,
+    pub jti: Option<String>,
+    pub custom: Map<String, Value>,
+}
+
+impl Claims {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the issuer (`iss`).
+    pub fn issuer(mut self, iss: impl Into<String>) -> Self {
+        self.iss = Some(iss.into());
+        self
+    }
+
+    /// Set the subject (`sub`).
+    pub fn subject(mut self, sub: impl Into<String>) -> Self {
+        self.sub = Some(sub.into());
+        self
+    }
+
+    /// Add an audience (`aud`).
+    pub fn audience(mut self, aud: impl Into<String>) -> Self {
+        self.aud.push(aud.into());
+        self
+    }
+
+    /// Set the expiration time (`exp`).
+    pub fn expires_at(mut self, exp: u64) -> Self {
+        self.exp = Some(exp);
+        self
+    }
+
+    /// Set the not-before time (`nbf`).
+    pub fn not_before(mut self, nbf: u64) -> Self {
+        self.nbf = Some(nbf);
+        self
+    }
+
+    /// Set the issued-at time (`iat`).
+    pub fn issued_at(mut self, iat: u64) -> Self {
+        self.iat = Some(iat);
+        self
+    }
+
+    /// Set the token ID (`jti`).
+    pub fn id(mut self, jti: impl Into<String>) -> Self {
+        self.jti = Some(jti.into());
+        self
+    }
+
+    /// Set a custom claim.
+    pub fn claim(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.custom.insert(name.into(), value.into());
+        self
+    }
+
+    /// Get a custom claim.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.custom.get(name)
+    }
+}
+
+/// A verified token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub header: Header,
+    pub claims: Claims,
+}
+
+/// Claim validation rules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validation {
+    /// Allowed clock skew in seconds, applied to `exp`, `nbf` and `iat`.
+    pub leeway: u64,
+    /// Require an `exp` claim.
+    pub require_exp: bool,
+    /// Required issuer, if any.
+    pub issuer: Option<String>,
+    /// Accepted audiences. When empty, tokens carrying an `aud` claim are
+    /// rejected, since the verifier cannot be one of their audiences.
+    pub audience: Vec<String>,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        Self {
+            leeway: 60,
+            require_exp: true,
+            issuer: None,
+            audience: Vec::new(),
+        }
+    }
+}
+
+impl Validation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the allowed clock skew in seconds.
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.leeway = seconds;
+        self
+    }
+
+    /// Set whether an `exp` claim is required.
+    pub fn require_exp(mut self, require: bool) -> Self {
+        self.require_exp = require;
+        self
+    }
+
+    /// Require the given issuer.
+    pub fn issuer(mut self, iss: impl Into<String>) -> Self {
+        self.issuer = Some(iss.into());
+        self
+    }
+
+    /// Accept the given audience.
+    pub fn audience(mut self, aud: impl Into<String>) -> Self {
+        self.audience.push(aud.into());
+        self
+    }
+}
+
+/// Creates signed tokens.
+pub trait JwtSigner {
+    /// The algorithm tokens are signed with.
+    fn algorithm(&self) -> Algorithm;
+
+    /// Sign the claims, returning a compact token.
+    fn sign(&self, claims: &Claims) -> Result<String, JwtError>;
+}
+
+/// Verifies tokens and validates their claims.
+pub trait JwtVerifier {
+    /// Verify a compact token's signature and claims.
+    fn verify(&self, token: &str) -> Result<Token, JwtError>;
+}
+
+/// JWT errors.
+#[derive(Debug)]
+pub enum JwtError {
+    /// The token is not a well-formed compact JWS.
+    Malformed(String),
+    /// The `alg` header names an algorithm that is not supported, including
+    /// `none`.
+    UnsupportedAlgorithm(String),
+    /// The `alg` header does not match the key's algorithm.
+    AlgorithmMismatch {
+        expected: Algorithm,
+        found: Algorithm,
+    },
+    /// No key is available for the token.
+    UnknownKey(Option<String>),
+    /// The key is unusable for its algorithm.
+    InvalidKey,
+    /// The signature does not verify.
+    InvalidSignature,
+    /// The token has expired.
+    Expired,
+    /// The token is not valid yet.
+    NotYetValid,
+    /// The token was issued in the future.
+    IssuedInFuture,
+    /// A required claim is missing.
+    MissingClaim(&'static str),
+    /// The issuer does not match.
+    InvalidIssuer,
+    /// None of the token's audiences are accepted.
+    InvalidAudience,
+    /// Other error.
+    Other(String),
+}
+
+impl fmt::Display for JwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(msg) => write!(f, "malformed token: {}", msg),
+            Self::UnsupportedAlgorithm(alg) => write!(f, "unsupported algorithm: {:?}", alg),
+            Self::AlgorithmMismatch { expected, found } => {
+                write!(
+                    f,
+                    "algorithm mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            Self::UnknownKey(Some(kid)) => write!(f, "unknown key: {:?}", kid),
+            Self::UnknownKey(None) => write!(f, "no key for token"),
+            Self::InvalidKey => write!(f, "invalid key"),
+            Self::InvalidSignature => write!(f, "invalid signature"),
+            Self::Expired => write!(f, "token has expired"),
+            Self::NotYetValid => write!(f, "token is not valid yet"),
+            Self::IssuedInFuture => write!(f, "token was issued in the future"),
+            Self::MissingClaim(claim) => write!(f, "missing claim: {}", claim),
+            Self::InvalidIssuer => write!(f, "invalid issuer"),
+            Self::InvalidAudience => write!(f, "invalid audience"),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for JwtError {}
@@ -6,7 +6,10 @@
 //! See ADR-0004 for rationale.
 
 use std::future::Future;
-use std::net::{IpAddr, SocketAddr};
+use std::net::SocketAddr;
+
+/// Name resolution is provided by portals-dns.
+pub use portals_dns::Resolver;
 
 /// Socket errors.
 #[derive(Debug)]
@@ -19,6 +22,8 @@ pub enum Error {
     NotConnected,
     Timeout,
     Access,
+    /// The host name could not be resolved.
+    Resolve(portals_dns::Error),
     Io(std::io::Error),
     Other(String),
 }
@@ -34,6 +39,7 @@ impl std::fmt::Display for Error {
             Self::NotConnected => write!(f, "not connected"),
             Self::Timeout => write!(f, "timeout"),
             Self::Access => write!(f, "access denied"),
+            Self::Resolve(e) => write!(f, "name resolution failed: {}", e),
             Self::Io(e) => write!(f, "I/O error: {}", e),
             Self::Other(s) => write!(f, "{}", s),
         }
@@ -48,6 +54,12 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<portals_dns::Error> for Error {
+    fn from(e: portals_dns::Error) -> Self {
+        Self::Resolve(e)
+    }
+}
+
 /// A capability to initiate TCP connections.
 ///
 /// The host provides this capability, potentially restricted to certain
@@ -59,6 +71,21 @@ pub trait TcpConnect {
     fn connect(&self, addr: SocketAddr) -> impl Future<Output = Result<Self::Stream, Error>>;
 }
 
+/// A capability to initiate TCP connections to hosts by name.
+///
+/// Backends resolve names through a [`Resolver`] they are given, so any DNS
+/// implementation can drive connections. IP literals skip resolution.
+pub trait TcpConnectHost {
+    type Stream: TcpStream;
+
+    /// Connect to a port on a named host.
+    fn connect_host(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> impl Future<Output = Result<Self::Stream, Error>>;
+}
+
 /// A bound TCP listener that accepts connections.
 ///
 /// This trait operates on an already-bound listener. The binding is done
@@ -127,9 +154,3 @@ pub trait UdpSocket {
     /// Get the local address.
     fn local_addr(&self) -> Result<SocketAddr, Error>;
 }
-
-/// DNS resolution.
-pub trait Resolver {
-    /// Resolve a hostname to IP addresses.
-    fn resolve(&self, host: &str) -> impl Future<Output = Result<Vec<IpAddr>, Error>>;
-}
@@ -5,7 +5,7 @@
 use std::fmt;
 
 pub use jiff::tz::{TimeZone, TimeZoneDatabase};
-pub use jiff::{Timestamp, Zoned};
+pub use jiff::{Timestamp, Zoned, civil::DateTime};
 
 /// Timezone errors.
 #[derive(Debug)]
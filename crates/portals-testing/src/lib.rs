@@ -0,0 +1,32 @@
+//! Test helpers shared by the portals crates.
+//!
+//! Not published; only meant as a dev-dependency.
+
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+/// Poll a future that is expected to be ready immediately.
+///
+/// # Panics
+///
+/// Panics if the future is pending.
+pub fn now<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future was not ready"),
+    }
+}
+
+/// Decode a hex string, as test vectors are written.
+///
+/// # Panics
+///
+/// Panics on an odd length or a character that is not a hex digit.
+pub fn unhex(hex: &str) -> Vec<u8> {
+    assert!(hex.len().is_multiple_of(2), "odd-length hex string");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -65,8 +65,12 @@ impl Method {
             Self::Trace => "TRACE",
         }
     }
+}
+
+impl std::str::FromStr for Method {
+    type Err = Error;
 
-    pub fn from_str(s: &str) -> Result<Self, Error> {
+    fn from_str(s: &str) -> Result<Self, Error> {
         match s {
             "GET" => Ok(Self::Get),
             "HEAD" => Ok(Self::Head),
@@ -135,7 +139,7 @@ pub fn parse_request<R: BufRead>(reader: &mut R) -> Result<Request, Error> {
         return Err(Error::InvalidRequestLine);
     }
 
-    let method = Method::from_str(parts[0])?;
+    let method: Method = parts[0].parse()?;
     let path = parts[1].to_string();
 
     // Headers